@@ -0,0 +1,377 @@
+//!  A module for analyzing the content of images.
+
+// from rust
+
+// from external crate
+
+// from local crate
+use draw::Rect;
+use error::{RasterError, RasterResult};
+use quantize;
+use Color;
+use Image;
+
+/// A struct for the statistics of a connected component found by
+/// `analysis::connected_components`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    /// Label of the pixels of the component in the label map, from 1.
+    pub label: u32,
+
+    /// Number of pixels.
+    pub area: usize,
+
+    /// Smallest rectangle holding all the pixels.
+    pub bounds: Rect,
+
+    /// Average position of the pixels.
+    pub centroid: (f32, f32),
+}
+
+/// Find the groups of touching foreground pixels of a mask, eg. to detect blobs.
+///
+/// Foreground pixels are the ones with a luminance of 128 or more, eg. the white pixels of masks
+/// made by `filter::threshold`, and touch when they are next to each other in any of the 8
+/// directions. Returns a label for each pixel, row by row, 0 for the background and from 1 for
+/// the components in the order their first pixel is met, and the statistics of each component.
+///
+/// # Examples
+/// ```
+/// use raster::{analysis, filter};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::threshold(&mut image, 200).unwrap();
+/// let (labels, components) = analysis::connected_components(&image);
+/// assert_eq!((image.width * image.height) as usize, labels.len());
+/// if let Some(largest) = components.iter().max_by_key(|c| c.area) {
+///     println!("Largest blob of {} pixels at {:?}", largest.area, largest.bounds);
+/// }
+/// ```
+pub fn connected_components(src: &Image) -> (Vec<u32>, Vec<Component>) {
+    let (w, h) = (src.width.max(0) as usize, src.height.max(0) as usize);
+    let foreground: Vec<bool> = src
+        .bytes
+        .chunks(4)
+        .map(|p| p[0] as f32 * 0.3 + p[1] as f32 * 0.59 + p[2] as f32 * 0.11 >= 127.5)
+        .collect();
+
+    let mut labels = vec![0u32; w * h];
+    let mut components = Vec::new();
+    let mut stack = Vec::new();
+    for start in 0..w * h {
+        if !foreground[start] || labels[start] != 0 {
+            continue;
+        }
+
+        // Flood fill the component from its first pixel.
+        let label = components.len() as u32 + 1;
+        let (mut left, mut top, mut right, mut bottom) = (w, h, 0, 0);
+        let (mut area, mut sum_x, mut sum_y) = (0, 0u64, 0u64);
+        labels[start] = label;
+        stack.push(start);
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % w, i / w);
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x);
+            bottom = bottom.max(y);
+            area += 1;
+            sum_x += x as u64;
+            sum_y += y as u64;
+
+            for ny in y.saturating_sub(1)..(y + 2).min(h) {
+                for nx in x.saturating_sub(1)..(x + 2).min(w) {
+                    let n = ny * w + nx;
+                    if foreground[n] && labels[n] == 0 {
+                        labels[n] = label;
+                        stack.push(n);
+                    }
+                }
+            }
+        }
+
+        components.push(Component {
+            label,
+            area,
+            bounds: Rect::new(
+                left as i32,
+                top as i32,
+                (right - left + 1) as i32,
+                (bottom - top + 1) as i32,
+            ),
+            centroid: (
+                (sum_x as f64 / area as f64) as f32,
+                (sum_y as f64 / area as f64) as f32,
+            ),
+        });
+    }
+
+    (labels, components)
+}
+
+/// Find the k dominant colors of an image.
+///
+/// Returns up to k colors with the share of the pixels closest to each, from 0.0 to 1.0, sorted
+/// from the most to the least common. The colors are found by median cut over a sample of at most
+/// 65536 pixels, so it stays fast on large images. Fully transparent pixels are ignored. Useful to
+/// theme a UI around an uploaded picture.
+///
+/// # Errors
+///
+/// If k is not in the range 1 - 256, this fails with `RasterError::InvalidPalette`.
+///
+/// # Examples
+/// ```
+/// use raster::analysis;
+///
+/// let image = raster::open("tests/in/sample.jpg").unwrap();
+/// for (color, share) in analysis::dominant_colors(&image, 5).unwrap() {
+///     println!("{:?} covers {:.0}%", color, share * 100.0);
+/// }
+/// ```
+pub fn dominant_colors(src: &Image, k: usize) -> RasterResult<Vec<(Color, f32)>> {
+    if k == 0 || k > 256 {
+        return Err(RasterError::InvalidPalette);
+    }
+
+    let opaque: Vec<[u8; 3]> = src
+        .bytes
+        .chunks(4)
+        .filter(|p| p[3] > 0)
+        .map(|p| [p[0], p[1], p[2]])
+        .collect();
+    let step = (opaque.len() / 65536).max(1);
+    let sample: Vec<[u8; 3]> = opaque.iter().step_by(step).cloned().collect();
+
+    let palette = quantize::median_cut(&sample, k);
+    let mut counts = vec![0u32; palette.len()];
+    for color in &sample {
+        counts[quantize::nearest(&palette, color)] += 1;
+    }
+
+    let total = sample.len() as f32;
+    let mut dominant: Vec<(Color, f32)> = palette
+        .iter()
+        .zip(counts)
+        .filter(|&(_, count)| count > 0)
+        .map(|(c, count)| (Color::rgb(c[0], c[1], c[2]), count as f32 / total))
+        .collect();
+    dominant.sort_by(|a, b| b.1.total_cmp(&a.1));
+    Ok(dominant)
+}
+
+/// An enum for the ways `analysis::match_template` scores the positions of the needle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchMethod {
+    /// Mean of the squared differences of the color channels, 0.0 for a perfect match. Fast and
+    /// exact, but sensitive to changes of brightness.
+    SquaredDifference,
+    /// Zero-mean normalized cross-correlation, from -1.0 - 1.0 where 1.0 is a perfect match. Does
+    /// not change when the brightness or contrast of the haystack changes.
+    NormalizedCrossCorrelation,
+}
+
+/// A struct for the results of `analysis::match_template`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateMatch {
+    /// Score of each position of the top left corner of the needle, row by row.
+    pub scores: Vec<f32>,
+
+    /// Number of positions per row of scores.
+    pub width: i32,
+
+    /// Number of rows of scores.
+    pub height: i32,
+
+    /// Position of the top left corner of the needle with the best score.
+    pub best: (i32, i32),
+
+    /// The best score, the lowest for `MatchMethod::SquaredDifference` and the highest for
+    /// `MatchMethod::NormalizedCrossCorrelation`.
+    pub best_score: f32,
+}
+
+/// Find where a small image appears in a larger one, eg. to check that a button is on a
+/// screenshot.
+///
+/// The needle is compared to the haystack at each position where it fits entirely, using the
+/// red, green and blue channels. Alpha is ignored. With `MatchMethod::NormalizedCrossCorrelation`,
+/// positions where the needle or the haystack under it has a single color score 0.0. On ties the
+/// first position, row by row, is the best.
+///
+/// # Errors
+///
+/// If the needle is empty or larger than the haystack, this fails with
+/// `RasterError::InvalidDimensions`.
+///
+/// # Examples
+/// ```
+/// use raster::{analysis, editor, MatchMethod, PositionMode};
+///
+/// let haystack = raster::open("tests/in/sample.jpg").unwrap();
+/// let mut needle = haystack.clone();
+/// editor::crop(&mut needle, 24, 16, PositionMode::TopLeft, 40, 30).unwrap();
+/// let found = analysis::match_template(&haystack, &needle, MatchMethod::SquaredDifference)
+///     .unwrap();
+/// assert_eq!((40, 30), found.best);
+/// assert_eq!(0.0, found.best_score);
+/// ```
+pub fn match_template(
+    haystack: &Image,
+    needle: &Image,
+    method: MatchMethod,
+) -> RasterResult<TemplateMatch> {
+    let (nw, nh) = (needle.width, needle.height);
+    if nw <= 0 || nh <= 0 || nw > haystack.width || nh > haystack.height {
+        return Err(RasterError::InvalidDimensions(nw, nh));
+    }
+
+    let (hw, nw, nh) = (haystack.width as usize, nw as usize, nh as usize);
+    let (width, height) = (hw - nw + 1, haystack.height as usize - nh + 1);
+    let colors = |image: &Image| -> Vec<f64> {
+        image
+            .bytes
+            .chunks(4)
+            .flat_map(|p| [p[0] as f64, p[1] as f64, p[2] as f64])
+            .collect()
+    };
+    let (hay, needle) = (colors(haystack), colors(needle));
+    let n = needle.len() as f64;
+
+    // Needle without its mean, and its norm.
+    let mean = needle.iter().sum::<f64>() / n;
+    let centered: Vec<f64> = needle.iter().map(|v| v - mean).collect();
+    let norm = centered.iter().map(|v| v * v).sum::<f64>().sqrt();
+
+    let row_len = nw * 3;
+    let mut scores = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let window = (0..nh).map(|ny| {
+                let start = ((y + ny) * hw + x) * 3;
+                &hay[start..start + row_len]
+            });
+            let score = match method {
+                MatchMethod::SquaredDifference => {
+                    let sum: f64 = window
+                        .zip(needle.chunks(row_len))
+                        .flat_map(|(a, b)| a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)))
+                        .sum();
+                    sum / n
+                }
+                MatchMethod::NormalizedCrossCorrelation => {
+                    let (mut sum, mut squares, mut cross) = (0.0, 0.0, 0.0);
+                    for (a, b) in window.zip(centered.chunks(row_len)) {
+                        for (a, b) in a.iter().zip(b) {
+                            sum += a;
+                            squares += a * a;
+                            cross += a * b;
+                        }
+                    }
+                    // The mean of the window cancels out against the centered needle.
+                    let window_norm = (squares - sum * sum / n).max(0.0).sqrt();
+                    if norm * window_norm > 1e-9 {
+                        (cross / (norm * window_norm)).clamp(-1.0, 1.0)
+                    } else {
+                        0.0
+                    }
+                }
+            };
+            scores.push(score as f32);
+        }
+    }
+
+    let mut best = 0;
+    for (i, &score) in scores.iter().enumerate() {
+        let better = match method {
+            MatchMethod::SquaredDifference => score < scores[best],
+            MatchMethod::NormalizedCrossCorrelation => score > scores[best],
+        };
+        if better {
+            best = i;
+        }
+    }
+
+    Ok(TemplateMatch {
+        best: ((best % width) as i32, (best / width) as i32),
+        best_score: scores[best],
+        scores,
+        width: width as i32,
+        height: height as i32,
+    })
+}
+
+/// Build an image pyramid, eg. for texture mipmaps or multi-scale algorithms.
+///
+/// Returns the image followed by up to levels copies, each half the size of the one before, with
+/// each pixel the average of 2x2 pixels. Odd sizes are rounded down and the last row or column is
+/// averaged into it, and the pyramid stops early at a 1x1 image. Colors are weighted by alpha so
+/// that transparent pixels do not darken their neighbors. An empty image has nothing to halve and
+/// gives a pyramid of only itself.
+///
+/// # Examples
+/// ```
+/// use raster::analysis;
+///
+/// let image = raster::open("tests/in/sample.jpg").unwrap();
+/// let pyramid = analysis::pyramid(&image, 3);
+/// assert_eq!(4, pyramid.len());
+/// assert_eq!(image.width / 8, pyramid[3].width);
+/// raster::save(&pyramid[2], "tests/out/test_pyramid.jpg").unwrap();
+/// ```
+pub fn pyramid(src: &Image, levels: usize) -> Vec<Image> {
+    let mut pyramid = vec![src.clone()];
+    for _ in 0..levels {
+        let last = &pyramid[pyramid.len() - 1];
+        if (last.width <= 1 && last.height <= 1) || last.width <= 0 || last.height <= 0 {
+            break;
+        }
+        let next = _halve(last);
+        pyramid.push(next);
+    }
+    pyramid
+}
+
+// Private functions
+
+// Half size image, averaging the pixels each new pixel covers.
+fn _halve(src: &Image) -> Image {
+    let (width, height) = (src.width as usize, src.height as usize);
+    let (w, h) = ((width / 2).max(1), (height / 2).max(1));
+
+    // Source rows or columns covered by a new one, the last takes the leftover odd one.
+    let span = |i: usize, size: usize, new_size: usize| {
+        let end = if i == new_size - 1 { size } else { i * 2 + 2 };
+        i * 2..end.min(size)
+    };
+
+    let mut bytes = Vec::with_capacity(w * h * 4);
+    for y in 0..h {
+        for x in 0..w {
+            let mut sums = [0u32; 4];
+            let mut count = 0;
+            for sy in span(y, height, h) {
+                for sx in span(x, width, w) {
+                    let p = &src.bytes[(sy * width + sx) * 4..][..4];
+                    let a = p[3] as u32;
+                    for c in 0..3 {
+                        sums[c] += p[c] as u32 * a;
+                    }
+                    sums[3] += a;
+                    count += 1;
+                }
+            }
+            for sum in &sums[0..3] {
+                let color = (sum + sums[3] / 2).checked_div(sums[3]).unwrap_or(0);
+                bytes.push(color as u8);
+            }
+            bytes.push(((sums[3] + count / 2) / count) as u8);
+        }
+    }
+
+    Image {
+        width: w as i32,
+        height: h as i32,
+        bytes,
+    }
+}
@@ -2,272 +2,314 @@
 // See https://en.wikipedia.org/wiki/Alpha_compositing
 
 // from rust
+use std::str::FromStr;
 
 // from external crate
 
 // from local crate
-use error::RasterResult;
+use error::{RasterError, RasterResult};
+use color::{lum, sat, set_lum, set_sat};
 use Image;
 use Color;
 
 /// Enumeration for blending modes.
-#[derive(Debug)]
+///
+/// Can also be parsed from the lowercase mode names used by older versions of raster.
+///
+/// # Examples
+/// ```
+/// use raster::BlendMode;
+///
+/// let mode: BlendMode = "overlay".parse().unwrap();
+/// assert_eq!(BlendMode::Overlay, mode);
+/// assert!("unknown".parse::<BlendMode>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BlendMode {
     Normal,
     Difference,
     Multiply,
     Overlay,
     Screen,
+    Darken,
+    Lighten,
+    SoftLight,
+    HardLight,
+    ColorDodge,
+    ColorBurn,
+    Exclusion,
+    Subtract,
+    Add,
+    Divide,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
 }
 
-pub fn difference(
-    image1: &Image,
-    image2: &Image,
-    loop_start_y: i32,
-    loop_end_y: i32,
-    loop_start_x: i32,
-    loop_end_x: i32,
-    offset_x: i32,
-    offset_y: i32,
-    opacity: f32,
-) -> RasterResult<Image> {
-    let mut canvas = image1.clone();
-
-    for y in loop_start_y..loop_end_y {
-        for x in loop_start_x..loop_end_x {
-            let canvas_x = x + offset_x;
-            let canvas_y = y + offset_y;
-            let rgba1 = image1.get_pixel(canvas_x, canvas_y)?;
-            let a1 = rgba1.a as f32 / 255.0; // convert to 0.0 - 1.0
-            let r1 = rgba1.r as f32 * a1;
-            let g1 = rgba1.g as f32 * a1;
-            let b1 = rgba1.b as f32 * a1;
-
-            let rgba2 = image2.get_pixel(x, y)?;
-            let a2 = rgba2.a as f32 / 255.0 * opacity; // convert to 0.0 - 1.0
-            let r2 = rgba2.r as f32;
-            let g2 = rgba2.g as f32;
-            let b2 = rgba2.b as f32;
-
-            let r3 = ch_alpha_f(r1, r2, BlendFunction::Difference, a2);
-            let g3 = ch_alpha_f(g1, g2, BlendFunction::Difference, a2);
-            let b3 = ch_alpha_f(b1, b2, BlendFunction::Difference, a2);
-            let a3 = 255;
-
-            canvas.set_pixel(
-                canvas_x,
-                canvas_y,
-                &Color::rgba(r3 as u8, g3 as u8, b3 as u8, a3 as u8),
-            )?;
+impl FromStr for BlendMode {
+    type Err = RasterError;
+
+    fn from_str(mode: &str) -> RasterResult<BlendMode> {
+        match mode {
+            "normal" => Ok(BlendMode::Normal),
+            "difference" => Ok(BlendMode::Difference),
+            "multiply" => Ok(BlendMode::Multiply),
+            "overlay" => Ok(BlendMode::Overlay),
+            "screen" => Ok(BlendMode::Screen),
+            "darken" => Ok(BlendMode::Darken),
+            "lighten" => Ok(BlendMode::Lighten),
+            "soft_light" => Ok(BlendMode::SoftLight),
+            "hard_light" => Ok(BlendMode::HardLight),
+            "color_dodge" => Ok(BlendMode::ColorDodge),
+            "color_burn" => Ok(BlendMode::ColorBurn),
+            "exclusion" => Ok(BlendMode::Exclusion),
+            "subtract" => Ok(BlendMode::Subtract),
+            "add" => Ok(BlendMode::Add),
+            "divide" => Ok(BlendMode::Divide),
+            "hue" => Ok(BlendMode::Hue),
+            "saturation" => Ok(BlendMode::Saturation),
+            "color" => Ok(BlendMode::Color),
+            "luminosity" => Ok(BlendMode::Luminosity),
+            _ => Err(RasterError::InvalidMode(mode.to_string())),
         }
     }
-
-    Ok(canvas)
 }
 
-pub fn multiply(
-    image1: &Image,
-    image2: &Image,
-    loop_start_y: i32,
-    loop_end_y: i32,
+/// The area of the top image that overlaps the canvas, and where it lands on the canvas.
+#[derive(Debug)]
+pub struct Overlap {
     loop_start_x: i32,
     loop_end_x: i32,
+    loop_start_y: i32,
+    loop_end_y: i32,
     offset_x: i32,
     offset_y: i32,
-    opacity: f32,
-) -> RasterResult<Image> {
-    let mut canvas = image1.clone();
-
-    for y in loop_start_y..loop_end_y {
-        for x in loop_start_x..loop_end_x {
-            let canvas_x = x + offset_x;
-            let canvas_y = y + offset_y;
-            let rgba1 = image1.get_pixel(canvas_x, canvas_y)?;
-            let a1 = rgba1.a as f32 / 255.0; // convert to 0.0 - 1.0
-            let r1 = rgba1.r as f32 * a1;
-            let g1 = rgba1.g as f32 * a1;
-            let b1 = rgba1.b as f32 * a1;
-
-            let rgba2 = image2.get_pixel(x, y)?;
-            let a2 = rgba2.a as f32 / 255.0 * opacity; // convert to 0.0 - 1.0
-            let r2 = rgba2.r as f32;
-            let g2 = rgba2.g as f32;
-            let b2 = rgba2.b as f32;
+}
 
-            let r3 = ch_alpha_f(r1, r2, BlendFunction::Multiply, a2);
-            let g3 = ch_alpha_f(g1, g2, BlendFunction::Multiply, a2);
-            let b3 = ch_alpha_f(b1, b2, BlendFunction::Multiply, a2);
-            let a3 = 255;
+impl Overlap {
+    /// Compute the overlapping area of an image of size (w2, h2) placed at (offset_x, offset_y)
+    /// on a canvas of size (w1, h1). Returns None if they do not overlap.
+    pub fn new(w1: i32, h1: i32, w2: i32, h2: i32, offset_x: i32, offset_y: i32) -> Option<Overlap> {
+        // Check if it overlaps
+        if (offset_x >= w1) || (offset_x + w2 <= 0) || (offset_y >= h1) || (offset_y + h2 <= 0) {
+            return None;
+        }
 
-            canvas.set_pixel(
-                canvas_x,
-                canvas_y,
-                &Color::rgba(r3 as u8, g3 as u8, b3 as u8, a3 as u8),
-            )?;
+        // Loop start X
+        let mut loop_start_x = 0;
+        let canvas_start_x = offset_x;
+        if canvas_start_x < 0 {
+            let diff = 0 - canvas_start_x;
+            loop_start_x += diff;
         }
-    }
 
-    Ok(canvas)
-}
+        // Loop end X
+        let mut loop_end_x = w2;
+        let canvas_end_x = offset_x + w2;
+        if canvas_end_x > w1 {
+            let diff = canvas_end_x - w1;
+            loop_end_x -= diff;
+        }
 
-pub fn normal(
-    image1: &Image,
-    image2: &Image,
-    loop_start_y: i32,
-    loop_end_y: i32,
-    loop_start_x: i32,
-    loop_end_x: i32,
-    offset_x: i32,
-    offset_y: i32,
-    opacity: f32,
-) -> RasterResult<Image> {
-    let mut canvas = image1.clone();
-
-    for y in loop_start_y..loop_end_y {
-        for x in loop_start_x..loop_end_x {
-            let canvas_x = x + offset_x;
-            let canvas_y = y + offset_y;
-            let color1 = image1.get_pixel(canvas_x, canvas_y)?;
-            let a1 = color1.a as f32 / 255.0; // convert to 0.0 - 1.0
-            let r1 = color1.r as f32 * a1;
-            let g1 = color1.g as f32 * a1;
-            let b1 = color1.b as f32 * a1;
-
-            let color2 = image2.get_pixel(x, y)?;
-            let a2 = color2.a as f32 / 255.0 * opacity; // convert to 0.0 - 1.0
-            let r2 = color2.r as f32;
-            let g2 = color2.g as f32;
-            let b2 = color2.b as f32;
-
-            let r3 = (a2 * r2) + ((1.0 - a2) * r1);
-            let g3 = (a2 * g2) + ((1.0 - a2) * g1);
-            let b3 = (a2 * b2) + ((1.0 - a2) * b1);
-            let a3 = 255;
+        // Loop start Y
+        let mut loop_start_y = 0;
+        let canvas_start_y = offset_y;
+        if canvas_start_y < 0 {
+            let diff = 0 - canvas_start_y;
+            loop_start_y += diff;
+        }
 
-            canvas.set_pixel(
-                canvas_x,
-                canvas_y,
-                &Color::rgba(r3 as u8, g3 as u8, b3 as u8, a3 as u8),
-            )?;
+        // Loop end Y
+        let mut loop_end_y = h2;
+        let canvas_end_y = offset_y + h2;
+        if canvas_end_y > h1 {
+            let diff = canvas_end_y - h1;
+            loop_end_y -= diff;
         }
-    }
 
-    Ok(canvas)
+        Some(Overlap {
+            loop_start_x,
+            loop_end_x,
+            loop_start_y,
+            loop_end_y,
+            offset_x,
+            offset_y,
+        })
+    }
 }
 
-pub fn overlay(
-    image1: &Image,
+/// Blend image2 on top of the canvas, in place, within the overlapping area.
+pub fn blend(
+    canvas: &mut Image,
     image2: &Image,
-    loop_start_y: i32,
-    loop_end_y: i32,
-    loop_start_x: i32,
-    loop_end_x: i32,
-    offset_x: i32,
-    offset_y: i32,
+    blend_mode: BlendMode,
+    overlap: &Overlap,
     opacity: f32,
-) -> RasterResult<Image> {
-    let mut canvas = image1.clone();
-
-    for y in loop_start_y..loop_end_y {
-        for x in loop_start_x..loop_end_x {
-            let canvas_x = x + offset_x;
-            let canvas_y = y + offset_y;
-            let rgba1 = image1.get_pixel(canvas_x, canvas_y)?;
-            let a1 = rgba1.a as f32 / 255.0; // convert to 0.0 - 1.0
-            let r1 = rgba1.r as f32 * a1;
-            let g1 = rgba1.g as f32 * a1;
-            let b1 = rgba1.b as f32 * a1;
-
-            let rgba2 = image2.get_pixel(x, y)?;
-            let a2 = rgba2.a as f32 / 255.0 * opacity; // convert to 0.0 - 1.0
-            let r2 = rgba2.r as f32;
-            let g2 = rgba2.g as f32;
-            let b2 = rgba2.b as f32;
+) -> RasterResult<()> {
+    match blend_mode {
+        BlendMode::Normal => blend_pixels(canvas, image2, overlap, opacity, |_, top| top),
+        BlendMode::Difference => blend_function(canvas, image2, overlap, opacity, BlendFunction::Difference),
+        BlendMode::Multiply => blend_function(canvas, image2, overlap, opacity, BlendFunction::Multiply),
+        BlendMode::Overlay => blend_function(canvas, image2, overlap, opacity, BlendFunction::Overlay),
+        BlendMode::Screen => blend_function(canvas, image2, overlap, opacity, BlendFunction::Screen),
+        BlendMode::Darken => blend_function(canvas, image2, overlap, opacity, BlendFunction::Darken),
+        BlendMode::Lighten => blend_function(canvas, image2, overlap, opacity, BlendFunction::Lighten),
+        BlendMode::SoftLight => blend_function(canvas, image2, overlap, opacity, BlendFunction::SoftLight),
+        BlendMode::HardLight => blend_function(canvas, image2, overlap, opacity, BlendFunction::HardLight),
+        BlendMode::ColorDodge => blend_function(canvas, image2, overlap, opacity, BlendFunction::ColorDodge),
+        BlendMode::ColorBurn => blend_function(canvas, image2, overlap, opacity, BlendFunction::ColorBurn),
+        BlendMode::Exclusion => blend_function(canvas, image2, overlap, opacity, BlendFunction::Exclusion),
+        BlendMode::Subtract => blend_function(canvas, image2, overlap, opacity, BlendFunction::Subtract),
+        BlendMode::Add => blend_function(canvas, image2, overlap, opacity, BlendFunction::Add),
+        BlendMode::Divide => blend_function(canvas, image2, overlap, opacity, BlendFunction::Divide),
+        BlendMode::Hue => blend_component(canvas, image2, overlap, opacity, BlendComponent::Hue),
+        BlendMode::Saturation => blend_component(canvas, image2, overlap, opacity, BlendComponent::Saturation),
+        BlendMode::Color => blend_component(canvas, image2, overlap, opacity, BlendComponent::Color),
+        BlendMode::Luminosity => blend_component(canvas, image2, overlap, opacity, BlendComponent::Luminosity),
+    }
+}
 
-            let r3 = ch_alpha_f(r1, r2, BlendFunction::Overlay, a2);
-            let g3 = ch_alpha_f(g1, g2, BlendFunction::Overlay, a2);
-            let b3 = ch_alpha_f(b1, b2, BlendFunction::Overlay, a2);
-            let a3 = 255;
+// PRIVATE FNs
 
-            canvas.set_pixel(
-                canvas_x,
-                canvas_y,
-                &Color::rgba(r3 as u8, g3 as u8, b3 as u8, a3 as u8),
-            )?;
-        }
-    }
+// Blend the overlapping area using a blend function applied on each color channel.
+fn blend_function(
+    canvas: &mut Image,
+    image2: &Image,
+    overlap: &Overlap,
+    opacity: f32,
+    f: BlendFunction,
+) -> RasterResult<()> {
+    blend_pixels(canvas, image2, overlap, opacity, |base, top| {
+        (
+            ch_blend_f(base.0, top.0, f),
+            ch_blend_f(base.1, top.1, f),
+            ch_blend_f(base.2, top.2, f),
+        )
+    })
+}
 
-    Ok(canvas)
+// Blend the overlapping area by combining hue, saturation and luminosity components of the base
+// and top colors.
+fn blend_component(
+    canvas: &mut Image,
+    image2: &Image,
+    overlap: &Overlap,
+    opacity: f32,
+    component: BlendComponent,
+) -> RasterResult<()> {
+    blend_pixels(canvas, image2, overlap, opacity, |base, top| match component {
+        BlendComponent::Hue => set_lum(set_sat(top, sat(base)), lum(base)),
+        BlendComponent::Saturation => set_lum(set_sat(base, sat(top)), lum(base)),
+        BlendComponent::Color => set_lum(top, lum(base)),
+        BlendComponent::Luminosity => set_lum(base, lum(top)),
+    })
 }
 
-pub fn screen(
-    image1: &Image,
+// Loop through the overlapping area and composite the top pixels over the base pixels using
+// Porter-Duff source-over. The blend closure mixes the base and top RGB where both are present.
+// See https://www.w3.org/TR/compositing-1/#generalformula
+fn blend_pixels<F>(
+    canvas: &mut Image,
     image2: &Image,
-    loop_start_y: i32,
-    loop_end_y: i32,
-    loop_start_x: i32,
-    loop_end_x: i32,
-    offset_x: i32,
-    offset_y: i32,
+    overlap: &Overlap,
     opacity: f32,
-) -> RasterResult<Image> {
-    let mut canvas = image1.clone();
-
-    for y in loop_start_y..loop_end_y {
-        for x in loop_start_x..loop_end_x {
-            let canvas_x = x + offset_x;
-            let canvas_y = y + offset_y;
-            let rgba1 = image1.get_pixel(canvas_x, canvas_y)?;
+    blend: F,
+) -> RasterResult<()>
+where
+    F: Fn(Rgb, Rgb) -> Rgb,
+{
+    for y in overlap.loop_start_y..overlap.loop_end_y {
+        for x in overlap.loop_start_x..overlap.loop_end_x {
+            let canvas_x = x + overlap.offset_x;
+            let canvas_y = y + overlap.offset_y;
+            let rgba1 = canvas.get_pixel(canvas_x, canvas_y)?;
             let a1 = rgba1.a as f32 / 255.0; // convert to 0.0 - 1.0
-            let r1 = rgba1.r as f32 * a1;
-            let g1 = rgba1.g as f32 * a1;
-            let b1 = rgba1.b as f32 * a1;
+            let rgb1 = (rgba1.r as f32, rgba1.g as f32, rgba1.b as f32);
 
             let rgba2 = image2.get_pixel(x, y)?;
             let a2 = rgba2.a as f32 / 255.0 * opacity; // convert to 0.0 - 1.0
-            let r2 = rgba2.r as f32;
-            let g2 = rgba2.g as f32;
-            let b2 = rgba2.b as f32;
-
-            let r3 = ch_alpha_f(r1, r2, BlendFunction::Screen, a2);
-            let g3 = ch_alpha_f(g1, g2, BlendFunction::Screen, a2);
-            let b3 = ch_alpha_f(b1, b2, BlendFunction::Screen, a2);
-            let a3 = 255;
+            let rgb2 = (rgba2.r as f32, rgba2.g as f32, rgba2.b as f32);
+
+            let a3 = a2 + a1 * (1.0 - a2);
+            if a3 == 0.0 {
+                continue; // Nothing to see
+            }
+
+            // Where the base is transparent, the top shows unmixed
+            let mixed = blend(rgb1, rgb2);
+            let top = (
+                ch_alpha(rgb2.0, mixed.0, a1),
+                ch_alpha(rgb2.1, mixed.1, a1),
+                ch_alpha(rgb2.2, mixed.2, a1),
+            );
+
+            // Composite premultiplied colors then convert back
+            let r3 = (a2 * top.0 + a1 * (1.0 - a2) * rgb1.0) / a3;
+            let g3 = (a2 * top.1 + a1 * (1.0 - a2) * rgb1.1) / a3;
+            let b3 = (a2 * top.2 + a1 * (1.0 - a2) * rgb1.2) / a3;
 
             canvas.set_pixel(
                 canvas_x,
                 canvas_y,
-                &Color::rgba(r3 as u8, g3 as u8, b3 as u8, a3 as u8),
+                &Color::rgba(
+                    r3.round() as u8,
+                    g3.round() as u8,
+                    b3.round() as u8,
+                    (a3 * 255.0).round() as u8,
+                ),
             )?;
         }
     }
 
-    Ok(canvas)
+    Ok(())
 }
 
-// PRIVATE FNs
+// Red, green and blue channels, each 0.0 - 255.0
+type Rgb = (f32, f32, f32);
+
 // base, top 0.0 - 255.0
 // opacity 0.0 - 1.0
 
 /*
 This is the private BlendFunction enum, not to be confused with BlendMode, which is for public
-consumption! BlendFunction differs only in lacking a Normal variant, as ch_alpha_f has no need for
-such things.
+consumption! BlendFunction only holds the separable modes, those applied on each channel
+separately, and lacks a Normal variant, as ch_blend_f has no need for such things.
 */
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum BlendFunction {
     Difference,
     Multiply,
     Overlay,
     Screen,
+    Darken,
+    Lighten,
+    SoftLight,
+    HardLight,
+    ColorDodge,
+    ColorBurn,
+    Exclusion,
+    Subtract,
+    Add,
+    Divide,
 }
 
-fn ch_alpha_f(base: f32, top: f32, f: BlendFunction, opacity: f32) -> f32 {
+fn ch_blend_f(base: f32, top: f32, f: BlendFunction) -> f32 {
     match f {
-        BlendFunction::Difference => ch_alpha(base, ch_difference(base, top), opacity),
-        BlendFunction::Multiply => ch_alpha(base, ch_multiply(base, top), opacity),
-        BlendFunction::Overlay => ch_alpha(base, ch_overlay(base, top), opacity),
-        BlendFunction::Screen => ch_alpha(base, ch_screen(base, top), opacity),
+        BlendFunction::Difference => ch_difference(base, top),
+        BlendFunction::Multiply => ch_multiply(base, top),
+        BlendFunction::Overlay => ch_overlay(base, top),
+        BlendFunction::Screen => ch_screen(base, top),
+        BlendFunction::Darken => ch_darken(base, top),
+        BlendFunction::Lighten => ch_lighten(base, top),
+        BlendFunction::SoftLight => ch_soft_light(base, top),
+        BlendFunction::HardLight => ch_hard_light(base, top),
+        BlendFunction::ColorDodge => ch_color_dodge(base, top),
+        BlendFunction::ColorBurn => ch_color_burn(base, top),
+        BlendFunction::Exclusion => ch_exclusion(base, top),
+        BlendFunction::Subtract => ch_subtract(base, top),
+        BlendFunction::Add => ch_add(base, top),
+        BlendFunction::Divide => ch_divide(base, top),
     }
 }
 
@@ -294,3 +336,95 @@ fn ch_overlay(base: f32, top: f32) -> f32 {
 fn ch_screen(base: f32, top: f32) -> f32 {
     255.0 - (((255.0 - base) * (255.0 - top)) / 255.0)
 }
+
+fn ch_darken(base: f32, top: f32) -> f32 {
+    base.min(top)
+}
+
+fn ch_lighten(base: f32, top: f32) -> f32 {
+    base.max(top)
+}
+
+// See https://www.w3.org/TR/compositing-1/#blendingsoftlight
+fn ch_soft_light(base: f32, top: f32) -> f32 {
+    let base = base / 255.0;
+    let top = top / 255.0;
+    let result = if top <= 0.5 {
+        base - (1.0 - 2.0 * top) * base * (1.0 - base)
+    } else {
+        let d = if base <= 0.25 {
+            ((16.0 * base - 12.0) * base + 4.0) * base
+        } else {
+            base.sqrt()
+        };
+        base + (2.0 * top - 1.0) * (d - base)
+    };
+    result * 255.0
+}
+
+// Overlay with base and top swapped.
+// See https://www.w3.org/TR/compositing-1/#blendinghardlight
+fn ch_hard_light(base: f32, top: f32) -> f32 {
+    if top <= 127.5 {
+        ch_multiply(base, 2.0 * top)
+    } else {
+        ch_screen(base, 2.0 * top - 255.0)
+    }
+}
+
+// See https://www.w3.org/TR/compositing-1/#blendingcolordodge
+fn ch_color_dodge(base: f32, top: f32) -> f32 {
+    if base == 0.0 {
+        0.0
+    } else if top >= 255.0 {
+        255.0 // Avoid dividing by zero
+    } else {
+        (base * 255.0 / (255.0 - top)).min(255.0)
+    }
+}
+
+// See https://www.w3.org/TR/compositing-1/#blendingcolorburn
+fn ch_color_burn(base: f32, top: f32) -> f32 {
+    if base >= 255.0 {
+        255.0
+    } else if top == 0.0 {
+        0.0 // Avoid dividing by zero
+    } else {
+        255.0 - ((255.0 - base) * 255.0 / top).min(255.0)
+    }
+}
+
+fn ch_exclusion(base: f32, top: f32) -> f32 {
+    base + top - 2.0 * base * top / 255.0
+}
+
+fn ch_subtract(base: f32, top: f32) -> f32 {
+    (base - top).max(0.0)
+}
+
+// Also known as linear dodge.
+fn ch_add(base: f32, top: f32) -> f32 {
+    (base + top).min(255.0)
+}
+
+fn ch_divide(base: f32, top: f32) -> f32 {
+    if top == 0.0 {
+        if base == 0.0 {
+            0.0
+        } else {
+            255.0 // Avoid dividing by zero
+        }
+    } else {
+        (base * 255.0 / top).min(255.0)
+    }
+}
+
+// Non-separable blend modes. These work on all color channels at once.
+// See https://www.w3.org/TR/compositing-1/#blendingnonseparable
+#[derive(Debug, Clone, Copy)]
+enum BlendComponent {
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
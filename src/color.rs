@@ -1,7 +1,6 @@
 //!  A module for handling colors.
 
 // from rust
-use std;
 
 // from external crate
 
@@ -24,7 +23,7 @@ pub struct Color {
     pub a: u8,
 }
 
-impl<'a> Color {
+impl Color {
     /// Returns a black Color.
     pub fn black() -> Color {
         Color {
@@ -194,14 +193,14 @@ impl<'a> Color {
             let mut h = 0.0;
 
             if chroma != 0.0 {
-                if (max - r).abs() < std::f32::EPSILON {
+                if (max - r).abs() < f32::EPSILON {
                     h = 60.0 * ((g - b) / chroma);
                     if h < 0.0 {
                         h += 360.0;
                     }
-                } else if (max - g).abs() < std::f32::EPSILON {
+                } else if (max - g).abs() < f32::EPSILON {
                     h = 60.0 * (((b - r) / chroma) + 2.0);
-                } else if (max - b).abs() < std::f32::EPSILON {
+                } else if (max - b).abs() < f32::EPSILON {
                     h = 60.0 * (((r - g) / chroma) + 4.0);
                 }
             }
@@ -236,8 +235,8 @@ impl<'a> Color {
     // Using f32 for s,v for accuracy when converting from RGB-HSV and vice-versa.
     pub fn to_rgb(h: u16, s: f32, v: f32) -> (u8, u8, u8) {
         let h = h as f32 / 60.0;
-        let s = s as f32 / 100.0; // Convert to 0.0 - 1.0
-        let v = v as f32 / 100.0;
+        let s = s / 100.0; // Convert to 0.0 - 1.0
+        let v = v / 100.0;
 
         let chroma = v * s;
 
@@ -286,6 +285,65 @@ impl<'a> Color {
         )
     }
 
+    /// Convert RGB to HSL (Hue, Saturation, Lightness).
+    ///
+    /// The hue is the same as with `Color::to_hsv`. Lightness is 0.0 for black, 100.0 for white
+    /// and 50.0 for pure colors.
+    ///
+    /// ```
+    /// use raster::Color;
+    ///
+    /// let hsl = Color::to_hsl(50, 50, 100);
+    ///
+    /// assert_eq!(240, hsl.0);
+    /// assert_eq!(33.0, (hsl.1).round()); // Saturation in float
+    /// assert_eq!(29.0, (hsl.2).round()); // Lightness in float
+    /// ```
+    pub fn to_hsl(r: u8, g: u8, b: u8) -> (u16, f32, f32) {
+        let (h, _, _) = Color::to_hsv(r, g, b);
+
+        let r = r as f32 / 255.0;
+        let g = g as f32 / 255.0;
+        let b = b as f32 / 255.0;
+
+        let min = rgb_min(r, g, b);
+        let max = rgb_max(r, g, b);
+
+        let l = (max + min) / 2.0;
+        let s = if max > min {
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        } else {
+            0.0
+        };
+
+        (h, s * 100.0, l * 100.0)
+    }
+
+    /// Convert HSL (Hue, Saturation, Lightness) to RGB.
+    ///
+    /// ```
+    /// use raster::Color;
+    ///
+    /// let rgb1 = (127, 70, 60);
+    /// let hsl = Color::to_hsl(rgb1.0, rgb1.1, rgb1.2); // Convert to HSL
+    /// let rgb2 = Color::from_hsl(hsl.0, hsl.1, hsl.2); // Convert back to RGB
+    ///
+    /// // Check if source RGB is equal to final RGB
+    /// assert_eq!(rgb1.0, rgb2.0);
+    /// assert_eq!(rgb1.1, rgb2.1);
+    /// assert_eq!(rgb1.2, rgb2.2);
+    /// ```
+    pub fn from_hsl(h: u16, s: f32, l: f32) -> (u8, u8, u8) {
+        let s = s / 100.0; // Convert to 0.0 - 1.0
+        let l = l / 100.0;
+
+        // Same color in HSV
+        let v = l + s * l.min(1.0 - l);
+        let sv = if v > 0.0 { 2.0 * (1.0 - l / v) } else { 0.0 };
+
+        Color::to_rgb(h, sv * 100.0, v * 100.0)
+    }
+
     /// Returns a white Color.
     pub fn white() -> Color {
         Color {
@@ -301,9 +359,7 @@ impl<'a> Color {
 
 // Convert a hex string to decimal. Eg. "00" -> 0. "FF" -> 255.
 fn _hex_dec(hex_string: &str) -> RasterResult<u8> {
-    u8::from_str_radix(hex_string, 16)
-        .map(|o| o as u8)
-        .map_err(RasterError::HexParse)
+    u8::from_str_radix(hex_string, 16).map_err(RasterError::HexParse)
 }
 
 fn rgb_min(r: f32, g: f32, b: f32) -> f32 {
@@ -325,3 +381,60 @@ fn rgb_max(r: f32, g: f32, b: f32) -> f32 {
         max
     }
 }
+
+// Helpers of the non-separable blend modes, on RGB channels in 0.0 - 255.0.
+// See https://www.w3.org/TR/compositing-1/#blendingnonseparable
+
+// Luminosity of a color.
+pub(crate) fn lum(c: (f32, f32, f32)) -> f32 {
+    0.3 * c.0 + 0.59 * c.1 + 0.11 * c.2
+}
+
+// Bring the channels back in range while keeping the luminosity.
+pub(crate) fn clip_color(c: (f32, f32, f32)) -> (f32, f32, f32) {
+    let l = lum(c);
+    let n = c.0.min(c.1).min(c.2);
+    let x = c.0.max(c.1).max(c.2);
+    let clip = |ch: f32| {
+        let mut ch = ch;
+        if n < 0.0 {
+            ch = l + (ch - l) * l / (l - n);
+        }
+        if x > 255.0 {
+            ch = l + (ch - l) * (255.0 - l) / (x - l);
+        }
+        ch
+    };
+    (clip(c.0), clip(c.1), clip(c.2))
+}
+
+// Shift a color to the luminosity l.
+pub(crate) fn set_lum(c: (f32, f32, f32), l: f32) -> (f32, f32, f32) {
+    let d = l - lum(c);
+    clip_color((c.0 + d, c.1 + d, c.2 + d))
+}
+
+// Saturation of a color, the range of its channels.
+pub(crate) fn sat(c: (f32, f32, f32)) -> f32 {
+    c.0.max(c.1).max(c.2) - c.0.min(c.1).min(c.2)
+}
+
+// Stretch a color to the saturation s, keeping the order of its channels.
+pub(crate) fn set_sat(c: (f32, f32, f32), s: f32) -> (f32, f32, f32) {
+    let mut channels = [c.0, c.1, c.2];
+    // Indices of the channels sorted from min to max
+    let mut order = [0, 1, 2];
+    order.sort_by(|&i, &j| channels[i].total_cmp(&channels[j]));
+    let (min, mid, max) = (order[0], order[1], order[2]);
+
+    if channels[max] > channels[min] {
+        channels[mid] = (channels[mid] - channels[min]) * s / (channels[max] - channels[min]);
+        channels[max] = s;
+    } else {
+        channels[mid] = 0.0;
+        channels[max] = 0.0;
+    }
+    channels[min] = 0.0;
+
+    (channels[0], channels[1], channels[2])
+}
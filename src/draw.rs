@@ -0,0 +1,1076 @@
+//!  A module for drawing shapes on images.
+
+// from rust
+use std::cmp::Ordering;
+
+// from external crate
+#[cfg(feature = "text")]
+use ab_glyph::{point, Font as GlyphFont, ScaleFont};
+
+// from local crate
+use error::{RasterError, RasterResult};
+use Color;
+use Image;
+#[cfg(feature = "text")]
+use text::Font;
+
+/// A point on an image, in pixels. Pixel centers are at whole coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    /// Horizontal position, from the left.
+    pub x: f32,
+
+    /// Vertical position, from the top.
+    pub y: f32,
+}
+
+impl Point {
+    /// Create a point.
+    ///
+    /// # Examples
+    /// ```
+    /// use raster::Point;
+    ///
+    /// let point = Point::new(10.0, 20.5);
+    /// assert_eq!(20.5, point.y);
+    /// ```
+    pub fn new(x: f32, y: f32) -> Point {
+        Point { x, y }
+    }
+}
+
+/// A rectangle on an image, in whole pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    /// Left edge.
+    pub x: i32,
+
+    /// Top edge.
+    pub y: i32,
+
+    /// Width in pixels.
+    pub width: i32,
+
+    /// Height in pixels.
+    pub height: i32,
+}
+
+impl Rect {
+    /// Create a rectangle from its top left corner and its size.
+    ///
+    /// # Examples
+    /// ```
+    /// use raster::Rect;
+    ///
+    /// let rect = Rect::new(10, 20, 100, 50);
+    /// assert_eq!(50, rect.height);
+    /// ```
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// An enum for the ways shapes can be rasterized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawMode {
+    /// Pixels are either painted or not. Keeps exact colors, eg. for masks.
+    Hard,
+    /// Pixels on the edges are partially painted by how much the shape covers them.
+    Antialiased,
+}
+
+/// Fill the area around a pixel that has a color close to its color, like a paint bucket.
+///
+/// Pixels are part of the area when each of their channels, alpha included, differs from the
+/// seed pixel by at most tolerance, and they touch the area on a side. They are replaced by the
+/// fill color without blending. Drawing the area in white on a black copy of the image gives a
+/// mask.
+///
+/// # Errors
+///
+/// If the seed pixel is outside of the image, this fails with `RasterError::PixelOutOfBounds`.
+///
+/// # Examples
+/// ```
+/// use raster::{draw, Color};
+///
+/// let mut image = raster::open("tests/in/sample.png").unwrap();
+/// draw::flood_fill(&mut image, 10, 10, &Color::red(), 30).unwrap();
+/// raster::save(&image, "tests/out/test_draw_flood_fill.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_draw_flood_fill.png)
+///
+pub fn flood_fill(
+    src: &mut Image,
+    x: i32,
+    y: i32,
+    color: &Color,
+    tolerance: u8,
+) -> RasterResult<()> {
+    if x < 0 || y < 0 || x >= src.width || y >= src.height {
+        return Err(RasterError::PixelOutOfBounds(x, y));
+    }
+
+    let (w, h) = (src.width as usize, src.height as usize);
+    let seed_index = (y as usize * w + x as usize) * 4;
+    let mut seed = [0u8; 4];
+    seed.copy_from_slice(&src.bytes[seed_index..seed_index + 4]);
+    let fill = [color.r, color.g, color.b, color.a];
+
+    // Pixels already filled are marked, so that the fill color never stops or extends the area.
+    let mut filled = vec![false; w * h];
+    let matches = |bytes: &[u8], filled: &[bool], i: usize| {
+        !filled[i]
+            && bytes[i * 4..i * 4 + 4]
+                .iter()
+                .zip(seed.iter())
+                .all(|(&a, &b)| (a as i16 - b as i16).abs() <= tolerance as i16)
+    };
+
+    // Fill a whole run of the row of each queued pixel, then queue the runs above and below it.
+    let mut queue = vec![(x as usize, y as usize)];
+    while let Some((x, y)) = queue.pop() {
+        let row = y * w;
+        if !matches(&src.bytes, &filled, row + x) {
+            continue;
+        }
+        let mut left = x;
+        while left > 0 && matches(&src.bytes, &filled, row + left - 1) {
+            left -= 1;
+        }
+        let mut right = x;
+        while right + 1 < w && matches(&src.bytes, &filled, row + right + 1) {
+            right += 1;
+        }
+        for mark in &mut filled[row + left..=row + right] {
+            *mark = true;
+        }
+        for pixel in src.bytes[(row + left) * 4..(row + right + 1) * 4].chunks_mut(4) {
+            pixel.copy_from_slice(&fill);
+        }
+
+        for next in [y.wrapping_sub(1), y + 1].iter().filter(|&&next| next < h) {
+            let next_row = next * w;
+            let mut in_run = false;
+            for nx in left..=right {
+                let inside = matches(&src.bytes, &filled, next_row + nx);
+                if inside && !in_run {
+                    queue.push((nx, *next));
+                }
+                in_run = inside;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Draw a straight line between two points.
+///
+/// Lines of thickness 1.0 use Bresenham's algorithm, or Wu's algorithm when antialiased. Thicker
+/// lines have round ends. The color is blended over the image and parts outside of the image are
+/// clipped.
+///
+/// # Errors
+///
+/// If thickness is zero or negative, this fails with `RasterError::InvalidThickness`.
+///
+/// # Examples
+/// ```
+/// use raster::{draw, Color, DrawMode, Point};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let (start, end) = (Point::new(10.0, 10.0), Point::new(200.0, 120.0));
+/// draw::line(&mut image, start, end, &Color::red(), 1.0, DrawMode::Hard).unwrap();
+/// let (start, end) = (Point::new(10.0, 30.0), Point::new(200.0, 140.0));
+/// draw::line(&mut image, start, end, &Color::red(), 5.0, DrawMode::Antialiased).unwrap();
+/// raster::save(&image, "tests/out/test_draw_line.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_draw_line.png)
+///
+pub fn line(
+    src: &mut Image,
+    p1: Point,
+    p2: Point,
+    color: &Color,
+    thickness: f32,
+    mode: DrawMode,
+) -> RasterResult<()> {
+    if thickness.is_nan() || thickness <= 0.0 {
+        return Err(RasterError::InvalidThickness(thickness));
+    }
+
+    // Only the part near the image is drawn, the cut ends are far enough to not show.
+    let (p1, p2) = match _clip_line(src, p1, p2, thickness / 2.0 + 2.0) {
+        Some(clipped) => clipped,
+        None => return Ok(()),
+    };
+
+    if thickness == 1.0 {
+        match mode {
+            DrawMode::Hard => _line_bresenham(src, p1, p2, color),
+            DrawMode::Antialiased => _line_wu(src, p1, p2, color),
+        }
+    } else {
+        _stroke(src, &[(p1, p2)], color, thickness, mode);
+    }
+    Ok(())
+}
+
+/// Draw an arc of a circle.
+///
+/// Angles are in degrees, clockwise from the right of the center, and the arc goes clockwise from
+/// start_angle to end_angle. The arc is stroked with antialiasing and round ends. The color is
+/// blended over the image and parts outside of the image are clipped.
+///
+/// # Errors
+///
+/// If radius is zero or negative, this fails with `RasterError::InvalidShapeRadius`.
+///
+/// If thickness is zero or negative, this fails with `RasterError::InvalidThickness`.
+///
+/// # Examples
+/// ```
+/// use raster::{draw, Color, Image, Point};
+///
+/// // A gauge at 70%
+/// let mut image = Image::blank(120, 120);
+/// let center = Point::new(60.0, 60.0);
+/// draw::arc(&mut image, center, 50.0, 135.0, 405.0, &Color::rgb(60, 60, 60), 10.0).unwrap();
+/// draw::arc(&mut image, center, 50.0, 135.0, 324.0, &Color::green(), 10.0).unwrap();
+/// raster::save(&image, "tests/out/test_draw_arc.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_draw_arc.png)
+///
+pub fn arc(
+    src: &mut Image,
+    center: Point,
+    radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+    color: &Color,
+    thickness: f32,
+) -> RasterResult<()> {
+    if radius.is_nan() || radius <= 0.0 {
+        return Err(RasterError::InvalidShapeRadius(radius));
+    }
+    if thickness.is_nan() || thickness <= 0.0 {
+        return Err(RasterError::InvalidThickness(thickness));
+    }
+
+    let points = _arc_points(center, radius, start_angle, end_angle);
+    let segments: Vec<(Point, Point)> = points.windows(2).map(|pair| (pair[0], pair[1])).collect();
+    _stroke(src, &segments, color, thickness, DrawMode::Antialiased);
+    Ok(())
+}
+
+/// Draw a pie slice of a circle with an optional stroke and an optional fill.
+///
+/// Angles are the same as in `draw::arc`. The slice is drawn like `draw::polygon` with the
+/// center and the points of the arc. A sweep of 360 degrees or more draws a whole disc.
+///
+/// # Errors
+///
+/// If radius is zero or negative, this fails with `RasterError::InvalidShapeRadius`.
+///
+/// If the stroke width is zero or negative, this fails with `RasterError::InvalidThickness`.
+///
+/// # Examples
+/// ```
+/// use raster::{draw, Color, DrawMode, Image, Point};
+///
+/// // A pie chart of 50%, 30% and 20%
+/// let mut image = Image::blank(120, 120);
+/// let center = Point::new(60.0, 60.0);
+/// let slices = [
+///     (-90.0, 90.0, Color::red()),
+///     (90.0, 198.0, Color::green()),
+///     (198.0, 270.0, Color::blue()),
+/// ];
+/// for (start, end, color) in slices.iter().cloned() {
+///     let stroke = Some((Color::white(), 2.0));
+///     draw::pie(&mut image, center, 50.0, start, end, stroke, Some(color), DrawMode::Antialiased)
+///         .unwrap();
+/// }
+/// raster::save(&image, "tests/out/test_draw_pie.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_draw_pie.png)
+///
+#[allow(clippy::too_many_arguments)]
+pub fn pie(
+    src: &mut Image,
+    center: Point,
+    radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+    stroke: Option<(Color, f32)>,
+    fill: Option<Color>,
+    mode: DrawMode,
+) -> RasterResult<()> {
+    if radius.is_nan() || radius <= 0.0 {
+        return Err(RasterError::InvalidShapeRadius(radius));
+    }
+
+    let mut points = _arc_points(center, radius, start_angle, end_angle);
+    if end_angle - start_angle < 360.0 {
+        points.push(center);
+    } else {
+        points.pop(); // Same as the first point
+    }
+    polygon_with_mode(src, &points, stroke, fill, mode)
+}
+
+/// Draw a quadratic or cubic Bézier curve.
+///
+/// Pass 3 control points for a quadratic curve or 4 for a cubic curve. The curve is split until
+/// its pieces are flat to a fraction of a pixel, then stroked with antialiasing and round ends.
+/// The color is blended over the image and parts outside of the image are clipped.
+///
+/// # Errors
+///
+/// If there are not 3 or 4 control points, this fails with `RasterError::InvalidCurve`.
+///
+/// If thickness is zero or negative, this fails with `RasterError::InvalidThickness`.
+///
+/// # Examples
+/// ```
+/// use raster::{draw, Color, Point};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let points = [
+///     Point::new(20.0, 150.0),
+///     Point::new(60.0, 10.0),
+///     Point::new(140.0, 200.0),
+///     Point::new(200.0, 40.0),
+/// ];
+/// draw::bezier(&mut image, &points, &Color::red(), 3.0).unwrap();
+/// raster::save(&image, "tests/out/test_draw_bezier.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_draw_bezier.png)
+///
+pub fn bezier(
+    src: &mut Image,
+    control_points: &[Point],
+    color: &Color,
+    thickness: f32,
+) -> RasterResult<()> {
+    if thickness.is_nan() || thickness <= 0.0 {
+        return Err(RasterError::InvalidThickness(thickness));
+    }
+    // Raise quadratic curves to cubic ones, which trace the same curve.
+    let cubic = match *control_points {
+        [p0, p1, p2] => [
+            p0,
+            Point::new(p0.x + (p1.x - p0.x) * 2.0 / 3.0, p0.y + (p1.y - p0.y) * 2.0 / 3.0),
+            Point::new(p2.x + (p1.x - p2.x) * 2.0 / 3.0, p2.y + (p1.y - p2.y) * 2.0 / 3.0),
+            p2,
+        ],
+        [p0, p1, p2, p3] => [p0, p1, p2, p3],
+        _ => return Err(RasterError::InvalidCurve),
+    };
+
+    let mut points = vec![cubic[0]];
+    _flatten_cubic(cubic, 0, &mut points);
+    let segments: Vec<(Point, Point)> = points.windows(2).map(|pair| (pair[0], pair[1])).collect();
+    _stroke(src, &segments, color, thickness, DrawMode::Antialiased);
+    Ok(())
+}
+
+/// Draw a circle with an optional stroke and an optional fill.
+///
+/// Same as `draw::ellipse` with both radii equal.
+///
+/// # Errors
+///
+/// If radius is zero or negative, this fails with `RasterError::InvalidShapeRadius`.
+///
+/// If the stroke width is zero or negative, this fails with `RasterError::InvalidThickness`.
+///
+/// # Examples
+/// ```
+/// use raster::{draw, Color, DrawMode, Image, Point};
+///
+/// let mut image = Image::blank(100, 100);
+/// let stroke = Some((Color::white(), 4.0));
+/// let fill = Some(Color::hex("#3f7fbf").unwrap());
+/// draw::circle(&mut image, Point::new(50.0, 50.0), 40.0, stroke, fill, DrawMode::Antialiased)
+///     .unwrap();
+/// raster::save(&image, "tests/out/test_draw_circle.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_draw_circle.png)
+///
+pub fn circle(
+    src: &mut Image,
+    center: Point,
+    radius: f32,
+    stroke: Option<(Color, f32)>,
+    fill: Option<Color>,
+    mode: DrawMode,
+) -> RasterResult<()> {
+    ellipse(src, center, radius, radius, stroke, fill, mode)
+}
+
+/// Draw an axis aligned ellipse with an optional stroke and an optional fill.
+///
+/// The stroke is a color and a width in pixels, centered on the outline. The fill is drawn first,
+/// under the stroke. Colors are blended over the image and parts outside of the image are
+/// clipped.
+///
+/// # Errors
+///
+/// If a radius is zero or negative, this fails with `RasterError::InvalidShapeRadius`.
+///
+/// If the stroke width is zero or negative, this fails with `RasterError::InvalidThickness`.
+///
+/// # Examples
+/// ```
+/// use raster::{draw, Color, DrawMode, Point};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let center = Point::new(120.0, 80.0);
+/// let stroke = Some((Color::red(), 3.0));
+/// draw::ellipse(&mut image, center, 60.0, 30.0, stroke, None, DrawMode::Antialiased).unwrap();
+/// raster::save(&image, "tests/out/test_draw_ellipse.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_draw_ellipse.png)
+///
+pub fn ellipse(
+    src: &mut Image,
+    center: Point,
+    rx: f32,
+    ry: f32,
+    stroke: Option<(Color, f32)>,
+    fill: Option<Color>,
+    mode: DrawMode,
+) -> RasterResult<()> {
+    for &radius in &[rx, ry] {
+        if radius.is_nan() || radius <= 0.0 {
+            return Err(RasterError::InvalidShapeRadius(radius));
+        }
+    }
+    let half = match stroke {
+        Some((_, width)) if width.is_nan() || width <= 0.0 => {
+            return Err(RasterError::InvalidThickness(width));
+        }
+        Some((_, width)) => width / 2.0,
+        None => 0.0,
+    };
+
+    let x_min = (center.x - rx - half - 1.0).floor().max(0.0) as i32;
+    let y_min = (center.y - ry - half - 1.0).floor().max(0.0) as i32;
+    let x_max = ((center.x + rx + half + 1.0).ceil() as i32).min(src.width - 1);
+    let y_max = ((center.y + ry + half + 1.0).ceil() as i32).min(src.height - 1);
+
+    for y in y_min..=y_max {
+        for x in x_min..=x_max {
+            let (dx, dy) = (x as f32 - center.x, y as f32 - center.y);
+            // Distance to the outline from the implicit equation divided by its gradient.
+            let f = (dx / rx).powi(2) + (dy / ry).powi(2) - 1.0;
+            let gradient = (2.0 * dx / (rx * rx)).hypot(2.0 * dy / (ry * ry));
+            let distance = if gradient > 0.0 {
+                f / gradient
+            } else {
+                -rx.min(ry)
+            };
+
+            if let Some(ref color) = fill {
+                _plot(src, x, y, color, _coverage(-distance, mode));
+            }
+            if let Some((ref color, _)) = stroke {
+                _plot(src, x, y, color, _coverage(half - distance.abs(), mode));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Draw a closed polygon with an optional stroke and an optional fill.
+///
+/// The fill uses the even-odd rule, so the inner pentagon of a five-pointed star drawn through
+/// its points is left empty. The stroke is a color and a width in pixels, centered on the edges,
+/// with round joins. The fill is drawn first, under the stroke. Colors are blended over the image
+/// and parts outside of the image are clipped. Nothing is filled with less than 3 points.
+///
+/// # Errors
+///
+/// If the stroke width is zero or negative, this fails with `RasterError::InvalidThickness`.
+///
+/// # Examples
+/// ```
+/// use raster::{draw, Color, Point};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let arrow = [
+///     Point::new(20.0, 60.0),
+///     Point::new(120.0, 60.0),
+///     Point::new(120.0, 30.0),
+///     Point::new(180.0, 80.0),
+///     Point::new(120.0, 130.0),
+///     Point::new(120.0, 100.0),
+///     Point::new(20.0, 100.0),
+/// ];
+/// let stroke = Some((Color::white(), 2.0));
+/// draw::polygon(&mut image, &arrow, stroke, Some(Color::red())).unwrap();
+/// raster::save(&image, "tests/out/test_draw_polygon.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_draw_polygon.png)
+///
+pub fn polygon(
+    src: &mut Image,
+    points: &[Point],
+    stroke: Option<(Color, f32)>,
+    fill: Option<Color>,
+) -> RasterResult<()> {
+    polygon_with_mode(src, points, stroke, fill, DrawMode::Hard)
+}
+
+/// Draw a closed polygon like `polygon`, choosing how the edges are drawn.
+///
+/// With `DrawMode::Antialiased`, the pixels on the edges of the fill are covered by the share of
+/// their area inside of the polygon, and the stroke is antialiased like a line.
+///
+/// # Errors
+///
+/// If the stroke width is zero or negative, this fails with `RasterError::InvalidThickness`.
+///
+/// # Examples
+/// ```
+/// use raster::{draw, Color, DrawMode, Image, Point};
+///
+/// let mut image = Image::blank(100, 100);
+/// let triangle = [Point::new(10.0, 90.0), Point::new(50.0, 10.0), Point::new(90.0, 90.0)];
+/// let fill = Some(Color::red());
+/// draw::polygon_with_mode(&mut image, &triangle, None, fill, DrawMode::Antialiased).unwrap();
+/// ```
+pub fn polygon_with_mode(
+    src: &mut Image,
+    points: &[Point],
+    stroke: Option<(Color, f32)>,
+    fill: Option<Color>,
+    mode: DrawMode,
+) -> RasterResult<()> {
+    if let Some((_, width)) = stroke {
+        if width.is_nan() || width <= 0.0 {
+            return Err(RasterError::InvalidThickness(width));
+        }
+    }
+    if points.is_empty() {
+        return Ok(());
+    }
+
+    let edges: Vec<(Point, Point)> = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(&a, &b)| (a, b))
+        .collect();
+
+    if let (Some(color), true) = (fill, points.len() >= 3) {
+        _fill_polygon(src, &edges, &color, mode);
+    }
+    if let Some((color, width)) = stroke {
+        _stroke(src, &edges, &color, width, mode);
+    }
+    Ok(())
+}
+
+/// Draw a rectangle with an optional stroke and an optional fill.
+///
+/// The stroke is a color and a width in pixels, drawn inside of the rectangle so that it stays
+/// within the given bounds. The fill covers the whole rectangle and is drawn first, under the
+/// stroke. Colors are blended over the image and parts outside of the image are clipped.
+///
+/// # Errors
+///
+/// If the rectangle has a negative width or height, this fails with
+/// `RasterError::InvalidDimensions`.
+///
+/// If the stroke width is zero or negative, this fails with `RasterError::InvalidThickness`.
+///
+/// # Examples
+/// ```
+/// use raster::{draw, Color, Rect};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let stroke = Some((Color::red(), 3));
+/// let fill = Some(Color::rgba(255, 0, 0, 64));
+/// draw::rect(&mut image, Rect::new(40, 30, 120, 80), stroke, fill).unwrap();
+/// raster::save(&image, "tests/out/test_draw_rect.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_draw_rect.png)
+///
+pub fn rect(
+    src: &mut Image,
+    rect: Rect,
+    stroke: Option<(Color, i32)>,
+    fill: Option<Color>,
+) -> RasterResult<()> {
+    if rect.width < 0 || rect.height < 0 {
+        return Err(RasterError::InvalidDimensions(rect.width, rect.height));
+    }
+    if let Some((_, width)) = stroke {
+        if width <= 0 {
+            return Err(RasterError::InvalidThickness(width as f32));
+        }
+    }
+
+    let (x1, y1) = (rect.x + rect.width, rect.y + rect.height);
+    if let Some(color) = fill {
+        _fill_rect(src, rect.x, rect.y, x1, y1, &color);
+    }
+    if let Some((color, width)) = stroke {
+        if width * 2 >= rect.width || width * 2 >= rect.height {
+            _fill_rect(src, rect.x, rect.y, x1, y1, &color);
+        } else {
+            // Top and bottom bands, then the left and right sides between them.
+            _fill_rect(src, rect.x, rect.y, x1, rect.y + width, &color);
+            _fill_rect(src, rect.x, y1 - width, x1, y1, &color);
+            _fill_rect(src, rect.x, rect.y + width, rect.x + width, y1 - width, &color);
+            _fill_rect(src, x1 - width, rect.y + width, x1, y1 - width, &color);
+        }
+    }
+    Ok(())
+}
+
+/// Draw text with a font. Needs the `text` feature.
+///
+/// The size is the em size in pixels, like font sizes in CSS. The position is the start of the
+/// baseline of the first line, so letters sit on it and descenders go below it. Pairs of letters
+/// are kerned when the font has kerning, and each newline starts a new line. The color is blended
+/// over the image with antialiasing and parts outside of the image are clipped.
+///
+/// # Errors
+///
+/// If size is zero or negative, this fails with `RasterError::InvalidFontSize`.
+///
+/// # Examples
+/// ```
+/// use raster::{draw, Color, Font, Point};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let font = Font::open("tests/in/DejaVuSans.ttf").unwrap();
+/// let position = Point::new(20.0, 60.0);
+/// draw::text(&mut image, "Hello, World!", &font, 32.0, position, &Color::white()).unwrap();
+/// raster::save(&image, "tests/out/test_draw_text.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_draw_text.png)
+///
+#[cfg(feature = "text")]
+pub fn text(
+    src: &mut Image,
+    text: &str,
+    font: &Font,
+    size: f32,
+    position: Point,
+    color: &Color,
+) -> RasterResult<()> {
+    if size.is_nan() || size <= 0.0 {
+        return Err(RasterError::InvalidFontSize(size));
+    }
+
+    let scaled = font.font.as_scaled(font.scale(size));
+    let line_height = font.line_height(size);
+    let (mut x, mut y) = (position.x, position.y);
+    let mut previous = None;
+    for c in text.chars() {
+        if c == '\n' {
+            x = position.x;
+            y += line_height;
+            previous = None;
+            continue;
+        }
+
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            x += scaled.kern(previous, id);
+        }
+        previous = Some(id);
+
+        // Outline positions are at the top left of pixels, pixel centers are half a pixel further.
+        let glyph = id.with_scale_and_position(scaled.scale(), point(x + 0.5, y + 0.5));
+        x += scaled.h_advance(id);
+        if let Some(outlined) = scaled.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                _plot(src, px, py, color, coverage);
+            });
+        }
+    }
+    Ok(())
+}
+
+// Private functions
+
+// Blend a color over a pixel with source-over, the coverage scaling the alpha of the color.
+// Pixels outside of the image are ignored.
+fn _plot(src: &mut Image, x: i32, y: i32, color: &Color, coverage: f32) {
+    if x < 0 || y < 0 || x >= src.width || y >= src.height {
+        return;
+    }
+    let a2 = color.a as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+    if a2 <= 0.0 {
+        return;
+    }
+
+    let i = ((y * src.width + x) * 4) as usize;
+    let pixel = &mut src.bytes[i..i + 4];
+    let a1 = pixel[3] as f32 / 255.0;
+    let a = a2 + a1 * (1.0 - a2);
+    let mix = |c1: u8, c2: u8| -> u8 {
+        ((c2 as f32 * a2 + c1 as f32 * a1 * (1.0 - a2)) / a).round().clamp(0.0, 255.0) as u8
+    };
+    pixel[0] = mix(pixel[0], color.r);
+    pixel[1] = mix(pixel[1], color.g);
+    pixel[2] = mix(pixel[2], color.b);
+    pixel[3] = (a * 255.0).round() as u8;
+}
+
+// Blend a color over the pixels from (x0, y0) included to (x1, y1) excluded.
+fn _fill_rect(src: &mut Image, x0: i32, y0: i32, x1: i32, y1: i32, color: &Color) {
+    for y in y0.max(0)..y1.min(src.height) {
+        for x in x0.max(0)..x1.min(src.width) {
+            _plot(src, x, y, color, 1.0);
+        }
+    }
+}
+
+// Fill a polygon with the even-odd rule. Hard fills the pixels whose center is inside, with one
+// scanline through the centers of each row. Antialiased covers each pixel by the share of its area
+// inside, summed from sub-scanlines spread over its height.
+fn _fill_polygon(src: &mut Image, edges: &[(Point, Point)], color: &Color, mode: DrawMode) {
+    let crossings_at = |scan: f32, crossings: &mut Vec<f32>| {
+        crossings.clear();
+        for &(a, b) in edges {
+            // Half open so that a vertex shared by two edges is only counted once.
+            if (a.y <= scan) != (b.y <= scan) {
+                crossings.push(a.x + (scan - a.y) * (b.x - a.x) / (b.y - a.y));
+            }
+        }
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    };
+    let y_min = edges.iter().fold(f32::MAX, |m, e| m.min(e.0.y));
+    let y_max = edges.iter().fold(f32::MIN, |m, e| m.max(e.0.y));
+    let mut crossings: Vec<f32> = Vec::new();
+
+    match mode {
+        DrawMode::Hard => {
+            let y_min = y_min.ceil().max(0.0) as i32;
+            let y_max = (y_max.floor() as i32).min(src.height - 1);
+            for y in y_min..=y_max {
+                crossings_at(y as f32, &mut crossings);
+                for pair in crossings.chunks(2) {
+                    if pair.len() == 2 {
+                        let x0 = pair[0].ceil().max(0.0) as i32;
+                        let x1 = (pair[1].ceil() as i32).min(src.width);
+                        for x in x0..x1 {
+                            _plot(src, x, y, color, 1.0);
+                        }
+                    }
+                }
+            }
+        }
+        DrawMode::Antialiased => {
+            const SUBSAMPLES: usize = 16;
+            let y_min = (y_min + 0.5).floor().max(0.0) as i32;
+            let y_max = ((y_max + 0.5).floor() as i32).min(src.height - 1);
+            let mut coverage = vec![0.0f32; src.width.max(0) as usize];
+            for y in y_min..=y_max {
+                for value in coverage.iter_mut() {
+                    *value = 0.0;
+                }
+                for sub in 0..SUBSAMPLES {
+                    let scan = y as f32 - 0.5 + (sub as f32 + 0.5) / SUBSAMPLES as f32;
+                    crossings_at(scan, &mut crossings);
+                    for pair in crossings.chunks(2) {
+                        if pair.len() != 2 {
+                            continue;
+                        }
+                        // Pixel x spans from x - 0.5 to x + 0.5.
+                        let (x0, x1) = (pair[0].max(-0.5), pair[1].min(src.width as f32 - 0.5));
+                        if x0 >= x1 {
+                            continue;
+                        }
+                        let first = (x0 + 0.5).floor() as i32;
+                        let last = ((x1 + 0.5).ceil() as i32 - 1).min(src.width - 1);
+                        for x in first.max(0)..=last {
+                            let overlap = x1.min(x as f32 + 0.5) - x0.max(x as f32 - 0.5);
+                            coverage[x as usize] += overlap.max(0.0) / SUBSAMPLES as f32;
+                        }
+                    }
+                }
+                for (x, &value) in coverage.iter().enumerate() {
+                    if value > 0.0 {
+                        _plot(src, x as i32, y, color, value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// One pixel wide line with Bresenham's algorithm.
+fn _line_bresenham(src: &mut Image, p1: Point, p2: Point, color: &Color) {
+    let (mut x, mut y) = (p1.x.round() as i32, p1.y.round() as i32);
+    let (x2, y2) = (p2.x.round() as i32, p2.y.round() as i32);
+    let dx = (x2 - x).abs();
+    let dy = -(y2 - y).abs();
+    let sx = if x < x2 { 1 } else { -1 };
+    let sy = if y < y2 { 1 } else { -1 };
+    let mut err = dx + dy;
+
+    loop {
+        _plot(src, x, y, color, 1.0);
+        if x == x2 && y == y2 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+// One pixel wide antialiased line with Xiaolin Wu's algorithm.
+// See https://en.wikipedia.org/wiki/Xiaolin_Wu%27s_line_algorithm
+fn _line_wu(src: &mut Image, p1: Point, p2: Point, color: &Color) {
+    let (mut x0, mut y0, mut x1, mut y1) = (p1.x, p1.y, p2.x, p2.y);
+    let steep = (y1 - y0).abs() > (x1 - x0).abs();
+    if steep {
+        std::mem::swap(&mut x0, &mut y0);
+        std::mem::swap(&mut x1, &mut y1);
+    }
+    if x0 > x1 {
+        std::mem::swap(&mut x0, &mut x1);
+        std::mem::swap(&mut y0, &mut y1);
+    }
+    let dx = x1 - x0;
+    let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+
+    // Plot along the major axis, swapping back the coordinates of steep lines.
+    let plot = |src: &mut Image, major: i32, minor: i32, coverage: f32| {
+        if steep {
+            _plot(src, minor, major, color, coverage);
+        } else {
+            _plot(src, major, minor, color, coverage);
+        }
+    };
+    let fract = |v: f32| v - v.floor();
+
+    // The end points are weighted by how much of their pixel the line covers.
+    let x_start = x0.round();
+    let y_start = y0 + gradient * (x_start - x0);
+    let gap = 1.0 - fract(x0 + 0.5);
+    plot(src, x_start as i32, y_start.floor() as i32, (1.0 - fract(y_start)) * gap);
+    plot(src, x_start as i32, y_start.floor() as i32 + 1, fract(y_start) * gap);
+
+    let x_end = x1.round();
+    let y_end = y1 + gradient * (x_end - x1);
+    let gap = fract(x1 + 0.5);
+    plot(src, x_end as i32, y_end.floor() as i32, (1.0 - fract(y_end)) * gap);
+    plot(src, x_end as i32, y_end.floor() as i32 + 1, fract(y_end) * gap);
+
+    let mut y = y_start + gradient;
+    for x in x_start as i32 + 1..x_end as i32 {
+        plot(src, x, y.floor() as i32, 1.0 - fract(y));
+        plot(src, x, y.floor() as i32 + 1, fract(y));
+        y += gradient;
+    }
+}
+
+// Cut a segment to the image grown by margin on all sides, with the Liang-Barsky algorithm.
+// Returns None if no part of the segment is left or a point is not finite.
+fn _clip_line(src: &Image, p1: Point, p2: Point, margin: f32) -> Option<(Point, Point)> {
+    let (x1, y1, x2, y2) = (p1.x as f64, p1.y as f64, p2.x as f64, p2.y as f64);
+    if ![x1, y1, x2, y2].iter().all(|v| v.is_finite()) {
+        return None;
+    }
+
+    let margin = margin as f64;
+    let (x_min, y_min) = (-margin, -margin);
+    let (x_max, y_max) = (src.width as f64 - 1.0 + margin, src.height as f64 - 1.0 + margin);
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    // Share of the segment where it enters and leaves the area, with the edge it crosses there.
+    let (mut t0, mut t1) = ((0.0f64, None), (1.0f64, None));
+    let edges = [
+        (-dx, x1 - x_min, (true, x_min)),
+        (dx, x_max - x1, (true, x_max)),
+        (-dy, y1 - y_min, (false, y_min)),
+        (dy, y_max - y1, (false, y_max)),
+    ];
+    for &(p, q, edge) in &edges {
+        if p == 0.0 {
+            // Parallel to this edge, and outside of it
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 && q / p > t0.0 {
+            t0 = (q / p, Some(edge));
+        } else if p > 0.0 && q / p < t1.0 {
+            t1 = (q / p, Some(edge));
+        }
+    }
+    if t0.0 > t1.0 {
+        return None;
+    }
+
+    // Measure from the closer end and put cut ends right on their edge, so that huge
+    // coordinates do not cancel out into a point far away from the line.
+    let at = |(t, edge): (f64, Option<(bool, f64)>)| {
+        let (mut x, mut y) = if t < 0.5 {
+            (x1 + dx * t, y1 + dy * t)
+        } else {
+            (x2 - dx * (1.0 - t), y2 - dy * (1.0 - t))
+        };
+        match edge {
+            Some((true, bound)) => x = bound,
+            Some((false, bound)) => y = bound,
+            None => (),
+        }
+        Point::new(x.clamp(x_min, x_max) as f32, y.clamp(y_min, y_max) as f32)
+    };
+    Some((at(t0), at(t1)))
+}
+
+// Stroke connected segments with round ends and joins. Each pixel is painted once from its
+// closest segment, so that joins of translucent strokes are not painted twice.
+fn _stroke(
+    src: &mut Image,
+    segments: &[(Point, Point)],
+    color: &Color,
+    thickness: f32,
+    mode: DrawMode,
+) {
+    let half = thickness / 2.0;
+    let reach = half + 1.0;
+    let x_min = segments.iter().fold(f32::MAX, |m, s| m.min(s.0.x).min(s.1.x));
+    let y_min = segments.iter().fold(f32::MAX, |m, s| m.min(s.0.y).min(s.1.y));
+    let x_min = (x_min - reach).floor().max(0.0) as i32;
+    let y_min = (y_min - reach).floor().max(0.0) as i32;
+    let x_max = segments.iter().fold(f32::MIN, |m, s| m.max(s.0.x).max(s.1.x));
+    let y_max = segments.iter().fold(f32::MIN, |m, s| m.max(s.0.y).max(s.1.y));
+    let x_max = ((x_max + reach).ceil() as i32).min(src.width - 1);
+    let y_max = ((y_max + reach).ceil() as i32).min(src.height - 1);
+    if x_min > x_max || y_min > y_max {
+        return;
+    }
+
+    // Closest distance of each pixel of the bounding box, only looking near each segment.
+    let w = (x_max - x_min + 1) as usize;
+    let mut distances = vec![f32::MAX; w * (y_max - y_min + 1) as usize];
+    for &(a, b) in segments {
+        let sx0 = ((a.x.min(b.x) - reach).floor() as i32).max(x_min);
+        let sy0 = ((a.y.min(b.y) - reach).floor() as i32).max(y_min);
+        let sx1 = ((a.x.max(b.x) + reach).ceil() as i32).min(x_max);
+        let sy1 = ((a.y.max(b.y) + reach).ceil() as i32).min(y_max);
+        for y in sy0..=sy1 {
+            for x in sx0..=sx1 {
+                let i = (y - y_min) as usize * w + (x - x_min) as usize;
+                let distance = _segment_distance(Point::new(x as f32, y as f32), a, b);
+                if distance < distances[i] {
+                    distances[i] = distance;
+                }
+            }
+        }
+    }
+
+    for y in y_min..=y_max {
+        for x in x_min..=x_max {
+            let distance = distances[(y - y_min) as usize * w + (x - x_min) as usize];
+            if distance < f32::MAX {
+                _plot(src, x, y, color, _coverage(half - distance, mode));
+            }
+        }
+    }
+}
+
+// Points along an arc, close enough that the chords stray less than a tenth of a pixel from it.
+fn _arc_points(center: Point, radius: f32, start_angle: f32, end_angle: f32) -> Vec<Point> {
+    let mut sweep = (end_angle - start_angle) % 360.0;
+    if sweep < 0.0 {
+        sweep += 360.0;
+    }
+    if end_angle - start_angle >= 360.0 {
+        sweep = 360.0;
+    }
+
+    // 2 acos(1 - 0.1 / radius), written so that it does not round to 0 for huge radii. The count
+    // is also kept to one point per pixel of the arc, and to a fixed maximum.
+    let radius = radius as f64;
+    let step = 4.0 * (0.05 / radius).sqrt().min(1.0).asin();
+    let sweep = (sweep as f64).to_radians();
+    let count = (sweep / step).min(sweep * radius).min(1_048_576.0).ceil().max(1.0) as usize;
+    let start = (start_angle as f64).to_radians();
+    (0..=count)
+        .map(|i| {
+            let angle = start + sweep * i as f64 / count as f64;
+            let x = center.x as f64 + radius * angle.cos();
+            let y = center.y as f64 + radius * angle.sin();
+            Point::new(x as f32, y as f32)
+        })
+        .collect()
+}
+
+// Split a cubic Bézier curve in halves until it is flat, pushing the end point of each flat
+// piece. See https://en.wikipedia.org/wiki/De_Casteljau%27s_algorithm
+fn _flatten_cubic(c: [Point; 4], depth: u32, points: &mut Vec<Point>) {
+    // Distance of the inner control points to the chord bounds the distance of the curve to it.
+    let chord_distance =
+        _segment_distance(c[1], c[0], c[3]).max(_segment_distance(c[2], c[0], c[3]));
+    if depth >= 16 || chord_distance <= 0.1 {
+        points.push(c[3]);
+        return;
+    }
+
+    let mid = |a: Point, b: Point| Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+    let (p01, p12, p23) = (mid(c[0], c[1]), mid(c[1], c[2]), mid(c[2], c[3]));
+    let (p012, p123) = (mid(p01, p12), mid(p12, p23));
+    let center = mid(p012, p123);
+    _flatten_cubic([c[0], p01, p012, center], depth + 1, points);
+    _flatten_cubic([center, p123, p23, c[3]], depth + 1, points);
+}
+
+// Distance from a point to the closest point of a segment.
+fn _segment_distance(p: Point, a: Point, b: Point) -> f32 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((p.x - a.x) * dx + (p.y - a.y) * dy) / length).clamp(0.0, 1.0)
+    };
+    (p.x - a.x - t * dx).hypot(p.y - a.y - t * dy)
+}
+
+// How much a pixel is covered from how far inside of a shape its center is.
+fn _coverage(inside: f32, mode: DrawMode) -> f32 {
+    match mode {
+        DrawMode::Hard => {
+            if inside >= 0.0 {
+                1.0
+            } else {
+                0.0
+            }
+        }
+        DrawMode::Antialiased => (inside + 0.5).clamp(0.0, 1.0),
+    }
+}
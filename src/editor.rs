@@ -2,21 +2,183 @@
 
 // from rust
 use std::cmp;
+use std::str::FromStr;
 
 // from external crate
 
 // from local crate
 use error::{RasterError, RasterResult};
-use blend::{self, BlendMode};
+use blend::{self, BlendMode, Overlap};
+use draw::Rect;
+use filter;
 use Color;
 use Image;
+use interpolate::InterpolationMode;
 use position::{Position, PositionMode};
 use transform;
 
+/// Add a border of the same width on all sides of an image.
+///
+/// The canvas grows by twice the width in each direction, the image itself is kept whole.
+///
+/// # Errors
+///
+/// If width is negative, this fails with `RasterError::InvalidBorder`.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, Color};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// editor::add_border(&mut image, 10, Color::white()).unwrap();
+/// raster::save(&image, "tests/out/test_add_border.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_add_border.jpg)
+///
+pub fn add_border(src: &mut Image, width: i32, color: Color) -> RasterResult<()> {
+    add_border_sides(src, width, width, width, width, color)
+}
+
+/// Add a border with a different width on each side of an image, in the order top, right,
+/// bottom, left.
+///
+/// # Errors
+///
+/// If a width is negative, this fails with `RasterError::InvalidBorder` with the first negative
+/// width.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, Color};
+///
+/// // A frame with a larger bottom for a caption
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// editor::add_border_sides(&mut image, 10, 10, 40, 10, Color::white()).unwrap();
+/// raster::save(&image, "tests/out/test_add_border_sides.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_add_border_sides.jpg)
+///
+pub fn add_border_sides(
+    src: &mut Image,
+    top: i32,
+    right: i32,
+    bottom: i32,
+    left: i32,
+    color: Color,
+) -> RasterResult<()> {
+    if let Some(&width) = [top, right, bottom, left].iter().find(|&&width| width < 0) {
+        return Err(RasterError::InvalidBorder(width));
+    }
+
+    let (w, h) = (src.width + left + right, src.height + top + bottom);
+    extend_canvas(src, w, h, left, top, &color);
+    Ok(())
+}
+
+/// An enum for where smaller images go across the direction of `editor::append`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alignment {
+    /// Top when appending horizontally, left when appending vertically.
+    Start,
+    /// Centered, rounding toward the start.
+    Center,
+    /// Bottom when appending horizontally, right when appending vertically.
+    End,
+}
+
+/// An enum for the directions images can be laid out in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    /// Left to right.
+    Horizontal,
+    /// Top to bottom.
+    Vertical,
+}
+
+/// Put images edge to edge in a new image, eg. for before and after comparison strips.
+///
+/// The images are placed in order with spacing pixels between them, and aligned across the
+/// direction. The space not covered by an image is filled with the background color.
+///
+/// # Errors
+///
+/// If there are no images, this fails with `RasterError::InvalidDimensions`. If the spacing is
+/// negative, this fails with `RasterError::InvalidSpacing`.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, Alignment, Color, Direction};
+///
+/// let image1 = raster::open("tests/in/sample.jpg").unwrap();
+/// let image2 = raster::open("tests/in/portrait.jpg").unwrap();
+/// let strip = editor::append(
+///     &[&image1, &image2],
+///     Direction::Horizontal,
+///     Alignment::Center,
+///     10,
+///     Color::white(),
+/// ).unwrap();
+/// raster::save(&strip, "tests/out/test_append.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_append.jpg)
+///
+pub fn append(
+    images: &[&Image],
+    direction: Direction,
+    alignment: Alignment,
+    spacing: i32,
+    background: Color,
+) -> RasterResult<Image> {
+    if images.is_empty() {
+        return Err(RasterError::InvalidDimensions(0, 0));
+    }
+    if spacing < 0 {
+        return Err(RasterError::InvalidSpacing(spacing));
+    }
+
+    // Lengths along and across the direction.
+    let size = |image: &Image| match direction {
+        Direction::Horizontal => (image.width, image.height),
+        Direction::Vertical => (image.height, image.width),
+    };
+    let along: i32 = images.iter().map(|image| size(image).0).sum::<i32>()
+        + spacing * (images.len() as i32 - 1);
+    let across = images.iter().map(|image| size(image).1).max().unwrap_or(0);
+
+    let mut canvas = match direction {
+        Direction::Horizontal => Image::blank(along, across),
+        Direction::Vertical => Image::blank(across, along),
+    };
+    fill(&mut canvas, background)?;
+
+    let mut start = 0;
+    for image in images {
+        let (length, thickness) = size(image);
+        let offset = match alignment {
+            Alignment::Start => 0,
+            Alignment::Center => (across - thickness) / 2,
+            Alignment::End => across - thickness,
+        };
+        match direction {
+            Direction::Horizontal => paste(&mut canvas, image, start, offset, PasteMode::Replace)?,
+            Direction::Vertical => paste(&mut canvas, image, offset, start, PasteMode::Replace)?,
+        }
+        start += length + spacing;
+    }
+
+    Ok(canvas)
+}
+
 /// Blend 2 images into one. The image1 is the base and image2 is the top.
 ///
 /// Opacity is any value from 0.0 - 1.0
 ///
+/// Transparency of both images is taken into account: image2 is composited over image1 using
+/// Porter-Duff source-over, so blending onto a transparent image1 keeps the resulting alpha.
+///
 /// The `offset_x` and `offset_y` are added to the final position. Can also be negative offsets.
 ///
 /// # Errors
@@ -41,6 +203,20 @@ use transform;
 /// let multiply = editor::blend(&image1, &image2, BlendMode::Multiply, 1.0, PositionMode::Center, 0, 0).unwrap();
 /// let overlay = editor::blend(&image1, &image2, BlendMode::Overlay, 1.0, PositionMode::Center, 0, 0).unwrap();
 /// let screen = editor::blend(&image1, &image2, BlendMode::Screen, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let darken = editor::blend(&image1, &image2, BlendMode::Darken, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let lighten = editor::blend(&image1, &image2, BlendMode::Lighten, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let soft_light = editor::blend(&image1, &image2, BlendMode::SoftLight, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let hard_light = editor::blend(&image1, &image2, BlendMode::HardLight, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let color_dodge = editor::blend(&image1, &image2, BlendMode::ColorDodge, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let color_burn = editor::blend(&image1, &image2, BlendMode::ColorBurn, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let exclusion = editor::blend(&image1, &image2, BlendMode::Exclusion, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let subtract = editor::blend(&image1, &image2, BlendMode::Subtract, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let add = editor::blend(&image1, &image2, BlendMode::Add, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let divide = editor::blend(&image1, &image2, BlendMode::Divide, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let hue = editor::blend(&image1, &image2, BlendMode::Hue, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let saturation = editor::blend(&image1, &image2, BlendMode::Saturation, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let color = editor::blend(&image1, &image2, BlendMode::Color, 1.0, PositionMode::Center, 0, 0).unwrap();
+/// let luminosity = editor::blend(&image1, &image2, BlendMode::Luminosity, 1.0, PositionMode::Center, 0, 0).unwrap();
 ///
 /// // Save it
 /// raster::save(&normal, "tests/out/test_blend_normal.png").unwrap();
@@ -48,6 +224,20 @@ use transform;
 /// raster::save(&multiply, "tests/out/test_blend_multiply.png").unwrap();
 /// raster::save(&overlay, "tests/out/test_blend_overlay.png").unwrap();
 /// raster::save(&screen, "tests/out/test_blend_screen.png").unwrap();
+/// raster::save(&darken, "tests/out/test_blend_darken.png").unwrap();
+/// raster::save(&lighten, "tests/out/test_blend_lighten.png").unwrap();
+/// raster::save(&soft_light, "tests/out/test_blend_soft_light.png").unwrap();
+/// raster::save(&hard_light, "tests/out/test_blend_hard_light.png").unwrap();
+/// raster::save(&color_dodge, "tests/out/test_blend_color_dodge.png").unwrap();
+/// raster::save(&color_burn, "tests/out/test_blend_color_burn.png").unwrap();
+/// raster::save(&exclusion, "tests/out/test_blend_exclusion.png").unwrap();
+/// raster::save(&subtract, "tests/out/test_blend_subtract.png").unwrap();
+/// raster::save(&add, "tests/out/test_blend_add.png").unwrap();
+/// raster::save(&divide, "tests/out/test_blend_divide.png").unwrap();
+/// raster::save(&hue, "tests/out/test_blend_hue.png").unwrap();
+/// raster::save(&saturation, "tests/out/test_blend_saturation.png").unwrap();
+/// raster::save(&color, "tests/out/test_blend_color.png").unwrap();
+/// raster::save(&luminosity, "tests/out/test_blend_luminosity.png").unwrap();
 /// ```
 /// ### Source Images
 ///
@@ -84,6 +274,76 @@ use transform;
 ///
 /// ![](https://kosinix.github.io/raster/out/test_blend_screen.png)
 ///
+///
+/// Darken
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_darken.png)
+///
+///
+/// Lighten
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_lighten.png)
+///
+///
+/// Soft Light
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_soft_light.png)
+///
+///
+/// Hard Light
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_hard_light.png)
+///
+///
+/// Color Dodge
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_color_dodge.png)
+///
+///
+/// Color Burn
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_color_burn.png)
+///
+///
+/// Exclusion
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_exclusion.png)
+///
+///
+/// Subtract
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_subtract.png)
+///
+///
+/// Add
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_add.png)
+///
+///
+/// Divide
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_divide.png)
+///
+///
+/// Hue
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_hue.png)
+///
+///
+/// Saturation
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_saturation.png)
+///
+///
+/// Color
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_color.png)
+///
+///
+/// Luminosity
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_luminosity.png)
+///
 pub fn blend(
     image1: &Image,
     image2: &Image,
@@ -93,118 +353,161 @@ pub fn blend(
     offset_x: i32,
     offset_y: i32,
 ) -> RasterResult<Image> {
-    let opacity = if opacity > 1.0 {
-        1.0
-    } else if opacity < 0.0 {
-        0.0
-    } else {
-        opacity
-    };
+    let mut canvas = image1.clone();
+    blend_layer(&mut canvas, image2, blend_mode, opacity, position, offset_x, offset_y)?;
 
-    // Turn into positioner struct
-    let positioner = Position::new(position, offset_x, offset_y);
+    Ok(canvas)
+}
 
-    // Position is for image2, image1 is canvas.
-    let (offset_x, offset_y) =
-        positioner.get_x_y(image1.width, image1.height, image2.width, image2.height)?;
-
-    let (w1, h1) = (image1.width, image1.height);
-    let (w2, h2) = (image2.width, image2.height);
-
-    // Check if it overlaps
-    if (offset_x >= w1) || (offset_x + w2 <= 0) || (offset_y >= h1) || (offset_y + h2 <= 0) {
-        return Err(RasterError::BlendingImageFallsOutsideCanvas);
-    }
-
-    // Loop start X
-    let mut loop_start_x = 0;
-    let canvas_start_x = offset_x;
-    if canvas_start_x < 0 {
-        let diff = 0 - canvas_start_x;
-        loop_start_x += diff;
-    }
-
-    // Loop end X
-    let mut loop_end_x = w2;
-    let canvas_end_x = offset_x + w2;
-    if canvas_end_x > w1 {
-        let diff = canvas_end_x - w1;
-        loop_end_x -= diff;
-    }
-
-    // Loop start Y
-    let mut loop_start_y = 0;
-    let canvas_start_y = offset_y;
-    if canvas_start_y < 0 {
-        let diff = 0 - canvas_start_y;
-        loop_start_y += diff;
-    }
-
-    // Loop end Y
-    let mut loop_end_y = h2;
-    let canvas_end_y = offset_y + h2;
-    if canvas_end_y > h1 {
-        let diff = canvas_end_y - h1;
-        loop_end_y -= diff;
-    }
-
-    match blend_mode {
-        BlendMode::Normal => blend::normal(
-            image1,
-            image2,
-            loop_start_y,
-            loop_end_y,
-            loop_start_x,
-            loop_end_x,
-            offset_x,
-            offset_y,
-            opacity,
-        ),
-        BlendMode::Difference => blend::difference(
-            image1,
-            image2,
-            loop_start_y,
-            loop_end_y,
-            loop_start_x,
-            loop_end_x,
-            offset_x,
-            offset_y,
-            opacity,
-        ),
-        BlendMode::Multiply => blend::multiply(
-            image1,
-            image2,
-            loop_start_y,
-            loop_end_y,
-            loop_start_x,
-            loop_end_x,
-            offset_x,
-            offset_y,
-            opacity,
-        ),
-        BlendMode::Overlay => blend::overlay(
-            image1,
-            image2,
-            loop_start_y,
-            loop_end_y,
-            loop_start_x,
-            loop_end_x,
-            offset_x,
-            offset_y,
-            opacity,
-        ),
-        BlendMode::Screen => blend::screen(
-            image1,
-            image2,
-            loop_start_y,
-            loop_end_y,
-            loop_start_x,
-            loop_end_x,
-            offset_x,
-            offset_y,
-            opacity,
-        ),
+/// A layer to be composited on top of a base image. See `editor::composite`.
+#[derive(Debug, Clone, Copy)]
+pub struct Layer<'a> {
+    /// The image of the layer.
+    pub image: &'a Image,
+
+    /// The mode used to blend the layer on the layers below it.
+    pub mode: BlendMode,
+
+    /// Opacity from 0.0 - 1.0.
+    pub opacity: f32,
+
+    /// Anchor position of the layer on the base image.
+    pub position: PositionMode,
+
+    /// Added to the final x position. Can be negative.
+    pub offset_x: i32,
+
+    /// Added to the final y position. Can be negative.
+    pub offset_y: i32,
+}
+
+/// Composite layers on top of a base image, from first to last.
+///
+/// This gives the same result as calling `editor::blend` for each layer but the base image is
+/// only copied once.
+///
+/// # Errors
+///
+/// If a layer falls outside the canvas area, then this fails with
+/// `RasterError::BlendingImageFallsOutsideCanvas`.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, BlendMode, Layer, PositionMode};
+///
+/// // Create images from file
+/// let image = raster::open("tests/in/sample.jpg").unwrap();
+/// let watermark = raster::open("tests/in/watermark.png").unwrap();
+///
+/// // Watermark the top left and bottom right corners
+/// let layers = [
+///     Layer {
+///         image: &watermark,
+///         mode: BlendMode::Normal,
+///         opacity: 1.0,
+///         position: PositionMode::TopLeft,
+///         offset_x: 10,
+///         offset_y: 10,
+///     },
+///     Layer {
+///         image: &watermark,
+///         mode: BlendMode::Screen,
+///         opacity: 0.5,
+///         position: PositionMode::BottomRight,
+///         offset_x: -10,
+///         offset_y: -10,
+///     },
+/// ];
+/// let composite = editor::composite(&image, &layers).unwrap();
+///
+/// raster::save(&composite, "tests/out/test_composite.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_composite.png)
+///
+pub fn composite(base: &Image, layers: &[Layer]) -> RasterResult<Image> {
+    let mut canvas = base.clone();
+    for layer in layers {
+        blend_layer(
+            &mut canvas,
+            layer.image,
+            layer.mode,
+            layer.opacity,
+            layer.position,
+            layer.offset_x,
+            layer.offset_y,
+        )?;
+    }
+
+    Ok(canvas)
+}
+
+/// Blend 2 images into one using a mask image. The image1 is the base and image2 is the top.
+///
+/// The mask must have the same dimensions as image2. Its grayscale value modulates the opacity of
+/// each pixel of image2: white shows image2 with the given opacity, black hides it. Transparent
+/// areas of the mask also hide image2.
+///
+/// # Errors
+///
+/// If the mask and image2 dimensions differ, this fails with `RasterError::DimensionsMismatch`.
+///
+/// See `editor::blend` for the other errors.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, BlendMode, Color, Image, PositionMode};
+///
+/// // Create images from file
+/// let image1 = raster::open("tests/in/sample.jpg").unwrap();
+/// let image2 = raster::open("tests/in/watermark.png").unwrap();
+///
+/// // Create a mask that fades image2 from left to right
+/// let mut mask = Image::blank(image2.width, image2.height);
+/// for y in 0..mask.height {
+///     for x in 0..mask.width {
+///         let gray = (255 * x / mask.width) as u8;
+///         mask.set_pixel(x, y, &Color::rgb(gray, gray, gray)).unwrap();
+///     }
+/// }
+///
+/// let image = editor::blend_with_mask(&image1, &image2, &mask, BlendMode::Normal, 1.0, PositionMode::Center, 0, 0).unwrap();
+///
+/// raster::save(&image, "tests/out/test_blend_with_mask.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_blend_with_mask.png)
+///
+#[allow(clippy::too_many_arguments)]
+pub fn blend_with_mask(
+    image1: &Image,
+    image2: &Image,
+    mask: &Image,
+    blend_mode: BlendMode,
+    opacity: f32,
+    position: PositionMode,
+    offset_x: i32,
+    offset_y: i32,
+) -> RasterResult<Image> {
+    if mask.width != image2.width || mask.height != image2.height {
+        return Err(RasterError::DimensionsMismatch);
+    }
+
+    // Bake the mask into the alpha channel of a copy of image2
+    let mut masked = image2.clone();
+    for y in 0..masked.height {
+        for x in 0..masked.width {
+            let m = mask.get_pixel(x, y)?;
+            let gray = (m.r as f32 * 0.3) + (m.g as f32 * 0.59) + (m.b as f32 * 0.11);
+            let factor = gray / 255.0 * (m.a as f32 / 255.0);
+
+            let mut pixel = masked.get_pixel(x, y)?;
+            pixel.a = (pixel.a as f32 * factor).round() as u8;
+            masked.set_pixel(x, y, &pixel)?;
+        }
     }
+
+    blend(image1, &masked, blend_mode, opacity, position, offset_x, offset_y)
 }
 
 /// Crop the image to the given dimension and position.
@@ -213,6 +516,11 @@ pub fn blend(
 /// Offsets can be used to nudge the final position. Or you can set the position to
 /// `PositionMode::TopLeft` and use the offsets as a normal screen x and y coordinates.
 ///
+/// # Errors
+///
+/// If the crop width or height is zero or negative, this fails with
+/// `RasterError::InvalidDimensions`.
+///
 /// # Examples
 ///
 /// ### Input
@@ -280,6 +588,10 @@ pub fn crop(
     offset_x: i32,
     offset_y: i32,
 ) -> RasterResult<()> {
+    if crop_width <= 0 || crop_height <= 0 {
+        return Err(RasterError::InvalidDimensions(crop_width, crop_height));
+    }
+
     // Turn into positioner struct
     let positioner = Position::new(position, offset_x, offset_y);
 
@@ -312,106 +624,811 @@ pub fn crop(
     Ok(())
 }
 
-/// Fill an image with color.
+/// Crop the image to the largest area of the given aspect ratio, eg. 16 and 9.
 ///
-/// # Examples
-/// ```
-/// use raster::Image;
-/// use raster::editor;
-/// use raster::Color;
+/// # Errors
 ///
-/// // Create a 100x100 image
-/// let mut image = Image::blank(100, 100);
+/// If ratio_w or ratio_h is zero or negative, this fails with `RasterError::InvalidDimensions`.
 ///
-/// // Fill it with red
-/// editor::fill(&mut image, Color::red()).unwrap();
+/// # Examples
+/// ```
+/// use raster::{editor, PositionMode};
 ///
-/// // Save it
-/// raster::save(&image, "tests/out/test_fill.png").unwrap();
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// editor::crop_aspect(&mut image, 1, 1, PositionMode::Center).unwrap();
+/// raster::save(&image, "tests/out/test_crop_aspect.jpg").unwrap();
 /// ```
 ///
+/// ![](https://kosinix.github.io/raster/out/test_crop_aspect.jpg)
 ///
-pub fn fill(src: &mut Image, color: Color) -> RasterResult<()> {
-    for y in 0..src.height {
-        for x in 0..src.width {
-            src.set_pixel(x, y, &color)?;
-        }
+pub fn crop_aspect(
+    src: &mut Image,
+    ratio_w: i32,
+    ratio_h: i32,
+    position: PositionMode,
+) -> RasterResult<()> {
+    if ratio_w <= 0 || ratio_h <= 0 {
+        return Err(RasterError::InvalidDimensions(ratio_w, ratio_h));
     }
 
-    Ok(())
-}
+    let (w, h) = (src.width as i64, src.height as i64);
+    let (rw, rh) = (ratio_w as i64, ratio_h as i64);
+    let (crop_width, crop_height) = if w * rh >= h * rw {
+        ((h * rw + rh / 2) / rh, h)
+    } else {
+        (w, (w * rh + rw / 2) / rw)
+    };
+    let crop_width = cmp::max(1, cmp::min(crop_width, w)) as i32;
+    let crop_height = cmp::max(1, cmp::min(crop_height, h)) as i32;
 
-/// An enum for the various modes that can be used for resizing.
-#[derive(Debug)]
-pub enum ResizeMode {
-    /// Resize image to exact dimensions ignoring aspect ratio.
-    Exact,
-    /// Resize image to exact width. Height parameter is ignored and is auto calculated instead.
-    ExactWidth,
-    /// Resize image to exact height. Width parameter is ignored and is auto calculated instead.
-    ExactHeight,
-    /// Resize an image to fit within the given width and height.
-    Fit,
-    /// Resize image to fill all the space in the given dimension. Excess parts are cropped.
-    Fill,
+    crop(src, crop_width, crop_height, position, 0, 0)
 }
 
-/// Resize an image to a given width, height and mode.
+/// Crop a circle out of the image, eg. for avatars.
 ///
-/// # Examples
-/// ### Resize Fit
-/// ```
-/// use raster::{editor, Color, Image, ResizeMode, BlendMode, PositionMode};
-///
-/// // Create an image from file
-/// let mut image1 = raster::open("tests/in/sample.jpg").unwrap();
-/// let mut image2 = raster::open("tests/in/portrait.jpg").unwrap();
+/// The result is a square of twice the radius, transparent outside the circle with a smooth edge.
+/// Parts of the circle outside the image are transparent too. Save as PNG to keep the
+/// transparency.
 ///
-/// // Resize it
-/// editor::resize(&mut image1, 200, 200, ResizeMode::Fit).unwrap();
-/// editor::resize(&mut image2, 200, 200, ResizeMode::Fit).unwrap();
-///
-/// // Superimpose images on a gray background
-/// let mut bg = Image::blank(200, 200);
-/// editor::fill(&mut bg, Color::hex("#CCCCCC").unwrap()).unwrap();
+/// # Errors
 ///
-/// let image1 = editor::blend(&bg, &image1, BlendMode::Normal, 1.0, PositionMode::TopLeft, 0, 0).unwrap();
-/// let image2 = editor::blend(&bg, &image2, BlendMode::Normal, 1.0, PositionMode::TopLeft, 0, 0).unwrap();
+/// If the radius is zero or negative, this fails with `RasterError::InvalidShapeRadius`.
 ///
-/// raster::save(&image1, "tests/out/test_resize_fit_1.jpg").unwrap();
-/// raster::save(&image2, "tests/out/test_resize_fit_2.jpg").unwrap();
+/// # Examples
 /// ```
+/// use raster::editor;
 ///
-/// The gray box shows the 200x200 imaginary box that the images "fit" in.
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let center = (image.width / 2, image.height / 2);
+/// editor::crop_circle(&mut image, center, 100).unwrap();
+/// raster::save(&image, "tests/out/test_crop_circle.png").unwrap();
+/// ```
 ///
+/// ![](https://kosinix.github.io/raster/out/test_crop_circle.png)
 ///
-/// ![](https://kosinix.github.io/raster/out/test_resize_fit_1.jpg) ![](https://kosinix.github.io/raster/out/test_resize_fit_2.jpg)
+pub fn crop_circle(src: &mut Image, center: (i32, i32), radius: i32) -> RasterResult<()> {
+    crop_ellipse(src, center, radius, radius)
+}
+
+/// Crop an ellipse out of the image.
 ///
-/// ### Resize Fill
-/// ```
-/// use raster::{editor, Color, Image, ResizeMode};
+/// The result is twice rx wide and twice ry high, transparent outside the ellipse with a smooth
+/// edge. See `editor::crop_circle`.
 ///
-/// // Create an image from file
-/// let mut image1 = raster::open("tests/in/sample.jpg").unwrap();
-/// let mut image2 = raster::open("tests/in/portrait.jpg").unwrap();
+/// # Errors
 ///
-/// // Resize it
-/// editor::resize(&mut image1, 200, 200, ResizeMode::Fill).unwrap();
-/// editor::resize(&mut image2, 200, 200, ResizeMode::Fill).unwrap();
+/// If rx or ry is zero or negative, this fails with `RasterError::InvalidShapeRadius`.
 ///
-/// raster::save(&image1, "tests/out/test_resize_fill_1.jpg").unwrap();
-/// raster::save(&image2, "tests/out/test_resize_fill_2.jpg").unwrap();
+/// # Examples
 /// ```
+/// use raster::editor;
 ///
-/// The image fills up the entire 200x200 box.
-///
-/// ![](https://kosinix.github.io/raster/out/test_resize_fill_1.jpg) ![](https://kosinix.github.io/raster/out/test_resize_fill_2.jpg)
-///
-/// ### Resize to Exact Width
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let center = (image.width / 2, image.height / 2);
+/// editor::crop_ellipse(&mut image, center, 150, 100).unwrap();
+/// raster::save(&image, "tests/out/test_crop_ellipse.png").unwrap();
 /// ```
-/// use raster::{editor, Color, Image, ResizeMode};
 ///
-/// // Create an image from file
+/// ![](https://kosinix.github.io/raster/out/test_crop_ellipse.png)
+///
+pub fn crop_ellipse(src: &mut Image, center: (i32, i32), rx: i32, ry: i32) -> RasterResult<()> {
+    for &radius in &[rx, ry] {
+        if radius <= 0 {
+            return Err(RasterError::InvalidShapeRadius(radius as f32));
+        }
+    }
+
+    let (w, h) = (rx * 2, ry * 2);
+    let mut dest = Image {
+        width: w,
+        height: h,
+        bytes: vec![0; (w * h) as usize * 4],
+    };
+    let (left, top) = (center.0 - rx, center.1 - ry);
+    let (rx, ry) = (rx as f32, ry as f32);
+    for y in 0..h {
+        for x in 0..w {
+            // Distance of the pixel center inside the edge, measured along the ray from the
+            // center.
+            let (px, py) = (x as f32 + 0.5 - rx, y as f32 + 0.5 - ry);
+            let n = ((px / rx).powi(2) + (py / ry).powi(2)).sqrt();
+            let inside = if n > 0.0 {
+                px.hypot(py) * (1.0 / n - 1.0)
+            } else {
+                rx.min(ry)
+            };
+            let coverage = (inside + 0.5).clamp(0.0, 1.0);
+            let (sx, sy) = (left + x, top + y);
+            if coverage == 0.0 || sx < 0 || sy < 0 || sx >= src.width || sy >= src.height {
+                continue;
+            }
+
+            let pixel = src.get_pixel(sx, sy)?;
+            let a = (pixel.a as f32 * coverage).round() as u8;
+            dest.set_pixel(x, y, &Color::rgba(pixel.r, pixel.g, pixel.b, a))?;
+        }
+    }
+    *src = dest;
+
+    Ok(())
+}
+
+/// Crop the image to the given dimension, picking the window with the most detail.
+///
+/// Instead of a fixed position, every window is scored by the sum of the luminance gradients
+/// inside it, so that crops keep the subject rather than flat sky or background. Windows with the
+/// same score are resolved in favor of the one closest to the center. A dimension larger than the
+/// image is reduced to the image.
+///
+/// # Errors
+///
+/// If the width or height is zero or negative, this fails with `RasterError::InvalidDimensions`.
+///
+/// # Examples
+/// ```
+/// use raster::editor;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// editor::crop_smart(&mut image, 200, 200).unwrap();
+/// raster::save(&image, "tests/out/test_crop_smart.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_crop_smart.jpg)
+///
+pub fn crop_smart(src: &mut Image, w: i32, h: i32) -> RasterResult<()> {
+    if w <= 0 || h <= 0 {
+        return Err(RasterError::InvalidDimensions(w, h));
+    }
+    let (w, h) = (w.min(src.width), h.min(src.height));
+    let (width, height) = (src.width as usize, src.height as usize);
+    if width == 0 || height == 0 {
+        return Ok(());
+    }
+
+    let luma: Vec<i32> = src
+        .bytes
+        .chunks(4)
+        .map(|p| (p[0] as i32 * 299 + p[1] as i32 * 587 + p[2] as i32 * 114) / 1000)
+        .collect();
+
+    // Summed area table of the gradient energy, with an extra row and column of zeros.
+    let mut sums = vec![0u64; (width + 1) * (height + 1)];
+    for y in 0..height {
+        let mut row = 0u64;
+        for x in 0..width {
+            let at = |x: usize, y: usize| luma[y * width + x];
+            let dx = at((x + 1).min(width - 1), y) - at(x.saturating_sub(1), y);
+            let dy = at(x, (y + 1).min(height - 1)) - at(x, y.saturating_sub(1));
+            row += (dx.abs() + dy.abs()) as u64;
+            sums[(y + 1) * (width + 1) + x + 1] = sums[y * (width + 1) + x + 1] + row;
+        }
+    }
+
+    let (w, h) = (w as usize, h as usize);
+    let window = |x: usize, y: usize| {
+        let at = |x: usize, y: usize| sums[y * (width + 1) + x];
+        at(x + w, y + h) + at(x, y) - at(x + w, y) - at(x, y + h)
+    };
+    let (cx, cy) = ((width - w) as i64, (height - h) as i64);
+    let off_center = |x: usize, y: usize| (2 * x as i64 - cx).abs() + (2 * y as i64 - cy).abs();
+
+    let mut best = (0, 0);
+    let mut best_score = (0, i64::MIN);
+    for y in 0..=height - h {
+        for x in 0..=width - w {
+            let score = (window(x, y), -off_center(x, y));
+            if score > best_score {
+                best_score = score;
+                best = (x, y);
+            }
+        }
+    }
+
+    crop(
+        src,
+        w as i32,
+        h as i32,
+        PositionMode::TopLeft,
+        best.0 as i32,
+        best.1 as i32,
+    )
+}
+
+/// Add a drop shadow behind an image, eg. for product cut-outs.
+///
+/// The shadow is the alpha of the image filled with color, moved by dx and dy and softened by
+/// blur pixels. The canvas grows so that the whole shadow fits, with a transparent background.
+/// Save as PNG to keep the transparency.
+///
+/// # Errors
+///
+/// If blur is negative, this fails with `RasterError::InvalidRadius`.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, Color};
+///
+/// let mut image = raster::open("tests/in/watermark.png").unwrap();
+/// editor::drop_shadow(&mut image, 6, 8, 10, &Color::rgba(0, 0, 0, 160)).unwrap();
+/// raster::save(&image, "tests/out/test_drop_shadow.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_drop_shadow.png)
+///
+pub fn drop_shadow(
+    src: &mut Image,
+    dx: i32,
+    dy: i32,
+    blur: i32,
+    color: &Color,
+) -> RasterResult<()> {
+    if blur < 0 {
+        return Err(RasterError::InvalidRadius(blur));
+    }
+
+    // Two box blurs of half the radius give a smooth falloff reaching 2 * half pixels out.
+    let half = (blur + 1) / 2;
+    let margin = half * 2;
+    let (left, right) = ((margin - dx).max(0), (margin + dx).max(0));
+    let (top, bottom) = ((margin - dy).max(0), (margin + dy).max(0));
+
+    let mut shadow = Image {
+        width: src.width + left + right,
+        height: src.height + top + bottom,
+        bytes: Vec::new(),
+    };
+    for _ in 0..shadow.width * shadow.height {
+        shadow.bytes.extend_from_slice(&[color.r, color.g, color.b, 0]);
+    }
+    for y in 0..src.height {
+        for x in 0..src.width {
+            let alpha = src.bytes[((y * src.width + x) * 4 + 3) as usize] as u32;
+            let i = (((y + top + dy) * shadow.width + x + left + dx) * 4 + 3) as usize;
+            shadow.bytes[i] = ((alpha * color.a as u32 + 127) / 255) as u8;
+        }
+    }
+    for _ in 0..2 {
+        filter::blur_box(&mut shadow, half)?;
+    }
+
+    paste(&mut shadow, src, left, top, PasteMode::Over)?;
+    *src = shadow;
+
+    Ok(())
+}
+
+/// Fill an image with color.
+///
+/// # Examples
+/// ```
+/// use raster::Image;
+/// use raster::editor;
+/// use raster::Color;
+///
+/// // Create a 100x100 image
+/// let mut image = Image::blank(100, 100);
+///
+/// // Fill it with red
+/// editor::fill(&mut image, Color::red()).unwrap();
+///
+/// // Save it
+/// raster::save(&image, "tests/out/test_fill.png").unwrap();
+/// ```
+///
+///
+pub fn fill(src: &mut Image, color: Color) -> RasterResult<()> {
+    for y in 0..src.height {
+        for x in 0..src.width {
+            src.set_pixel(x, y, &color)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Merge bracketed shots of the same scene into one well exposed image, without going through
+/// HDR.
+///
+/// Implements the exposure fusion of Mertens et al. Each pixel of each shot is weighted by its
+/// contrast, saturation and how close its channels are to mid-gray, the weights being normalized
+/// over the shots. To avoid seams where the weights change, the shots are blended level by level
+/// of Laplacian pyramids with Gaussian pyramids of the weights. Alpha is blended like the colors.
+/// The shots should be aligned, eg. shot from a tripod.
+///
+/// # Errors
+///
+/// If there are no images, this fails with `RasterError::InvalidDimensions`. If the images do
+/// not all have the same dimensions, this fails with `RasterError::DimensionsMismatch`.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, filter};
+///
+/// let image = raster::open("tests/in/sample.jpg").unwrap();
+/// let mut dark = image.clone();
+/// filter::exposure(&mut dark, -1.5).unwrap();
+/// let mut bright = image.clone();
+/// filter::exposure(&mut bright, 1.5).unwrap();
+/// let fused = editor::fuse_exposures(&[&dark, &image, &bright]).unwrap();
+/// raster::save(&fused, "tests/out/test_fuse_exposures.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_fuse_exposures.jpg)
+///
+pub fn fuse_exposures(images: &[&Image]) -> RasterResult<Image> {
+    let first = match images.first() {
+        Some(first) => first,
+        None => return Err(RasterError::InvalidDimensions(0, 0)),
+    };
+    if images
+        .iter()
+        .any(|i| i.width != first.width || i.height != first.height)
+    {
+        return Err(RasterError::DimensionsMismatch);
+    }
+
+    // Normalize the weights so that they sum to 1 at each pixel, or share it equally where all
+    // the shots have no weight.
+    let (w, h) = (first.width as usize, first.height as usize);
+    let mut weights: Vec<Vec<f32>> = images.iter().map(|image| fusion_weights(image)).collect();
+    let share = 1.0 / images.len() as f32;
+    for i in 0..w * h {
+        let sum: f32 = weights.iter().map(|weight| weight[i]).sum();
+        for weight in weights.iter_mut() {
+            weight[i] = if sum > 0.0 { weight[i] / sum } else { share };
+        }
+    }
+
+    // Sizes of the pyramid levels, halving down to 1 pixel wide or high.
+    let mut sizes = vec![(w, h)];
+    let (mut lw, mut lh) = (w, h);
+    while lw > 1 && lh > 1 {
+        lw = lw.div_ceil(2);
+        lh = lh.div_ceil(2);
+        sizes.push((lw, lh));
+    }
+
+    let mut fused: Vec<Vec<f32>> = sizes.iter().map(|&(w, h)| vec![0.0; w * h * 4]).collect();
+    for (image, weight) in images.iter().zip(weights) {
+        let mut colors: Vec<f32> = image.bytes.iter().map(|&b| b as f32).collect();
+        let mut weight = weight;
+        for (level, &(w, h)) in sizes.iter().enumerate() {
+            // Laplacian level: the details lost by halving, or what is left at the top.
+            let (details, smaller, smaller_weight) = if level + 1 < sizes.len() {
+                let (sw, sh) = sizes[level + 1];
+                let smaller = halve(&colors, w, h, 4);
+                let mut details = colors;
+                for (value, up) in details.iter_mut().zip(double(&smaller, (sw, sh), (w, h), 4)) {
+                    *value -= up;
+                }
+                (details, smaller, halve(&weight, w, h, 1))
+            } else {
+                (colors, Vec::new(), Vec::new())
+            };
+
+            for (i, values) in details.chunks(4).enumerate() {
+                for (c, value) in values.iter().enumerate() {
+                    fused[level][i * 4 + c] += value * weight[i];
+                }
+            }
+            colors = smaller;
+            weight = smaller_weight;
+        }
+    }
+
+    // Collapse the pyramid from the top.
+    let mut values = fused.pop().unwrap_or_default();
+    for (level, details) in fused.into_iter().enumerate().rev() {
+        values = double(&values, sizes[level + 1], sizes[level], 4);
+        for (value, detail) in values.iter_mut().zip(details) {
+            *value += detail;
+        }
+    }
+
+    Ok(Image {
+        width: first.width,
+        height: first.height,
+        bytes: values.iter().map(|v| v.round().clamp(0.0, 255.0) as u8).collect(),
+    })
+}
+
+/// Lay images out in a grid, eg. for contact sheets and gallery previews.
+///
+/// Each image is resized to fit a cell of cell_size, keeping its aspect ratio, and centered in it.
+/// Cells are filled row by row with padding pixels between them and around the grid. The space
+/// not covered by an image is filled with the background color.
+///
+/// # Errors
+///
+/// If there are no images or the cell size is not positive, this fails with
+/// `RasterError::InvalidDimensions`. If columns is not positive, this fails with
+/// `RasterError::InvalidColumns`. If the padding is negative, this fails with
+/// `RasterError::InvalidSpacing`.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, Color};
+///
+/// let image1 = raster::open("tests/in/sample.jpg").unwrap();
+/// let image2 = raster::open("tests/in/portrait.jpg").unwrap();
+/// let image3 = raster::open("tests/in/crop-test.jpg").unwrap();
+/// let sheet = editor::montage(&[&image1, &image2, &image3], 2, (150, 100), 8, Color::white())
+///     .unwrap();
+/// raster::save(&sheet, "tests/out/test_montage.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_montage.jpg)
+///
+pub fn montage(
+    images: &[&Image],
+    columns: i32,
+    cell_size: (i32, i32),
+    padding: i32,
+    background: Color,
+) -> RasterResult<Image> {
+    let (cell_w, cell_h) = cell_size;
+    if images.is_empty() {
+        return Err(RasterError::InvalidDimensions(0, 0));
+    }
+    if columns <= 0 {
+        return Err(RasterError::InvalidColumns(columns));
+    }
+    if padding < 0 {
+        return Err(RasterError::InvalidSpacing(padding));
+    }
+    if cell_w <= 0 || cell_h <= 0 {
+        return Err(RasterError::InvalidDimensions(cell_w, cell_h));
+    }
+
+    let columns = cmp::min(columns, images.len() as i32);
+    let rows = (images.len() as i32 + columns - 1) / columns;
+    let mut canvas = Image::blank(
+        columns * (cell_w + padding) + padding,
+        rows * (cell_h + padding) + padding,
+    );
+    fill(&mut canvas, background)?;
+
+    for (i, image) in images.iter().enumerate() {
+        let mut cell = (*image).clone();
+        resize(&mut cell, cell_w, cell_h, ResizeMode::Fit)?;
+        let (column, row) = (i as i32 % columns, i as i32 / columns);
+        let x = padding + column * (cell_w + padding) + (cell_w - cell.width) / 2;
+        let y = padding + row * (cell_h + padding) + (cell_h - cell.height) / 2;
+        paste(&mut canvas, &cell, x, y, PasteMode::Over)?;
+    }
+
+    Ok(canvas)
+}
+
+/// Options for packing images into a sheet with `editor::pack`.
+///
+/// The padding defaults to 0 and the sheet size is not rounded.
+///
+/// # Examples
+/// ```
+/// use raster::PackOptions;
+///
+/// // Sheets at most 1024 pixels wide, 2 pixels between sprites, for GPU textures
+/// let options = PackOptions::new(1024).padding(2).power_of_two(true);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PackOptions {
+    max_width: i32,
+    padding: i32,
+    power_of_two: bool,
+}
+
+impl PackOptions {
+    /// Create options for sheets at most max_width pixels wide.
+    pub fn new(max_width: i32) -> PackOptions {
+        PackOptions {
+            max_width,
+            padding: 0,
+            power_of_two: false,
+        }
+    }
+
+    /// Leave padding pixels between the images and around the sheet, so that sampling a sprite
+    /// does not bleed into its neighbors.
+    pub fn padding(self, padding: i32) -> PackOptions {
+        PackOptions { padding, ..self }
+    }
+
+    /// Round the width and height of the sheet up to powers of two.
+    pub fn power_of_two(self, power_of_two: bool) -> PackOptions {
+        PackOptions {
+            power_of_two,
+            ..self
+        }
+    }
+}
+
+/// Pack images into one sheet, eg. for texture atlases. This is the inverse of slicing a sprite
+/// sheet.
+///
+/// Returns the sheet and where each image was placed, in the order of the images. Images are
+/// sorted from tallest to shortest and put on the first row of the sheet with room left, which
+/// wastes little space for sprites of similar heights. The space not covered by an image is
+/// transparent.
+///
+/// # Errors
+///
+/// If there are no images, this fails with `RasterError::InvalidDimensions`. If the padding is
+/// negative, this fails with `RasterError::InvalidSpacing`. If an image does not fit in the
+/// maximum width, this fails with `RasterError::InvalidSheetWidth`.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, PackOptions};
+///
+/// let image1 = raster::open("tests/in/sample.jpg").unwrap();
+/// let image2 = raster::open("tests/in/watermark.png").unwrap();
+/// let options = PackOptions::new(1024).padding(2);
+/// let (sheet, rects) = editor::pack(&[&image1, &image2], &options).unwrap();
+/// assert_eq!(2, rects.len());
+/// raster::save(&sheet, "tests/out/test_pack.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_pack.png)
+///
+pub fn pack(images: &[&Image], options: &PackOptions) -> RasterResult<(Image, Vec<Rect>)> {
+    let padding = options.padding;
+    if images.is_empty() {
+        return Err(RasterError::InvalidDimensions(0, 0));
+    }
+    if padding < 0 {
+        return Err(RasterError::InvalidSpacing(padding));
+    }
+    if images.iter().any(|image| image.width + padding * 2 > options.max_width) {
+        return Err(RasterError::InvalidSheetWidth(options.max_width));
+    }
+
+    let mut order: Vec<usize> = (0..images.len()).collect();
+    order.sort_by_key(|&i| cmp::Reverse(images[i].height));
+
+    // Rows of the sheet as (top, height, used width).
+    let mut shelves: Vec<(i32, i32, i32)> = Vec::new();
+    let mut rects = vec![Rect::new(0, 0, 0, 0); images.len()];
+    let mut bottom = padding;
+    for i in order {
+        let image = images[i];
+        let room = |shelf: &&mut (i32, i32, i32)| {
+            image.height <= shelf.1 && shelf.2 + image.width + padding <= options.max_width
+        };
+        let shelf = match shelves.iter_mut().find(room) {
+            Some(shelf) => shelf,
+            None => {
+                shelves.push((bottom, image.height, padding));
+                bottom += image.height + padding;
+                shelves.last_mut().unwrap()
+            }
+        };
+        rects[i] = Rect::new(shelf.2, shelf.0, image.width, image.height);
+        shelf.2 += image.width + padding;
+    }
+
+    let mut width = shelves.iter().map(|shelf| shelf.2).max().unwrap_or(0);
+    let mut height = bottom;
+    if options.power_of_two {
+        width = (width as u32).next_power_of_two() as i32;
+        height = (height as u32).next_power_of_two() as i32;
+    }
+
+    let mut sheet = Image {
+        width,
+        height,
+        bytes: vec![0; (width * height) as usize * 4],
+    };
+    for (image, rect) in images.iter().zip(rects.iter()) {
+        paste(&mut sheet, image, rect.x, rect.y, PasteMode::Replace)?;
+    }
+
+    Ok((sheet, rects))
+}
+
+/// Place the image on a larger canvas of the background color. This is the inverse of crop.
+///
+/// Useful to letterbox an image to a fixed aspect ratio without scaling it.
+///
+/// # Errors
+///
+/// If the width or height is smaller than the image, this fails with
+/// `RasterError::InvalidDimensions`.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, Color, PositionMode};
+///
+/// // Letterbox to a square
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let size = image.width.max(image.height);
+/// editor::pad(&mut image, size, size, PositionMode::Center, Color::black()).unwrap();
+/// raster::save(&image, "tests/out/test_pad.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_pad.jpg)
+///
+pub fn pad(
+    src: &mut Image,
+    width: i32,
+    height: i32,
+    position: PositionMode,
+    background: Color,
+) -> RasterResult<()> {
+    if width < src.width || height < src.height {
+        return Err(RasterError::InvalidDimensions(width, height));
+    }
+
+    let positioner = Position::new(position, 0, 0);
+    let (x, y) = positioner.get_x_y(width, height, src.width, src.height)?;
+    extend_canvas(src, width, height, x, y, &background);
+    Ok(())
+}
+
+/// An enum for the ways `editor::paste` puts pixels on the base image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PasteMode {
+    /// Copy the pixels as they are, alpha included.
+    Replace,
+    /// Composite the pixels over the base using their alpha. Opaque and fully transparent pixels
+    /// are still copied or skipped without blend math.
+    Over,
+}
+
+/// Paste an image on top of another at a given position, without blend modes or opacity.
+///
+/// Much faster than `editor::blend` for stamping sprites: with `PasteMode::Replace` whole rows are
+/// copied at once. Parts of top outside of the base are clipped, it may even be completely outside.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, Image, PasteMode};
+///
+/// let mut base = raster::open("tests/in/sample.jpg").unwrap();
+/// let sprite = raster::open("tests/in/watermark.png").unwrap();
+/// editor::paste(&mut base, &sprite, 10, 10, PasteMode::Over).unwrap();
+/// raster::save(&base, "tests/out/test_paste.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_paste.png)
+///
+pub fn paste(src: &mut Image, top: &Image, x: i32, y: i32, mode: PasteMode) -> RasterResult<()> {
+    // Columns and rows of top that land on the base.
+    let (x0, x1) = ((-x).max(0), top.width.min(src.width - x));
+    let (y0, y1) = ((-y).max(0), top.height.min(src.height - y));
+    if x0 >= x1 || y0 >= y1 {
+        return Ok(());
+    }
+
+    let len = (x1 - x0) as usize * 4;
+    for ty in y0..y1 {
+        let from = ((ty * top.width + x0) * 4) as usize;
+        let to = (((ty + y) * src.width + x0 + x) * 4) as usize;
+        let top_row = &top.bytes[from..from + len];
+        let base_row = &mut src.bytes[to..to + len];
+        match mode {
+            PasteMode::Replace => base_row.copy_from_slice(top_row),
+            PasteMode::Over => {
+                for (base, top) in base_row.chunks_mut(4).zip(top_row.chunks(4)) {
+                    match top[3] {
+                        255 => base.copy_from_slice(top),
+                        0 => {}
+                        alpha => {
+                            // Source-over, see https://www.w3.org/TR/compositing-1/#generalformula
+                            let a2 = alpha as f32 / 255.0;
+                            let a1 = base[3] as f32 / 255.0;
+                            let a = a2 + a1 * (1.0 - a2);
+                            for c in 0..3 {
+                                let mixed = top[c] as f32 * a2 + base[c] as f32 * a1 * (1.0 - a2);
+                                base[c] = (mixed / a).round() as u8;
+                            }
+                            base[3] = (a * 255.0).round() as u8;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// An enum for the various modes that can be used for resizing.
+///
+/// Can also be parsed from the mode names used by older versions of raster, eg. "exact_width".
+///
+/// # Examples
+/// ```
+/// use raster::ResizeMode;
+///
+/// let mode: ResizeMode = "fit".parse().unwrap();
+/// assert_eq!(ResizeMode::Fit, mode);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResizeMode {
+    /// Resize image to exact dimensions ignoring aspect ratio.
+    Exact,
+    /// Resize image to exact width. Height parameter is ignored and is auto calculated instead.
+    ExactWidth,
+    /// Resize image to exact height. Width parameter is ignored and is auto calculated instead.
+    ExactHeight,
+    /// Resize an image to fit within the given width and height.
+    Fit,
+    /// Resize image to fill all the space in the given dimension. Excess parts are cropped.
+    Fill,
+}
+
+impl FromStr for ResizeMode {
+    type Err = RasterError;
+
+    fn from_str(mode: &str) -> RasterResult<ResizeMode> {
+        match mode {
+            "exact" => Ok(ResizeMode::Exact),
+            "exact_width" => Ok(ResizeMode::ExactWidth),
+            "exact_height" => Ok(ResizeMode::ExactHeight),
+            "fit" => Ok(ResizeMode::Fit),
+            "fill" => Ok(ResizeMode::Fill),
+            _ => Err(RasterError::InvalidMode(mode.to_string())),
+        }
+    }
+}
+
+/// Resize an image to a given width, height and mode.
+///
+/// # Examples
+/// ### Resize Fit
+/// ```
+/// use raster::{editor, Color, Image, ResizeMode, BlendMode, PositionMode};
+///
+/// // Create an image from file
+/// let mut image1 = raster::open("tests/in/sample.jpg").unwrap();
+/// let mut image2 = raster::open("tests/in/portrait.jpg").unwrap();
+///
+/// // Resize it
+/// editor::resize(&mut image1, 200, 200, ResizeMode::Fit).unwrap();
+/// editor::resize(&mut image2, 200, 200, ResizeMode::Fit).unwrap();
+///
+/// // Superimpose images on a gray background
+/// let mut bg = Image::blank(200, 200);
+/// editor::fill(&mut bg, Color::hex("#CCCCCC").unwrap()).unwrap();
+///
+/// let image1 = editor::blend(&bg, &image1, BlendMode::Normal, 1.0, PositionMode::TopLeft, 0, 0).unwrap();
+/// let image2 = editor::blend(&bg, &image2, BlendMode::Normal, 1.0, PositionMode::TopLeft, 0, 0).unwrap();
+///
+/// raster::save(&image1, "tests/out/test_resize_fit_1.jpg").unwrap();
+/// raster::save(&image2, "tests/out/test_resize_fit_2.jpg").unwrap();
+/// ```
+///
+/// The gray box shows the 200x200 imaginary box that the images "fit" in.
+///
+///
+/// ![](https://kosinix.github.io/raster/out/test_resize_fit_1.jpg) ![](https://kosinix.github.io/raster/out/test_resize_fit_2.jpg)
+///
+/// ### Resize Fill
+/// ```
+/// use raster::{editor, Color, Image, ResizeMode};
+///
+/// // Create an image from file
+/// let mut image1 = raster::open("tests/in/sample.jpg").unwrap();
+/// let mut image2 = raster::open("tests/in/portrait.jpg").unwrap();
+///
+/// // Resize it
+/// editor::resize(&mut image1, 200, 200, ResizeMode::Fill).unwrap();
+/// editor::resize(&mut image2, 200, 200, ResizeMode::Fill).unwrap();
+///
+/// raster::save(&image1, "tests/out/test_resize_fill_1.jpg").unwrap();
+/// raster::save(&image2, "tests/out/test_resize_fill_2.jpg").unwrap();
+/// ```
+///
+/// The image fills up the entire 200x200 box.
+///
+/// ![](https://kosinix.github.io/raster/out/test_resize_fill_1.jpg) ![](https://kosinix.github.io/raster/out/test_resize_fill_2.jpg)
+///
+/// ### Resize to Exact Width
+/// ```
+/// use raster::{editor, Color, Image, ResizeMode};
+///
+/// // Create an image from file
 /// let mut image1 = raster::open("tests/in/sample.jpg").unwrap();
 /// let mut image2 = raster::open("tests/in/portrait.jpg").unwrap();
 ///
@@ -469,11 +1486,823 @@ pub enum ResizeMode {
 /// ![](https://kosinix.github.io/raster/out/test_resize_exact_1.jpg) ![](https://kosinix.github.io/raster/out/test_resize_exact_2.jpg)
 ///
 pub fn resize(src: &mut Image, w: i32, h: i32, mode: ResizeMode) -> RasterResult<()> {
+    resize_with(src, w, h, mode, InterpolationMode::Bicubic)
+}
+
+/// Resize an image to a given width, height and mode using a given interpolation method.
+///
+/// `editor::resize` uses `InterpolationMode::Bicubic`. Use `InterpolationMode::Lanczos3` for
+/// higher quality downscaling of photos or `InterpolationMode::Nearest` to keep hard pixel edges.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, InterpolationMode, ResizeMode};
+///
+/// // Create an image from file
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+///
+/// // Resize it
+/// editor::resize_with(&mut image, 200, 200, ResizeMode::Fit, InterpolationMode::Lanczos3).unwrap();
+///
+/// raster::save(&image, "tests/out/test_resize_fit_lanczos3.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_resize_fit_lanczos3.jpg)
+///
+pub fn resize_with(
+    src: &mut Image,
+    w: i32,
+    h: i32,
+    mode: ResizeMode,
+    interpolation: InterpolationMode,
+) -> RasterResult<()> {
     match mode {
-        ResizeMode::Exact => transform::resize_exact(src, w, h),
-        ResizeMode::ExactWidth => transform::resize_exact_width(src, w),
-        ResizeMode::ExactHeight => transform::resize_exact_height(src, h),
-        ResizeMode::Fit => transform::resize_fit(src, w, h),
-        ResizeMode::Fill => transform::resize_fill(src, w, h),
+        ResizeMode::Exact => transform::resize_exact_with(src, w, h, interpolation),
+        ResizeMode::ExactWidth => transform::resize_exact_width_with(src, w, interpolation),
+        ResizeMode::ExactHeight => transform::resize_exact_height_with(src, h, interpolation),
+        ResizeMode::Fit => transform::resize_fit_with(src, w, h, interpolation),
+        ResizeMode::Fill => transform::resize_fill_with(src, w, h, interpolation),
+    }
+}
+
+/// Resize an image to a given width and height by seam carving, without distorting its subjects.
+///
+/// Paths of pixels with the least detail, called seams, are removed one at a time until the image
+/// is small enough, so flat backgrounds shrink while detailed areas are kept. To enlarge, the
+/// seams that would be removed first are duplicated, up to the current size at a time. Each seam
+/// needs a pass over the whole image, so this is much slower than `editor::resize`.
+///
+/// # Errors
+///
+/// If the width or height is zero or negative, this fails with `RasterError::InvalidDimensions`.
+///
+/// # Examples
+/// ```
+/// use raster::editor;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// editor::resize(&mut image, 160, 120, raster::ResizeMode::Fit).unwrap();
+/// editor::resize_liquid(&mut image, 120, 120).unwrap();
+/// raster::save(&image, "tests/out/test_resize_liquid.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_resize_liquid.jpg)
+///
+pub fn resize_liquid(src: &mut Image, w: i32, h: i32) -> RasterResult<()> {
+    if w <= 0 || h <= 0 {
+        return Err(RasterError::InvalidDimensions(w, h));
+    }
+    if src.width <= 0 || src.height <= 0 {
+        return Ok(());
+    }
+
+    carve_width(src, w);
+    // Horizontal seams are the vertical seams of the transposed image.
+    transpose(src);
+    carve_width(src, h);
+    transpose(src);
+
+    Ok(())
+}
+
+/// Resize an image by stretching its middle only, keeping the corners as they are. Also known as
+/// nine-patch or 9-slice scaling, eg. for buttons and speech bubbles.
+///
+/// The insets are the sizes of the top, right, bottom and left borders. The corners are copied
+/// unchanged, the top and bottom edges are stretched horizontally, the left and right edges
+/// vertically and the center both ways.
+///
+/// # Errors
+///
+/// If an inset is negative, the insets do not fit in the image or the new size is smaller than
+/// the insets, this fails with `RasterError::InvalidDimensions`.
+///
+/// # Examples
+/// ```
+/// use raster::editor;
+///
+/// let mut image = raster::open("tests/in/watermark.png").unwrap();
+/// editor::resize_nine_patch(&mut image, (10, 10, 10, 10), 400, 120).unwrap();
+/// raster::save(&image, "tests/out/test_resize_nine_patch.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_resize_nine_patch.png)
+///
+pub fn resize_nine_patch(
+    src: &mut Image,
+    insets: (i32, i32, i32, i32),
+    w: i32,
+    h: i32,
+) -> RasterResult<()> {
+    let (top, right, bottom, left) = insets;
+    let fits = top >= 0
+        && right >= 0
+        && bottom >= 0
+        && left >= 0
+        && left + right <= cmp::min(src.width, w)
+        && top + bottom <= cmp::min(src.height, h);
+    if !fits {
+        return Err(RasterError::InvalidDimensions(w, h));
+    }
+
+    // Start and size of the 3 columns and rows, in the image and in the result.
+    let columns = [
+        (0, left, 0, left),
+        (left, src.width - left - right, left, w - left - right),
+        (src.width - right, right, w - right, right),
+    ];
+    let rows = [
+        (0, top, 0, top),
+        (top, src.height - top - bottom, top, h - top - bottom),
+        (src.height - bottom, bottom, h - bottom, bottom),
+    ];
+
+    let mut dest = Image::blank(w, h);
+    for &(y, height, dest_y, dest_height) in &rows {
+        for &(x, width, dest_x, dest_width) in &columns {
+            if width == 0 || height == 0 || dest_width == 0 || dest_height == 0 {
+                continue;
+            }
+            let mut patch = src.clone();
+            crop(&mut patch, width, height, PositionMode::TopLeft, x, y)?;
+            if (width, height) != (dest_width, dest_height) {
+                transform::resize_exact_with(
+                    &mut patch,
+                    dest_width,
+                    dest_height,
+                    InterpolationMode::Bilinear,
+                )?;
+            }
+            paste(&mut dest, &patch, dest_x, dest_y, PasteMode::Replace)?;
+        }
+    }
+    *src = dest;
+
+    Ok(())
+}
+
+/// Round the corners of an image, making them transparent with a smooth edge.
+///
+/// The radius is limited to half the width or height of the image, so a large radius on a square
+/// image gives a circle. Save as PNG to keep the transparency.
+///
+/// # Errors
+///
+/// If the radius is negative, this fails with `RasterError::InvalidRadius`.
+///
+/// # Examples
+/// ```
+/// use raster::editor;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// editor::round_corners(&mut image, 30).unwrap();
+/// raster::save(&image, "tests/out/test_round_corners.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_round_corners.png)
+///
+pub fn round_corners(src: &mut Image, radius: i32) -> RasterResult<()> {
+    if radius < 0 {
+        return Err(RasterError::InvalidRadius(radius));
+    }
+    let r = radius.min(src.width / 2).min(src.height / 2) as f32;
+    if r == 0.0 {
+        return Ok(());
+    }
+
+    // Distance of a pixel center past the center of the nearest corner circle, or 0.
+    let past = |p: i32, size: i32| -> f32 {
+        let p = p as f32 + 0.5;
+        if p < r {
+            r - p
+        } else if p > size as f32 - r {
+            p - (size as f32 - r)
+        } else {
+            0.0
+        }
+    };
+
+    let (w, h) = (src.width, src.height);
+    for (i, pixel) in src.bytes.chunks_mut(4).enumerate() {
+        let (dx, dy) = (past(i as i32 % w, w), past(i as i32 / w, h));
+        if dx > 0.0 && dy > 0.0 {
+            let coverage = (r - dx.hypot(dy) + 0.5).clamp(0.0, 1.0);
+            pixel[3] = (pixel[3] as f32 * coverage).round() as u8;
+        }
+    }
+    Ok(())
+}
+
+/// An enum for the ways `editor::stack` combines the frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StackMode {
+    /// Average of the frames. Reduces random noise, eg. of burst shots.
+    Mean,
+    /// Median of the frames. Also removes things only in a few frames, eg. passers-by.
+    Median,
+    /// Brightest value of the frames, eg. for star trails or light painting.
+    Max,
+    /// Darkest value of the frames.
+    Min,
+}
+
+/// Combine frames of the same scene into one image, channel by channel.
+///
+/// Each channel of each pixel, alpha included, becomes the mean, median, maximum or minimum of
+/// that channel in all the frames. With an even number of frames the median is the average of
+/// the 2 middle values. The frames should be aligned, eg. shot from a tripod.
+///
+/// # Errors
+///
+/// If there are no frames, this fails with `RasterError::InvalidDimensions`. If the frames do not
+/// all have the same dimensions, this fails with `RasterError::DimensionsMismatch`.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, filter, NoiseType, StackMode};
+///
+/// let image = raster::open("tests/in/sample.jpg").unwrap();
+/// let mut frames = Vec::new();
+/// for seed in 0..5 {
+///     let mut frame = image.clone();
+///     filter::add_noise(&mut frame, NoiseType::Gaussian, 0.2, false, seed).unwrap();
+///     frames.push(frame);
+/// }
+/// let frames: Vec<_> = frames.iter().collect();
+/// let stacked = editor::stack(&frames, StackMode::Median).unwrap();
+/// raster::save(&stacked, "tests/out/test_stack.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_stack.jpg)
+///
+pub fn stack(frames: &[&Image], mode: StackMode) -> RasterResult<Image> {
+    let first = match frames.first() {
+        Some(first) => first,
+        None => return Err(RasterError::InvalidDimensions(0, 0)),
+    };
+    if frames
+        .iter()
+        .any(|f| f.width != first.width || f.height != first.height)
+    {
+        return Err(RasterError::DimensionsMismatch);
+    }
+
+    let n = frames.len();
+    let mut values = vec![0u8; n];
+    let mut stacked = (*first).clone();
+    for (i, byte) in stacked.bytes.iter_mut().enumerate() {
+        for (value, frame) in values.iter_mut().zip(frames.iter()) {
+            *value = frame.bytes[i];
+        }
+        *byte = match mode {
+            StackMode::Mean => {
+                let sum: usize = values.iter().map(|&v| v as usize).sum();
+                ((sum + n / 2) / n) as u8
+            }
+            StackMode::Median => {
+                values.sort_unstable();
+                let (low, high) = (values[(n - 1) / 2] as u16, values[n / 2] as u16);
+                (low + high).div_ceil(2) as u8
+            }
+            StackMode::Max => values.iter().cloned().max().unwrap_or(0),
+            StackMode::Min => values.iter().cloned().min().unwrap_or(0),
+        };
+    }
+
+    Ok(stacked)
+}
+
+/// Join strips that overlap side by side, eg. scans of a large page or map tiles, blending the
+/// seams.
+///
+/// Each image overlaps the previous one by overlap_px columns. In the middle of the overlap the
+/// previous image fades into the next one over blend_width columns, at most the overlap. A blend
+/// width of 0 gives a hard seam in the middle of the overlap. The images should be aligned and
+/// have the same height.
+///
+/// # Errors
+///
+/// If there are no images, this fails with `RasterError::InvalidDimensions`. If overlap_px or
+/// blend_width is negative or if the overlap is wider than an image, this fails with
+/// `RasterError::InvalidOverlap` with the offending value. If the images do not all have the same
+/// height, this fails with `RasterError::DimensionsMismatch`.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, PositionMode};
+///
+/// let image = raster::open("tests/in/sample.jpg").unwrap();
+/// let (w, h) = (image.width, image.height);
+/// let mut left = image.clone();
+/// editor::crop(&mut left, w / 2 + 40, h, PositionMode::TopLeft, 0, 0).unwrap();
+/// let mut right = image.clone();
+/// editor::crop(&mut right, w - w / 2 + 40, h, PositionMode::TopRight, 0, 0).unwrap();
+/// let stitched = editor::stitch(&[&left, &right], 80, 40).unwrap();
+/// assert_eq!(w, stitched.width);
+/// raster::save(&stitched, "tests/out/test_stitch.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_stitch.jpg)
+///
+pub fn stitch(images: &[&Image], overlap_px: i32, blend_width: i32) -> RasterResult<Image> {
+    let first = match images.first() {
+        Some(first) => first,
+        None => return Err(RasterError::InvalidDimensions(0, 0)),
+    };
+    if overlap_px < 0 || images.iter().any(|i| i.width < overlap_px) {
+        return Err(RasterError::InvalidOverlap(overlap_px));
+    }
+    if blend_width < 0 {
+        return Err(RasterError::InvalidOverlap(blend_width));
+    }
+    if images.iter().any(|i| i.height != first.height) {
+        return Err(RasterError::DimensionsMismatch);
+    }
+
+    let overlap = overlap_px as usize;
+    let blend = cmp::min(blend_width, overlap_px) as usize;
+    // Share of the next image in each column of the overlap.
+    let start = (overlap - blend) / 2;
+    let shares: Vec<f32> = (0..overlap)
+        .map(|i| {
+            if blend == 0 {
+                if i >= overlap / 2 {
+                    1.0
+                } else {
+                    0.0
+                }
+            } else {
+                ((i as f32 - start as f32 + 0.5) / blend as f32).clamp(0.0, 1.0)
+            }
+        })
+        .collect();
+
+    let height = first.height as usize;
+    let mut rows: Vec<Vec<u8>> = first
+        .bytes
+        .chunks(first.width.max(1) as usize * 4)
+        .map(|row| row.to_vec())
+        .collect();
+    rows.resize(height, Vec::new());
+    for image in &images[1..] {
+        let width = image.width as usize;
+        for (y, row) in rows.iter_mut().enumerate() {
+            let next = &image.bytes[y * width * 4..(y + 1) * width * 4];
+            let seam = row.len() - overlap * 4;
+            for (i, (value, &top)) in row[seam..].iter_mut().zip(next).enumerate() {
+                let share = shares[i / 4];
+                *value = (*value as f32 + (top as f32 - *value as f32) * share).round() as u8;
+            }
+            row.extend_from_slice(&next[overlap * 4..]);
+        }
+    }
+
+    let width = rows.first().map_or(0, |row| row.len() / 4) as i32;
+    Ok(Image {
+        width,
+        height: first.height,
+        bytes: rows.concat(),
+    })
+}
+
+/// Resize an image to several sizes at once, eg. for responsive images.
+///
+/// Each size is a width, height and mode as given to `editor::resize`. The largest sizes are made
+/// first and smaller ones are resized from the smallest result still large enough, rather than
+/// from the original. This is faster and each step shrinks the image less, which softens aliasing.
+/// Only results that keep the whole image with its aspect ratio are reused, so `ResizeMode::Fit`,
+/// `ResizeMode::ExactWidth` and `ResizeMode::ExactHeight`. The thumbnails are returned in the
+/// order of the sizes.
+///
+/// # Errors
+///
+/// If the image has a zero width or height, this fails with `RasterError::InvalidDimensions`.
+///
+/// Fails like `editor::resize` if a size is invalid.
+///
+/// # Examples
+/// ```
+/// use raster::{editor, ResizeMode};
+///
+/// let image = raster::open("tests/in/sample.jpg").unwrap();
+/// let sizes = [
+///     (400, 400, ResizeMode::Fit),
+///     (200, 200, ResizeMode::Fit),
+///     (100, 100, ResizeMode::Fill),
+/// ];
+/// let thumbnails = editor::thumbnails(&image, &sizes).unwrap();
+/// raster::save(&thumbnails[2], "tests/out/test_thumbnails.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_thumbnails.jpg)
+///
+pub fn thumbnails(src: &Image, sizes: &[(i32, i32, ResizeMode)]) -> RasterResult<Vec<Image>> {
+    if src.width <= 0 || src.height <= 0 {
+        return Err(RasterError::InvalidDimensions(src.width, src.height));
+    }
+
+    // Size of the whole image once resized, before any crop.
+    let (width, height) = (src.width as f32, src.height as f32);
+    let scaled = |&(w, h, mode): &(i32, i32, ResizeMode)| -> (i32, i32) {
+        let (w, h) = (w as f32, h as f32);
+        let (sx, sy) = match mode {
+            ResizeMode::Exact => (w / width, h / height),
+            ResizeMode::ExactWidth => (w / width, w / width),
+            ResizeMode::ExactHeight => (h / height, h / height),
+            ResizeMode::Fit => {
+                let s = (w / width).min(h / height);
+                (s, s)
+            }
+            ResizeMode::Fill => {
+                let s = (w / width).max(h / height);
+                (s, s)
+            }
+        };
+        ((width * sx).ceil() as i32, (height * sy).ceil() as i32)
+    };
+
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| {
+        let (w, h) = scaled(&sizes[i]);
+        cmp::Reverse(w as i64 * h as i64)
+    });
+
+    let mut thumbnails: Vec<Option<Image>> = vec![None; sizes.len()];
+    for i in order {
+        let (w, h) = scaled(&sizes[i]);
+        let reusable = |j: usize| match sizes[j].2 {
+            ResizeMode::Fit | ResizeMode::ExactWidth | ResizeMode::ExactHeight => true,
+            ResizeMode::Exact | ResizeMode::Fill => false,
+        };
+        let source = thumbnails
+            .iter()
+            .enumerate()
+            .filter_map(|(j, thumbnail)| thumbnail.as_ref().filter(|_| reusable(j)))
+            .filter(|thumbnail| thumbnail.width >= w && thumbnail.height >= h)
+            .min_by_key(|thumbnail| thumbnail.width as i64 * thumbnail.height as i64)
+            .unwrap_or(src);
+
+        let mut thumbnail = source.clone();
+        let (w, h, mode) = sizes[i];
+        resize(&mut thumbnail, w, h, mode)?;
+        thumbnails[i] = Some(thumbnail);
+    }
+
+    Ok(thumbnails.into_iter().flatten().collect())
+}
+
+/// Repeat an image as a pattern over a new image of the given width and height, eg. for
+/// backgrounds.
+///
+/// The offset moves the pattern, the top left corner of a tile lands on it. With mirror, every
+/// other tile is flipped so that the edges of neighboring tiles match and no seams show.
+///
+/// # Errors
+///
+/// If the width or height is zero or negative, or the image is empty, this fails with
+/// `RasterError::InvalidDimensions`.
+///
+/// # Examples
+/// ```
+/// use raster::editor;
+///
+/// let image = raster::open("tests/in/watermark.png").unwrap();
+/// let pattern = editor::tile(&image, 800, 600, (0, 0), true).unwrap();
+/// raster::save(&pattern, "tests/out/test_tile.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_tile.png)
+///
+pub fn tile(src: &Image, w: i32, h: i32, offset: (i32, i32), mirror: bool) -> RasterResult<Image> {
+    if w <= 0 || h <= 0 {
+        return Err(RasterError::InvalidDimensions(w, h));
+    }
+    if src.width <= 0 || src.height <= 0 {
+        return Err(RasterError::InvalidDimensions(src.width, src.height));
+    }
+
+    // Position in the tile of a position on the canvas.
+    let wrap = |i: i32, size: i32| {
+        if mirror {
+            let m = i.rem_euclid(size * 2);
+            if m < size {
+                m
+            } else {
+                size * 2 - 1 - m
+            }
+        } else {
+            i.rem_euclid(size)
+        }
+    };
+
+    let mut dest = Image::blank(w, h);
+    for (i, pixel) in dest.bytes.chunks_mut(4).enumerate() {
+        let x = wrap(i as i32 % w - offset.0, src.width);
+        let y = wrap(i as i32 / w - offset.1, src.height);
+        let start = ((y * src.width + x) * 4) as usize;
+        pixel.copy_from_slice(&src.bytes[start..start + 4]);
+    }
+
+    Ok(dest)
+}
+
+/// Repeat a watermark over the whole image.
+///
+/// The watermark is rotated by angle degrees clockwise, eg. -45.0 for a diagonal going up, then
+/// tiled with spacing pixels between copies. Every other row is shifted by half a tile so that
+/// copies do not line up in columns. Opacity is any value from 0.0 - 1.0.
+///
+/// # Errors
+///
+/// If the watermark plus the spacing is not at least 1 pixel wide and high, this fails with
+/// `RasterError::InvalidSpacing`.
+///
+/// # Examples
+/// ```
+/// use raster::editor;
+///
+/// let image = raster::open("tests/in/sample.jpg").unwrap();
+/// let watermark = raster::open("tests/in/watermark.png").unwrap();
+/// let tiled = editor::watermark_tile(&image, &watermark, 20, -30.0, 0.4).unwrap();
+/// raster::save(&tiled, "tests/out/test_watermark_tile.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_watermark_tile.png)
+///
+pub fn watermark_tile(
+    src: &Image,
+    mark: &Image,
+    spacing: i32,
+    angle: f32,
+    opacity: f32,
+) -> RasterResult<Image> {
+    let opacity = opacity.clamp(0.0, 1.0);
+    let mut mark = mark.clone();
+    if angle != 0.0 {
+        transform::rotate(&mut mark, angle, Color::rgba(0, 0, 0, 0))?;
+    }
+
+    let (step_x, step_y) = (mark.width + spacing, mark.height + spacing);
+    if step_x <= 0 || step_y <= 0 {
+        return Err(RasterError::InvalidSpacing(spacing));
+    }
+
+    let mut canvas = src.clone();
+    let margin = spacing / 2;
+    let mut y = margin;
+    let mut row = 0;
+    while y < canvas.height {
+        let mut x = if row % 2 == 0 { margin } else { margin - step_x / 2 };
+        while x < canvas.width {
+            if let Some(overlap) =
+                Overlap::new(canvas.width, canvas.height, mark.width, mark.height, x, y)
+            {
+                blend::blend(&mut canvas, &mark, BlendMode::Normal, &overlap, opacity)?;
+            }
+            x += step_x;
+        }
+        y += step_y;
+        row += 1;
+    }
+
+    Ok(canvas)
+}
+
+// Private functions
+
+// Place the image at (x, y) on a larger canvas of the color.
+fn extend_canvas(src: &mut Image, w: i32, h: i32, x: i32, y: i32, color: &Color) {
+    let mut canvas = Image::blank(w, h);
+    for pixel in canvas.bytes.chunks_mut(4) {
+        pixel.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+    }
+
+    let row_len = src.width as usize * 4;
+    for (row, line) in src.bytes.chunks(row_len.max(1)).enumerate() {
+        let start = ((y as usize + row) * w as usize + x as usize) * 4;
+        canvas.bytes[start..start + row_len].copy_from_slice(line);
+    }
+    *src = canvas;
+}
+
+// Remove or duplicate vertical seams until the image is w pixels wide.
+fn carve_width(src: &mut Image, w: i32) {
+    while src.width > w {
+        let seam = find_seam(src);
+        remove_seam(src, &seam);
+    }
+
+    while src.width < w {
+        // Find the seams to duplicate by removing them from a copy, keeping track of the original
+        // column of each pixel left.
+        let n = (w - src.width).min(src.width);
+        let mut copy = src.clone();
+        let mut columns: Vec<Vec<usize>> =
+            (0..src.height).map(|_| (0..src.width as usize).collect()).collect();
+        let mut duplicate = vec![vec![false; src.width as usize]; src.height as usize];
+        for _ in 0..n {
+            let seam = find_seam(&copy);
+            for (y, &x) in seam.iter().enumerate() {
+                duplicate[y][columns[y].remove(x)] = true;
+            }
+            remove_seam(&mut copy, &seam);
+        }
+
+        // Each duplicated pixel is followed by the average of itself and its right neighbor.
+        let width = src.width as usize;
+        let mut bytes = Vec::with_capacity((src.width + n) as usize * src.height as usize * 4);
+        for (row, duplicate) in src.bytes.chunks(width * 4).zip(duplicate.iter()) {
+            for x in 0..width {
+                let pixel = &row[x * 4..x * 4 + 4];
+                bytes.extend_from_slice(pixel);
+                if duplicate[x] {
+                    let next = &row[(x + 1).min(width - 1) * 4..][..4];
+                    for c in 0..4 {
+                        bytes.push((pixel[c] as u16 + next[c] as u16).div_ceil(2) as u8);
+                    }
+                }
+            }
+        }
+        src.width += n;
+        src.bytes = bytes;
+    }
+}
+
+// Column of each row of the vertical seam with the least energy, the energy of a pixel being the
+// differences between its neighbors on both axes.
+fn find_seam(src: &Image) -> Vec<usize> {
+    let (width, height) = (src.width as usize, src.height as usize);
+    let at = |x: usize, y: usize| &src.bytes[(y * width + x) * 4..][..4];
+    let difference = |a: &[u8], b: &[u8]| -> u32 {
+        a.iter().zip(b).map(|(&a, &b)| (a as i32 - b as i32).unsigned_abs()).sum()
+    };
+
+    // Lowest total energy of a seam from the top down to each pixel.
+    let mut costs = vec![0u32; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let energy = difference(at(x.saturating_sub(1), y), at((x + 1).min(width - 1), y))
+                + difference(at(x, y.saturating_sub(1)), at(x, (y + 1).min(height - 1)));
+            let above = if y == 0 {
+                0
+            } else {
+                let row = &costs[(y - 1) * width..y * width];
+                *row[x.saturating_sub(1)..=(x + 1).min(width - 1)].iter().min().unwrap()
+            };
+            costs[y * width + x] = energy + above;
+        }
+    }
+
+    // Walk back up from the cheapest end.
+    let mut seam = vec![0; height];
+    let last = &costs[(height - 1) * width..];
+    let mut x = (0..width).min_by_key(|&x| last[x]).unwrap_or(0);
+    for y in (0..height).rev() {
+        if y < height - 1 {
+            let row = &costs[y * width..(y + 1) * width];
+            let from = x.saturating_sub(1);
+            x = (from..=(x + 1).min(width - 1)).min_by_key(|&x| row[x]).unwrap();
+        }
+        seam[y] = x;
+    }
+    seam
+}
+
+// Remove one pixel per row at the columns of the seam.
+fn remove_seam(src: &mut Image, seam: &[usize]) {
+    let width = src.width as usize;
+    let mut bytes = Vec::with_capacity((width - 1) * seam.len() * 4);
+    for (row, &x) in src.bytes.chunks(width * 4).zip(seam) {
+        bytes.extend_from_slice(&row[..x * 4]);
+        bytes.extend_from_slice(&row[(x + 1) * 4..]);
+    }
+    src.width -= 1;
+    src.bytes = bytes;
+}
+
+// Swap the rows and columns of the image.
+fn transpose(src: &mut Image) {
+    let (width, height) = (src.width as usize, src.height as usize);
+    let mut bytes = vec![0; src.bytes.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let from = (y * width + x) * 4;
+            let to = (x * height + y) * 4;
+            bytes[to..to + 4].copy_from_slice(&src.bytes[from..from + 4]);
+        }
     }
+    src.width = height as i32;
+    src.height = width as i32;
+    src.bytes = bytes;
+}
+
+// Weight of each pixel for exposure fusion: the product of its contrast, saturation and how
+// well exposed it is.
+fn fusion_weights(src: &Image) -> Vec<f32> {
+    let (w, h) = (src.width as usize, src.height as usize);
+    let gray: Vec<f32> = src
+        .bytes
+        .chunks(4)
+        .map(|p| (p[0] as f32 + p[1] as f32 + p[2] as f32) / 765.0)
+        .collect();
+    let at = |x: usize, y: usize| gray[y * w + x];
+
+    let mut weights = Vec::with_capacity(w * h);
+    for (i, p) in src.bytes.chunks(4).enumerate() {
+        let (x, y) = (i % w, i / w);
+        let neighbors = at(x.saturating_sub(1), y)
+            + at((x + 1).min(w - 1), y)
+            + at(x, y.saturating_sub(1))
+            + at(x, (y + 1).min(h - 1));
+        let contrast = (neighbors - 4.0 * gray[i]).abs();
+
+        let rgb = [p[0] as f32 / 255.0, p[1] as f32 / 255.0, p[2] as f32 / 255.0];
+        let mean = (rgb[0] + rgb[1] + rgb[2]) / 3.0;
+        let saturation = (rgb.iter().map(|c| (c - mean) * (c - mean)).sum::<f32>() / 3.0).sqrt();
+        let exposedness: f32 = rgb
+            .iter()
+            .map(|c| (-(c - 0.5) * (c - 0.5) / 0.08).exp())
+            .product();
+
+        weights.push(contrast * saturation * exposedness);
+    }
+    weights
+}
+
+// Average the blocks of 2x2 pixels of channels values per pixel, cut at the edges.
+fn halve(values: &[f32], w: usize, h: usize, channels: usize) -> Vec<f32> {
+    let (hw, hh) = (w.div_ceil(2), h.div_ceil(2));
+    let mut halved = vec![0.0; hw * hh * channels];
+    for y in 0..hh {
+        for x in 0..hw {
+            let (xs, ys) = (2 * x..(2 * x + 2).min(w), 2 * y..(2 * y + 2).min(h));
+            let n = (xs.len() * ys.len()) as f32;
+            for sy in ys {
+                for sx in xs.clone() {
+                    for c in 0..channels {
+                        halved[(y * hw + x) * channels + c] +=
+                            values[(sy * w + sx) * channels + c] / n;
+                    }
+                }
+            }
+        }
+    }
+    halved
+}
+
+// Scale values up from size to a size about twice as large with bilinear interpolation.
+fn double(values: &[f32], size: (usize, usize), to: (usize, usize), channels: usize) -> Vec<f32> {
+    let ((w, h), (tw, th)) = (size, to);
+    // Position in the small image of a pixel center, and its neighbor on the other side.
+    let source = |t: usize, len: usize| -> (usize, usize, f32) {
+        let s = ((t as f32 + 0.5) / 2.0 - 0.5).clamp(0.0, (len - 1) as f32);
+        let low = s.floor() as usize;
+        (low, (low + 1).min(len - 1), s - low as f32)
+    };
+
+    let mut doubled = Vec::with_capacity(tw * th * channels);
+    for y in 0..th {
+        let (y0, y1, fy) = source(y, h);
+        for x in 0..tw {
+            let (x0, x1, fx) = source(x, w);
+            for c in 0..channels {
+                let at = |x: usize, y: usize| values[(y * w + x) * channels + c];
+                let top = at(x0, y0) + (at(x1, y0) - at(x0, y0)) * fx;
+                let bottom = at(x0, y1) + (at(x1, y1) - at(x0, y1)) * fx;
+                doubled.push(top + (bottom - top) * fy);
+            }
+        }
+    }
+    doubled
+}
+
+// Blend image2 on top of the canvas in place.
+fn blend_layer(
+    canvas: &mut Image,
+    image2: &Image,
+    blend_mode: BlendMode,
+    opacity: f32,
+    position: PositionMode,
+    offset_x: i32,
+    offset_y: i32,
+) -> RasterResult<()> {
+    let opacity = opacity.clamp(0.0, 1.0);
+
+    // Turn into positioner struct
+    let positioner = Position::new(position, offset_x, offset_y);
+
+    // Position is for image2, canvas is image1.
+    let (offset_x, offset_y) =
+        positioner.get_x_y(canvas.width, canvas.height, image2.width, image2.height)?;
+
+    let overlap = Overlap::new(
+        canvas.width,
+        canvas.height,
+        image2.width,
+        image2.height,
+        offset_x,
+        offset_y,
+    ).ok_or(RasterError::BlendingImageFallsOutsideCanvas)?;
+
+    blend::blend(canvas, image2, blend_mode, &overlap, opacity)
 }
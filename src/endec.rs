@@ -1,6 +1,7 @@
 //!  A module for encoding/decoding.
 
 // from rust
+use std::borrow::Cow;
 use std::path::Path;
 use std::fs::File;
 use std::io::BufWriter;
@@ -13,6 +14,7 @@ use png;
 use error::{RasterError, RasterResult};
 use Image;
 use ImageFormat;
+use IndexedImage;
 
 // Decode GIF
 pub fn decode_gif(image_file: &File) -> RasterResult<Image> {
@@ -26,13 +28,13 @@ pub fn decode_gif(image_file: &File) -> RasterResult<Image> {
 
     // Read frame 1.
     // TODO: Work on all frames
-    if let Some(_) = reader.next_frame_info()? {
+    if reader.next_frame_info()?.is_some() {
         let mut bytes = vec![0; reader.buffer_size()];
         reader.read_into_buffer(&mut bytes)?;
         Ok(Image {
             width: reader.width() as i32,
             height: reader.height() as i32,
-            bytes: bytes,
+            bytes,
         })
     } else {
         Err(RasterError::Decode(
@@ -57,6 +59,30 @@ pub fn encode_gif(image: &Image, path: &Path) -> RasterResult<()> {
     Ok(())
 }
 
+// Encode GIF using the palette of an indexed image
+pub fn encode_gif_indexed(image: &IndexedImage, path: &Path) -> RasterResult<()> {
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    let mut palette = Vec::with_capacity(image.palette.len() * 3);
+    for color in &image.palette {
+        palette.extend_from_slice(&[color.r, color.g, color.b]);
+    }
+    // GIF only has one fully transparent color.
+    let transparent = image.palette.iter().position(|color| color.a == 0);
+
+    let frame = gif::Frame {
+        width: image.width as u16,
+        height: image.height as u16,
+        palette: Some(palette),
+        transparent: transparent.map(|index| index as u8),
+        buffer: Cow::Borrowed(&image.indices),
+        ..gif::Frame::default()
+    };
+    let mut encoder = gif::Encoder::new(writer, frame.width, frame.height, &[])?;
+    encoder.write_frame(&frame).map_err(RasterError::Io)?;
+    Ok(())
+}
+
 // Decode PNG
 pub fn decode_png(image_file: &File) -> RasterResult<Image> {
     let decoder = png::Decoder::new(image_file);
@@ -68,19 +94,17 @@ pub fn decode_png(image_file: &File) -> RasterResult<Image> {
     if info.color_type == png::ColorType::RGB {
         // Applies only to RGB
 
-        let mut insert_count = 0;
         let len = (info.width * info.height) as usize;
-        for i in 0..len {
+        for (insert_count, i) in (0..len).enumerate() {
             // TODO: This is slow!
             let insert_pos = 3 * (i + 1) + insert_count;
             bytes.insert(insert_pos, 255);
-            insert_count += 1;
         }
     } //  TODO other ::ColorType
     Ok(Image {
         width: info.width as i32,
         height: info.height as i32,
-        bytes: bytes,
+        bytes,
     })
 }
 
@@ -88,7 +112,7 @@ pub fn decode_png(image_file: &File) -> RasterResult<Image> {
 pub fn encode_png(image: &Image, path: &Path) -> RasterResult<()> {
     // Open the file with basic error check
     let file = File::create(path)?;
-    let ref mut w = BufWriter::new(file);
+    let w = &mut BufWriter::new(file);
 
     let mut encoder = png::Encoder::new(w, image.width as u32, image.height as u32);
     png::HasParameters::set(&mut encoder, png::ColorType::RGBA);
@@ -96,3 +120,27 @@ pub fn encode_png(image: &Image, path: &Path) -> RasterResult<()> {
     let mut writer = encoder.write_header()?;
     Ok(writer.write_image_data(&image.bytes)?)
 }
+
+// Encode PNG using the palette of an indexed image
+pub fn encode_png_indexed(image: &IndexedImage, path: &Path) -> RasterResult<()> {
+    let file = File::create(path)?;
+    let w = &mut BufWriter::new(file);
+
+    let mut encoder = png::Encoder::new(w, image.width as u32, image.height as u32);
+    png::HasParameters::set(&mut encoder, png::ColorType::Indexed);
+    png::HasParameters::set(&mut encoder, png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+
+    let mut palette = Vec::with_capacity(image.palette.len() * 3);
+    for color in &image.palette {
+        palette.extend_from_slice(&[color.r, color.g, color.b]);
+    }
+    writer.write_chunk(*b"PLTE", &palette)?;
+
+    // Alpha of the entries up to the last one which is not opaque.
+    let alphas: Vec<u8> = image.palette.iter().map(|color| color.a).collect();
+    if let Some(last) = alphas.iter().rposition(|&a| a < 255) {
+        writer.write_chunk(*b"tRNS", &alphas[..last + 1])?;
+    }
+    Ok(writer.write_image_data(&image.indices)?)
+}
@@ -1,6 +1,8 @@
 //!  A module for error types.
 
 // from rust
+use std::error::Error;
+use std::fmt;
 use std::io::Error as IoError;
 use std::num::ParseIntError;
 
@@ -35,10 +37,185 @@ pub enum RasterError {
     Encode(ImageFormat, String),
     /// Unsupported image format.
     UnsupportedFormat(String),
+    /// A mode string that does not match any known mode.
+    InvalidMode(String),
+    /// Width or height that is zero or negative.
+    InvalidDimensions(i32, i32),
+    /// Images that are expected to have the same width and height but don't.
+    DimensionsMismatch,
+    /// A geometric transformation that cannot be inverted, eg. a collapsed quadrilateral.
+    InvalidTransform,
+    /// A filter radius that is negative.
+    InvalidRadius(i32),
+    /// A Gaussian standard deviation that is zero or negative.
+    InvalidSigma(f32),
+    /// A convolution kernel that is not square with an odd size, or that has a zero divisor.
+    InvalidKernel,
+    /// A low and high threshold pair where a threshold is negative or the low one is above the
+    /// high one.
+    InvalidThresholds(f32, f32),
+    /// Contrast amount outside of -100.0 - 100.0.
+    InvalidContrast(f32),
+    /// Input black and white points where black is not below white.
+    InvalidLevels(u8, u8),
+    /// Tone curve with less than 2 points or with inputs that are not increasing, or Bézier curve
+    /// without 3 or 4 control points.
+    InvalidCurve,
+    /// A block size that is not an odd number of at least 3.
+    InvalidBlockSize(i32),
+    /// A percentage outside of the range accepted by a filter.
+    InvalidPercentage(f32),
+    /// A palette without colors, with more than 256 colors or with less than 2 levels.
+    InvalidPalette,
+    /// An image with more distinct colors than a palette can hold.
+    TooManyColors(usize),
+    /// A gradient without stops, or with stops outside of 0.0 - 1.0 or out of order.
+    InvalidGradient,
+    /// A line thickness or stroke width that is zero or negative.
+    InvalidThickness(f32),
+    /// Font data that cannot be parsed as a TrueType or OpenType font.
+    InvalidFont,
+    /// A font size that is zero or negative.
+    InvalidFontSize(f32),
+    /// A number of intensity levels outside of 1 - 256.
+    InvalidIntensityLevels(usize),
+    /// A wave length that is zero or negative.
+    InvalidWavelength(f32),
+    /// A tone mapping white point that is zero or negative.
+    InvalidWhitePoint(f32),
+    /// A structuring element that is not square with an odd size.
+    InvalidStructuringElement,
+    /// A shape radius that is zero or negative.
+    InvalidShapeRadius(f32),
+    /// A halftone cell size below 2.
+    InvalidCellSize(i32),
+    /// A text box width that is zero or negative.
+    InvalidWidth(f32),
+    /// A border width that is negative.
+    InvalidBorder(i32),
+    /// A spacing or padding between images that is too small, most often negative.
+    InvalidSpacing(i32),
+    /// A number of grid columns that is zero or negative.
+    InvalidColumns(i32),
+    /// A sheet width that an image and its padding do not fit in.
+    InvalidSheetWidth(i32),
+    /// An overlap or blend width between images that is negative or wider than an image.
+    InvalidOverlap(i32),
     /// Error that does not belong in other variants.
     Unexpected,
 }
 
+impl fmt::Display for RasterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RasterError::Io(ref err) => write!(f, "I/O error: {}", err),
+            RasterError::PixelOutOfBounds(x, y) => {
+                write!(f, "Pixel ({}, {}) is outside of the image", x, y)
+            }
+            RasterError::InvalidStartIndex(index) => write!(f, "Invalid start index {}", index),
+            RasterError::InvalidHex => write!(f, "Invalid hex color format"),
+            RasterError::HexParse(ref err) => write!(f, "Error parsing hex color: {}", err),
+            RasterError::BlendingImageFallsOutsideCanvas => {
+                write!(f, "Blending image falls outside the canvas")
+            }
+            RasterError::InvalidGamma(gamma) => {
+                write!(f, "Invalid gamma {}. Must be in range 0.01 - 9.99", gamma)
+            }
+            RasterError::Decode(ref format, ref msg) => {
+                write!(f, "Error decoding {:?}: {}", format, msg)
+            }
+            RasterError::Encode(ref format, ref msg) => {
+                write!(f, "Error encoding {:?}: {}", format, msg)
+            }
+            RasterError::UnsupportedFormat(ref ext) => {
+                write!(f, "Unsupported image format \"{}\"", ext)
+            }
+            RasterError::InvalidMode(ref mode) => write!(f, "Invalid mode \"{}\"", mode),
+            RasterError::InvalidDimensions(w, h) => write!(f, "Invalid dimensions {}x{}", w, h),
+            RasterError::DimensionsMismatch => write!(f, "Image dimensions do not match"),
+            RasterError::InvalidTransform => write!(f, "Transformation cannot be inverted"),
+            RasterError::InvalidRadius(radius) => {
+                write!(f, "Invalid radius {}. Must be zero or greater", radius)
+            }
+            RasterError::InvalidSigma(sigma) => {
+                write!(f, "Invalid sigma {}. Must be greater than zero", sigma)
+            }
+            RasterError::InvalidKernel => write!(f, "Invalid convolution kernel"),
+            RasterError::InvalidThresholds(low, high) => {
+                write!(
+                    f,
+                    "Invalid thresholds {} and {}. Must be zero or greater and in order",
+                    low, high
+                )
+            }
+            RasterError::InvalidContrast(amount) => {
+                write!(f, "Invalid contrast {}. Must be in range -100.0 - 100.0", amount)
+            }
+            RasterError::InvalidLevels(black, white) => {
+                write!(f, "Invalid levels {} - {}. Black must be below white", black, white)
+            }
+            RasterError::InvalidCurve => write!(f, "Invalid curve points"),
+            RasterError::InvalidBlockSize(size) => {
+                write!(f, "Invalid block size {}. Must be odd and at least 3", size)
+            }
+            RasterError::InvalidPercentage(percent) => write!(f, "Invalid percentage {}", percent),
+            RasterError::InvalidPalette => write!(f, "Invalid palette"),
+            RasterError::TooManyColors(count) => {
+                write!(f, "Too many colors {}. A palette holds at most 256", count)
+            }
+            RasterError::InvalidGradient => write!(f, "Invalid gradient stops"),
+            RasterError::InvalidThickness(thickness) => {
+                write!(f, "Invalid thickness {}. Must be above 0", thickness)
+            }
+            RasterError::InvalidFont => write!(f, "Invalid font data"),
+            RasterError::InvalidFontSize(size) => {
+                write!(f, "Invalid font size {}. Must be above 0", size)
+            }
+            RasterError::InvalidIntensityLevels(levels) => {
+                write!(f, "Invalid intensity levels {}. Must be 1 - 256", levels)
+            }
+            RasterError::InvalidWavelength(wavelength) => {
+                write!(f, "Invalid wavelength {}. Must be above 0", wavelength)
+            }
+            RasterError::InvalidWhitePoint(white) => {
+                write!(f, "Invalid white point {}. Must be above 0", white)
+            }
+            RasterError::InvalidStructuringElement => write!(f, "Invalid structuring element"),
+            RasterError::InvalidShapeRadius(radius) => {
+                write!(f, "Invalid radius {}. Must be above 0", radius)
+            }
+            RasterError::InvalidCellSize(size) => {
+                write!(f, "Invalid cell size {}. Must be at least 2", size)
+            }
+            RasterError::InvalidWidth(width) => {
+                write!(f, "Invalid width {}. Must be above 0", width)
+            }
+            RasterError::InvalidBorder(width) => {
+                write!(f, "Invalid border width {}. Must be zero or greater", width)
+            }
+            RasterError::InvalidSpacing(spacing) => write!(f, "Invalid spacing {}", spacing),
+            RasterError::InvalidColumns(columns) => {
+                write!(f, "Invalid number of columns {}. Must be above 0", columns)
+            }
+            RasterError::InvalidSheetWidth(width) => {
+                write!(f, "Invalid sheet width {}. An image does not fit", width)
+            }
+            RasterError::InvalidOverlap(overlap) => write!(f, "Invalid overlap {}", overlap),
+            RasterError::Unexpected => write!(f, "Unexpected error"),
+        }
+    }
+}
+
+impl Error for RasterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            RasterError::Io(ref err) => Some(err),
+            RasterError::HexParse(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 /// Convert std::io::Error to RasterError::Io
 impl From<IoError> for RasterError {
     fn from(err: IoError) -> RasterError {
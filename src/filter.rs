@@ -1,12 +1,15 @@
 //!  A module for filtering pixels.
 
 // from rust
-use std::cmp;
 
 // from external crate
 
 // from local crate
+use editor::Direction;
 use error::{RasterError, RasterResult};
+use gradient::{self, GradientStop};
+use interpolate;
+use tonemap::{linear_to_srgb, srgb_to_linear};
 use Image;
 use Color;
 
@@ -28,453 +31,3141 @@ pub enum Orientation {
     Both,
 }
 
-/// Apply box or Gaussian blur.
+/// An enum for the ways of computing the gray of a pixel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GrayscaleMode {
+    /// Luminosity with the Rec. 601 weights of standard definition video.
+    Rec601,
+    /// Luminosity with the Rec. 709 weights of HD video and sRGB.
+    Rec709,
+    /// Average of the red, green and blue channels.
+    Average,
+    /// Middle of the lightest and darkest channels, like removing all saturation in HSL.
+    Desaturate,
+}
+
+/// An enum for the kinds of noise added by `filter::add_noise`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseType {
+    /// Normally distributed offsets, like film grain and sensor noise.
+    Gaussian,
+    /// Offsets evenly distributed in a range.
+    Uniform,
+    /// Random pixels set to black or white, like dust and dead pixels.
+    SaltPepper,
+}
+
+/// An enum for the shapes of the ink in `filter::halftone`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HalftoneShape {
+    /// Round dots, like newspaper photos.
+    Dot,
+    /// Parallel lines along the grid.
+    Line,
+    /// Square dots.
+    Square,
+}
+
+/// An enum for the colors an image is reduced to when dithering.
+#[derive(Debug, Clone)]
+pub enum DitherPalette {
+    /// Evenly spaced levels per channel, eg. 2 for channels that are either 0 or 255.
+    Levels(u8),
+    /// The colors of a palette, ignoring their alpha.
+    Colors(Vec<Color>),
+}
+
+/// An enum for the algorithms that can be used for automatic white balance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WhiteBalanceMode {
+    /// Assume the average color of the image is gray.
+    GrayWorld,
+    /// Assume the lightest color of the image is white.
+    WhitePatch,
+}
+
+/// An enum for the local thresholds of adaptive thresholding.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThresholdMode {
+    /// Mean of the block around the pixel.
+    Mean,
+    /// Mean of the block around the pixel weighted by a Gaussian, so closer pixels count more.
+    Gaussian,
+}
+
+/// An enum for the channels of a pixel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+/// An enum for how pixels outside of the image are read when convolving.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeMode {
+    /// Repeat the nearest edge pixel.
+    Clamp,
+    /// Continue from the opposite edge, as if the image was tiled.
+    Wrap,
+    /// Reflect the image at its edges, without repeating the edge pixel.
+    Mirror,
+}
+
+/// A square convolution kernel of odd size.
 ///
-/// # Examples
-/// ### Box Blur
+/// The weighted sum of the pixels under the kernel is divided by the divisor, then the offset is
+/// added. The divisor defaults to 1.0, the offset to 0.0 and the edge mode to `EdgeMode::Clamp`.
 ///
+/// # Examples
 /// ```
-/// use raster::{filter, BlurMode};
+/// use raster::{EdgeMode, Kernel};
 ///
-/// // Create image from file
-/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
-/// filter::blur(&mut image, BlurMode::Box).unwrap();
-/// raster::save(&image, "tests/out/test_filter_box_blur.jpg").unwrap();
+/// // 3x3 box blur reading across the edges as if the image was tiled
+/// let kernel = Kernel::new(&[[1.0; 3]; 3]).unwrap().divisor(9.0).edge(EdgeMode::Wrap);
+/// assert_eq!(3, kernel.size());
 /// ```
-/// ### Before
-/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kernel {
+    size: usize,
+    // Row major weights
+    weights: Vec<f32>,
+    divisor: f32,
+    offset: f32,
+    edge: EdgeMode,
+}
+
+impl Kernel {
+    /// Create a kernel from its rows.
+    ///
+    /// # Errors
+    ///
+    /// If the rows do not form a square matrix of odd size, this fails with
+    /// `RasterError::InvalidKernel`.
+    pub fn new<R: AsRef<[f32]>>(rows: &[R]) -> RasterResult<Kernel> {
+        let size = rows.len();
+        if size % 2 != 1 || rows.iter().any(|row| row.as_ref().len() != size) {
+            return Err(RasterError::InvalidKernel);
+        }
+
+        Ok(Kernel {
+            size,
+            weights: rows.iter().flat_map(|row| row.as_ref().to_vec()).collect(),
+            divisor: 1.0,
+            offset: 0.0,
+            edge: EdgeMode::Clamp,
+        })
+    }
+
+    /// Divide the weighted sum by divisor.
+    pub fn divisor(self, divisor: f32) -> Kernel {
+        Kernel { divisor, ..self }
+    }
+
+    /// Add offset to the result, eg. 128.0 to center signed results on mid gray.
+    pub fn offset(self, offset: f32) -> Kernel {
+        Kernel { offset, ..self }
+    }
+
+    /// Read the pixels outside of the image according to edge.
+    pub fn edge(self, edge: EdgeMode) -> Kernel {
+        Kernel { edge, ..self }
+    }
+
+    /// The number of rows and columns.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// Add random noise, eg. for film grain.
 ///
-/// ### After
-/// ![](https://kosinix.github.io/raster/out/test_filter_box_blur.jpg)
+/// The amount is a value from 0.0 - 1.0. For `NoiseType::Gaussian` it is the standard deviation
+/// and for `NoiseType::Uniform` the largest offset, both as a share of the 0 - 255 range. For
+/// `NoiseType::SaltPepper` it is the share of pixels set to black or white. With monochrome, the
+/// red, green and blue channels of a pixel get the same offset so that the noise has no color.
+/// The same seed always gives the same noise. Alpha is left untouched.
 ///
-/// ### Gaussian Blur
+/// # Errors
+///
+/// If the amount is outside 0.0 - 1.0, this fails with `RasterError::InvalidPercentage`.
 ///
+/// # Examples
 /// ```
-/// use raster::{filter, BlurMode};
+/// use raster::{filter, NoiseType};
 ///
-/// // Create image from file
 /// let mut image = raster::open("tests/in/sample.jpg").unwrap();
-/// filter::blur(&mut image, BlurMode::Gaussian).unwrap();
-/// raster::save(&image, "tests/out/test_filter_gaussian_blur.jpg").unwrap();
+/// filter::add_noise(&mut image, NoiseType::Gaussian, 0.08, true, 42).unwrap();
+/// raster::save(&image, "tests/out/test_filter_add_noise.jpg").unwrap();
 /// ```
+///
 /// ### Before
 /// ![](https://kosinix.github.io/raster/in/sample.jpg)
 ///
 /// ### After
-/// ![](https://kosinix.github.io/raster/out/test_filter_gaussian_blur.jpg)
+/// ![](https://kosinix.github.io/raster/out/test_filter_add_noise.jpg)
 ///
-pub fn blur(src: &mut Image, mode: BlurMode) -> RasterResult<()> {
-    match mode {
-        BlurMode::Box => blur_box(src),
-        BlurMode::Gaussian => blur_gaussian(src),
+pub fn add_noise(
+    src: &mut Image,
+    noise: NoiseType,
+    amount: f32,
+    monochrome: bool,
+    seed: u64,
+) -> RasterResult<()> {
+    if amount.is_nan() || !(0.0..=1.0).contains(&amount) {
+        return Err(RasterError::InvalidPercentage(amount));
+    }
+
+    let mut rng = Rng::new(seed);
+    let offset = |rng: &mut Rng| match noise {
+        NoiseType::Gaussian => rng.gaussian() * amount * 255.0,
+        NoiseType::Uniform => (rng.next_f32() * 2.0 - 1.0) * amount * 255.0,
+        NoiseType::SaltPepper => {
+            if rng.next_f32() >= amount {
+                0.0
+            } else if rng.next_f32() < 0.5 {
+                -255.0
+            } else {
+                255.0
+            }
+        }
+    };
+
+    for pixel in src.bytes.chunks_mut(4) {
+        let shared = if monochrome { offset(&mut rng) } else { 0.0 };
+        for channel in &mut pixel[0..3] {
+            let offset = if monochrome { shared } else { offset(&mut rng) };
+            *channel = (*channel as f32 + offset).round().clamp(0.0, 255.0) as u8;
+        }
     }
+
+    Ok(())
 }
 
-/// Apply brightness.
+/// Stretch each color channel to the full 0 - 255 range.
 ///
-/// A brightness of < 0.0 will darken the image and brightness of > 1.0 will lighten it.
+/// The darkest and lightest `clip_percent` percent of the values of each channel are clipped to
+/// black and full, then the rest is stretched in between like `filter::levels`. Clipping a little,
+/// eg. 0.5, keeps a few outliers from limiting the stretch. Channels with a single value left
+/// are unchanged. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If the clip percentage is outside of 0.0 - 50.0, this fails with
+/// `RasterError::InvalidPercentage`.
 ///
 /// # Examples
 /// ```
 /// use raster::filter;
 ///
 /// let mut image = raster::open("tests/in/sample.jpg").unwrap();
-/// filter::brightness(&mut image, 1.5).unwrap();
-/// raster::save(&image, "tests/out/test_filter_brightness.jpg").unwrap();
+/// filter::auto_levels(&mut image, 0.5).unwrap();
+/// raster::save(&image, "tests/out/test_filter_auto_levels.jpg").unwrap();
 /// ```
 ///
 /// ### Before
 /// ![](https://kosinix.github.io/raster/in/sample.jpg)
 ///
 /// ### After
-/// ![](https://kosinix.github.io/raster/out/test_filter_brightness.jpg)
+/// ![](https://kosinix.github.io/raster/out/test_filter_auto_levels.jpg)
 ///
-pub fn brightness(src: &mut Image, factor: f32) -> RasterResult<()> {
-    let w: i32 = src.width;
-    let h: i32 = src.height;
-
-    // if gamma < 0.01 || gamma > 9.99{
-    //     return Err(format!("Incorrect gamma value {}. Must be in range 0.01 - 9.99.", gamma));
-    // }
-    // let factor = 255.0 * factor;
+pub fn auto_levels(src: &mut Image, clip_percent: f32) -> RasterResult<()> {
+    if !(0.0..=50.0).contains(&clip_percent) {
+        return Err(RasterError::InvalidPercentage(clip_percent));
+    }
 
-    for y in 0..h {
-        for x in 0..w {
-            let p = src.get_pixel(x, y)?;
-            let r = cmp::max(0, cmp::min(255, (p.r as f32 * factor) as i32));
-            let g = cmp::max(0, cmp::min(255, (p.g as f32 * factor) as i32));
-            let b = cmp::max(0, cmp::min(255, (p.b as f32 * factor) as i32));
-            // TODO: Should alpha be included?
-            let a = cmp::max(0, cmp::min(255, (p.a as f32 * factor) as i32));
+    let clip = clip_percent as f64 / 100.0;
+    for c in 0..3 {
+        let cdf = _channel_cdf(src, c);
+        let black = cdf.iter().position(|&share| share > clip).unwrap_or(0);
+        let white = cdf
+            .iter()
+            .position(|&share| share >= 1.0 - clip)
+            .unwrap_or(255)
+            .max(black);
+        if black == white {
+            continue;
+        }
 
-            src.set_pixel(x, y, &Color::rgba(r as u8, g as u8, b as u8, a as u8))?;
+        let lut = _levels_lut(black as u8, white as u8, 1.0, 0, 255)?;
+        for pixel in src.bytes.chunks_mut(4) {
+            pixel[c] = lut[pixel[c] as usize];
         }
     }
 
     Ok(())
 }
 
-/// Apply a convolution matrix.
+/// Remove a color cast automatically.
 ///
-/// The divisor is applied as the last step of convolution.
+/// The red, green and blue channels are scaled so that the reference color found by the mode
+/// becomes neutral: the average color with `WhiteBalanceMode::GrayWorld`, or the lightest color
+/// with `WhiteBalanceMode::WhitePatch`. The lightest color is taken at the 99th percentile of
+/// each channel so that a few specular highlights don't decide it. Alpha is left untouched.
 ///
 /// # Examples
 /// ```
-/// use raster::filter;
+/// use raster::{filter, WhiteBalanceMode};
 ///
-/// // Create image from file
 /// let mut image = raster::open("tests/in/sample.jpg").unwrap();
-/// let matrix: [[i32; 3]; 3] = [
-///     [0, 0, 0],
-///     [0, 1, 0],
-///     [0, 0, 0]
-/// ];
-/// filter::convolve(&mut image, matrix, 1).unwrap();
-/// raster::save(&image, "tests/out/test_filter_convolve.jpg").unwrap();
+/// filter::auto_white_balance(&mut image, WhiteBalanceMode::GrayWorld).unwrap();
+/// raster::save(&image, "tests/out/test_filter_auto_white_balance.jpg").unwrap();
 /// ```
-pub fn convolve(src: &mut Image, matrix: [[i32; 3]; 3], divisor: i32) -> RasterResult<()> {
-    let w: i32 = src.width;
-    let h: i32 = src.height;
-    let m_size = 3; // Matrix size
-
-    let copy = src.clone(); // Create a copy as input of pixels
-
-    for y in 0..h {
-        for x in 0..w {
-            let mstarty = y - 1;
-            let mstartx = x - 1;
-
-            let mut accum_red: i32 = 0;
-            let mut accum_green: i32 = 0;
-            let mut accum_blue: i32 = 0;
-            let mut accum_alpha: i32 = 0;
-
-            for (m_index_y, mut src_y) in (0..).zip(mstarty..mstarty + m_size) {
-                if src_y < 0 {
-                    src_y = 0;
-                } else if src_y > h - 1 {
-                    src_y = h - 1;
-                }
-
-                for (m_index_x, mut src_x) in (0..).zip(mstartx..mstartx + m_size) {
-                    if src_x < 0 {
-                        src_x = 0;
-                    } else if src_x > w - 1 {
-                        src_x = w - 1;
-                    }
-
-                    let pixel = copy.get_pixel(src_x, src_y)?;
-                    accum_red += pixel.r as i32 * matrix[m_index_y][m_index_x];
-                    accum_green += pixel.g as i32 * matrix[m_index_y][m_index_x];
-                    accum_blue += pixel.b as i32 * matrix[m_index_y][m_index_x];
-                    accum_alpha += pixel.a as i32 * matrix[m_index_y][m_index_x];
-                }
-            }
-
-            if divisor != 1 {
-                accum_red /= divisor;
-                accum_green /= divisor;
-                accum_blue /= divisor;
-                accum_alpha /= divisor;
-            }
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_auto_white_balance.jpg)
+///
+pub fn auto_white_balance(src: &mut Image, mode: WhiteBalanceMode) -> RasterResult<()> {
+    if src.bytes.is_empty() {
+        return Ok(());
+    }
 
-            if accum_red < 0 {
-                accum_red = 0;
+    let mut references = [0.0f32; 3];
+    for (c, reference) in references.iter_mut().enumerate() {
+        *reference = match mode {
+            WhiteBalanceMode::GrayWorld => {
+                let sum: f32 = src.bytes.chunks(4).map(|p| p[c] as f32).sum();
+                sum / (src.bytes.len() / 4) as f32
             }
-            if accum_green < 0 {
-                accum_green = 0;
-            }
-            if accum_blue < 0 {
-                accum_blue = 0;
-            }
-            if accum_alpha < 0 {
-                accum_alpha = 0;
+            WhiteBalanceMode::WhitePatch => {
+                let cdf = _channel_cdf(src, c);
+                cdf.iter().position(|&share| share >= 0.99).unwrap_or(255) as f32
             }
+        };
+    }
 
-            if accum_red > 255 {
-                accum_red = 255;
-            }
-            if accum_green > 255 {
-                accum_green = 255;
-            }
-            if accum_blue > 255 {
-                accum_blue = 255;
-            }
-            if accum_alpha > 255 {
-                accum_alpha = 255;
-            }
+    // Gray world keeps the average brightness, white patch makes the reference full white.
+    let target = match mode {
+        WhiteBalanceMode::GrayWorld => references.iter().sum::<f32>() / 3.0,
+        WhiteBalanceMode::WhitePatch => 255.0,
+    };
+    let mut luts = [[0u8; 256]; 3];
+    for (lut, reference) in luts.iter_mut().zip(references.iter()) {
+        let gain = if *reference > 0.0 { target / reference } else { 1.0 };
+        for (value, entry) in lut.iter_mut().enumerate() {
+            *entry = (value as f32 * gain).round().clamp(0.0, 255.0) as u8;
+        }
+    }
 
-            src.set_pixel(
-                x,
-                y,
-                &Color::rgba(
-                    accum_red as u8,
-                    accum_green as u8,
-                    accum_blue as u8,
-                    accum_alpha as u8,
-                ),
-            )?;
+    for pixel in src.bytes.chunks_mut(4) {
+        for (channel, lut) in pixel[0..3].iter_mut().zip(luts.iter()) {
+            *channel = lut[*channel as usize];
         }
     }
 
     Ok(())
 }
 
-/// Apply emboss.
+/// Apply box or Gaussian blur.
 ///
 /// # Examples
+/// ### Box Blur
+///
 /// ```
-/// use raster::filter;
+/// use raster::{filter, BlurMode};
 ///
 /// // Create image from file
 /// let mut image = raster::open("tests/in/sample.jpg").unwrap();
-/// filter::emboss(&mut image).unwrap();
-/// raster::save(&image, "tests/out/test_filter_emboss.jpg").unwrap();
+/// filter::blur(&mut image, BlurMode::Box).unwrap();
+/// raster::save(&image, "tests/out/test_filter_box_blur.jpg").unwrap();
 /// ```
-///
 /// ### Before
 /// ![](https://kosinix.github.io/raster/in/sample.jpg)
 ///
 /// ### After
-/// ![](https://kosinix.github.io/raster/out/test_filter_emboss.jpg)
+/// ![](https://kosinix.github.io/raster/out/test_filter_box_blur.jpg)
 ///
-pub fn emboss(src: &mut Image) -> RasterResult<()> {
-    let matrix: [[i32; 3]; 3] = [[-2, -1, 0], [-1, 1, 1], [0, 1, 2]];
-    convolve(src, matrix, 1)
-}
-
-/// Apply Sobel edge detection.
+/// ### Gaussian Blur
 ///
-/// # Examples
 /// ```
-/// use raster::{filter, Orientation};
+/// use raster::{filter, BlurMode};
 ///
 /// // Create image from file
 /// let mut image = raster::open("tests/in/sample.jpg").unwrap();
-/// filter::sobel(&mut image, Orientation::Horizontal).unwrap();
-/// raster::save(&image, "tests/out/test_filter_sobel_x.jpg").unwrap();
+/// filter::blur(&mut image, BlurMode::Gaussian).unwrap();
+/// raster::save(&image, "tests/out/test_filter_gaussian_blur.jpg").unwrap();
 /// ```
-///
 /// ### Before
 /// ![](https://kosinix.github.io/raster/in/sample.jpg)
 ///
 /// ### After
-/// ![](https://kosinix.github.io/raster/out/test_filter_sobel_x.jpg)
+/// ![](https://kosinix.github.io/raster/out/test_filter_gaussian_blur.jpg)
 ///
-pub fn sobel(src: &mut Image, mode: Orientation) -> RasterResult<()> {
-    grayscale(src)?;
-    let matrix = match mode {
-        Orientation::Horizontal => [[-1, 0, 1], [-2, 0, 2], [-1, 0, 1]],
-        Orientation::Vertical => [[-1, -2, -1], [0, 0, 0], [1, 2, 1]],
-        Orientation::DiagonalUp => [[0, -1, -2], [1, 0, -1], [2, 1, 0]],
-        Orientation::DiagonalDown => [[-2, -1, 0], [-1, 0, 1], [0, 1, 2]],
-        Orientation::Both => {
-            return sobel_both(
-                src,
-                [[-1, 0, 1], [-2, 0, 2], [-1, 0, 1]],
-                [[-1, -2, -1], [0, 0, 0], [1, 2, 1]],
-            )
-        }
-        Orientation::DiagonalBoth => {
-            return sobel_both(
-                src,
-                [[0, -1, -2], [1, 0, -1], [2, 1, 0]],
-                [[-2, -1, 0], [-1, 0, 1], [0, 1, 2]],
-            )
-        }
-    };
-    convolve(src, matrix, 1)
-}
-
-fn sobel_both(
-    src: &mut Image,
-    matrix_one: [[i32; 3]; 3],
-    matrix_two: [[i32; 3]; 3],
-) -> RasterResult<()> {
-    let mut image_x = src.clone();
-    let mut image_y = src.clone();
-    convolve(&mut image_x, matrix_one, 1)?;
-    convolve(&mut image_y, matrix_two, 1)?;
-
-    let w: i32 = src.width;
-    let h: i32 = src.height;
-    for y in 0..h {
-        for x in 0..w {
-            let pixel_x = image_x.get_pixel(x, y)?;
-            let pixel_y = image_y.get_pixel(x, y)?;
-            // Calculate the sum of the derivatives with sqrt((dImage/dx)²+(dImage/dy)²)
-            let pixel = ((pixel_x.r as f64).powi(2) + (pixel_y.r as f64).powi(2)).sqrt();
-            src.set_pixel(
-                x,
-                y,
-                &Color::rgba(pixel as u8, pixel as u8, pixel as u8, pixel_x.a as u8),
-            )?;
-        }
+pub fn blur(src: &mut Image, mode: BlurMode) -> RasterResult<()> {
+    match mode {
+        BlurMode::Box => blur_box_3x3(src),
+        BlurMode::Gaussian => blur_gaussian(src),
     }
-
-    Ok(())
 }
 
-/// Apply a gamma correction.
+/// Apply a box blur of the given radius.
 ///
-/// Gamma can be a value from 0.01 - 9.99.
-/// A gamma < 1.0 will darken and a gamma > 1.0 will lighten the image.
+/// Each pixel becomes the average of the (2 * radius + 1) x (2 * radius + 1) square around it,
+/// with the edge pixels repeated outside the image. The averages are computed with a sliding
+/// window so the cost does not depend on the radius, which makes it cheap for softening
+/// backgrounds. A radius of 0 leaves the image unchanged and a radius of 1 is the same as
+/// `blur(src, BlurMode::Box)`.
+///
+/// # Errors
+///
+/// If the radius is negative, this fails with `RasterError::InvalidRadius`.
 ///
 /// # Examples
+///
 /// ```
 /// use raster::filter;
 ///
 /// let mut image = raster::open("tests/in/sample.jpg").unwrap();
-/// filter::gamma(&mut image, 2.0).unwrap();
-/// raster::save(&image, "tests/out/test_filter_gamma.jpg").unwrap();
+/// filter::blur_box(&mut image, 8).unwrap();
+/// raster::save(&image, "tests/out/test_filter_blur_box.jpg").unwrap();
 /// ```
 ///
 /// ### Before
 /// ![](https://kosinix.github.io/raster/in/sample.jpg)
 ///
 /// ### After
-/// ![](https://kosinix.github.io/raster/out/test_filter_gamma.jpg)
+/// ![](https://kosinix.github.io/raster/out/test_filter_blur_box.jpg)
 ///
-// http://stackoverflow.com/questions/14088889/changing-a-color-brightness
-pub fn gamma(src: &mut Image, gamma: f32) -> RasterResult<()> {
-    let w: i32 = src.width;
-    let h: i32 = src.height;
-
-    if gamma < 0.01 || gamma > 9.99 {
-        return Err(RasterError::InvalidGamma(gamma));
+pub fn blur_box(src: &mut Image, radius: i32) -> RasterResult<()> {
+    if radius < 0 {
+        return Err(RasterError::InvalidRadius(radius));
     }
+    if radius == 0 || src.width <= 0 || src.height <= 0 {
+        return Ok(());
+    }
+
+    let w = src.width as usize;
+    let h = src.height as usize;
+    let r = radius as usize;
 
+    // Horizontal pass, keeping the raw sums so that the result is only divided once.
+    let mut sums = vec![0u64; w * h * 4];
     for y in 0..h {
-        for x in 0..w {
-            let p = src.get_pixel(x, y)?;
-            let r = (p.r as f32 / 255.0).powf(gamma) * 255.0;
-            let g = (p.g as f32 / 255.0).powf(gamma) * 255.0;
-            let b = (p.b as f32 / 255.0).powf(gamma) * 255.0;
+        let row = &src.bytes[y * w * 4..(y + 1) * w * 4];
+        let out = &mut sums[y * w * 4..(y + 1) * w * 4];
+        _sliding_sum(w, r, |i, c| row[i * 4 + c] as u64, |i, c, sum| out[i * 4 + c] = sum);
+    }
 
-            src.set_pixel(x, y, &Color::rgba(r as u8, g as u8, b as u8, p.a as u8))?;
-        }
+    // Vertical pass over the horizontal sums.
+    let divisor = ((2 * r + 1) * (2 * r + 1)) as u64;
+    for x in 0..w {
+        let bytes = &mut src.bytes;
+        _sliding_sum(
+            h,
+            r,
+            |i, c| sums[(i * w + x) * 4 + c],
+            |i, c, sum| bytes[(i * w + x) * 4 + c] = (sum / divisor) as u8,
+        );
     }
 
     Ok(())
 }
 
-/// Turn into grayscale image.
+/// Apply an edge preserving bilateral filter.
+///
+/// Like a Gaussian blur, each pixel becomes a weighted average of its neighbors, with the weights
+/// falling off with the distance given by `spatial_sigma`. The weights also fall off with the
+/// color difference given by `range_sigma`, so pixels on the other side of an edge barely count
+/// and the edge stays sharp while smooth surfaces are softened. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If either sigma is zero or negative, this fails with `RasterError::InvalidSigma`.
 ///
 /// # Examples
+///
 /// ```
 /// use raster::filter;
 ///
 /// let mut image = raster::open("tests/in/sample.jpg").unwrap();
-/// filter::grayscale(&mut image).unwrap();
-/// raster::save(&image, "tests/out/test_filter_grayscale.jpg").unwrap();
+/// filter::bilateral(&mut image, 3.0, 30.0).unwrap();
+/// raster::save(&image, "tests/out/test_filter_bilateral.jpg").unwrap();
 /// ```
 ///
 /// ### Before
 /// ![](https://kosinix.github.io/raster/in/sample.jpg)
 ///
 /// ### After
-/// ![](https://kosinix.github.io/raster/out/test_filter_grayscale.jpg)
+/// ![](https://kosinix.github.io/raster/out/test_filter_bilateral.jpg)
 ///
-pub fn grayscale(src: &mut Image) -> RasterResult<()> {
-    let w: i32 = src.width;
-    let h: i32 = src.height;
+pub fn bilateral(src: &mut Image, spatial_sigma: f32, range_sigma: f32) -> RasterResult<()> {
+    for &sigma in &[spatial_sigma, range_sigma] {
+        if sigma.is_nan() || sigma <= 0.0 {
+            return Err(RasterError::InvalidSigma(sigma));
+        }
+    }
+
+    let w = src.width;
+    let h = src.height;
+    let copy = src.bytes.clone();
+
+    // The window spans 2 standard deviations on each side. The range weight of a color difference
+    // is the product of the weights of each channel difference, which are looked up.
+    let r = (2.0 * spatial_sigma).ceil() as i32;
+    let size = (2 * r + 1) as usize;
+    let mut spatial = vec![0.0f32; size * size];
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let d2 = (dx * dx + dy * dy) as f32;
+            spatial[((dy + r) as usize) * size + (dx + r) as usize] =
+                (-d2 / (2.0 * spatial_sigma * spatial_sigma)).exp();
+        }
+    }
+    let range: Vec<f32> = (0..256)
+        .map(|d| (-((d * d) as f32) / (2.0 * range_sigma * range_sigma)).exp())
+        .collect();
 
     for y in 0..h {
         for x in 0..w {
-            let p = src.get_pixel(x, y)?;
-            let gray = (p.r as f32 * 0.3) + (p.g as f32 * 0.59) + (p.b as f32 * 0.11);
+            let start = (y * w + x) as usize * 4;
+            let center = &copy[start..start + 3];
+            let mut sums = [0.0f32; 3];
+            let mut total = 0.0f32;
 
-            src.set_pixel(
-                x,
-                y,
-                &Color::rgba(gray as u8, gray as u8, gray as u8, gray as u8),
-            )?;
+            for dy in -r..=r {
+                let sy = (y + dy).clamp(0, h - 1);
+                for dx in -r..=r {
+                    let sx = (x + dx).clamp(0, w - 1);
+                    let i = (sy * w + sx) as usize * 4;
+                    let mut weight = spatial[((dy + r) as usize) * size + (dx + r) as usize];
+                    for c in 0..3 {
+                        let diff = (copy[i + c] as i32 - center[c] as i32).unsigned_abs();
+                        weight *= range[diff as usize];
+                    }
+                    for (c, sum) in sums.iter_mut().enumerate() {
+                        *sum += copy[i + c] as f32 * weight;
+                    }
+                    total += weight;
+                }
+            }
+
+            for (c, sum) in sums.iter().enumerate() {
+                src.bytes[start + c] = (sum / total).round().clamp(0.0, 255.0) as u8;
+            }
         }
     }
 
     Ok(())
 }
 
-/// Change saturation.
-///
-/// Pass a float value for sat. < 0.0 to decrease and > 0.0 to increase. Eg 0.5 for 50% increase
-/// in saturation.
+/// Apply brightness.
 ///
-/// Note: Saturation does not look good at the moment.
+/// A brightness of < 0.0 will darken the image and brightness of > 1.0 will lighten it.
 ///
 /// # Examples
 /// ```
 /// use raster::filter;
 ///
-/// // Create image from file
-/// let mut image = raster::open("tests/in/sample.png").unwrap();
-/// filter::saturation(&mut image, 0.5).unwrap();
-/// raster::save(&image, "tests/out/test_filter_saturation.jpg").unwrap();
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::brightness(&mut image, 1.5).unwrap();
+/// raster::save(&image, "tests/out/test_filter_brightness.jpg").unwrap();
 /// ```
 ///
 /// ### Before
-/// ![](https://kosinix.github.io/raster/in/sample.png)
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
 ///
 /// ### After
-/// ![](https://kosinix.github.io/raster/out/test_filter_saturation.jpg)
+/// ![](https://kosinix.github.io/raster/out/test_filter_brightness.jpg)
 ///
-pub fn saturation(src: &mut Image, sat: f32) -> RasterResult<()> {
+pub fn brightness(src: &mut Image, factor: f32) -> RasterResult<()> {
     let w: i32 = src.width;
     let h: i32 = src.height;
 
+    // if gamma < 0.01 || gamma > 9.99{
+    //     return Err(format!("Incorrect gamma value {}. Must be in range 0.01 - 9.99.", gamma));
+    // }
+    // let factor = 255.0 * factor;
+
     for y in 0..h {
         for x in 0..w {
             let p = src.get_pixel(x, y)?;
-            let hsv = Color::to_hsv(p.r, p.g, p.b);
-            let s = hsv.1;
-            let factor = (100.0 - s) * sat; // use % remaining
-            let mut new_s = s + factor;
-            if new_s > 100.0 {
-                new_s = 100.0;
-            } else if new_s < 0.0 {
-                new_s = 0.0;
-            }
-            let rgb = Color::to_rgb(hsv.0, new_s, hsv.2);
+            let r = ((p.r as f32 * factor) as i32).clamp(0, 255);
+            let g = ((p.g as f32 * factor) as i32).clamp(0, 255);
+            let b = ((p.b as f32 * factor) as i32).clamp(0, 255);
+            // TODO: Should alpha be included?
+            let a = ((p.a as f32 * factor) as i32).clamp(0, 255);
 
-            src.set_pixel(x, y, &Color::rgb(rgb.0, rgb.1, rgb.2))?;
+            src.set_pixel(x, y, &Color::rgba(r as u8, g as u8, b as u8, a as u8))?;
         }
     }
 
     Ok(())
 }
 
-/// Apply sharpen.
+/// Detect edges with the Canny algorithm.
+///
+/// The grayscale image is smoothed with a 5x5 Gaussian, its Sobel gradients are computed and
+/// thinned to 1 pixel wide ridges by keeping only the local maxima across the edges. Pixels with
+/// a gradient magnitude of at least `high` are edges, and pixels of at least `low` are edges
+/// when they connect to another edge. The image is replaced by white edges on black, keeping its
+/// alpha. The magnitude of a sharp black to white edge is about 1000, so thresholds like 50 and
+/// 150 work for most photos.
+///
+/// # Errors
+///
+/// If a threshold is negative or `low` is above `high`, this fails with
+/// `RasterError::InvalidThresholds`.
 ///
 /// # Examples
+///
 /// ```
 /// use raster::filter;
 ///
-/// // Create image from file
 /// let mut image = raster::open("tests/in/sample.jpg").unwrap();
-/// filter::sharpen(&mut image).unwrap();
-/// raster::save(&image, "tests/out/test_filter_sharpen.jpg").unwrap();
+/// filter::canny(&mut image, 50.0, 150.0).unwrap();
+/// raster::save(&image, "tests/out/test_filter_canny.jpg").unwrap();
 /// ```
+///
 /// ### Before
 /// ![](https://kosinix.github.io/raster/in/sample.jpg)
 ///
 /// ### After
-/// ![](https://kosinix.github.io/raster/out/test_filter_sharpen.jpg)
+/// ![](https://kosinix.github.io/raster/out/test_filter_canny.jpg)
 ///
-pub fn sharpen(src: &mut Image) -> RasterResult<()> {
-    let matrix: [[i32; 3]; 3] = [[0, -1, 0], [-1, 5, -1], [0, -1, 0]];
-    convolve(src, matrix, 1)
+pub fn canny(src: &mut Image, low: f32, high: f32) -> RasterResult<()> {
+    if !(low >= 0.0 && low <= high) {
+        return Err(RasterError::InvalidThresholds(low, high));
+    }
+    if src.width <= 0 || src.height <= 0 {
+        return Ok(());
+    }
+
+    let w = src.width;
+    let h = src.height;
+    let gaussian = Kernel::new(&[
+        [2.0, 4.0, 5.0, 4.0, 2.0],
+        [4.0, 9.0, 12.0, 9.0, 4.0],
+        [5.0, 12.0, 15.0, 12.0, 5.0],
+        [4.0, 9.0, 12.0, 9.0, 4.0],
+        [2.0, 4.0, 5.0, 4.0, 2.0],
+    ])?.divisor(159.0);
+    let smoothed = _convolve_channel(&_luma(src), w, h, &gaussian);
+    let (gx, gy) = _sobel_gradients(&smoothed, w, h)?;
+    let magnitude = _magnitude(&gx, &gy);
+
+    // Non-maximum suppression: compare each pixel with its 2 neighbors along the gradient,
+    // rounded to a horizontal, vertical or diagonal direction.
+    let at = |x: i32, y: i32| {
+        if x < 0 || y < 0 || x >= w || y >= h {
+            0.0
+        } else {
+            magnitude[(y * w + x) as usize]
+        }
+    };
+    let mut thin = vec![0.0f32; magnitude.len()];
+    for y in 0..h {
+        for x in 0..w {
+            let i = (y * w + x) as usize;
+            let m = magnitude[i];
+            if m < low {
+                continue;
+            }
+            let mut angle = gy[i].atan2(gx[i]).to_degrees();
+            if angle < 0.0 {
+                angle += 180.0;
+            }
+            let (dx, dy) = if !(22.5..157.5).contains(&angle) {
+                (1, 0)
+            } else if angle < 67.5 {
+                (1, 1)
+            } else if angle < 112.5 {
+                (0, 1)
+            } else {
+                (-1, 1)
+            };
+            // Strict on one side so that a ridge 2 pixels wide keeps only one of them.
+            if m > at(x + dx, y + dy) && m >= at(x - dx, y - dy) {
+                thin[i] = m;
+            }
+        }
+    }
+
+    // Hysteresis: grow the strong edges through the connected weak ones.
+    let mut edges = vec![false; thin.len()];
+    let mut stack: Vec<i32> = (0..thin.len() as i32)
+        .filter(|&i| thin[i as usize] >= high)
+        .collect();
+    for &i in &stack {
+        edges[i as usize] = true;
+    }
+    while let Some(i) = stack.pop() {
+        let (x, y) = (i % w, i / w);
+        for ny in y - 1..=y + 1 {
+            for nx in x - 1..=x + 1 {
+                if nx < 0 || ny < 0 || nx >= w || ny >= h {
+                    continue;
+                }
+                let n = (ny * w + nx) as usize;
+                if !edges[n] && thin[n] >= low && thin[n] > 0.0 {
+                    edges[n] = true;
+                    stack.push(n as i32);
+                }
+            }
+        }
+    }
+
+    for (pixel, &edge) in src.bytes.chunks_mut(4).zip(edges.iter()) {
+        let value = if edge { 255 } else { 0 };
+        pixel[0] = value;
+        pixel[1] = value;
+        pixel[2] = value;
+    }
+
+    Ok(())
+}
+
+/// Split the red and blue channels apart toward the edges, like a cheap lens or a VHS tape.
+///
+/// The red channel is scaled up from the center and the blue channel down, so that they are moved
+/// by shift pixels in opposite directions at the corners and not at all at the center. A negative
+/// shift swaps the directions. Alpha is left untouched.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::chromatic_aberration(&mut image, 6.0).unwrap();
+/// raster::save(&image, "tests/out/test_filter_chromatic_aberration.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_chromatic_aberration.jpg)
+///
+pub fn chromatic_aberration(src: &mut Image, shift: f32) -> RasterResult<()> {
+    let (w, h) = (src.width as f64, src.height as f64);
+    let (cx, cy) = (w / 2.0, h / 2.0);
+    let scale = shift as f64 / cx.hypot(cy).max(1.0);
+    let bg = Color::rgba(0, 0, 0, 0);
+
+    let copy = src.clone();
+    for y in 0..src.height {
+        for x in 0..src.width {
+            let (dx, dy) = (x as f64 + 0.5 - cx, y as f64 + 0.5 - cy);
+            // Sample position of a channel scaled by factor from the center, kept on the image.
+            let at = |factor: f64| {
+                (
+                    (cx + dx * factor).clamp(0.5, w - 0.5),
+                    (cy + dy * factor).clamp(0.5, h - 0.5),
+                )
+            };
+            let (rx, ry) = at(1.0 - scale);
+            let (bx, by) = at(1.0 + scale);
+            let red = interpolate::sample_bilinear(&copy, rx, ry, &bg)?.r;
+            let blue = interpolate::sample_bilinear(&copy, bx, by, &bg)?.b;
+
+            let start = ((y * src.width + x) * 4) as usize;
+            src.bytes[start] = red;
+            src.bytes[start + 2] = blue;
+        }
+    }
+
+    Ok(())
+}
+
+/// Change contrast.
+///
+/// Pass a value from -100.0 to 100.0. Values < 0.0 pull the channels towards mid gray, with -100.0
+/// giving a flat gray image, and values > 0.0 push them away from it, with 100.0 giving only
+/// black and full channels. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If the amount is outside of -100.0 - 100.0, this fails with `RasterError::InvalidContrast`.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::contrast(&mut image, 40.0).unwrap();
+/// raster::save(&image, "tests/out/test_filter_contrast.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_contrast.jpg)
+///
+pub fn contrast(src: &mut Image, amount: f32) -> RasterResult<()> {
+    if !(-100.0..=100.0).contains(&amount) {
+        return Err(RasterError::InvalidContrast(amount));
+    }
+
+    // The usual contrast correction factor, with the amount scaled to -255 - 255.
+    let c = amount * 2.55;
+    let factor = (259.0 * (c + 255.0)) / (255.0 * (259.0 - c));
+    let mut lut = [0u8; 256];
+    for (value, entry) in lut.iter_mut().enumerate() {
+        *entry = (factor * (value as f32 - 128.0) + 128.0).round().clamp(0.0, 255.0) as u8;
+    }
+
+    for pixel in src.bytes.chunks_mut(4) {
+        for channel in &mut pixel[0..3] {
+            *channel = lut[*channel as usize];
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply a curves adjustment to the color channels.
+///
+/// Works like the Curves dialog of Photoshop. The points map input values to output values and
+/// are joined by a monotone cubic spline, which never overshoots between points. Values before
+/// the first point or after the last one keep the output of that point. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If there are less than 2 points or their inputs are not increasing, this fails with
+/// `RasterError::InvalidCurve`.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// // S-curve adding contrast to the midtones
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::curves(&mut image, &[(0, 0), (64, 48), (192, 208), (255, 255)]).unwrap();
+/// raster::save(&image, "tests/out/test_filter_curves.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_curves.jpg)
+///
+pub fn curves(src: &mut Image, points: &[(u8, u8)]) -> RasterResult<()> {
+    let lut = _curve_lut(points)?;
+    for pixel in src.bytes.chunks_mut(4) {
+        for channel in &mut pixel[0..3] {
+            *channel = lut[*channel as usize];
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply a curves adjustment to a single channel.
+///
+/// Same as `filter::curves` but only changes the given channel.
+///
+/// # Errors
+///
+/// Same as `filter::curves`.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, Channel};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::curves_channel(&mut image, Channel::Red, &[(0, 20), (128, 150), (255, 255)]).unwrap();
+/// raster::save(&image, "tests/out/test_filter_curves_channel.jpg").unwrap();
+/// ```
+pub fn curves_channel(src: &mut Image, channel: Channel, points: &[(u8, u8)]) -> RasterResult<()> {
+    let lut = _curve_lut(points)?;
+    let c = _channel_index(channel);
+    for pixel in src.bytes.chunks_mut(4) {
+        pixel[c] = lut[pixel[c] as usize];
+    }
+
+    Ok(())
+}
+
+/// Apply a convolution kernel.
+///
+/// All 4 channels are convolved and the results are truncated to the 0 - 255 range.
+///
+/// The built-in filters with a fixed kernel, eg. `blur`, `sharpen` or `emboss`, are convolutions
+/// too. Blurs of any radius, ie. `blur_box` and the Gaussian blur of `unsharp_mask` and
+/// `tilt_shift`, use separable passes instead: a square kernel costs the square of its size per
+/// pixel and rounds the result to whole values.
+///
+/// # Errors
+///
+/// If the kernel divisor is zero, this fails with `RasterError::InvalidKernel`.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, Kernel};
+///
+/// // Create image from file
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let kernel = Kernel::new(&[
+///     [0.0, 0.0, 0.0],
+///     [0.0, 1.0, 0.0],
+///     [0.0, 0.0, 0.0]
+/// ]).unwrap();
+/// filter::convolve(&mut image, &kernel).unwrap();
+/// raster::save(&image, "tests/out/test_filter_convolve.jpg").unwrap();
+/// ```
+pub fn convolve(src: &mut Image, kernel: &Kernel) -> RasterResult<()> {
+    if kernel.divisor == 0.0 {
+        return Err(RasterError::InvalidKernel);
+    }
+    if src.width <= 0 || src.height <= 0 {
+        return Ok(());
+    }
+
+    let w = src.width as usize;
+    let h = src.height as usize;
+    let size = kernel.size;
+    let r = (size / 2) as i32;
+
+    // Source column and row of each position the kernel can reach, from -r to the size + r.
+    let columns: Vec<usize> = (-r..w as i32 + r).map(|x| _edge(x, w, kernel.edge)).collect();
+    let rows: Vec<usize> = (-r..h as i32 + r).map(|y| _edge(y, h, kernel.edge)).collect();
+
+    let copy = src.bytes.clone(); // Create a copy as input of pixels
+    for y in 0..h {
+        for x in 0..w {
+            let mut accum = [0.0f32; 4];
+            for ky in 0..size {
+                let row = rows[y + ky] * w;
+                for kx in 0..size {
+                    let weight = kernel.weights[ky * size + kx];
+                    if weight == 0.0 {
+                        continue;
+                    }
+                    let i = (row + columns[x + kx]) * 4;
+                    for (c, sum) in accum.iter_mut().enumerate() {
+                        *sum += copy[i + c] as f32 * weight;
+                    }
+                }
+            }
+
+            let start = (y * w + x) * 4;
+            for (c, sum) in accum.iter().enumerate() {
+                let value = sum / kernel.divisor + kernel.offset;
+                src.bytes[start + c] = value.clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove noise, eg. from high ISO phone pictures, with a simplified non-local means filter.
+///
+/// Each pixel becomes a weighted average of the pixels up to 3 pixels away. The weight of a pixel
+/// depends on how much the 3x3 patch around it looks like the patch around the pixel being
+/// denoised, so repeated textures and edges are kept while random noise is averaged away. The
+/// strength is roughly the amount of noise to remove in the 0 - 255 range, eg. 10.0 for light
+/// noise and 30.0 for heavy noise. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If the strength is zero or negative, this fails with `RasterError::InvalidSigma`.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::denoise(&mut image, 15.0).unwrap();
+/// raster::save(&image, "tests/out/test_filter_denoise.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_denoise.jpg)
+///
+pub fn denoise(src: &mut Image, strength: f32) -> RasterResult<()> {
+    if strength.is_nan() || strength <= 0.0 {
+        return Err(RasterError::InvalidSigma(strength));
+    }
+    if src.width <= 0 || src.height <= 0 {
+        return Ok(());
+    }
+
+    let (w, h) = (src.width as usize, src.height as usize);
+    let (search, patch) = (3i32, 1usize);
+    let decay = 1.0 / (strength * strength * 3.0 * ((2 * patch + 1) * (2 * patch + 1)) as f32);
+    let mut weights = vec![0f32; w * h];
+    let mut sums = vec![[0f32; 3]; w * h];
+    let mut distances = vec![0f32; w * h];
+
+    // For each offset, compare every patch with the patch at that offset at once, then add the
+    // offset pixel to the average with the weight of the comparison.
+    for dy in -search..=search {
+        for dx in -search..=search {
+            let shifted = |x: usize, y: usize| {
+                let sx = (x as i32 + dx).clamp(0, w as i32 - 1) as usize;
+                let sy = (y as i32 + dy).clamp(0, h as i32 - 1) as usize;
+                sy * w + sx
+            };
+            for y in 0..h {
+                for x in 0..w {
+                    let (a, b) = ((y * w + x) * 4, shifted(x, y) * 4);
+                    distances[y * w + x] = (0..3)
+                        .map(|c| {
+                            let d = src.bytes[a + c] as f32 - src.bytes[b + c] as f32;
+                            d * d
+                        })
+                        .sum();
+                }
+            }
+            let area = ((2 * patch + 1) * (2 * patch + 1)) as f32;
+            let patches = _box_mean(&distances, w, h, patch);
+
+            for y in 0..h {
+                for x in 0..w {
+                    let i = y * w + x;
+                    let weight = (-patches[i] * area * decay).exp();
+                    let b = shifted(x, y) * 4;
+                    weights[i] += weight;
+                    for (c, sum) in sums[i].iter_mut().enumerate() {
+                        *sum += src.bytes[b + c] as f32 * weight;
+                    }
+                }
+            }
+        }
+    }
+
+    for (i, pixel) in src.bytes.chunks_mut(4).enumerate() {
+        for (value, sum) in pixel[0..3].iter_mut().zip(sums[i].iter()) {
+            *value = (sum / weights[i]).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    Ok(())
+}
+
+/// Blur each pixel by how far its depth is from the focal depth, for a synthetic depth of field.
+///
+/// The depth is read from the luminance of a grayscale depth map, eg. from a phone camera, with
+/// the same dimensions as the image. Pixels at focal_depth stay sharp and the blur radius grows
+/// linearly up to max_blur pixels for a depth difference of 255. Each pixel becomes the average
+/// of the square of that radius around it. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If the depth map does not have the dimensions of the image, this fails with
+/// `RasterError::DimensionsMismatch`. If max_blur is negative, this fails with
+/// `RasterError::InvalidRadius`.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, Color, Image};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// // Near at the bottom, far at the top
+/// let mut depth = Image::blank(image.width, image.height);
+/// for y in 0..image.height {
+///     let value = (y * 255 / (image.height - 1)) as u8;
+///     for x in 0..image.width {
+///         depth.set_pixel(x, y, &Color::rgb(value, value, value)).unwrap();
+///     }
+/// }
+/// filter::depth_blur(&mut image, &depth, 200, 8).unwrap();
+/// raster::save(&image, "tests/out/test_filter_depth_blur.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_depth_blur.jpg)
+///
+pub fn depth_blur(
+    src: &mut Image,
+    depth_map: &Image,
+    focal_depth: u8,
+    max_blur: i32,
+) -> RasterResult<()> {
+    if depth_map.width != src.width || depth_map.height != src.height {
+        return Err(RasterError::DimensionsMismatch);
+    }
+    if max_blur < 0 {
+        return Err(RasterError::InvalidRadius(max_blur));
+    }
+    if max_blur == 0 || src.width <= 0 || src.height <= 0 {
+        return Ok(());
+    }
+
+    // Summed area tables of the color channels, with an extra row and column of zeros.
+    let (w, h) = (src.width as usize, src.height as usize);
+    let stride = w + 1;
+    let mut tables = vec![[0f64; 3]; stride * (h + 1)];
+    for y in 0..h {
+        let mut row = [0f64; 3];
+        for x in 0..w {
+            let p = &src.bytes[(y * w + x) * 4..][..3];
+            for c in 0..3 {
+                row[c] += p[c] as f64;
+                tables[(y + 1) * stride + x + 1][c] = tables[y * stride + x + 1][c] + row[c];
+            }
+        }
+    }
+
+    let depths = _luma(depth_map);
+    for y in 0..h {
+        for x in 0..w {
+            let distance = (depths[y * w + x] - focal_depth as f32).abs() / 255.0;
+            let r = (distance * max_blur as f32).round() as usize;
+            if r == 0 {
+                continue;
+            }
+
+            let (x0, y0) = (x.saturating_sub(r), y.saturating_sub(r));
+            let (x1, y1) = ((x + r + 1).min(w), (y + r + 1).min(h));
+            let n = ((x1 - x0) * (y1 - y0)) as f64;
+            let pixel = &mut src.bytes[(y * w + x) * 4..][..3];
+            for (c, value) in pixel.iter_mut().enumerate() {
+                let sum = tables[y1 * stride + x1][c] - tables[y0 * stride + x1][c]
+                    - tables[y1 * stride + x0][c]
+                    + tables[y0 * stride + x0][c];
+                *value = (sum / n).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reduce the colors with Floyd-Steinberg dithering.
+///
+/// Each pixel is replaced by the nearest color of the palette and the difference is spread over
+/// the next pixels, so that areas average to their original color instead of showing bands.
+/// Useful before saving to formats with few colors such as GIF. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If the palette has less than 2 levels or no colors, this fails with
+/// `RasterError::InvalidPalette`.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, DitherPalette};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::dither_floyd_steinberg(&mut image, &DitherPalette::Levels(2)).unwrap();
+/// raster::save(&image, "tests/out/test_filter_dither_floyd_steinberg.png").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_dither_floyd_steinberg.png)
+///
+pub fn dither_floyd_steinberg(src: &mut Image, palette: &DitherPalette) -> RasterResult<()> {
+    match *palette {
+        DitherPalette::Levels(levels) if levels < 2 => return Err(RasterError::InvalidPalette),
+        DitherPalette::Colors(ref colors) if colors.is_empty() => {
+            return Err(RasterError::InvalidPalette)
+        }
+        _ => {}
+    }
+
+    let nearest = |value: [f32; 3]| -> [f32; 3] {
+        match *palette {
+            DitherPalette::Levels(levels) => {
+                let step = 255.0 / (levels - 1) as f32;
+                value.map(|v| ((v / step).round() * step).clamp(0.0, 255.0))
+            }
+            DitherPalette::Colors(ref colors) => {
+                let distance = |c: &Color| {
+                    let d = [c.r as f32 - value[0], c.g as f32 - value[1], c.b as f32 - value[2]];
+                    d[0] * d[0] + d[1] * d[1] + d[2] * d[2]
+                };
+                let mut best = &colors[0];
+                for color in colors {
+                    if distance(color) < distance(best) {
+                        best = color;
+                    }
+                }
+                [best.r as f32, best.g as f32, best.b as f32]
+            }
+        }
+    };
+
+    let w = src.width as usize;
+    let h = src.height as usize;
+    let mut values: Vec<[f32; 3]> = src
+        .bytes
+        .chunks(4)
+        .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+        .collect();
+
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * w + x;
+            let old = values[i];
+            let new = nearest(old);
+            src.bytes[i * 4..i * 4 + 3].copy_from_slice(&new.map(|v| v as u8));
+
+            // Spread the error: 7/16 right, 3/16 bottom left, 5/16 bottom, 1/16 bottom right
+            let error = [old[0] - new[0], old[1] - new[1], old[2] - new[2]];
+            let mut spread = |j: usize, weight: f32| {
+                for (value, e) in values[j].iter_mut().zip(error.iter()) {
+                    *value += e * weight;
+                }
+            };
+            if x + 1 < w {
+                spread(i + 1, 7.0 / 16.0);
+            }
+            if y + 1 < h {
+                if x > 0 {
+                    spread(i + w - 1, 3.0 / 16.0);
+                }
+                spread(i + w, 5.0 / 16.0);
+                if x + 1 < w {
+                    spread(i + w + 1, 1.0 / 16.0);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply edge enhance.
+///
+/// Boosts the difference between each pixel and its 8 neighbors, making edges stand out while
+/// flat areas are left unchanged. This is the classic `[[-1, -1, -1], [-1, 10, -1], [-1, -1, -1]]`
+/// kernel divided by 2. Alpha is left untouched.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// // Create image from file
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::edge_enhance(&mut image).unwrap();
+/// raster::save(&image, "tests/out/test_filter_edge_enhance.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_edge_enhance.jpg)
+///
+pub fn edge_enhance(src: &mut Image) -> RasterResult<()> {
+    let kernel = Kernel::new(&[[-1.0, -1.0, -1.0], [-1.0, 10.0, -1.0], [-1.0, -1.0, -1.0]])?;
+    _convolve_color(src, &kernel.divisor(2.0))
+}
+
+/// Apply emboss.
+///
+/// Flat areas become mid gray and edges are raised or sunk as if lit from the top left, like the
+/// GD emboss filter. Alpha is left untouched.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// // Create image from file
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::emboss(&mut image).unwrap();
+/// raster::save(&image, "tests/out/test_filter_emboss.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_emboss.jpg)
+///
+pub fn emboss(src: &mut Image) -> RasterResult<()> {
+    let kernel = Kernel::new(&[[1.5, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, -1.5]])?;
+    _convolve_color(src, &kernel.offset(127.0))
+}
+
+/// Apply Sobel edge detection.
+///
+/// The image is converted to grayscale and replaced by its gradient along the orientation. With
+/// `Orientation::Both` and `Orientation::DiagonalBoth`, it is replaced by the magnitude of the
+/// gradients along the two orientations, which finds edges in any direction. Alpha is left
+/// untouched.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, Orientation};
+///
+/// // Create image from file
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::sobel(&mut image, Orientation::Horizontal).unwrap();
+/// raster::save(&image, "tests/out/test_filter_sobel_x.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_sobel_x.jpg)
+///
+pub fn sobel(src: &mut Image, mode: Orientation) -> RasterResult<()> {
+    if src.width <= 0 || src.height <= 0 {
+        return Ok(());
+    }
+
+    let horizontal = [[-1.0, 0.0, 1.0], [-2.0, 0.0, 2.0], [-1.0, 0.0, 1.0]];
+    let vertical = [[-1.0, -2.0, -1.0], [0.0, 0.0, 0.0], [1.0, 2.0, 1.0]];
+    let diagonal_up = [[0.0, -1.0, -2.0], [1.0, 0.0, -1.0], [2.0, 1.0, 0.0]];
+    let diagonal_down = [[-2.0, -1.0, 0.0], [-1.0, 0.0, 1.0], [0.0, 1.0, 2.0]];
+
+    let luma = _luma(src);
+    let gradient = |matrix: [[f32; 3]; 3]| -> RasterResult<Vec<f32>> {
+        Ok(_convolve_channel(&luma, src.width, src.height, &Kernel::new(&matrix)?))
+    };
+    let values = match mode {
+        Orientation::Horizontal => gradient(horizontal)?,
+        Orientation::Vertical => gradient(vertical)?,
+        Orientation::DiagonalUp => gradient(diagonal_up)?,
+        Orientation::DiagonalDown => gradient(diagonal_down)?,
+        Orientation::Both => _magnitude(&gradient(horizontal)?, &gradient(vertical)?),
+        Orientation::DiagonalBoth => _magnitude(&gradient(diagonal_up)?, &gradient(diagonal_down)?),
+    };
+
+    for (pixel, value) in src.bytes.chunks_mut(4).zip(values) {
+        let gray = value.clamp(0.0, 255.0) as u8;
+        pixel[0] = gray;
+        pixel[1] = gray;
+        pixel[2] = gray;
+    }
+
+    Ok(())
+}
+
+/// Compute the Sobel gradient magnitude and direction of an image.
+///
+/// The image is converted to grayscale first. The magnitude image holds how strong the edge at
+/// each pixel is. The direction image holds the angle the brightness increases towards, mapped
+/// from -180 - 180 degrees to 0 - 255, with 0 degrees pointing right and 90 degrees pointing
+/// down. Both images are gray and keep the alpha of the source.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let image = raster::open("tests/in/sample.jpg").unwrap();
+/// let (magnitude, direction) = filter::sobel_gradient(&image).unwrap();
+/// raster::save(&magnitude, "tests/out/test_filter_sobel_magnitude.jpg").unwrap();
+/// raster::save(&direction, "tests/out/test_filter_sobel_direction.jpg").unwrap();
+/// ```
+///
+/// ### Magnitude
+/// ![](https://kosinix.github.io/raster/out/test_filter_sobel_magnitude.jpg)
+///
+/// ### Direction
+/// ![](https://kosinix.github.io/raster/out/test_filter_sobel_direction.jpg)
+///
+pub fn sobel_gradient(src: &Image) -> RasterResult<(Image, Image)> {
+    if src.width <= 0 || src.height <= 0 {
+        return Ok((src.clone(), src.clone()));
+    }
+
+    let (gx, gy) = _sobel_gradients(&_luma(src), src.width, src.height)?;
+
+    let mut magnitude = src.clone();
+    let mut direction = src.clone();
+    for (i, (x, y)) in gx.iter().zip(gy.iter()).enumerate() {
+        let length = (x * x + y * y).sqrt().clamp(0.0, 255.0) as u8;
+        let angle = (y.atan2(*x) + std::f32::consts::PI) / (2.0 * std::f32::consts::PI);
+        let angle = (angle * 255.0).round() as u8;
+        magnitude.bytes[i * 4..i * 4 + 3].copy_from_slice(&[length; 3]);
+        direction.bytes[i * 4..i * 4 + 3].copy_from_slice(&[angle; 3]);
+    }
+
+    Ok((magnitude, direction))
+}
+
+/// Equalize the luminance histogram.
+///
+/// The luminance is remapped so that it spreads evenly over 0 - 255, which brings out the details
+/// of low contrast images such as faded scans. The pixels are converted to YCbCr and only their
+/// luminance is changed, so their hues are kept. Alpha is left untouched.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::equalize(&mut image).unwrap();
+/// raster::save(&image, "tests/out/test_filter_equalize.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_equalize.jpg)
+///
+pub fn equalize(src: &mut Image) -> RasterResult<()> {
+    let luma: Vec<f32> = src
+        .bytes
+        .chunks(4)
+        .map(|p| p[0] as f32 * 0.299 + p[1] as f32 * 0.587 + p[2] as f32 * 0.114)
+        .collect();
+    let histogram = _luma_histogram(&luma);
+
+    // Map each luminance through the cumulative histogram, stretched so the darkest level is 0.
+    let mut cdf = [0u32; 256];
+    let mut count = 0;
+    for (value, &n) in histogram.iter().enumerate() {
+        count += n;
+        cdf[value] = count;
+    }
+    let total = luma.len() as f32;
+    let cdf_min = cdf.iter().cloned().find(|&c| c > 0).unwrap_or(0) as f32;
+    if total - cdf_min <= 0.0 {
+        return Ok(()); // A single luminance, nothing to spread
+    }
+
+    for (pixel, &y) in src.bytes.chunks_mut(4).zip(luma.iter()) {
+        let level = y.round().clamp(0.0, 255.0) as usize;
+        let new_y = (cdf[level] as f32 - cdf_min) / (total - cdf_min) * 255.0;
+
+        // Keep the chroma of the pixel while changing its luminance.
+        let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+        let cb = -0.168_736 * r - 0.331_264 * g + 0.5 * b;
+        let cr = 0.5 * r - 0.418_688 * g - 0.081_312 * b;
+        let rgb = [
+            new_y + 1.402 * cr,
+            new_y - 0.344_136 * cb - 0.714_136 * cr,
+            new_y + 1.772 * cb,
+        ];
+        for (channel, value) in pixel.iter_mut().zip(rgb.iter()) {
+            *channel = value.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    Ok(())
+}
+
+/// Change exposure by a number of stops.
+///
+/// Each stop doubles the light for values > 0.0 or halves it for values < 0.0, like opening or
+/// closing a camera aperture. The channels are converted from sRGB to linear light before being
+/// multiplied by 2^stops and converted back, so midtones and shadows brighten the way they would
+/// in a camera instead of being shifted evenly. Alpha is left untouched.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::exposure(&mut image, 0.5).unwrap();
+/// raster::save(&image, "tests/out/test_filter_exposure.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_exposure.jpg)
+///
+pub fn exposure(src: &mut Image, stops: f32) -> RasterResult<()> {
+    let factor = 2.0f32.powf(stops);
+    let mut lut = [0u8; 256];
+    for (value, entry) in lut.iter_mut().enumerate() {
+        let linear = srgb_to_linear(value as f32 / 255.0) * factor;
+        *entry = (linear_to_srgb(linear.min(1.0)) * 255.0).round() as u8;
+    }
+
+    for pixel in src.bytes.chunks_mut(4) {
+        for channel in &mut pixel[0..3] {
+            *channel = lut[*channel as usize];
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply a gamma correction.
+///
+/// Gamma can be a value from 0.01 - 9.99.
+/// A gamma < 1.0 will darken and a gamma > 1.0 will lighten the image. Alpha is left untouched.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::gamma(&mut image, 2.0).unwrap();
+/// raster::save(&image, "tests/out/test_filter_gamma.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_gamma.jpg)
+///
+// http://stackoverflow.com/questions/14088889/changing-a-color-brightness
+pub fn gamma(src: &mut Image, gamma: f32) -> RasterResult<()> {
+    if !(0.01..=9.99).contains(&gamma) {
+        return Err(RasterError::InvalidGamma(gamma));
+    }
+
+    // Look up the 256 possible channel values instead of computing a power per channel.
+    let mut lut = [0u8; 256];
+    for (value, entry) in lut.iter_mut().enumerate() {
+        *entry = ((value as f32 / 255.0).powf(1.0 / gamma) * 255.0).round() as u8;
+    }
+
+    for pixel in src.bytes.chunks_mut(4) {
+        for channel in &mut pixel[0..3] {
+            *channel = lut[*channel as usize];
+        }
+    }
+
+    Ok(())
+}
+
+/// Map the luminance of each pixel through a color ramp.
+///
+/// Black takes the color of stop 0.0 and white the color of stop 1.0, so two stops give a
+/// duotone and three stops a tritone. The alpha of the stops is multiplied with the alpha of the
+/// pixel.
+///
+/// # Errors
+///
+/// If there are no stops, or their positions are not in the range 0.0 - 1.0 and in increasing
+/// order, this fails with `RasterError::InvalidGradient`.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, Color, GradientStop};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let stops = [
+///     GradientStop::new(0.0, Color::hex("#1d1e5c").unwrap()),
+///     GradientStop::new(1.0, Color::hex("#f7c948").unwrap()),
+/// ];
+/// filter::gradient_map(&mut image, &stops).unwrap();
+/// raster::save(&image, "tests/out/test_filter_gradient_map.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_gradient_map.jpg)
+///
+pub fn gradient_map(src: &mut Image, stops: &[GradientStop]) -> RasterResult<()> {
+    gradient::check_stops(stops)?;
+
+    let lut: Vec<Color> = (0..256)
+        .map(|value| gradient::sample(stops, value as f32 / 255.0))
+        .collect();
+
+    for pixel in src.bytes.chunks_mut(4) {
+        let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+        let gray = (r * 0.299 + g * 0.587 + b * 0.114).round().clamp(0.0, 255.0) as usize;
+        let color = &lut[gray];
+        pixel[0] = color.r;
+        pixel[1] = color.g;
+        pixel[2] = color.b;
+        pixel[3] = ((pixel[3] as u32 * color.a as u32 + 127) / 255) as u8;
+    }
+
+    Ok(())
+}
+
+/// Turn into grayscale image.
+///
+/// The red, green and blue channels are all set to the gray computed by the mode. Alpha is left
+/// untouched.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, GrayscaleMode};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::grayscale(&mut image, GrayscaleMode::Rec601).unwrap();
+/// raster::save(&image, "tests/out/test_filter_grayscale.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_grayscale.jpg)
+///
+pub fn grayscale(src: &mut Image, mode: GrayscaleMode) -> RasterResult<()> {
+    for pixel in src.bytes.chunks_mut(4) {
+        let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+        let gray = match mode {
+            GrayscaleMode::Rec601 => r * 0.299 + g * 0.587 + b * 0.114,
+            GrayscaleMode::Rec709 => r * 0.2126 + g * 0.7152 + b * 0.0722,
+            GrayscaleMode::Average => (r + g + b) / 3.0,
+            GrayscaleMode::Desaturate => (r.max(g).max(b) + r.min(g).min(b)) / 2.0,
+        };
+        let gray = gray.round().clamp(0.0, 255.0) as u8;
+        pixel[0] = gray;
+        pixel[1] = gray;
+        pixel[2] = gray;
+    }
+
+    Ok(())
+}
+
+/// Turn the image into black ink on white paper, with ink shapes that grow with the darkness of
+/// the image, like printed posters and newspapers.
+///
+/// The image is divided into a grid of cell_size pixels, rotated by angle degrees clockwise, eg.
+/// 45.0 for the classic look. Each cell gets one shape sized after the luminance at its center,
+/// with smooth edges. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If the cell size is below 2, this fails with `RasterError::InvalidCellSize`.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, HalftoneShape};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::halftone(&mut image, 8, 45.0, HalftoneShape::Dot).unwrap();
+/// raster::save(&image, "tests/out/test_filter_halftone.png").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_halftone.png)
+///
+pub fn halftone(
+    src: &mut Image,
+    cell_size: i32,
+    angle: f32,
+    shape: HalftoneShape,
+) -> RasterResult<()> {
+    if cell_size < 2 {
+        return Err(RasterError::InvalidCellSize(cell_size));
+    }
+
+    let (w, h) = (src.width, src.height);
+    let luma: Vec<f32> = src
+        .bytes
+        .chunks(4)
+        .map(|p| (p[0] as f32 * 0.299 + p[1] as f32 * 0.587 + p[2] as f32 * 0.114) / 255.0)
+        .collect();
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let cell = cell_size as f32;
+    let radii: Vec<f32> = (0..256).map(|d| _dot_radius(d as f32 / 255.0) * cell).collect();
+
+    for (i, pixel) in src.bytes.chunks_mut(4).enumerate() {
+        let (x, y) = ((i as i32 % w) as f32 + 0.5, (i as i32 / w) as f32 + 0.5);
+        // Position in the rotated grid and offset from the center of its cell.
+        let (u, v) = (x * cos + y * sin, -x * sin + y * cos);
+        let (cu, cv) = (((u / cell).floor() + 0.5) * cell, ((v / cell).floor() + 0.5) * cell);
+        let (du, dv) = (u - cu, v - cv);
+
+        // Darkness at the center of the cell, read back on the image.
+        let sx = ((cu * cos - cv * sin) as i32).clamp(0, w - 1);
+        let sy = ((cu * sin + cv * cos) as i32).clamp(0, h - 1);
+        let darkness = 1.0 - luma[(sy * w + sx) as usize];
+
+        // Distance inside the edge of a shape covering darkness of the cell.
+        let inside = match shape {
+            HalftoneShape::Dot => radii[(darkness * 255.0).round() as usize] - du.hypot(dv),
+            HalftoneShape::Line => cell * darkness / 2.0 - dv.abs(),
+            HalftoneShape::Square => cell * darkness.sqrt() / 2.0 - du.abs().max(dv.abs()),
+        };
+        let ink = if darkness <= 0.0 {
+            0.0
+        } else {
+            (inside + 0.5).clamp(0.0, 1.0)
+        };
+        let value = (255.0 * (1.0 - ink)).round() as u8;
+        pixel[0..3].copy_from_slice(&[value; 3]);
+    }
+
+    Ok(())
+}
+
+/// Change saturation.
+///
+/// Pass a float value for sat. < 0.0 to decrease and > 0.0 to increase. Eg 0.5 for 50% increase
+/// in saturation.
+///
+/// Note: Saturation does not look good at the moment.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// // Create image from file
+/// let mut image = raster::open("tests/in/sample.png").unwrap();
+/// filter::saturation(&mut image, 0.5).unwrap();
+/// raster::save(&image, "tests/out/test_filter_saturation.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.png)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_saturation.jpg)
+///
+pub fn saturation(src: &mut Image, sat: f32) -> RasterResult<()> {
+    let w: i32 = src.width;
+    let h: i32 = src.height;
+
+    for y in 0..h {
+        for x in 0..w {
+            let p = src.get_pixel(x, y)?;
+            let hsv = Color::to_hsv(p.r, p.g, p.b);
+            let s = hsv.1;
+            let factor = (100.0 - s) * sat; // use % remaining
+            let new_s = (s + factor).clamp(0.0, 100.0);
+            let rgb = Color::to_rgb(hsv.0, new_s, hsv.2);
+
+            src.set_pixel(x, y, &Color::rgb(rgb.0, rgb.1, rgb.2))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Invert the colors to make a negative.
+///
+/// Each color channel becomes 255 minus its value. Alpha is left untouched.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::invert(&mut image).unwrap();
+/// raster::save(&image, "tests/out/test_filter_invert.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_invert.jpg)
+///
+pub fn invert(src: &mut Image) -> RasterResult<()> {
+    invert_channels(src, &[Channel::Red, Channel::Green, Channel::Blue])
+}
+
+/// Invert only the given channels.
+///
+/// Useful to turn a luminance image into a mask, eg. inverting `Channel::Alpha` of an image
+/// whose alpha was copied from its luminance.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, Channel};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::invert_channels(&mut image, &[Channel::Red]).unwrap();
+/// raster::save(&image, "tests/out/test_filter_invert_channels.jpg").unwrap();
+/// ```
+pub fn invert_channels(src: &mut Image, channels: &[Channel]) -> RasterResult<()> {
+    let mut selected = [false; 4];
+    for &channel in channels {
+        selected[_channel_index(channel)] = true;
+    }
+
+    for pixel in src.bytes.chunks_mut(4) {
+        for (value, &invert) in pixel.iter_mut().zip(selected.iter()) {
+            if invert {
+                *value = 255 - *value;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply a Kuwahara filter, a painterly smoothing that keeps edges sharp.
+///
+/// The square of radius around each pixel is split into 4 overlapping quadrants that include the
+/// pixel, and the pixel becomes the average color of the quadrant with the least variance in
+/// luminance. Quadrants are cut at the image edges. Flat areas are smoothed while the quadrant on
+/// the same side of an edge as the pixel is picked, so edges stay sharp. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If the radius is negative, this fails with `RasterError::InvalidRadius`.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::kuwahara(&mut image, 4).unwrap();
+/// raster::save(&image, "tests/out/test_filter_kuwahara.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_kuwahara.jpg)
+///
+pub fn kuwahara(src: &mut Image, radius: i32) -> RasterResult<()> {
+    if radius < 0 {
+        return Err(RasterError::InvalidRadius(radius));
+    }
+    if radius == 0 || src.width <= 0 || src.height <= 0 {
+        return Ok(());
+    }
+
+    // Summed area tables of the color channels, the luminance and the squared luminance, with an
+    // extra row and column of zeros.
+    let (w, h) = (src.width as usize, src.height as usize);
+    let stride = w + 1;
+    let mut tables = vec![[0f64; 5]; stride * (h + 1)];
+    for y in 0..h {
+        let mut row = [0f64; 5];
+        for x in 0..w {
+            let p = &src.bytes[(y * w + x) * 4..][..3];
+            let luma = p[0] as f64 * 0.299 + p[1] as f64 * 0.587 + p[2] as f64 * 0.114;
+            let values = [p[0] as f64, p[1] as f64, p[2] as f64, luma, luma * luma];
+            for (sum, value) in row.iter_mut().zip(values.iter()) {
+                *sum += value;
+            }
+            let above = tables[y * stride + x + 1];
+            let cell = &mut tables[(y + 1) * stride + x + 1];
+            for c in 0..5 {
+                cell[c] = above[c] + row[c];
+            }
+        }
+    }
+    // Sums over the columns x0..x1 and rows y0..y1.
+    let area = |x0: usize, y0: usize, x1: usize, y1: usize| {
+        let (a, b) = (tables[y0 * stride + x0], tables[y0 * stride + x1]);
+        let (c, d) = (tables[y1 * stride + x0], tables[y1 * stride + x1]);
+        let mut sums = [0f64; 5];
+        for (i, sum) in sums.iter_mut().enumerate() {
+            *sum = d[i] - b[i] - c[i] + a[i];
+        }
+        sums
+    };
+
+    let r = radius as usize;
+    for y in 0..h {
+        for x in 0..w {
+            let (left, top) = (x.saturating_sub(r), y.saturating_sub(r));
+            let (right, bottom) = ((x + r + 1).min(w), (y + r + 1).min(h));
+            let quadrants = [
+                (left, top, x + 1, y + 1),
+                (x, top, right, y + 1),
+                (left, y, x + 1, bottom),
+                (x, y, right, bottom),
+            ];
+
+            let mut best = (f64::MAX, [0f64; 5], 1.0);
+            for &(x0, y0, x1, y1) in &quadrants {
+                let n = ((x1 - x0) * (y1 - y0)) as f64;
+                let sums = area(x0, y0, x1, y1);
+                let mean = sums[3] / n;
+                let variance = sums[4] / n - mean * mean;
+                if variance < best.0 {
+                    best = (variance, sums, n);
+                }
+            }
+
+            let (_, sums, n) = best;
+            let pixel = &mut src.bytes[(y * w + x) * 4..][..3];
+            for (value, sum) in pixel.iter_mut().zip(sums.iter()) {
+                *value = (sum / n).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply a levels adjustment to the color channels.
+///
+/// Works like the Levels dialog of Photoshop. The input range `in_black` - `in_white` is stretched
+/// to 0 - 255, with the values outside of it clipped, then the midtones are adjusted by `gamma`
+/// like `filter::gamma` and the result is compressed to the output range `out_black` -
+/// `out_white`. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If `in_black` is not below `in_white`, this fails with `RasterError::InvalidLevels`. If the
+/// gamma is outside of 0.01 - 9.99, this fails with `RasterError::InvalidGamma`.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::levels(&mut image, 20, 230, 1.2, 0, 255).unwrap();
+/// raster::save(&image, "tests/out/test_filter_levels.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_levels.jpg)
+///
+pub fn levels(
+    src: &mut Image,
+    in_black: u8,
+    in_white: u8,
+    gamma: f32,
+    out_black: u8,
+    out_white: u8,
+) -> RasterResult<()> {
+    let lut = _levels_lut(in_black, in_white, gamma, out_black, out_white)?;
+    for pixel in src.bytes.chunks_mut(4) {
+        for channel in &mut pixel[0..3] {
+            *channel = lut[*channel as usize];
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply a levels adjustment to a single channel.
+///
+/// Same as `filter::levels` but only changes the given channel, eg. to correct a color cast by
+/// moving the white point of the red channel alone.
+///
+/// # Errors
+///
+/// Same as `filter::levels`.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, Channel};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::levels_channel(&mut image, Channel::Blue, 0, 220, 1.0, 0, 255).unwrap();
+/// raster::save(&image, "tests/out/test_filter_levels_channel.jpg").unwrap();
+/// ```
+pub fn levels_channel(
+    src: &mut Image,
+    channel: Channel,
+    in_black: u8,
+    in_white: u8,
+    gamma: f32,
+    out_black: u8,
+    out_white: u8,
+) -> RasterResult<()> {
+    let lut = _levels_lut(in_black, in_white, gamma, out_black, out_white)?;
+    let c = _channel_index(channel);
+    for pixel in src.bytes.chunks_mut(4) {
+        pixel[c] = lut[pixel[c] as usize];
+    }
+
+    Ok(())
+}
+
+/// Match the histograms of an image to those of a reference image.
+///
+/// Each color channel is remapped so that its distribution of values follows the one of the same
+/// channel in the reference, eg. to make a batch of photos look consistent with a chosen one. The
+/// images don't need to have the same dimensions. Alpha is left untouched.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let reference = raster::open("tests/in/sample.png").unwrap();
+/// filter::match_histogram(&mut image, &reference).unwrap();
+/// raster::save(&image, "tests/out/test_filter_match_histogram.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_match_histogram.jpg)
+///
+pub fn match_histogram(src: &mut Image, reference: &Image) -> RasterResult<()> {
+    if reference.bytes.is_empty() {
+        return Ok(());
+    }
+
+    for c in 0..3 {
+        let cdf = _channel_cdf(src, c);
+        let reference_cdf = _channel_cdf(reference, c);
+
+        // Map each value to the first reference value at the same point of the distribution.
+        let mut lut = [0u8; 256];
+        let mut r = 0;
+        for (value, entry) in lut.iter_mut().enumerate() {
+            while r < 255 && reference_cdf[r] < cdf[value] {
+                r += 1;
+            }
+            *entry = r as u8;
+        }
+
+        for pixel in src.bytes.chunks_mut(4) {
+            pixel[c] = lut[pixel[c] as usize];
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply a median filter of the given radius.
+///
+/// Each channel of a pixel is replaced by the median of that channel over the
+/// (2 * radius + 1) x (2 * radius + 1) square around it, with the edge pixels repeated outside the
+/// image. This removes speckle noise while keeping edges sharp. The medians are read from
+/// per-channel histograms that slide along each row, so larger radii stay fast.
+///
+/// # Errors
+///
+/// If the radius is negative, this fails with `RasterError::InvalidRadius`.
+///
+/// # Examples
+///
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::median(&mut image, 2).unwrap();
+/// raster::save(&image, "tests/out/test_filter_median.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_median.jpg)
+///
+pub fn median(src: &mut Image, radius: i32) -> RasterResult<()> {
+    if radius < 0 {
+        return Err(RasterError::InvalidRadius(radius));
+    }
+    if radius == 0 || src.width <= 0 || src.height <= 0 {
+        return Ok(());
+    }
+
+    let w = src.width;
+    let h = src.height;
+    let copy = src.bytes.clone();
+    let index = |x: i32, y: i32| (y.clamp(0, h - 1) * w + x.clamp(0, w - 1)) as usize * 4;
+    let half = ((2 * radius + 1) * (2 * radius + 1) / 2) as u32;
+
+    for y in 0..h {
+        // Fill the histograms for the window of the first pixel, then slide it to the right by
+        // removing the column that leaves and adding the one that enters.
+        let mut histograms = [[0u32; 256]; 4];
+        for wy in y - radius..=y + radius {
+            for wx in -radius..=radius {
+                let i = index(wx, wy);
+                for (c, histogram) in histograms.iter_mut().enumerate() {
+                    histogram[copy[i + c] as usize] += 1;
+                }
+            }
+        }
+
+        for x in 0..w {
+            if x > 0 {
+                for wy in y - radius..=y + radius {
+                    let leave = index(x - radius - 1, wy);
+                    let enter = index(x + radius, wy);
+                    for (c, histogram) in histograms.iter_mut().enumerate() {
+                        histogram[copy[leave + c] as usize] -= 1;
+                        histogram[copy[enter + c] as usize] += 1;
+                    }
+                }
+            }
+
+            let start = (y * w + x) as usize * 4;
+            for (c, histogram) in histograms.iter().enumerate() {
+                src.bytes[start + c] = _histogram_median(histogram, half);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Give the image the look of an oil painting.
+///
+/// The pixels within radius of each pixel are sorted into intensity_levels buckets by intensity,
+/// and the pixel becomes the average color of the fullest bucket. Fewer levels give broader
+/// strokes, a larger radius bigger ones. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If the radius is negative, this fails with `RasterError::InvalidRadius`. If intensity_levels is
+/// not in the range 1 - 256, this fails with `RasterError::InvalidIntensityLevels`.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::oil_paint(&mut image, 3, 20).unwrap();
+/// raster::save(&image, "tests/out/test_filter_oil_paint.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_oil_paint.jpg)
+///
+pub fn oil_paint(src: &mut Image, radius: i32, intensity_levels: usize) -> RasterResult<()> {
+    if radius < 0 {
+        return Err(RasterError::InvalidRadius(radius));
+    }
+    if intensity_levels == 0 || intensity_levels > 256 {
+        return Err(RasterError::InvalidIntensityLevels(intensity_levels));
+    }
+
+    let (w, h) = (src.width, src.height);
+    let levels: Vec<usize> = src
+        .bytes
+        .chunks(4)
+        .map(|p| (p[0] as usize + p[1] as usize + p[2] as usize) * intensity_levels / (3 * 256))
+        .collect();
+
+    let mut bytes = src.bytes.clone();
+    let mut counts = vec![0u32; intensity_levels];
+    let mut sums = vec![[0u32; 3]; intensity_levels];
+    for y in 0..h {
+        for x in 0..w {
+            counts.iter_mut().for_each(|count| *count = 0);
+            sums.iter_mut().for_each(|sum| *sum = [0; 3]);
+            for ny in (y - radius).max(0)..(y + radius + 1).min(h) {
+                for nx in (x - radius).max(0)..(x + radius + 1).min(w) {
+                    let i = (ny * w + nx) as usize;
+                    let level = levels[i];
+                    counts[level] += 1;
+                    for (sum, &value) in sums[level].iter_mut().zip(&src.bytes[i * 4..i * 4 + 3]) {
+                        *sum += value as u32;
+                    }
+                }
+            }
+
+            let (fullest, &count) = counts
+                .iter()
+                .enumerate()
+                .max_by_key(|&(_, count)| count)
+                .unwrap();
+            let start = ((y * w + x) * 4) as usize;
+            for (value, sum) in bytes[start..start + 3].iter_mut().zip(sums[fullest].iter()) {
+                *value = ((sum + count / 2) / count) as u8;
+            }
+        }
+    }
+    src.bytes = bytes;
+
+    Ok(())
+}
+
+/// Sort runs of pixels by luminance, a glitch art effect.
+///
+/// Each row, or each column with `Direction::Vertical`, is split into runs of consecutive pixels
+/// whose luminance is within the threshold range, inclusive. The pixels of each run are sorted
+/// from dark to light, alpha included, while pixels outside the range stay in place. A wider range
+/// gives longer streaks.
+///
+/// # Errors
+///
+/// If the low threshold is above the high one, this fails with `RasterError::InvalidThresholds`.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, Direction};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::pixel_sort(&mut image, Direction::Vertical, (60, 220)).unwrap();
+/// raster::save(&image, "tests/out/test_filter_pixel_sort.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_pixel_sort.jpg)
+///
+pub fn pixel_sort(src: &mut Image, direction: Direction, threshold: (u8, u8)) -> RasterResult<()> {
+    let (low, high) = threshold;
+    if low > high {
+        return Err(RasterError::InvalidThresholds(low as f32, high as f32));
+    }
+
+    let (w, h) = (src.width as usize, src.height as usize);
+    let (lines, length) = match direction {
+        Direction::Horizontal => (h, w),
+        Direction::Vertical => (w, h),
+    };
+    let index = |line: usize, i: usize| match direction {
+        Direction::Horizontal => line * w + i,
+        Direction::Vertical => i * w + line,
+    };
+    let luma = |p: &[u8]| (p[0] as u32 * 299 + p[1] as u32 * 587 + p[2] as u32 * 114) / 1000;
+
+    let mut run: Vec<[u8; 4]> = Vec::new();
+    for line in 0..lines {
+        let mut i = 0;
+        while i < length {
+            let start = i;
+            run.clear();
+            while i < length {
+                let at = index(line, i) * 4;
+                let pixel = [
+                    src.bytes[at],
+                    src.bytes[at + 1],
+                    src.bytes[at + 2],
+                    src.bytes[at + 3],
+                ];
+                if !(low as u32..=high as u32).contains(&luma(&pixel)) {
+                    break;
+                }
+                run.push(pixel);
+                i += 1;
+            }
+
+            run.sort_by_key(|p| luma(p));
+            for (k, pixel) in run.iter().enumerate() {
+                let at = index(line, start + k) * 4;
+                src.bytes[at..at + 4].copy_from_slice(pixel);
+            }
+            if run.is_empty() {
+                i += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply sharpen.
+///
+/// Each pixel is pushed away from the average of its 4 direct neighbors by `amount`. An amount of
+/// 0.0 leaves the image unchanged and 1.0 is the classic `[[0, -1, 0], [-1, 5, -1], [0, -1, 0]]`
+/// kernel.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// // Create image from file
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::sharpen(&mut image, 1.0).unwrap();
+/// raster::save(&image, "tests/out/test_filter_sharpen.jpg").unwrap();
+/// ```
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_sharpen.jpg)
+///
+pub fn sharpen(src: &mut Image, amount: f32) -> RasterResult<()> {
+    let kernel = Kernel::new(&[
+        [0.0, -amount, 0.0],
+        [-amount, 1.0 + 4.0 * amount, -amount],
+        [0.0, -amount, 0.0],
+    ])?;
+    convolve(src, &kernel)
+}
+
+/// Apply an unsharp mask.
+///
+/// The image is blurred with a Gaussian of the given radius and the difference between the
+/// original and the blurred copy is added back, multiplied by `amount` (1.0 adds it once).
+/// Channels that differ from the blurred copy by less than `threshold` are left alone, which keeps
+/// flat areas and noise from being sharpened. This is useful to crisp up images softened by
+/// resizing. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If the radius is negative, this fails with `RasterError::InvalidRadius`.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// // Create image from file
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::unsharp_mask(&mut image, 2, 0.8, 3).unwrap();
+/// raster::save(&image, "tests/out/test_filter_unsharp_mask.jpg").unwrap();
+/// ```
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_unsharp_mask.jpg)
+///
+pub fn unsharp_mask(src: &mut Image, radius: i32, amount: f32, threshold: u8) -> RasterResult<()> {
+    if radius < 0 {
+        return Err(RasterError::InvalidRadius(radius));
+    }
+    if radius == 0 || src.width <= 0 || src.height <= 0 {
+        return Ok(());
+    }
+
+    let blurred = _gaussian(src, radius as usize);
+    for (i, byte) in src.bytes.iter_mut().enumerate() {
+        if i % 4 == 3 {
+            continue;
+        }
+        let diff = *byte as f32 - blurred[i];
+        if diff.abs() < threshold as f32 {
+            continue;
+        }
+        *byte = (*byte as f32 + diff * amount).round().clamp(0.0, 255.0) as u8;
+    }
+
+    Ok(())
+}
+
+/// Shift the color temperature.
+///
+/// Works like the white balance temperature of a photo editor: the image is corrected as if it
+/// was lit by a light `kelvin_shift` degrees warmer or cooler than daylight (6500K). Values > 0.0
+/// make it warmer (more yellow) and values < 0.0 make it cooler (more blue), eg. 1500.0 for
+/// a photo taken in the shade. The channels are scaled in linear light, keeping the luminance of
+/// grays. Alpha is left untouched.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::temperature(&mut image, 1500.0).unwrap();
+/// raster::save(&image, "tests/out/test_filter_temperature.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_temperature.jpg)
+///
+pub fn temperature(src: &mut Image, kelvin_shift: f32) -> RasterResult<()> {
+    let daylight = _kelvin_to_rgb(6500.0);
+    let light = _kelvin_to_rgb((6500.0 + kelvin_shift).clamp(1000.0, 40000.0));
+    _scale_channels(
+        src,
+        [
+            daylight[0] / light[0],
+            daylight[1] / light[1],
+            daylight[2] / light[2],
+        ],
+    );
+    Ok(())
+}
+
+/// Shift the tint between green and magenta.
+///
+/// Works like the white balance tint of a photo editor, to remove the cast of fluorescent or LED
+/// lights. The tint goes from -100.0 (green) to 100.0 (magenta), values outside of that range are
+/// clamped. The channels are scaled in linear light, keeping the luminance of grays. Alpha is left
+/// untouched.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::tint(&mut image, 30.0).unwrap();
+/// raster::save(&image, "tests/out/test_filter_tint.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_tint.jpg)
+///
+pub fn tint(src: &mut Image, green_magenta: f32) -> RasterResult<()> {
+    // At the ends of the range, green is halved or doubled relative to red and blue.
+    let green = 2.0f32.powf(-green_magenta.clamp(-100.0, 100.0) / 100.0);
+    _scale_channels(src, [1.0, green, 1.0]);
+    Ok(())
+}
+
+/// Turn into a black and white image.
+///
+/// Pixels with a luminance of at least `value` become white and the others become black, eg.
+/// as the first step of cleaning up a scanned document. Alpha is left untouched.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::threshold(&mut image, 128).unwrap();
+/// raster::save(&image, "tests/out/test_filter_threshold.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_threshold.jpg)
+///
+pub fn threshold(src: &mut Image, value: u8) -> RasterResult<()> {
+    let luma = _luma(src);
+    _binarize(src, &luma, |_| value as f32);
+    Ok(())
+}
+
+/// Turn into a black and white image with a threshold computed around each pixel.
+///
+/// Each pixel is compared with the mean luminance of the `block_size` x `block_size` square
+/// around it minus `offset`, so that unevenly lit photos, eg. of receipts, still binarize cleanly.
+/// A positive offset keeps flat areas white. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If the block size is not an odd number of at least 3, this fails with
+/// `RasterError::InvalidBlockSize`.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, ThresholdMode};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::threshold_adaptive(&mut image, 15, 5.0, ThresholdMode::Gaussian).unwrap();
+/// raster::save(&image, "tests/out/test_filter_threshold_adaptive.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_threshold_adaptive.jpg)
+///
+pub fn threshold_adaptive(
+    src: &mut Image,
+    block_size: i32,
+    offset: f32,
+    mode: ThresholdMode,
+) -> RasterResult<()> {
+    if block_size < 3 || block_size % 2 != 1 {
+        return Err(RasterError::InvalidBlockSize(block_size));
+    }
+    if src.width <= 0 || src.height <= 0 {
+        return Ok(());
+    }
+
+    let w = src.width;
+    let h = src.height;
+    let r = (block_size / 2) as usize;
+    let luma = _luma(src);
+    let means = match mode {
+        ThresholdMode::Mean => _box_mean(&luma, w as usize, h as usize, r),
+        ThresholdMode::Gaussian => {
+            // Same sigma as OpenCV picks for the block size
+            let sigma = 0.3 * (r as f32 - 1.0) + 0.8;
+            let row: Vec<f32> = (0..block_size)
+                .map(|i| {
+                    let d = (i - r as i32) as f32;
+                    (-(d * d) / (2.0 * sigma * sigma)).exp()
+                })
+                .collect();
+            let rows: Vec<Vec<f32>> = row
+                .iter()
+                .map(|a| row.iter().map(|b| a * b).collect())
+                .collect();
+            let total: f32 = row.iter().sum::<f32>().powi(2);
+            _convolve_channel(&luma, w, h, &Kernel::new(&rows)?.divisor(total))
+        }
+    };
+
+    _binarize(src, &luma, |i| means[i] - offset);
+    Ok(())
+}
+
+/// Turn into a black and white image with a threshold picked by Otsu's method.
+///
+/// The threshold is the luminance that best separates the histogram into a dark and a light
+/// class, so it adapts to each image instead of having to be guessed, eg. for scans with
+/// different exposures. The image is then thresholded like `filter::threshold` and the threshold
+/// used is returned.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let threshold = filter::threshold_otsu(&mut image).unwrap();
+/// println!("Thresholded at {}", threshold);
+/// raster::save(&image, "tests/out/test_filter_threshold_otsu.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_threshold_otsu.jpg)
+///
+pub fn threshold_otsu(src: &mut Image) -> RasterResult<u8> {
+    let luma = _luma(src);
+    let histogram = _luma_histogram(&luma);
+    let total = luma.len() as f64;
+    let sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(value, &n)| value as f64 * n as f64)
+        .sum();
+
+    // Find the threshold maximizing the variance between the classes below and at or above it.
+    let mut best = (0.0, 0);
+    let mut dark_count = 0.0;
+    let mut dark_sum = 0.0;
+    for t in 1..256 {
+        dark_count += histogram[t - 1] as f64;
+        dark_sum += (t - 1) as f64 * histogram[t - 1] as f64;
+        let light_count = total - dark_count;
+        if dark_count == 0.0 || light_count == 0.0 {
+            continue;
+        }
+        let dark_mean = dark_sum / dark_count;
+        let light_mean = (sum - dark_sum) / light_count;
+        let variance = dark_count * light_count * (dark_mean - light_mean).powi(2);
+        if variance > best.0 {
+            best = (variance, t);
+        }
+    }
+
+    let value = best.1 as u8;
+    _binarize(src, &luma, |_| value as f32);
+    Ok(value)
+}
+
+/// Make a photo look like a miniature model, with a sharp horizontal band and blurred, more
+/// saturated surroundings.
+///
+/// The focus band is given as the top and bottom of the sharp area, as fractions of the height
+/// from 0.0 - 1.0, eg. (0.45, 0.65). Outside of it the blur grows over a quarter of the height up
+/// to a Gaussian blur of blur_amount pixels. Then the saturation is changed by saturation_boost
+/// like `filter::saturation`, eg. 0.3 for the toy look. Alpha is left untouched.
+///
+/// # Errors
+///
+/// If the band is outside 0.0 - 1.0 or its top is below its bottom, this fails with
+/// `RasterError::InvalidPercentage`. If the blur amount is negative, this fails with
+/// `RasterError::InvalidRadius`.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::tilt_shift(&mut image, (0.45, 0.65), 6, 0.3).unwrap();
+/// raster::save(&image, "tests/out/test_filter_tilt_shift.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_tilt_shift.jpg)
+///
+pub fn tilt_shift(
+    src: &mut Image,
+    focus_band: (f32, f32),
+    blur_amount: i32,
+    saturation_boost: f32,
+) -> RasterResult<()> {
+    let (top, bottom) = focus_band;
+    for &value in &[top, bottom] {
+        if value.is_nan() || !(0.0..=1.0).contains(&value) {
+            return Err(RasterError::InvalidPercentage(value));
+        }
+    }
+    if top > bottom {
+        return Err(RasterError::InvalidPercentage(top));
+    }
+    if blur_amount < 0 {
+        return Err(RasterError::InvalidRadius(blur_amount));
+    }
+
+    if blur_amount > 0 && src.width > 0 && src.height > 0 {
+        let blurred = _gaussian(src, blur_amount as usize);
+        let h = src.height as f32;
+        let row_len = src.width as usize * 4;
+        for (y, row) in src.bytes.chunks_mut(row_len).enumerate() {
+            // Distance out of the band, as a share of the transition.
+            let position = (y as f32 + 0.5) / h;
+            let distance = (top - position).max(position - bottom).max(0.0);
+            let t = (distance / 0.25).min(1.0);
+            if t == 0.0 {
+                continue;
+            }
+            let mix = t * t * (3.0 - 2.0 * t);
+            for (i, byte) in row.iter_mut().enumerate() {
+                if i % 4 == 3 {
+                    continue;
+                }
+                let value = *byte as f32 + (blurred[y * row_len + i] - *byte as f32) * mix;
+                *byte = value.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+
+    saturation(src, saturation_boost)
+}
+
+/// Change vibrance.
+///
+/// Like saturation, but muted colors are boosted more than already saturated ones, and skin tones
+/// (hues between red and yellow) only get half of the boost so that faces don't turn orange.
+/// Pass a float value for amount. < 0.0 to decrease and > 0.0 to increase, eg. 0.5 for a mild
+/// boost. Alpha is left untouched.
+///
+/// # Examples
+/// ```
+/// use raster::filter;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::vibrance(&mut image, 0.8).unwrap();
+/// raster::save(&image, "tests/out/test_filter_vibrance.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_vibrance.jpg)
+///
+pub fn vibrance(src: &mut Image, amount: f32) -> RasterResult<()> {
+    for pixel in src.bytes.chunks_mut(4) {
+        let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let saturation = (max - min) / 255.0;
+
+        let mut boost = amount * (1.0 - saturation);
+        if r >= g && g >= b {
+            boost *= 0.5;
+        }
+
+        // Move the channels away from the gray of the same luminance.
+        let luma = r * 0.3 + g * 0.59 + b * 0.11;
+        for channel in &mut pixel[0..3] {
+            let value = luma + (*channel as f32 - luma) * (1.0 + boost);
+            *channel = value.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    Ok(())
+}
+
+/// Fade the image toward a color at the edges, with a smooth radial falloff.
+///
+/// Use black to darken the edges or white to lighten them. The center is given as fractions of
+/// the width and height, eg. (0.5, 0.5) for the middle of the image. Pixels closer to the center
+/// than radius are untouched, beyond it the fade grows until it reaches strength at the farthest
+/// corner. Radius is a fraction of the distance from the center to that corner. Strength and
+/// radius are values from 0.0 - 1.0. The alpha of the color scales the strength, the alpha of the
+/// image is left untouched.
+///
+/// # Errors
+///
+/// If strength or radius is outside 0.0 - 1.0, this fails with
+/// `RasterError::InvalidPercentage`.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, Color};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::vignette(&mut image, 0.8, 0.4, (0.5, 0.5), &Color::black()).unwrap();
+/// raster::save(&image, "tests/out/test_filter_vignette.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_filter_vignette.jpg)
+///
+pub fn vignette(
+    src: &mut Image,
+    strength: f32,
+    radius: f32,
+    center: (f32, f32),
+    color: &Color,
+) -> RasterResult<()> {
+    for &value in &[strength, radius] {
+        if value.is_nan() || !(0.0..=1.0).contains(&value) {
+            return Err(RasterError::InvalidPercentage(value));
+        }
+    }
+
+    let (w, h) = (src.width as f32, src.height as f32);
+    let (cx, cy) = (center.0 * w, center.1 * h);
+    let farthest = (cx.max(w - cx)).hypot(cy.max(h - cy));
+    if farthest <= 0.0 {
+        return Ok(());
+    }
+    let strength = strength * color.a as f32 / 255.0;
+    let target = [color.r as f32, color.g as f32, color.b as f32];
+
+    let width = src.width.max(1) as usize;
+    for (i, pixel) in src.bytes.chunks_mut(4).enumerate() {
+        let x = (i % width) as f32 + 0.5;
+        let y = (i / width) as f32 + 0.5;
+        let d = (x - cx).hypot(y - cy) / farthest;
+        if d <= radius {
+            continue;
+        }
+
+        // Smoothstep from the radius to the farthest corner.
+        let t = ((d - radius) / (1.0 - radius)).min(1.0);
+        let amount = strength * t * t * (3.0 - 2.0 * t);
+        for (channel, target) in pixel[0..3].iter_mut().zip(target.iter()) {
+            let value = *channel as f32 + (target - *channel as f32) * amount;
+            *channel = value.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    Ok(())
 }
 
 // Private functions
 
+// Radius of a dot centered on a cell of size 1 that covers the share of the cell. Past 0.5 the dot
+// is cut by the sides of the cell, so the radius is found by bisection.
+fn _dot_radius(share: f32) -> f32 {
+    let covered = |r: f32| {
+        let mut area = std::f32::consts::PI * r * r;
+        if r > 0.5 {
+            // Remove the 4 circular segments beyond the sides.
+            let segment = r * r * (0.5 / r).acos() - 0.5 * (r * r - 0.25).sqrt();
+            area -= 4.0 * segment;
+        }
+        area
+    };
+
+    let (mut low, mut high) = (0.0, std::f32::consts::FRAC_1_SQRT_2);
+    for _ in 0..24 {
+        let mid = (low + high) / 2.0;
+        if covered(mid) < share {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    high
+}
+
+// Sum the 4 channels of each item over a window of radius r along a line of len items, repeating
+// the end items outside the line. The window slides by adding the item entering it and removing
+// the one leaving it.
+fn _sliding_sum<G, S>(len: usize, r: usize, get: G, mut set: S)
+where
+    G: Fn(usize, usize) -> u64,
+    S: FnMut(usize, usize, u64),
+{
+    let last = len - 1;
+    for c in 0..4 {
+        let mut sum = (r as u64 + 1) * get(0, c);
+        for i in 1..=r {
+            sum += get(i.min(last), c);
+        }
+        for i in 0..len {
+            set(i, c, sum);
+            let enter = (i + r + 1).min(last);
+            let leave = i.saturating_sub(r);
+            sum = sum + get(enter, c) - get(leave, c);
+        }
+    }
+}
+
+// The index along a line of len items to read for position i, which may be outside of the line.
+fn _edge(i: i32, len: usize, mode: EdgeMode) -> usize {
+    let n = len as i32;
+    let i = match mode {
+        EdgeMode::Clamp => i.clamp(0, n - 1),
+        EdgeMode::Wrap => i.rem_euclid(n),
+        EdgeMode::Mirror if n == 1 => 0,
+        EdgeMode::Mirror => {
+            let period = 2 * n - 2;
+            let m = i.rem_euclid(period);
+            if m < n {
+                m
+            } else {
+                period - m
+            }
+        }
+    };
+    i as usize
+}
+
+// Convolve the color channels, keeping the alpha of each pixel.
+fn _convolve_color(src: &mut Image, kernel: &Kernel) -> RasterResult<()> {
+    let alpha: Vec<u8> = src.bytes.iter().skip(3).step_by(4).cloned().collect();
+    convolve(src, kernel)?;
+    for (pixel, a) in src.bytes.chunks_mut(4).zip(alpha) {
+        pixel[3] = a;
+    }
+    Ok(())
+}
+
+// Multiply the color channels by gains in linear light. The gains are first normalized so that
+// grays keep their luminance.
+fn _scale_channels(src: &mut Image, gains: [f32; 3]) {
+    let luma = gains[0] * 0.2126 + gains[1] * 0.7152 + gains[2] * 0.0722;
+    let mut luts = [[0u8; 256]; 3];
+    for (lut, gain) in luts.iter_mut().zip(gains.iter()) {
+        for (value, entry) in lut.iter_mut().enumerate() {
+            let linear = srgb_to_linear(value as f32 / 255.0) * gain / luma;
+            *entry = (linear_to_srgb(linear.min(1.0)) * 255.0).round() as u8;
+        }
+    }
+
+    for pixel in src.bytes.chunks_mut(4) {
+        for (channel, lut) in pixel[0..3].iter_mut().zip(luts.iter()) {
+            *channel = lut[*channel as usize];
+        }
+    }
+}
+
+// Linear light color of a black body at the temperature in kelvin, using Tanner Helland's fit of
+// the sRGB values.
+fn _kelvin_to_rgb(kelvin: f32) -> [f32; 3] {
+    let t = kelvin / 100.0;
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (t - 60.0).powf(-0.133_204_76)
+    };
+    let g = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_16 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+
+    // Keep a little of every channel so that the gains stay finite.
+    [r, g, b].map(|c| srgb_to_linear(c.clamp(1.0, 255.0) / 255.0))
+}
+
+// The position of a channel within the 4 bytes of a pixel.
+fn _channel_index(channel: Channel) -> usize {
+    match channel {
+        Channel::Red => 0,
+        Channel::Green => 1,
+        Channel::Blue => 2,
+        Channel::Alpha => 3,
+    }
+}
+
+// Lookup table of a monotone cubic spline through the points, using the Fritsch-Carlson tangents.
+fn _curve_lut(points: &[(u8, u8)]) -> RasterResult<[u8; 256]> {
+    if points.len() < 2 || points.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+        return Err(RasterError::InvalidCurve);
+    }
+
+    let xs: Vec<f32> = points.iter().map(|p| p.0 as f32).collect();
+    let ys: Vec<f32> = points.iter().map(|p| p.1 as f32).collect();
+    let n = points.len();
+
+    // Secant slopes, then tangents averaging them, flattened at local extrema.
+    let slopes: Vec<f32> = (0..n - 1)
+        .map(|k| (ys[k + 1] - ys[k]) / (xs[k + 1] - xs[k]))
+        .collect();
+    let mut tangents = vec![0.0f32; n];
+    tangents[0] = slopes[0];
+    tangents[n - 1] = slopes[n - 2];
+    for k in 1..n - 1 {
+        if slopes[k - 1] * slopes[k] > 0.0 {
+            tangents[k] = (slopes[k - 1] + slopes[k]) / 2.0;
+        }
+    }
+
+    // Shrink the tangents that would make a segment overshoot.
+    for k in 0..n - 1 {
+        if slopes[k] == 0.0 {
+            tangents[k] = 0.0;
+            tangents[k + 1] = 0.0;
+            continue;
+        }
+        let a = tangents[k] / slopes[k];
+        let b = tangents[k + 1] / slopes[k];
+        let length = (a * a + b * b).sqrt();
+        if length > 3.0 {
+            tangents[k] = 3.0 / length * a * slopes[k];
+            tangents[k + 1] = 3.0 / length * b * slopes[k];
+        }
+    }
+
+    let mut lut = [0u8; 256];
+    for (value, entry) in lut.iter_mut().enumerate() {
+        let x = value as f32;
+        let y = if x <= xs[0] {
+            ys[0]
+        } else if x >= xs[n - 1] {
+            ys[n - 1]
+        } else {
+            // Cubic Hermite interpolation on the segment containing x
+            let k = xs.iter().rposition(|&px| px <= x).unwrap_or(0);
+            let h = xs[k + 1] - xs[k];
+            let t = (x - xs[k]) / h;
+            let t2 = t * t;
+            let t3 = t2 * t;
+            (2.0 * t3 - 3.0 * t2 + 1.0) * ys[k]
+                + (t3 - 2.0 * t2 + t) * h * tangents[k]
+                + (-2.0 * t3 + 3.0 * t2) * ys[k + 1]
+                + (t3 - t2) * h * tangents[k + 1]
+        };
+        *entry = y.round().clamp(0.0, 255.0) as u8;
+    }
+    Ok(lut)
+}
+
+// Lookup table of a levels adjustment.
+fn _levels_lut(
+    in_black: u8,
+    in_white: u8,
+    gamma: f32,
+    out_black: u8,
+    out_white: u8,
+) -> RasterResult<[u8; 256]> {
+    if in_black >= in_white {
+        return Err(RasterError::InvalidLevels(in_black, in_white));
+    }
+    if !(0.01..=9.99).contains(&gamma) {
+        return Err(RasterError::InvalidGamma(gamma));
+    }
+
+    let mut lut = [0u8; 256];
+    let in_range = (in_white - in_black) as f32;
+    let out_range = out_white as f32 - out_black as f32;
+    for (value, entry) in lut.iter_mut().enumerate() {
+        let v = ((value as f32 - in_black as f32) / in_range).clamp(0.0, 1.0);
+        let v = v.powf(1.0 / gamma);
+        *entry = (out_black as f32 + v * out_range).round() as u8;
+    }
+    Ok(lut)
+}
+
+// Mean of a single channel image over a window of radius r, repeating the edge pixels outside
+// the image. The sums slide along the rows then the columns, like blur_box.
+fn _box_mean(values: &[f32], w: usize, h: usize, r: usize) -> Vec<f32> {
+    let slide = |get: &dyn Fn(usize) -> f32, len: usize, set: &mut dyn FnMut(usize, f32)| {
+        let last = len - 1;
+        let mut sum = (r as f32 + 1.0) * get(0);
+        for i in 1..=r {
+            sum += get(i.min(last));
+        }
+        for i in 0..len {
+            set(i, sum);
+            sum += get((i + r + 1).min(last)) - get(i.saturating_sub(r));
+        }
+    };
+
+    let mut rows = vec![0.0f32; w * h];
+    for y in 0..h {
+        slide(&|x| values[y * w + x], w, &mut |x, sum| rows[y * w + x] = sum);
+    }
+    let size = (2 * r + 1) as f32;
+    let mut out = vec![0.0f32; w * h];
+    for x in 0..w {
+        slide(&|y| rows[y * w + x], h, &mut |y, sum| out[y * w + x] = sum / (size * size));
+    }
+    out
+}
+
+// Share of the pixels with a value at or below each value of channel c.
+fn _channel_cdf(src: &Image, c: usize) -> [f64; 256] {
+    let mut histogram = [0u32; 256];
+    for pixel in src.bytes.chunks(4) {
+        histogram[pixel[c] as usize] += 1;
+    }
+
+    let total = (src.bytes.len() / 4).max(1) as f64;
+    let mut cdf = [0.0f64; 256];
+    let mut count = 0;
+    for (value, &n) in histogram.iter().enumerate() {
+        count += n;
+        cdf[value] = count as f64 / total;
+    }
+    cdf
+}
+
+// Number of pixels with each rounded luminance.
+fn _luma_histogram(luma: &[f32]) -> [u32; 256] {
+    let mut histogram = [0u32; 256];
+    for &l in luma {
+        histogram[l.round().clamp(0.0, 255.0) as usize] += 1;
+    }
+    histogram
+}
+
+// Set each pixel to white when its luminance reaches the threshold of its index, or to black.
+fn _binarize<F>(src: &mut Image, luma: &[f32], threshold: F)
+where
+    F: Fn(usize) -> f32,
+{
+    for (i, (pixel, &l)) in src.bytes.chunks_mut(4).zip(luma).enumerate() {
+        let value = if l.round() >= threshold(i) { 255 } else { 0 };
+        pixel[0] = value;
+        pixel[1] = value;
+        pixel[2] = value;
+    }
+}
+
+// Luminance of each pixel, with the same weights as grayscale.
+fn _luma(src: &Image) -> Vec<f32> {
+    src.bytes
+        .chunks(4)
+        .map(|p| p[0] as f32 * 0.3 + p[1] as f32 * 0.59 + p[2] as f32 * 0.11)
+        .collect()
+}
+
+// Convolve a single channel image without rounding or clamping the results.
+fn _convolve_channel(values: &[f32], w: i32, h: i32, kernel: &Kernel) -> Vec<f32> {
+    let w = w as usize;
+    let h = h as usize;
+    let size = kernel.size;
+    let r = (size / 2) as i32;
+    let columns: Vec<usize> = (-r..w as i32 + r).map(|x| _edge(x, w, kernel.edge)).collect();
+    let rows: Vec<usize> = (-r..h as i32 + r).map(|y| _edge(y, h, kernel.edge)).collect();
+
+    let mut out = vec![0.0f32; w * h];
+    for y in 0..h {
+        for x in 0..w {
+            let mut sum = 0.0;
+            for ky in 0..size {
+                for kx in 0..size {
+                    sum += values[rows[y + ky] * w + columns[x + kx]]
+                        * kernel.weights[ky * size + kx];
+                }
+            }
+            out[y * w + x] = sum / kernel.divisor + kernel.offset;
+        }
+    }
+    out
+}
+
+// Signed horizontal and vertical Sobel gradients of a single channel image.
+fn _sobel_gradients(values: &[f32], w: i32, h: i32) -> RasterResult<(Vec<f32>, Vec<f32>)> {
+    let horizontal = Kernel::new(&[[-1.0, 0.0, 1.0], [-2.0, 0.0, 2.0], [-1.0, 0.0, 1.0]])?;
+    let vertical = Kernel::new(&[[-1.0, -2.0, -1.0], [0.0, 0.0, 0.0], [1.0, 2.0, 1.0]])?;
+    Ok((
+        _convolve_channel(values, w, h, &horizontal),
+        _convolve_channel(values, w, h, &vertical),
+    ))
+}
+
+// Calculate the sum of the derivatives with sqrt((dImage/dx)²+(dImage/dy)²)
+fn _magnitude(gx: &[f32], gy: &[f32]) -> Vec<f32> {
+    gx.iter().zip(gy).map(|(x, y)| (x * x + y * y).sqrt()).collect()
+}
+
+// The value at position half in the sorted window counted by the histogram.
+fn _histogram_median(histogram: &[u32; 256], half: u32) -> u8 {
+    let mut count = 0;
+    for (value, n) in histogram.iter().enumerate() {
+        count += n;
+        if count > half {
+            return value as u8;
+        }
+    }
+    255
+}
+
+// Blur the 4 channels with a separable Gaussian reaching r pixels on each side, repeating the edge
+// pixels outside the image. The result is kept as floats so that callers can compare it with the
+// original without rounding. Separable passes instead of a `Kernel` keep large radii fast.
+fn _gaussian(src: &Image, r: usize) -> Vec<f32> {
+    let w = src.width as usize;
+    let h = src.height as usize;
+
+    // The kernel spans 2 standard deviations on each side.
+    let sigma = r as f32 / 2.0;
+    let mut kernel: Vec<f32> = (0..=2 * r)
+        .map(|i| {
+            let d = i as f32 - r as f32;
+            (-(d * d) / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    let total: f32 = kernel.iter().sum();
+    for k in &mut kernel {
+        *k /= total;
+    }
+
+    let mut rows = vec![0.0f32; w * h * 4];
+    for y in 0..h {
+        for x in 0..w {
+            for (k, weight) in kernel.iter().enumerate() {
+                let sx = (x + k).saturating_sub(r).min(w - 1);
+                for c in 0..4 {
+                    rows[(y * w + x) * 4 + c] += src.bytes[(y * w + sx) * 4 + c] as f32 * weight;
+                }
+            }
+        }
+    }
+
+    let mut out = vec![0.0f32; w * h * 4];
+    for y in 0..h {
+        for (k, weight) in kernel.iter().enumerate() {
+            let sy = (y + k).saturating_sub(r).min(h - 1);
+            for x in 0..w {
+                for c in 0..4 {
+                    out[(y * w + x) * 4 + c] += rows[(sy * w + x) * 4 + c] * weight;
+                }
+            }
+        }
+    }
+    out
+}
+
+// Pseudo random number generator, SplitMix64. Small and fast, not for cryptography.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // In 0.0 - 1.0, 1.0 excluded.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // Standard normal distribution, by the Box-Muller transform.
+    fn gaussian(&mut self) -> f32 {
+        let u1 = 1.0 - self.next_f32();
+        let u2 = self.next_f32();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
+    }
+}
+
 // Box
-fn blur_box(src: &mut Image) -> RasterResult<()> {
-    let matrix: [[i32; 3]; 3] = [[1, 1, 1], [1, 1, 1], [1, 1, 1]];
-    convolve(src, matrix, 9)
+fn blur_box_3x3(src: &mut Image) -> RasterResult<()> {
+    let kernel = Kernel::new(&[[1.0; 3]; 3])?;
+    convolve(src, &kernel.divisor(9.0))
 }
 
 // Gaussian
 fn blur_gaussian(src: &mut Image) -> RasterResult<()> {
-    let matrix: [[i32; 3]; 3] = [[1, 2, 1], [2, 4, 2], [1, 2, 1]];
-    convolve(src, matrix, 16)
+    let kernel = Kernel::new(&[[1.0, 2.0, 1.0], [2.0, 4.0, 2.0], [1.0, 2.0, 1.0]])?;
+    convolve(src, &kernel.divisor(16.0))
 }
@@ -0,0 +1,268 @@
+//!  A module for generating gradient images.
+
+// from rust
+use std::f32::consts::PI;
+
+// from external crate
+
+// from local crate
+use error::{RasterError, RasterResult};
+use Color;
+use Image;
+
+/// A color at a position along a gradient.
+#[derive(Debug, Clone)]
+pub struct GradientStop {
+    /// Position from 0.0 (start of the gradient) to 1.0 (end of the gradient).
+    pub position: f32,
+
+    /// Color at that position.
+    pub color: Color,
+}
+
+impl GradientStop {
+    /// Create a gradient stop.
+    ///
+    /// # Examples
+    /// ```
+    /// use raster::{Color, GradientStop};
+    ///
+    /// let stop = GradientStop::new(0.5, Color::red());
+    /// assert_eq!(0.5, stop.position);
+    /// ```
+    pub fn new(position: f32, color: Color) -> GradientStop {
+        GradientStop { position, color }
+    }
+}
+
+/// Create an image filled with a linear gradient from start_color to end_color.
+///
+/// The angle is in degrees, clockwise: 0.0 goes from left to right and 90.0 from top to bottom.
+/// The gradient spans the image so that the corners get the start and end colors.
+///
+/// # Errors
+///
+/// If width or height is zero or negative, this fails with `RasterError::InvalidDimensions`.
+///
+/// # Examples
+/// ```
+/// use raster::{gradient, Color};
+///
+/// let image = gradient::linear(200, 100, &Color::red(), &Color::blue(), 45.0).unwrap();
+/// raster::save(&image, "tests/out/test_gradient_linear.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_gradient_linear.png)
+///
+pub fn linear(
+    width: i32,
+    height: i32,
+    start_color: &Color,
+    end_color: &Color,
+    angle: f32,
+) -> RasterResult<Image> {
+    let stops = [
+        GradientStop::new(0.0, start_color.clone()),
+        GradientStop::new(1.0, end_color.clone()),
+    ];
+    linear_stops(width, height, &stops, angle)
+}
+
+/// Create an image filled with a linear gradient through several color stops.
+///
+/// Same as `gradient::linear`, with the colors of the stops in between. Before the first stop and
+/// after the last one the color of that stop is used. Two stops at the same position make a hard
+/// edge. Colors are interpolated with premultiplied alpha, so fading to transparent does not
+/// darken.
+///
+/// # Errors
+///
+/// If width or height is zero or negative, this fails with `RasterError::InvalidDimensions`.
+///
+/// If there are no stops, or their positions are not in the range 0.0 - 1.0 and in increasing
+/// order, this fails with `RasterError::InvalidGradient`.
+///
+/// # Examples
+/// ```
+/// use raster::{gradient, Color, GradientStop};
+///
+/// let stops = [
+///     GradientStop::new(0.0, Color::red()),
+///     GradientStop::new(0.5, Color::white()),
+///     GradientStop::new(1.0, Color::rgba(0, 0, 255, 0)),
+/// ];
+/// let image = gradient::linear_stops(200, 100, &stops, 90.0).unwrap();
+/// raster::save(&image, "tests/out/test_gradient_linear_stops.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_gradient_linear_stops.png)
+///
+pub fn linear_stops(
+    width: i32,
+    height: i32,
+    stops: &[GradientStop],
+    angle: f32,
+) -> RasterResult<Image> {
+    let radians = angle.to_radians();
+    let (dx, dy) = (radians.cos(), radians.sin());
+    // Length of the gradient line, the projection of the image on the direction.
+    let length = (width as f32 * dx).abs() + (height as f32 * dy).abs();
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+
+    _render(width, height, stops, |x, y| {
+        ((x - cx) * dx + (y - cy) * dy) / length + 0.5
+    })
+}
+
+/// Create an image filled with a radial gradient around a center.
+///
+/// The center is in pixels and may be outside of the image. Stop 0.0 is at the center and stop
+/// 1.0 at the farthest corner of the image. A transparent center fading to black makes a vignette
+/// mask for `editor::blend`.
+///
+/// # Errors
+///
+/// If width or height is zero or negative, this fails with `RasterError::InvalidDimensions`.
+///
+/// If there are no stops, or their positions are not in the range 0.0 - 1.0 and in increasing
+/// order, this fails with `RasterError::InvalidGradient`.
+///
+/// # Examples
+/// ```
+/// use raster::{gradient, Color, GradientStop};
+///
+/// let stops = [
+///     GradientStop::new(0.0, Color::rgba(0, 0, 0, 0)),
+///     GradientStop::new(0.5, Color::rgba(0, 0, 0, 0)),
+///     GradientStop::new(1.0, Color::rgba(0, 0, 0, 200)),
+/// ];
+/// let image = gradient::radial(200, 100, (100.0, 50.0), &stops).unwrap();
+/// raster::save(&image, "tests/out/test_gradient_radial.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_gradient_radial.png)
+///
+pub fn radial(
+    width: i32,
+    height: i32,
+    center: (f32, f32),
+    stops: &[GradientStop],
+) -> RasterResult<Image> {
+    let (cx, cy) = center;
+    let dx = cx.abs().max((width as f32 - cx).abs());
+    let dy = cy.abs().max((height as f32 - cy).abs());
+    let radius = dx.hypot(dy);
+
+    _render(width, height, stops, |x, y| (x - cx).hypot(y - cy) / radius)
+}
+
+/// Create an image filled with a conic gradient sweeping around a center.
+///
+/// The center is in pixels. Stop 0.0 points to the right of the center and the stops follow
+/// clockwise, all the way around to 1.0. Use the same color for the first and last stops to
+/// avoid a seam.
+///
+/// # Errors
+///
+/// If width or height is zero or negative, this fails with `RasterError::InvalidDimensions`.
+///
+/// If there are no stops, or their positions are not in the range 0.0 - 1.0 and in increasing
+/// order, this fails with `RasterError::InvalidGradient`.
+///
+/// # Examples
+/// ```
+/// use raster::{gradient, Color, GradientStop};
+///
+/// let stops = [
+///     GradientStop::new(0.0, Color::red()),
+///     GradientStop::new(1.0 / 3.0, Color::green()),
+///     GradientStop::new(2.0 / 3.0, Color::blue()),
+///     GradientStop::new(1.0, Color::red()),
+/// ];
+/// let image = gradient::conic(200, 200, (100.0, 100.0), &stops).unwrap();
+/// raster::save(&image, "tests/out/test_gradient_conic.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_gradient_conic.png)
+///
+pub fn conic(
+    width: i32,
+    height: i32,
+    center: (f32, f32),
+    stops: &[GradientStop],
+) -> RasterResult<Image> {
+    let (cx, cy) = center;
+    let turn = 2.0 * PI;
+
+    _render(width, height, stops, |x, y| {
+        let angle = (y - cy).atan2(x - cx); // Clockwise as y points down
+        (angle + turn) % turn / turn
+    })
+}
+
+/// Color of a gradient at position t, clamped to the first and last stops.
+pub(crate) fn sample(stops: &[GradientStop], t: f32) -> Color {
+    let last = &stops[stops.len() - 1];
+    if t >= last.position {
+        return last.color.clone();
+    }
+    let next = stops.iter().position(|stop| stop.position > t).unwrap_or(0);
+    if next == 0 {
+        return stops[0].color.clone();
+    }
+
+    let (a, b) = (&stops[next - 1], &stops[next]);
+    let f = (t - a.position) / (b.position - a.position);
+    let alpha = a.color.a as f32 + (b.color.a as f32 - a.color.a as f32) * f;
+    if alpha <= 0.0 {
+        return Color::rgba(0, 0, 0, 0);
+    }
+    let mix = |ca: u8, cb: u8| -> u8 {
+        let pa = ca as f32 * a.color.a as f32;
+        let pb = cb as f32 * b.color.a as f32;
+        ((pa + (pb - pa) * f) / alpha).round().clamp(0.0, 255.0) as u8
+    };
+    Color::rgba(
+        mix(a.color.r, b.color.r),
+        mix(a.color.g, b.color.g),
+        mix(a.color.b, b.color.b),
+        alpha.round() as u8,
+    )
+}
+
+/// Check that there are stops, in the range 0.0 - 1.0 and in increasing order.
+pub(crate) fn check_stops(stops: &[GradientStop]) -> RasterResult<()> {
+    let in_range = stops
+        .iter()
+        .all(|stop| stop.position >= 0.0 && stop.position <= 1.0);
+    let ordered = stops
+        .windows(2)
+        .all(|pair| pair[0].position <= pair[1].position);
+    if stops.is_empty() || !in_range || !ordered {
+        Err(RasterError::InvalidGradient)
+    } else {
+        Ok(())
+    }
+}
+
+// Private functions
+
+// Create an image with the gradient color at the position given for the center of each pixel.
+fn _render<F>(width: i32, height: i32, stops: &[GradientStop], position: F) -> RasterResult<Image>
+where
+    F: Fn(f32, f32) -> f32,
+{
+    if width <= 0 || height <= 0 {
+        return Err(RasterError::InvalidDimensions(width, height));
+    }
+    check_stops(stops)?;
+
+    let mut image = Image::blank(width, height);
+    for (i, pixel) in image.bytes.chunks_mut(4).enumerate() {
+        let x = (i as i32 % width) as f32 + 0.5;
+        let y = (i as i32 / width) as f32 + 0.5;
+        let color = sample(stops, position(x, y));
+        pixel.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+    }
+    Ok(image)
+}
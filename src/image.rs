@@ -8,6 +8,7 @@ use std::collections::HashMap;
 // from local crate
 use error::{RasterError, RasterResult};
 use color::Color;
+use quantize::{self, QuantizeMode};
 
 /// A struct for easily representing a raster image.
 #[derive(Debug, Clone)]
@@ -22,7 +23,7 @@ pub struct Image {
     pub bytes: Vec<u8>,
 }
 
-impl<'a> Image {
+impl Image {
     /// Create a blank image. Default color is black.
     ///
     /// # Examples
@@ -47,7 +48,7 @@ impl<'a> Image {
         Image {
             width: w,
             height: h,
-            bytes: bytes,
+            bytes,
         }
     }
 
@@ -231,7 +232,7 @@ impl<'a> Image {
     /// ```
     pub fn set_pixel(&mut self, x: i32, y: i32, color: &Color) -> RasterResult<()> {
         let rgba = 4; // length
-        let start = (y * &self.width) + x;
+        let start = (y * self.width) + x;
         let start = start * rgba;
 
         if x >= self.width || y >= self.height {
@@ -249,6 +250,178 @@ impl<'a> Image {
     }
 }
 
+/// A struct for representing an image as a palette of colors and one palette index per pixel.
+///
+/// Takes a quarter of the memory of an `Image` and maps directly to palette based GIF and PNG
+/// files. See `raster::save_indexed`.
+#[derive(Debug, Clone)]
+pub struct IndexedImage {
+    /// Width of image in pixels.
+    pub width: i32,
+
+    /// Height of image in pixels.
+    pub height: i32,
+
+    /// Colors of the image, at most 256.
+    pub palette: Vec<Color>,
+
+    /// Index in the palette of each pixel, row by row.
+    pub indices: Vec<u8>,
+}
+
+impl IndexedImage {
+    /// Create an indexed image holding exactly the colors of an image.
+    ///
+    /// Palette entries keep their alpha and are in the order in which they first appear.
+    ///
+    /// # Errors
+    ///
+    /// If the image has more than 256 distinct colors, this fails with
+    /// `RasterError::TooManyColors`. Use `IndexedImage::quantize` to reduce them first.
+    ///
+    /// # Examples
+    /// ```
+    /// use raster::{Color, Image, IndexedImage};
+    ///
+    /// let mut image = Image::blank(2, 2);
+    /// image.set_pixel(1, 1, &Color::red()).unwrap();
+    ///
+    /// let indexed = IndexedImage::from_image(&image).unwrap();
+    /// assert_eq!(2, indexed.palette.len());
+    /// assert_eq!(vec![0, 0, 0, 1], indexed.indices);
+    /// ```
+    pub fn from_image(src: &Image) -> RasterResult<IndexedImage> {
+        let mut lookup: HashMap<[u8; 4], usize> = HashMap::new();
+        let mut palette = Vec::new();
+        let mut indices = Vec::with_capacity(src.bytes.len() / 4);
+        for p in src.bytes.chunks(4) {
+            let color = [p[0], p[1], p[2], p[3]];
+            let next = lookup.len();
+            let index = *lookup.entry(color).or_insert(next);
+            if index == palette.len() {
+                palette.push(Color::rgba(p[0], p[1], p[2], p[3]));
+            }
+            indices.push(index as u8); // Only kept when there are 256 colors or less
+        }
+
+        if palette.len() > 256 {
+            return Err(RasterError::TooManyColors(palette.len()));
+        }
+        Ok(IndexedImage {
+            width: src.width,
+            height: src.height,
+            palette,
+            indices,
+        })
+    }
+
+    /// Create an indexed image of at most n_colors colors picked with a quantization mode.
+    ///
+    /// Each pixel gets the nearest palette color. Fully transparent pixels share one extra
+    /// transparent palette entry, which counts towards n_colors. The alpha of other pixels is
+    /// dropped.
+    ///
+    /// # Errors
+    ///
+    /// If n_colors is not in the range 1 - 256, or is 1 while the image has both transparent and
+    /// visible pixels, this fails with `RasterError::InvalidPalette`.
+    ///
+    /// # Examples
+    /// ```
+    /// use raster::IndexedImage;
+    /// use raster::quantize::QuantizeMode;
+    ///
+    /// let image = raster::open("tests/in/sample.jpg").unwrap();
+    /// let indexed = IndexedImage::quantize(&image, 64, QuantizeMode::Octree).unwrap();
+    /// assert!(indexed.palette.len() <= 64);
+    /// raster::save_indexed(&indexed, "tests/out/test_indexed_quantize.gif").unwrap();
+    /// ```
+    pub fn quantize(
+        src: &Image,
+        n_colors: usize,
+        mode: QuantizeMode,
+    ) -> RasterResult<IndexedImage> {
+        if n_colors == 0 || n_colors > 256 {
+            return Err(RasterError::InvalidPalette);
+        }
+
+        let visible: Vec<[u8; 3]> = src
+            .bytes
+            .chunks(4)
+            .filter(|p| p[3] > 0)
+            .map(|p| [p[0], p[1], p[2]])
+            .collect();
+        let transparent = visible.len() < src.bytes.len() / 4;
+        let slots = if transparent { n_colors - 1 } else { n_colors };
+        if slots == 0 && !visible.is_empty() {
+            return Err(RasterError::InvalidPalette);
+        }
+
+        let colors = if visible.is_empty() {
+            Vec::new()
+        } else {
+            match mode {
+                QuantizeMode::MedianCut => quantize::median_cut(&visible, slots),
+                QuantizeMode::Octree => quantize::octree(&visible, slots),
+            }
+        };
+
+        let mut palette: Vec<Color> = colors.iter().map(|c| Color::rgb(c[0], c[1], c[2])).collect();
+        if transparent {
+            palette.push(Color::rgba(0, 0, 0, 0));
+        }
+
+        let mut cache: HashMap<[u8; 3], usize> = HashMap::new();
+        let indices = src
+            .bytes
+            .chunks(4)
+            .map(|p| {
+                if p[3] == 0 {
+                    colors.len() as u8
+                } else {
+                    let color = [p[0], p[1], p[2]];
+                    *cache
+                        .entry(color)
+                        .or_insert_with(|| quantize::nearest(&colors, &color)) as u8
+                }
+            })
+            .collect();
+
+        Ok(IndexedImage {
+            width: src.width,
+            height: src.height,
+            palette,
+            indices,
+        })
+    }
+
+    /// Convert back to an RGBA image.
+    ///
+    /// Indices outside of the palette become transparent black.
+    ///
+    /// # Examples
+    /// ```
+    /// use raster::{compare, Image, IndexedImage};
+    ///
+    /// let image = Image::blank(4, 4);
+    /// let indexed = IndexedImage::from_image(&image).unwrap();
+    /// assert!(compare::equal(&image, &indexed.to_image()).unwrap());
+    /// ```
+    pub fn to_image(&self) -> Image {
+        let transparent = Color::rgba(0, 0, 0, 0);
+        let mut bytes = Vec::with_capacity(self.indices.len() * 4);
+        for &index in &self.indices {
+            let color = self.palette.get(index as usize).unwrap_or(&transparent);
+            bytes.extend_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+        Image {
+            width: self.width,
+            height: self.height,
+            bytes,
+        }
+    }
+}
+
 /// Holds histogram information.
 pub type Histogram = (
     HashMap<u8, u32>,
@@ -2,33 +2,46 @@
 
 // from rust
 use std::cmp;
+use std::f64::consts::PI;
 
 // from external crate
 
 // from local crate
-use error::RasterResult;
+use error::{RasterError, RasterResult};
 use Image;
 use Color;
 
 /// An enum for the various modes that can be used for interpolation.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InterpolationMode {
     Bilinear,
     Bicubic,
     Nearest,
+    /// Lanczos filter with a window of 3 lobes. Slowest but sharpest, best for downscaling photos.
+    Lanczos3,
 }
 
 /// Resample an image into a new size using a given interpolation method.
+///
+/// # Errors
+///
+/// If the new width or height is zero or negative, this fails with
+/// `RasterError::InvalidDimensions`.
 pub fn resample(
     src: &mut Image,
     w: i32,
     h: i32,
     interpolation: InterpolationMode,
 ) -> RasterResult<()> {
+    if w <= 0 || h <= 0 {
+        return Err(RasterError::InvalidDimensions(w, h));
+    }
+
     match interpolation {
         InterpolationMode::Bilinear => bilinear(src, w, h),
-        InterpolationMode::Bicubic => bilinear(src, w, h), // TODO: bicubic
+        InterpolationMode::Bicubic => bicubic(src, w, h),
         InterpolationMode::Nearest => nearest(src, w, h),
+        InterpolationMode::Lanczos3 => lanczos3(src, w, h),
     }
 }
 
@@ -54,63 +67,123 @@ pub fn nearest(src: &mut Image, w: i32, h: i32) -> RasterResult<()> {
     Ok(())
 }
 
-/// Interpolate using linear function.
-pub fn bilinear(src: &mut Image, w2: i32, h2: i32) -> RasterResult<()> {
-    bilinear_width(src, w2).and_then(|_| bilinear_height(src, h2))
-}
-
-// Private functions
-
-/// Interpolate the width using linear function.
-fn bilinear_width(src: &mut Image, w2: i32) -> RasterResult<()> {
-    let w1 = src.width;
-    let h1 = src.height;
-
-    let x_ratio: f64 = w1 as f64 / w2 as f64;
-
-    let mut dest = Image::blank(w2, h1);
-
-    let offset_x = (w2 / w1 / 2) as i32;
-
-    let x_start = 0 - offset_x;
-    let x_end = w2 - offset_x;
-
-    for y in 0..h1 {
-        for x in x_start..x_end {
-            let src_x = {
-                let src_x = x as f64 * x_ratio;
-                if src_x < 0.0 {
-                    0.0 // limit lower bound to 0
-                } else {
-                    src_x
-                }
-            };
-
-            let src_x_int = (src_x).floor() as i32;
-
-            let src_x_int2 = cmp::min(src_x_int + 1, w1 - 1); // limit range within $w1-1
-
-            // limit range from 0 - 1
-            let t_x = src_x - src_x_int as f64;
-
-            let src_color1 = src.get_pixel(src_x_int, y)?;
-            let src_color2 = src.get_pixel(src_x_int2, y)?;
+/// Sample the color at a fractional position using bilinear interpolation.
+///
+/// Pixel centers are at half coordinates, eg. (0.5, 0.5) is the center of the top left pixel.
+/// Positions outside the image take the background color, which gives smooth anti-aliased edges
+/// when transforming images. Colors are weighted by their alpha so transparent pixels don't
+/// bleed their color.
+///
+/// # Examples
+/// ```
+/// use raster::{interpolate, Color, Image};
+///
+/// let mut image = Image::blank(2, 1);
+/// image.set_pixel(1, 0, &Color::white()).unwrap();
+///
+/// // Halfway between the black and white pixels
+/// let pixel = interpolate::sample_bilinear(&image, 1.0, 0.5, &Color::black()).unwrap();
+/// assert_eq!(128, pixel.r);
+/// ```
+pub fn sample_bilinear(src: &Image, x: f64, y: f64, bg: &Color) -> RasterResult<Color> {
+    let x = x - 0.5;
+    let y = y - 0.5;
+    let x1 = x.floor() as i32;
+    let y1 = y.floor() as i32;
+    let x_diff = x - x1 as f64;
+    let y_diff = y - y1 as f64;
+
+    let mut accum = [0.0; 4];
+    let neighbors = [
+        (x1, y1, (1.0 - x_diff) * (1.0 - y_diff)),
+        (x1 + 1, y1, x_diff * (1.0 - y_diff)),
+        (x1, y1 + 1, (1.0 - x_diff) * y_diff),
+        (x1 + 1, y1 + 1, x_diff * y_diff),
+    ];
+    for &(px, py, weight) in &neighbors {
+        if weight == 0.0 {
+            continue;
+        }
+        let pixel = if px >= 0 && px < src.width && py >= 0 && py < src.height {
+            src.get_pixel(px, py)?
+        } else {
+            bg.clone()
+        };
+        let alpha = pixel.a as f64 * weight;
+        accum[0] += pixel.r as f64 * alpha;
+        accum[1] += pixel.g as f64 * alpha;
+        accum[2] += pixel.b as f64 * alpha;
+        accum[3] += alpha;
+    }
 
-            // red
-            let red = _lerp(src_color1.r, src_color2.r, t_x);
+    if accum[3] == 0.0 {
+        return Ok(Color::rgba(0, 0, 0, 0));
+    }
+    Ok(Color::rgba(
+        (accum[0] / accum[3]).round() as u8,
+        (accum[1] / accum[3]).round() as u8,
+        (accum[2] / accum[3]).round() as u8,
+        accum[3].round() as u8,
+    ))
+}
 
-            // green
-            let green = _lerp(src_color1.g, src_color2.g, t_x);
+/// Sample the color at a fractional position using a given interpolation method.
+///
+/// Same as `sample_bilinear`, positions outside the image take the background color.
+///
+/// # Examples
+/// ```
+/// use raster::{interpolate, Color, Image, InterpolationMode};
+///
+/// let mut image = Image::blank(2, 1);
+/// image.set_pixel(1, 0, &Color::white()).unwrap();
+///
+/// let pixel = interpolate::sample(&image, 1.2, 0.5, InterpolationMode::Nearest, &Color::black()).unwrap();
+/// assert_eq!(255, pixel.r);
+/// ```
+pub fn sample(
+    src: &Image,
+    x: f64,
+    y: f64,
+    interpolation: InterpolationMode,
+    bg: &Color,
+) -> RasterResult<Color> {
+    match interpolation {
+        InterpolationMode::Nearest => {
+            let (px, py) = (x.floor() as i32, y.floor() as i32);
+            if px >= 0 && px < src.width && py >= 0 && py < src.height {
+                src.get_pixel(px, py)
+            } else {
+                Ok(bg.clone())
+            }
+        }
+        InterpolationMode::Bilinear => sample_bilinear(src, x, y, bg),
+        InterpolationMode::Bicubic => _sample_kernel(src, x, y, 2, _catmull_rom, bg),
+        InterpolationMode::Lanczos3 => _sample_kernel(src, x, y, 3, _lanczos3, bg),
+    }
+}
 
-            // blue
-            let blue = _lerp(src_color1.b, src_color2.b, t_x);
+/// Interpolate using cubic convolution (Catmull-Rom).
+///
+/// Each destination pixel is computed from a 4x4 neighborhood of source pixels. When
+/// downscaling, the neighborhood is widened by the scale ratio so that all the source pixels
+/// under the destination pixel contribute to it. Colors are weighted by their alpha. Pixels at the
+/// edges are clamped to the image bounds.
+pub fn bicubic(src: &mut Image, w2: i32, h2: i32) -> RasterResult<()> {
+    let dest = _resample_separable(src, w2, h2, 2.0, _catmull_rom);
+    src.width = dest.width;
+    src.height = dest.height;
+    src.bytes = dest.bytes;
 
-            // alpha
-            let alpha = _lerp(src_color1.a, src_color2.a, t_x);
+    Ok(())
+}
 
-            dest.set_pixel(x + offset_x, y, &Color::rgba(red, green, blue, alpha))?;
-        }
-    }
+/// Interpolate using a Lanczos filter with a window of 3 lobes.
+///
+/// The filter is applied in separate horizontal and vertical passes. Like bicubic, the filter is
+/// widened when downscaling so that fine details are averaged instead of aliased.
+pub fn lanczos3(src: &mut Image, w2: i32, h2: i32) -> RasterResult<()> {
+    let dest = _resample_separable(src, w2, h2, 3.0, _lanczos3);
     src.width = dest.width;
     src.height = dest.height;
     src.bytes = dest.bytes;
@@ -118,74 +191,241 @@ fn bilinear_width(src: &mut Image, w2: i32) -> RasterResult<()> {
     Ok(())
 }
 
-/// Interpolate the height using linear function.
-fn bilinear_height(src: &mut Image, h2: i32) -> RasterResult<()> {
+/// Interpolate using linear function.
+///
+/// Each destination pixel is mapped back to the center of its footprint in the source image and
+/// its color is computed from the 4 nearest source pixels. Colors are weighted by their alpha, like
+/// `sample_bilinear`, so that transparent pixels do not darken the edges next to them. Pixels at
+/// the edges are clamped to the image bounds.
+pub fn bilinear(src: &mut Image, w2: i32, h2: i32) -> RasterResult<()> {
     let w1 = src.width;
     let h1 = src.height;
 
+    let x_ratio: f64 = w1 as f64 / w2 as f64;
     let y_ratio: f64 = h1 as f64 / h2 as f64;
 
-    let mut dest = Image::blank(w1, h2);
-
-    let offset_y = (h2 / h1 / 2) as i32;
-
-    let y_start = 0 - offset_y;
-    let y_end = h2 - offset_y;
-
-    for x in 0..w1 {
-        for y in y_start..y_end {
-            let src_y = {
-                let src_y = y as f64 * y_ratio;
-                if src_y < 0.0 {
-                    0.0 // limit lower bound to 0
-                } else {
-                    src_y
-                }
-            };
-
-            let src_y_int = (src_y).floor() as i32;
+    let mut dest = Image::blank(w2, h2);
+    for y in 0..h2 {
+        let (src_y1, src_y2, y_diff) = _sample_coords(y, y_ratio, h1);
+
+        for x in 0..w2 {
+            let (src_x1, src_x2, x_diff) = _sample_coords(x, x_ratio, w1);
+
+            let neighbors = [
+                (src_x1, src_y1, (1.0 - x_diff) * (1.0 - y_diff)),
+                (src_x2, src_y1, x_diff * (1.0 - y_diff)),
+                (src_x1, src_y2, (1.0 - x_diff) * y_diff),
+                (src_x2, src_y2, x_diff * y_diff),
+            ];
+            let mut accum = [0.0; 4];
+            for &(px, py, weight) in &neighbors {
+                let pixel = src.get_pixel(px, py)?;
+                let alpha = pixel.a as f64 * weight;
+                accum[0] += pixel.r as f64 * alpha;
+                accum[1] += pixel.g as f64 * alpha;
+                accum[2] += pixel.b as f64 * alpha;
+                accum[3] += alpha;
+            }
+
+            if accum[3] > 0.0 {
+                let channel = |value: f64| value.round().clamp(0.0, 255.0) as u8;
+                let color = Color::rgba(
+                    channel(accum[0] / accum[3]),
+                    channel(accum[1] / accum[3]),
+                    channel(accum[2] / accum[3]),
+                    channel(accum[3]),
+                );
+                dest.set_pixel(x, y, &color)?;
+            } else {
+                dest.set_pixel(x, y, &Color::rgba(0, 0, 0, 0))?;
+            }
+        }
+    }
+    src.width = dest.width;
+    src.height = dest.height;
+    src.bytes = dest.bytes;
 
-            let src_y_int2 = cmp::min(src_y_int + 1, h1 - 1); // limit range within $h1-1
+    Ok(())
+}
 
-            // limit range from 0 - 1
-            let t_y = src_y - src_y_int as f64;
+// Private functions
 
-            let src_color1 = src.get_pixel(x, src_y_int)?;
-            let src_color2 = src.get_pixel(x, src_y_int2)?;
+// Map a destination coordinate to the 2 neighboring source coordinates and the distance from the
+// first one. Both coordinates are clamped within 0 - (size - 1).
+fn _sample_coords(dest: i32, ratio: f64, size: i32) -> (i32, i32, f64) {
+    let src = ((dest as f64 + 0.5) * ratio - 0.5).max(0.0);
+    let src_int = cmp::min(src.floor() as i32, size - 1);
+    let src_int2 = cmp::min(src_int + 1, size - 1);
 
-            // red
-            let red = _lerp(src_color1.r, src_color2.r, t_y);
+    (src_int, src_int2, src - src_int as f64)
+}
 
-            // green
-            let green = _lerp(src_color1.g, src_color2.g, t_y);
+// Sample the color at a fractional position using a filter kernel non-zero within
+// -support..support. Colors are weighted by their alpha.
+fn _sample_kernel(
+    src: &Image,
+    x: f64,
+    y: f64,
+    support: i32,
+    kernel: fn(f64) -> f64,
+    bg: &Color,
+) -> RasterResult<Color> {
+    let x = x - 0.5;
+    let y = y - 0.5;
+    let x1 = x.floor() as i32;
+    let y1 = y.floor() as i32;
+
+    let mut accum = [0.0; 4];
+    let mut total = 0.0;
+    for py in y1 - support + 1..y1 + support + 1 {
+        let weight_y = kernel(y - py as f64);
+        for px in x1 - support + 1..x1 + support + 1 {
+            let weight = weight_y * kernel(x - px as f64);
+            let pixel = if px >= 0 && px < src.width && py >= 0 && py < src.height {
+                src.get_pixel(px, py)?
+            } else {
+                bg.clone()
+            };
+            let alpha = pixel.a as f64 * weight;
+            accum[0] += pixel.r as f64 * alpha;
+            accum[1] += pixel.g as f64 * alpha;
+            accum[2] += pixel.b as f64 * alpha;
+            accum[3] += alpha;
+            total += weight;
+        }
+    }
 
-            // blue
-            let blue = _lerp(src_color1.b, src_color2.b, t_y);
+    if accum[3] <= 0.0 || total == 0.0 {
+        return Ok(Color::rgba(0, 0, 0, 0));
+    }
+    let channel = |value: f64| value.round().clamp(0.0, 255.0) as u8;
+    Ok(Color::rgba(
+        channel(accum[0] / accum[3]),
+        channel(accum[1] / accum[3]),
+        channel(accum[2] / accum[3]),
+        channel(accum[3] / total),
+    ))
+}
 
-            // alpha
-            let alpha = _lerp(src_color1.a, src_color2.a, t_y);
+// Resample using a separable filter kernel, first horizontally then vertically. The kernel is
+// evaluated on distances in destination pixels and is non-zero within -support..support.
+fn _resample_separable(src: &Image, w2: i32, h2: i32, support: f64, kernel: fn(f64) -> f64) -> Image {
+    let w1 = src.width as usize;
+    let h1 = src.height as usize;
+    let w2 = w2 as usize;
+    let h2 = h2 as usize;
+
+    // Horizontal pass, from w1 x h1 to w2 x h1, on colors premultiplied by their alpha
+    let x_weights = _kernel_weights(w1, w2, support, kernel);
+    let mut horizontal = vec![0.0; w2 * h1 * 4];
+    for y in 0..h1 {
+        for (x, weights) in x_weights.iter().enumerate() {
+            let dest = (y * w2 + x) * 4;
+            for &(src_x, weight) in weights {
+                let start = (y * w1 + src_x) * 4;
+                let alpha = src.bytes[start + 3] as f64 * weight;
+                for c in 0..3 {
+                    horizontal[dest + c] += src.bytes[start + c] as f64 * alpha;
+                }
+                horizontal[dest + 3] += alpha;
+            }
+        }
+    }
 
-            dest.set_pixel(x, y + offset_y, &Color::rgba(red, green, blue, alpha))?;
+    // Vertical pass, from w2 x h1 to w2 x h2
+    let y_weights = _kernel_weights(h1, h2, support, kernel);
+    let mut bytes = Vec::with_capacity(w2 * h2 * 4);
+    for weights in &y_weights {
+        for x in 0..w2 {
+            let mut channels = [0.0; 4];
+            for &(src_y, weight) in weights {
+                let start = (src_y * w2 + x) * 4;
+                for (c, channel) in channels.iter_mut().enumerate() {
+                    *channel += horizontal[start + c] * weight;
+                }
+            }
+
+            // Back to straight colors. The kernel can ring below zero, so only keep the pixels
+            // that end up with some alpha.
+            let alpha = channels[3].round().clamp(0.0, 255.0);
+            if alpha > 0.0 {
+                for channel in &channels[..3] {
+                    bytes.push((channel / channels[3]).round().clamp(0.0, 255.0) as u8);
+                }
+                bytes.push(alpha as u8);
+            } else {
+                bytes.extend_from_slice(&[0, 0, 0, 0]);
+            }
         }
     }
-    src.width = dest.width;
-    src.height = dest.height;
-    src.bytes = dest.bytes;
 
-    Ok(())
+    Image {
+        width: w2 as i32,
+        height: h2 as i32,
+        bytes,
+    }
 }
 
-// Simple linear function
-fn _lerp(a: u8, b: u8, t: f64) -> u8 {
-    let a = a as f64;
-    let b = b as f64;
+// Compute for each destination index the contributing source indices and their normalized
+// weights. Source indices outside the image are clamped to the edges.
+fn _kernel_weights(
+    size1: usize,
+    size2: usize,
+    support: f64,
+    kernel: fn(f64) -> f64,
+) -> Vec<Vec<(usize, f64)>> {
+    let ratio = size1 as f64 / size2 as f64;
+    let scale = ratio.max(1.0); // Widen the kernel when downscaling
+    let radius = support * scale;
+
+    (0..size2)
+        .map(|dest| {
+            let center = (dest as f64 + 0.5) * ratio;
+            let start = (center - radius).floor() as i64;
+            let end = (center + radius).ceil() as i64;
+
+            let mut weights: Vec<(usize, f64)> = (start..end)
+                .map(|i| {
+                    let weight = kernel((i as f64 + 0.5 - center) / scale);
+                    let i = i.clamp(0, size1 as i64 - 1) as usize;
+                    (i, weight)
+                })
+                .filter(|&(_, weight)| weight != 0.0)
+                .collect();
+
+            let total: f64 = weights.iter().map(|&(_, weight)| weight).sum();
+            if total != 0.0 {
+                for weight in &mut weights {
+                    weight.1 /= total;
+                }
+            }
+            weights
+        })
+        .collect()
+}
 
-    (a + (t * (b - a))) as u8
+// Catmull-Rom cubic convolution kernel (a = -0.5)
+fn _catmull_rom(x: f64) -> f64 {
+    let a = -0.5;
+    let x = x.abs();
+    if x < 1.0 {
+        ((a + 2.0) * x - (a + 3.0)) * x * x + 1.0
+    } else if x < 2.0 {
+        ((a * x - 5.0 * a) * x + 8.0 * a) * x - 4.0 * a
+    } else {
+        0.0
+    }
 }
 
-// Linear function using difference
-fn _bilinear(a: u8, b: u8, c: u8, d: u8, x_diff: f64, y_diff: f64) -> u8 {
-    // Y = A(1-w)(1-h) + B(w)(1-h) + C(h)(1-w) + Dwh
-    (a as f64 * (1.0 - x_diff) * (1.0 - y_diff) + b as f64 * (x_diff) * (1.0 - y_diff)
-        + c as f64 * (y_diff) * (1.0 - x_diff) + d as f64 * (x_diff * y_diff)) as u8
+// Lanczos kernel with a window of 3 lobes: sinc(x) * sinc(x / 3)
+fn _lanczos3(x: f64) -> f64 {
+    let a = 3.0;
+    if x == 0.0 {
+        1.0
+    } else if x.abs() < a {
+        let pi_x = PI * x;
+        a * pi_x.sin() * (pi_x / a).sin() / (pi_x * pi_x)
+    } else {
+        0.0
+    }
 }
@@ -79,17 +79,30 @@
 //! ![](https://kosinix.github.io/raster/out/test_transform_rotate_45.png)
 //! ![](https://kosinix.github.io/raster/out/test_transform_rotate_45cc.png)
 //!
+//! ## Drawing Text
+//!
+//! Text can be drawn with TrueType and OpenType fonts when the `text` feature is enabled, see
+//! `draw::text`.
+//!
 //! ## And Many More...
 //!
 //! More options are available, checkout the modules below.
 //!
 
 // modules
+pub mod analysis;
 pub mod compare;
+pub mod draw;
 pub mod editor;
 pub mod error;
 pub mod filter;
+pub mod gradient;
 pub mod interpolate;
+pub mod morphology;
+pub mod quantize;
+#[cfg(feature = "text")]
+pub mod text;
+pub mod tonemap;
 pub mod transform;
 mod blend;
 mod color;
@@ -98,12 +111,13 @@ mod image;
 mod position;
 
 // crates
+#[cfg(feature = "text")]
+extern crate ab_glyph;
 extern crate gif;
 extern crate image as piston_image;
 extern crate png;
 
 // from rust
-use std::ascii::AsciiExt;
 use std::fs::File;
 use std::path::Path;
 
@@ -114,16 +128,46 @@ use piston_image::GenericImage;
 use error::{RasterError, RasterResult};
 
 // re-exports
+pub use analysis::Component;
+pub use analysis::MatchMethod;
+pub use analysis::TemplateMatch;
 pub use blend::BlendMode;
 pub use color::Color;
+pub use draw::DrawMode;
+pub use draw::Point;
+pub use draw::Rect;
+pub use editor::Alignment;
+pub use editor::Direction;
+pub use editor::Layer;
+pub use editor::PackOptions;
+pub use editor::PasteMode;
 pub use editor::ResizeMode;
+pub use editor::StackMode;
 pub use filter::BlurMode;
+pub use filter::Channel;
+pub use filter::DitherPalette;
+pub use filter::EdgeMode;
+pub use filter::GrayscaleMode;
+pub use filter::HalftoneShape;
+pub use filter::Kernel;
+pub use filter::NoiseType;
 pub use filter::Orientation;
+pub use filter::ThresholdMode;
+pub use filter::WhiteBalanceMode;
+pub use gradient::GradientStop;
 pub use image::Histogram;
 pub use image::Image;
 pub use image::ImageFormat;
+pub use image::IndexedImage;
 pub use interpolate::InterpolationMode;
 pub use position::PositionMode;
+#[cfg(feature = "text")]
+pub use text::Font;
+#[cfg(feature = "text")]
+pub use text::TextAlign;
+#[cfg(feature = "text")]
+pub use text::TextBox;
+pub use transform::Transform;
 pub use transform::TransformMode;
 
 /// Create an image from an image file.
@@ -165,7 +209,7 @@ pub fn open(image_file: &str) -> RasterResult<Image> {
             Ok(Image {
                 width: w as i32,
                 height: h as i32,
-                bytes: bytes,
+                bytes,
             })
         }
         "png" => Ok(endec::decode_png(&file)?),
@@ -196,17 +240,47 @@ pub fn save(image: &Image, out: &str) -> RasterResult<()> {
         .map_or("".to_string(), |s| s.to_ascii_lowercase());
 
     match &ext[..] {
-        "gif" => Ok(endec::encode_gif(&image, &path)?),
+        "gif" => Ok(endec::encode_gif(image, path)?),
         "jpg" | "jpeg" => {
             piston_image::save_buffer(
-                &path,
+                path,
                 &image.bytes,
                 image.width as u32,
                 image.height as u32,
                 piston_image::RGBA(8),
             ).map_err(|_| RasterError::Encode(ImageFormat::Jpeg, "Format".to_string()))
         }
-        "png" => Ok(endec::encode_png(&image, &path)?),
+        "png" => Ok(endec::encode_png(image, path)?),
+        _ => Err(RasterError::UnsupportedFormat(ext)),
+    }
+}
+
+/// Save an indexed image to a palette based GIF or PNG file, detected from the file extension.
+///
+/// # Errors
+///
+/// This function can return `RasterError::Io`, `RasterError::Encode`, or
+/// `RasterError::UnsupportedFormat` upon failure. Other formats than GIF and PNG are not
+/// supported, convert with `IndexedImage::to_image` and use `raster::save` for those.
+///
+/// # Examples
+///
+/// ```
+/// use raster::IndexedImage;
+///
+/// let image = raster::open("tests/in/sample.gif").unwrap();
+/// let indexed = IndexedImage::from_image(&image).unwrap();
+/// raster::save_indexed(&indexed, "tests/out/test_indexed.png").unwrap();
+/// ```
+pub fn save_indexed(image: &IndexedImage, out: &str) -> RasterResult<()> {
+    let path = Path::new(out);
+    let ext = path.extension()
+        .and_then(|s| s.to_str())
+        .map_or("".to_string(), |s| s.to_ascii_lowercase());
+
+    match &ext[..] {
+        "gif" => Ok(endec::encode_gif_indexed(image, path)?),
+        "png" => Ok(endec::encode_png_indexed(image, path)?),
         _ => Err(RasterError::UnsupportedFormat(ext)),
     }
 }
@@ -0,0 +1,488 @@
+//!  A module for morphological operations, eg. to clean up masks.
+
+// from rust
+
+// from external crate
+
+// from local crate
+use error::{RasterError, RasterResult};
+use Image;
+
+/// A square structuring element of odd size, the shape of the neighborhood used by the
+/// morphological operations.
+///
+/// The pixel being processed is at the center of the element and only the neighbors where the
+/// element is `true` are taken into account.
+///
+/// # Examples
+/// ```
+/// use raster::morphology::StructuringElement;
+///
+/// // Horizontal line of 5 pixels
+/// let line = StructuringElement::new(&[
+///     [false; 5],
+///     [false; 5],
+///     [true; 5],
+///     [false; 5],
+///     [false; 5],
+/// ]).unwrap();
+/// assert_eq!(5, line.size());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructuringElement {
+    size: usize,
+    // Row major mask
+    mask: Vec<bool>,
+}
+
+impl StructuringElement {
+    /// Create a structuring element from its rows.
+    ///
+    /// # Errors
+    ///
+    /// If the rows do not form a square matrix of odd size, this fails with
+    /// `RasterError::InvalidStructuringElement`.
+    pub fn new<R: AsRef<[bool]>>(rows: &[R]) -> RasterResult<StructuringElement> {
+        let size = rows.len();
+        if size % 2 != 1 || rows.iter().any(|row| row.as_ref().len() != size) {
+            return Err(RasterError::InvalidStructuringElement);
+        }
+
+        Ok(StructuringElement {
+            size,
+            mask: rows.iter().flat_map(|row| row.as_ref().to_vec()).collect(),
+        })
+    }
+
+    /// Create a square of radius pixels around the center, eg. 1 for 3x3.
+    ///
+    /// # Errors
+    ///
+    /// If the radius is negative, this fails with `RasterError::InvalidRadius`.
+    pub fn square(radius: i32) -> RasterResult<StructuringElement> {
+        StructuringElement::shape(radius, |_, _| true)
+    }
+
+    /// Create a cross of arms of radius pixels, the 4 direct neighbors for 1.
+    ///
+    /// # Errors
+    ///
+    /// If the radius is negative, this fails with `RasterError::InvalidRadius`.
+    pub fn cross(radius: i32) -> RasterResult<StructuringElement> {
+        StructuringElement::shape(radius, |x, y| x == 0 || y == 0)
+    }
+
+    /// Create a disk of radius pixels, the pixels whose center is within the radius.
+    ///
+    /// # Errors
+    ///
+    /// If the radius is negative, this fails with `RasterError::InvalidRadius`.
+    pub fn disk(radius: i32) -> RasterResult<StructuringElement> {
+        StructuringElement::shape(radius, |x, y| x * x + y * y <= radius * radius)
+    }
+
+    /// The number of rows and columns.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // Element of radius with the pixels at x, y from the center where inside is true.
+    fn shape<F>(radius: i32, inside: F) -> RasterResult<StructuringElement>
+    where
+        F: Fn(i32, i32) -> bool,
+    {
+        if radius < 0 {
+            return Err(RasterError::InvalidRadius(radius));
+        }
+
+        let mut mask = Vec::new();
+        for y in -radius..=radius {
+            for x in -radius..=radius {
+                mask.push(inside(x, y));
+            }
+        }
+        Ok(StructuringElement {
+            size: (radius * 2 + 1) as usize,
+            mask,
+        })
+    }
+}
+
+/// Shrink the bright areas of an image, eg. to remove small specks from a mask.
+///
+/// Each color channel of a pixel becomes the smallest value of that channel under the
+/// structuring element. On a black and white mask, white areas lose their outline and white spots
+/// smaller than the element disappear. Pixels outside of the image are ignored. Alpha is left
+/// untouched.
+///
+/// # Examples
+/// ```
+/// use raster::morphology::{self, StructuringElement};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// morphology::erode(&mut image, &StructuringElement::disk(2).unwrap()).unwrap();
+/// raster::save(&image, "tests/out/test_morphology_erode.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_morphology_erode.jpg)
+///
+pub fn erode(src: &mut Image, element: &StructuringElement) -> RasterResult<()> {
+    _extreme(src, element, |a, b| a.min(b));
+    Ok(())
+}
+
+/// Grow the bright areas of an image, eg. to fill small holes in a mask.
+///
+/// Each color channel of a pixel becomes the largest value of that channel under the structuring
+/// element, mirrored. On a black and white mask, white areas gain an outline and black spots
+/// smaller than the element are filled. Pixels outside of the image are ignored. Alpha is left
+/// untouched.
+///
+/// # Examples
+/// ```
+/// use raster::morphology::{self, StructuringElement};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// morphology::dilate(&mut image, &StructuringElement::disk(2).unwrap()).unwrap();
+/// raster::save(&image, "tests/out/test_morphology_dilate.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_morphology_dilate.jpg)
+///
+pub fn dilate(src: &mut Image, element: &StructuringElement) -> RasterResult<()> {
+    let mirrored = StructuringElement {
+        size: element.size,
+        mask: element.mask.iter().rev().cloned().collect(),
+    };
+    _extreme(src, &mirrored, |a, b| a.max(b));
+    Ok(())
+}
+
+/// Erode then dilate an image, removing bright specks smaller than the structuring element while
+/// keeping the size of larger bright areas.
+///
+/// # Examples
+/// ```
+/// use raster::morphology::{self, StructuringElement};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// morphology::open(&mut image, &StructuringElement::disk(2).unwrap()).unwrap();
+/// raster::save(&image, "tests/out/test_morphology_open.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_morphology_open.jpg)
+///
+pub fn open(src: &mut Image, element: &StructuringElement) -> RasterResult<()> {
+    erode(src, element)?;
+    dilate(src, element)
+}
+
+/// Dilate then erode an image, filling dark holes and gaps smaller than the structuring element
+/// while keeping the size of larger bright areas.
+///
+/// # Examples
+/// ```
+/// use raster::morphology::{self, StructuringElement};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// morphology::close(&mut image, &StructuringElement::disk(2).unwrap()).unwrap();
+/// raster::save(&image, "tests/out/test_morphology_close.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_morphology_close.jpg)
+///
+pub fn close(src: &mut Image, element: &StructuringElement) -> RasterResult<()> {
+    dilate(src, element)?;
+    erode(src, element)
+}
+
+/// Replace an image by the difference between its dilation and its erosion, which outlines the
+/// edges of the areas.
+///
+/// # Examples
+/// ```
+/// use raster::morphology::{self, StructuringElement};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// morphology::gradient(&mut image, &StructuringElement::square(1).unwrap()).unwrap();
+/// raster::save(&image, "tests/out/test_morphology_gradient.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_morphology_gradient.jpg)
+///
+pub fn gradient(src: &mut Image, element: &StructuringElement) -> RasterResult<()> {
+    let mut eroded = src.clone();
+    erode(&mut eroded, element)?;
+    dilate(src, element)?;
+    _subtract(src, &eroded);
+    Ok(())
+}
+
+/// Replace an image by the difference between it and its opening, which keeps only the bright
+/// details smaller than the structuring element, eg. to even out an uneven background.
+///
+/// # Examples
+/// ```
+/// use raster::morphology::{self, StructuringElement};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// morphology::tophat(&mut image, &StructuringElement::disk(5).unwrap()).unwrap();
+/// raster::save(&image, "tests/out/test_morphology_tophat.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_morphology_tophat.jpg)
+///
+pub fn tophat(src: &mut Image, element: &StructuringElement) -> RasterResult<()> {
+    let mut opened = src.clone();
+    open(&mut opened, element)?;
+    _subtract(src, &opened);
+    Ok(())
+}
+
+/// Replace an image by the difference between its closing and it, which keeps only the dark
+/// details smaller than the structuring element, as bright values.
+///
+/// # Examples
+/// ```
+/// use raster::morphology::{self, StructuringElement};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// morphology::blackhat(&mut image, &StructuringElement::disk(5).unwrap()).unwrap();
+/// raster::save(&image, "tests/out/test_morphology_blackhat.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_morphology_blackhat.jpg)
+///
+pub fn blackhat(src: &mut Image, element: &StructuringElement) -> RasterResult<()> {
+    let original = src.clone();
+    close(src, element)?;
+    _subtract(src, &original);
+    Ok(())
+}
+
+/// An enum for the ways distances are measured by `morphology::distance_transform`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DistanceMetric {
+    /// Straight line distance, gives round shapes.
+    Euclidean,
+    /// Number of horizontal and vertical steps, gives diamond shapes.
+    Manhattan,
+    /// Number of steps in any of the 8 directions, gives square shapes.
+    Chebyshev,
+}
+
+/// Compute the distance of each pixel to the nearest foreground pixel, eg. for signed distance
+/// field textures or to feather masks.
+///
+/// Foreground pixels are the ones with a luminance of 128 or more, eg. the white pixels of masks
+/// made by `filter::threshold`, and have a distance of 0. Returns the distances in pixels, row
+/// by row. Euclidean distances are exact. If there is no foreground pixel, all the distances are
+/// infinite.
+///
+/// # Examples
+/// ```
+/// use raster::morphology::{self, DistanceMetric};
+/// use raster::{filter, Image};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// filter::threshold(&mut image, 200).unwrap();
+/// let distances = morphology::distance_transform(&image, DistanceMetric::Euclidean);
+///
+/// // Brighter further from the foreground
+/// let mut field = Image::blank(image.width, image.height);
+/// for (pixel, distance) in field.bytes.chunks_mut(4).zip(distances) {
+///     let value = (distance * 8.0).min(255.0) as u8;
+///     pixel[0..3].copy_from_slice(&[value, value, value]);
+/// }
+/// raster::save(&field, "tests/out/test_morphology_distance_transform.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_morphology_distance_transform.png)
+///
+pub fn distance_transform(src: &Image, metric: DistanceMetric) -> Vec<f32> {
+    let (w, h) = (src.width.max(0) as usize, src.height.max(0) as usize);
+    let foreground: Vec<bool> = src
+        .bytes
+        .chunks(4)
+        .map(|p| p[0] as f32 * 0.3 + p[1] as f32 * 0.59 + p[2] as f32 * 0.11 >= 127.5)
+        .collect();
+
+    match metric {
+        DistanceMetric::Euclidean => {
+            // Squared distances along the columns, then along the rows from them.
+            let mut squared: Vec<f64> = foreground
+                .iter()
+                .map(|&f| if f { 0.0 } else { f64::INFINITY })
+                .collect();
+            for x in 0..w {
+                let column: Vec<f64> = (0..h).map(|y| squared[y * w + x]).collect();
+                for (y, value) in _squared_distances(&column).into_iter().enumerate() {
+                    squared[y * w + x] = value;
+                }
+            }
+            for row in squared.chunks_mut(w.max(1)) {
+                let distances = _squared_distances(row);
+                row.copy_from_slice(&distances);
+            }
+            squared.iter().map(|&d| d.sqrt() as f32).collect()
+        }
+        DistanceMetric::Manhattan | DistanceMetric::Chebyshev => {
+            // Two passes over the image, each taking the steps from the neighbors already seen.
+            let diagonal = metric == DistanceMetric::Chebyshev;
+            let mut distances: Vec<f32> = foreground
+                .iter()
+                .map(|&f| if f { 0.0 } else { f32::INFINITY })
+                .collect();
+            let mut step = |x: usize, y: usize, dx: i32, dy: i32| {
+                let (sx, sy) = (x as i32 + dx, y as i32 + dy);
+                if sx >= 0 && sy >= 0 && (sx as usize) < w && (sy as usize) < h {
+                    let from = distances[sy as usize * w + sx as usize] + 1.0;
+                    let to = &mut distances[y * w + x];
+                    *to = to.min(from);
+                }
+            };
+            for y in 0..h {
+                for x in 0..w {
+                    step(x, y, -1, 0);
+                    step(x, y, 0, -1);
+                    if diagonal {
+                        step(x, y, -1, -1);
+                        step(x, y, 1, -1);
+                    }
+                }
+            }
+            for y in (0..h).rev() {
+                for x in (0..w).rev() {
+                    step(x, y, 1, 0);
+                    step(x, y, 0, 1);
+                    if diagonal {
+                        step(x, y, 1, 1);
+                        step(x, y, -1, 1);
+                    }
+                }
+            }
+            distances
+        }
+    }
+}
+
+// Private functions
+
+// Set each color channel to the extreme of that channel under the element, according to pick.
+fn _extreme<F>(src: &mut Image, element: &StructuringElement, pick: F)
+where
+    F: Fn(u8, u8) -> u8,
+{
+    let r = (element.size / 2) as i32;
+    let offsets: Vec<(i32, i32)> = element
+        .mask
+        .iter()
+        .enumerate()
+        .filter(|&(_, &inside)| inside)
+        .map(|(i, _)| {
+            let i = i as i32;
+            (i % element.size as i32 - r, i / element.size as i32 - r)
+        })
+        .collect();
+    if offsets.is_empty() {
+        return;
+    }
+
+    let (w, h) = (src.width, src.height);
+    let mut bytes = src.bytes.clone();
+    for y in 0..h {
+        for x in 0..w {
+            let i = ((y * w + x) * 4) as usize;
+            let mut extreme: Option<[u8; 3]> = None;
+            for &(dx, dy) in &offsets {
+                let (sx, sy) = (x + dx, y + dy);
+                if sx < 0 || sy < 0 || sx >= w || sy >= h {
+                    continue;
+                }
+                let p = &src.bytes[((sy * w + sx) * 4) as usize..][..3];
+                extreme = Some(match extreme {
+                    Some(e) => [pick(e[0], p[0]), pick(e[1], p[1]), pick(e[2], p[2])],
+                    None => [p[0], p[1], p[2]],
+                });
+            }
+            if let Some(extreme) = extreme {
+                bytes[i..i + 3].copy_from_slice(&extreme);
+            }
+        }
+    }
+    src.bytes = bytes;
+}
+
+// Subtract the color channels of other from src, clipping at 0.
+fn _subtract(src: &mut Image, other: &Image) {
+    for (pixel, other) in src.bytes.chunks_mut(4).zip(other.bytes.chunks(4)) {
+        for (value, &other) in pixel[0..3].iter_mut().zip(other) {
+            *value = value.saturating_sub(other);
+        }
+    }
+}
+
+// Squared distance of each position to the nearest position with a finite value, that value
+// added, along a line. Lower envelope of parabolas by Felzenszwalb and Huttenlocher.
+fn _squared_distances(values: &[f64]) -> Vec<f64> {
+    // Positions of the parabolas of the envelope and where each starts to be the lowest.
+    let mut roots: Vec<usize> = Vec::new();
+    let mut starts: Vec<f64> = Vec::new();
+    for q in (0..values.len()).filter(|&q| values[q].is_finite()) {
+        let mut start = f64::NEG_INFINITY;
+        while let Some(&p) = roots.last() {
+            let (fq, fp) = (values[q] + (q * q) as f64, values[p] + (p * p) as f64);
+            start = (fq - fp) / (2.0 * (q - p) as f64);
+            if start > starts[starts.len() - 1] {
+                break;
+            }
+            roots.pop();
+            starts.pop();
+            start = f64::NEG_INFINITY;
+        }
+        roots.push(q);
+        starts.push(start);
+    }
+
+    let mut k = 0;
+    (0..values.len())
+        .map(|q| {
+            if roots.is_empty() {
+                return f64::INFINITY;
+            }
+            while k + 1 < roots.len() && starts[k + 1] < q as f64 {
+                k += 1;
+            }
+            let d = q as f64 - roots[k] as f64;
+            d * d + values[roots[k]]
+        })
+        .collect()
+}
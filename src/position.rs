@@ -1,14 +1,26 @@
 //!  A module for computing position on an image.
 
 // from rust
+use std::str::FromStr;
 
 // from external crate
 
 // from local crate
-use error::RasterResult;
+use error::{RasterError, RasterResult};
 
 /// Enumeration for different anchor positions.
-#[derive(Debug)]
+///
+/// Can also be parsed from the hyphenated position names used by older versions of raster, eg.
+/// "top-left" or "center".
+///
+/// # Examples
+/// ```
+/// use raster::PositionMode;
+///
+/// let position: PositionMode = "bottom-right".parse().unwrap();
+/// assert_eq!(PositionMode::BottomRight, position);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PositionMode {
     TopLeft,
     TopCenter,
@@ -21,6 +33,25 @@ pub enum PositionMode {
     BottomRight,
 }
 
+impl FromStr for PositionMode {
+    type Err = RasterError;
+
+    fn from_str(position: &str) -> RasterResult<PositionMode> {
+        match position {
+            "top-left" => Ok(PositionMode::TopLeft),
+            "top-center" => Ok(PositionMode::TopCenter),
+            "top-right" => Ok(PositionMode::TopRight),
+            "center-left" => Ok(PositionMode::CenterLeft),
+            "center" => Ok(PositionMode::Center),
+            "center-right" => Ok(PositionMode::CenterRight),
+            "bottom-left" => Ok(PositionMode::BottomLeft),
+            "bottom-center" => Ok(PositionMode::BottomCenter),
+            "bottom-right" => Ok(PositionMode::BottomRight),
+            _ => Err(RasterError::InvalidMode(position.to_string())),
+        }
+    }
+}
+
 /// Struct for computing position on an image.
 pub struct Position {
     position: PositionMode,
@@ -31,9 +62,9 @@ pub struct Position {
 impl Position {
     pub fn new(position: PositionMode, offset_x: i32, offset_y: i32) -> Position {
         Position {
-            position: position,
-            offset_x: offset_x,
-            offset_y: offset_y,
+            position,
+            offset_x,
+            offset_y,
         }
     }
 
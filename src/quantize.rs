@@ -0,0 +1,254 @@
+//!  A module for reducing the number of colors of an image.
+
+// from rust
+use std::collections::HashMap;
+
+// from external crate
+
+// from local crate
+use error::{RasterError, RasterResult};
+use Color;
+use Image;
+
+/// An enum for the algorithms that can be used for color quantization.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuantizeMode {
+    /// Split the box of colors along its widest channel until there are enough boxes. Gives
+    /// balanced palettes for photos.
+    MedianCut,
+    /// Sort the colors in an octree and merge its least used leaves. Keeps small but distinct
+    /// color areas.
+    Octree,
+}
+
+/// Reduce an image to at most n_colors colors.
+///
+/// Returns the quantized image and the palette it uses. Each pixel is replaced by the nearest
+/// color of the palette, so combine with `filter::dither_floyd_steinberg` and
+/// `DitherPalette::Colors` to avoid banding. Alpha is left untouched and not part of the palette.
+///
+/// # Errors
+///
+/// If n_colors is not in the range 1 - 256, this fails with `RasterError::InvalidPalette`.
+///
+/// # Examples
+/// ```
+/// use raster::quantize::{self, QuantizeMode};
+///
+/// let image = raster::open("tests/in/sample.jpg").unwrap();
+/// let (quantized, palette) = quantize::reduce(&image, 16, QuantizeMode::MedianCut).unwrap();
+/// assert!(palette.len() <= 16);
+/// raster::save(&quantized, "tests/out/test_quantize_reduce.png").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_quantize_reduce.png)
+///
+pub fn reduce(
+    src: &Image,
+    n_colors: usize,
+    mode: QuantizeMode,
+) -> RasterResult<(Image, Vec<Color>)> {
+    if n_colors == 0 || n_colors > 256 {
+        return Err(RasterError::InvalidPalette);
+    }
+
+    let colors: Vec<[u8; 3]> = src.bytes.chunks(4).map(|p| [p[0], p[1], p[2]]).collect();
+    let palette = match mode {
+        QuantizeMode::MedianCut => median_cut(&colors, n_colors),
+        QuantizeMode::Octree => octree(&colors, n_colors),
+    };
+
+    let mut quantized = src.clone();
+    if !palette.is_empty() {
+        let mut cache: HashMap<[u8; 3], usize> = HashMap::new();
+        for (pixel, color) in quantized.bytes.chunks_mut(4).zip(colors.iter()) {
+            let index = *cache
+                .entry(*color)
+                .or_insert_with(|| nearest(&palette, color));
+            pixel[0..3].copy_from_slice(&palette[index]);
+        }
+    }
+
+    let palette = palette.iter().map(|c| Color::rgb(c[0], c[1], c[2])).collect();
+    Ok((quantized, palette))
+}
+
+/// Index of the palette color closest to color.
+pub(crate) fn nearest(palette: &[[u8; 3]], color: &[u8; 3]) -> usize {
+    let distance = |p: &[u8; 3]| -> i32 {
+        (0..3)
+            .map(|c| {
+                let d = p[c] as i32 - color[c] as i32;
+                d * d
+            })
+            .sum()
+    };
+
+    let mut best = 0;
+    for (i, p) in palette.iter().enumerate() {
+        if distance(p) < distance(&palette[best]) {
+            best = i;
+        }
+    }
+    best
+}
+
+/// Palette of up to n_colors colors found by median cut.
+pub(crate) fn median_cut(colors: &[[u8; 3]], n_colors: usize) -> Vec<[u8; 3]> {
+    if colors.is_empty() {
+        return Vec::new();
+    }
+
+    let range = |colors: &[[u8; 3]], c: usize| -> u8 {
+        let min = colors.iter().map(|p| p[c]).min().unwrap_or(0);
+        let max = colors.iter().map(|p| p[c]).max().unwrap_or(0);
+        max - min
+    };
+
+    let mut boxes: Vec<Vec<[u8; 3]>> = vec![colors.to_vec()];
+    while boxes.len() < n_colors {
+        // Split the box with the widest channel range at the median of that channel.
+        let mut widest = None;
+        for (i, b) in boxes.iter().enumerate() {
+            let (c, r) = (0..3).map(|c| (c, range(b, c))).max_by_key(|&(_, r)| r).unwrap();
+            let wider = match widest {
+                Some((_, _, best)) => r > best,
+                None => r > 0,
+            };
+            if wider {
+                widest = Some((i, c, r));
+            }
+        }
+        let (i, c, _) = match widest {
+            Some(widest) => widest,
+            None => break, // Every box holds a single color
+        };
+
+        let mut b = boxes.swap_remove(i);
+        b.sort_unstable_by_key(|p| p[c]);
+        let mut median = b.len() / 2;
+        // Keep equal values on the same side so that both halves are not empty.
+        while median > 0 && b[median - 1][c] == b[median][c] {
+            median -= 1;
+        }
+        if median == 0 {
+            median = b.iter().position(|p| p[c] != b[0][c]).unwrap_or(b.len());
+        }
+        let upper = b.split_off(median);
+        boxes.push(b);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|b| average(b)).collect()
+}
+
+/// Palette of up to n_colors colors found by merging the leaves of an octree.
+pub(crate) fn octree(colors: &[[u8; 3]], n_colors: usize) -> Vec<[u8; 3]> {
+    // Nodes of the tree, the root is at index 0. Each level splits on one more bit of the 3
+    // channels, down to leaves at depth 8 holding single colors.
+    struct Node {
+        children: [Option<usize>; 8],
+        depth: usize,
+        count: u64,
+        sums: [u64; 3],
+        leaf: bool,
+    }
+    let new_node = |depth| Node {
+        children: [None; 8],
+        depth,
+        count: 0,
+        sums: [0; 3],
+        leaf: depth == 8,
+    };
+
+    let mut nodes = vec![new_node(0)];
+    for color in colors {
+        let mut index = 0;
+        loop {
+            let node = &mut nodes[index];
+            node.count += 1;
+            for (sum, &value) in node.sums.iter_mut().zip(color.iter()) {
+                *sum += value as u64;
+            }
+            if node.leaf {
+                break;
+            }
+            let shift = 7 - node.depth;
+            let child = (((color[0] >> shift) & 1) << 2
+                | ((color[1] >> shift) & 1) << 1
+                | ((color[2] >> shift) & 1)) as usize;
+            let depth = node.depth + 1;
+            index = match node.children[child] {
+                Some(next) => next,
+                None => {
+                    let next = nodes.len();
+                    nodes[index].children[child] = Some(next);
+                    nodes.push(new_node(depth));
+                    next
+                }
+            };
+        }
+    }
+
+    // Merge the least used deepest nodes into leaves until there are few enough leaves. The
+    // sums of a node already include its children, so merging only drops them.
+    let leaves = |nodes: &[Node]| -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut stack = vec![0];
+        while let Some(i) = stack.pop() {
+            if nodes[i].leaf {
+                found.push(i);
+            } else {
+                stack.extend(nodes[i].children.iter().flatten());
+            }
+        }
+        found
+    };
+    let mut leaf_count = leaves(&nodes).len();
+    let mut candidates: Vec<usize> = (0..nodes.len()).filter(|&i| !nodes[i].leaf).collect();
+    candidates.sort_by_key(|&i| (std::cmp::Reverse(nodes[i].depth), nodes[i].count));
+    for i in candidates {
+        if leaf_count <= n_colors {
+            break;
+        }
+        let merged = nodes[i].children.iter().flatten().count();
+        nodes[i].leaf = true;
+        leaf_count = leaf_count + 1 - merged;
+    }
+
+    leaves(&nodes)
+        .iter()
+        .filter(|&&i| nodes[i].count > 0)
+        .map(|&i| {
+            let n = nodes[i].count;
+            let s = nodes[i].sums;
+            [
+                ((s[0] + n / 2) / n) as u8,
+                ((s[1] + n / 2) / n) as u8,
+                ((s[2] + n / 2) / n) as u8,
+            ]
+        })
+        .collect()
+}
+
+// Private functions
+
+// Average of colors.
+fn average(colors: &[[u8; 3]]) -> [u8; 3] {
+    let n = colors.len() as u64;
+    let mut sums = [0u64; 3];
+    for color in colors {
+        for (sum, &value) in sums.iter_mut().zip(color.iter()) {
+            *sum += value as u64;
+        }
+    }
+    [
+        ((sums[0] + n / 2) / n) as u8,
+        ((sums[1] + n / 2) / n) as u8,
+        ((sums[2] + n / 2) / n) as u8,
+    ]
+}
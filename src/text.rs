@@ -0,0 +1,344 @@
+//!  A module for fonts and text layout. Needs the `text` feature.
+//!
+//! ```rust,ignore
+//! [dependencies]
+//!
+//! raster = { version = "x.x.x", features = ["text"] }
+//! ```
+
+// from rust
+use std::fmt;
+use std::fs;
+
+// from external crate
+use ab_glyph::{self, Font as GlyphFont, FontVec, PxScale, ScaleFont};
+
+// from local crate
+use draw::{self, Point};
+use error::{RasterError, RasterResult};
+use Color;
+use Image;
+
+/// A TrueType or OpenType font.
+pub struct Font {
+    pub(crate) font: FontVec,
+}
+
+impl Font {
+    /// Load a font from a .ttf or .otf file.
+    ///
+    /// # Errors
+    ///
+    /// This fails with `RasterError::Io` if the file cannot be read and with
+    /// `RasterError::InvalidFont` if it is not a font.
+    ///
+    /// # Examples
+    /// ```
+    /// use raster::Font;
+    ///
+    /// let font = Font::open("tests/in/DejaVuSans.ttf").unwrap();
+    /// assert!(Font::open("tests/in/sample.png").is_err());
+    /// ```
+    pub fn open(font_file: &str) -> RasterResult<Font> {
+        Font::from_bytes(fs::read(font_file)?)
+    }
+
+    /// Load a font from the bytes of a .ttf or .otf file, eg. embedded with `include_bytes!`.
+    ///
+    /// # Errors
+    ///
+    /// If the bytes are not a font, this fails with `RasterError::InvalidFont`.
+    pub fn from_bytes(bytes: Vec<u8>) -> RasterResult<Font> {
+        let font = FontVec::try_from_vec(bytes).map_err(|_| RasterError::InvalidFont)?;
+        Ok(Font { font })
+    }
+
+    /// Width in pixels of a line of text drawn with `draw::text`, including kerning.
+    ///
+    /// # Examples
+    /// ```
+    /// use raster::Font;
+    ///
+    /// let font = Font::open("tests/in/DejaVuSans.ttf").unwrap();
+    /// assert!(font.width("Hello", 20.0) < font.width("Hello, World!", 20.0));
+    /// assert_eq!(0.0, font.width("", 20.0));
+    /// ```
+    pub fn width(&self, text: &str, size: f32) -> f32 {
+        let scaled = self.font.as_scaled(self.scale(size));
+        let mut width = 0.0;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(previous) = previous {
+                width += scaled.kern(previous, id);
+            }
+            width += scaled.h_advance(id);
+            previous = Some(id);
+        }
+        width
+    }
+
+    // Scale where size is the em size in pixels, like font sizes in CSS.
+    pub(crate) fn scale(&self, size: f32) -> PxScale {
+        let units_per_em = self.font.units_per_em().unwrap_or(1000.0);
+        PxScale::from(size * self.font.height_unscaled() / units_per_em)
+    }
+
+    // Height of the letters above the baseline.
+    pub(crate) fn ascent(&self, size: f32) -> f32 {
+        self.font.as_scaled(self.scale(size)).ascent()
+    }
+
+    // Distance between the baselines of two lines.
+    pub(crate) fn line_height(&self, size: f32) -> f32 {
+        let scaled = self.font.as_scaled(self.scale(size));
+        scaled.height() + scaled.line_gap()
+    }
+}
+
+impl fmt::Debug for Font {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Font {{ glyphs: {} }}", ab_glyph::Font::glyph_count(&self.font))
+    }
+}
+
+/// An enum for the horizontal alignment of the lines of a `TextBox`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+    /// Stretch the spaces between words so that lines fill the width, except the last line of
+    /// each paragraph which is aligned left.
+    Justify,
+}
+
+/// A box of text broken into lines that fit a width.
+///
+/// Lines are broken between words, and inside words longer than the width. Each newline starts a
+/// new paragraph. With a maximum number of lines, text that does not fit ends with an ellipsis.
+///
+/// # Examples
+/// ```
+/// use raster::{Color, Font, Image, Point, TextAlign, TextBox};
+///
+/// let font = Font::open("tests/in/DejaVuSans.ttf").unwrap();
+/// let text_box = TextBox::new(&font, 18.0, 260.0)
+///     .unwrap()
+///     .line_spacing(1.2)
+///     .align(TextAlign::Center)
+///     .max_lines(3);
+///
+/// let caption = "The quick brown fox jumps over the lazy dog, again and again, until the dog \
+///                finally wakes up and chases it away.";
+/// let mut image = Image::blank(300, 100);
+/// text_box.draw(&mut image, caption, Point::new(20.0, 10.0), &Color::white()).unwrap();
+/// raster::save(&image, "tests/out/test_text_box.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_text_box.png)
+///
+#[derive(Debug, Clone)]
+pub struct TextBox<'a> {
+    font: &'a Font,
+    size: f32,
+    max_width: f32,
+    line_spacing: f32,
+    align: TextAlign,
+    max_lines: Option<usize>,
+}
+
+impl<'a> TextBox<'a> {
+    /// Create a text box with a font, a font size and the maximum width of lines in pixels.
+    ///
+    /// Lines are aligned left with the line height of the font until changed.
+    ///
+    /// # Errors
+    ///
+    /// If size is zero or negative, this fails with `RasterError::InvalidFontSize`. If max_width
+    /// is zero or negative, this fails with `RasterError::InvalidWidth`.
+    pub fn new(font: &'a Font, size: f32, max_width: f32) -> RasterResult<TextBox<'a>> {
+        if size.is_nan() || size <= 0.0 {
+            return Err(RasterError::InvalidFontSize(size));
+        }
+        if max_width.is_nan() || max_width <= 0.0 {
+            return Err(RasterError::InvalidWidth(max_width));
+        }
+        Ok(TextBox {
+            font,
+            size,
+            max_width,
+            line_spacing: 1.0,
+            align: TextAlign::Left,
+            max_lines: None,
+        })
+    }
+
+    /// Set the distance between lines as a multiple of the line height of the font.
+    pub fn line_spacing(self, line_spacing: f32) -> TextBox<'a> {
+        TextBox {
+            line_spacing,
+            ..self
+        }
+    }
+
+    /// Set the horizontal alignment of the lines.
+    pub fn align(self, align: TextAlign) -> TextBox<'a> {
+        TextBox { align, ..self }
+    }
+
+    /// Set the maximum number of lines. The last line ends with an ellipsis when text is cut.
+    pub fn max_lines(self, max_lines: usize) -> TextBox<'a> {
+        TextBox {
+            max_lines: Some(max_lines),
+            ..self
+        }
+    }
+
+    /// Break text into the lines drawn by `TextBox::draw`.
+    ///
+    /// # Examples
+    /// ```
+    /// use raster::{Font, TextBox};
+    ///
+    /// let font = Font::open("tests/in/DejaVuSans.ttf").unwrap();
+    /// let text_box = TextBox::new(&font, 20.0, 120.0).unwrap().max_lines(2);
+    ///
+    /// let lines = text_box.lines("one two three four five six seven eight nine ten");
+    /// assert_eq!(2, lines.len());
+    /// assert!(lines[1].ends_with('…'));
+    /// ```
+    pub fn lines(&self, text: &str) -> Vec<String> {
+        self._layout(text)
+            .into_iter()
+            .map(|line| line.words.join(" "))
+            .collect()
+    }
+
+    /// Height in pixels of the box holding the text, from the top of the first line to the
+    /// bottom of the last one.
+    pub fn height(&self, text: &str) -> f32 {
+        let count = self._layout(text).len();
+        if count == 0 {
+            0.0
+        } else {
+            let line_height = self.font.line_height(self.size);
+            line_height + (count - 1) as f32 * line_height * self.line_spacing
+        }
+    }
+
+    /// Draw the text in the box, with position at the top left of the box.
+    ///
+    /// # Errors
+    ///
+    /// This fails in the same cases as `draw::text`.
+    pub fn draw(
+        &self,
+        src: &mut Image,
+        text: &str,
+        position: Point,
+        color: &Color,
+    ) -> RasterResult<()> {
+        let line_height = self.font.line_height(self.size) * self.line_spacing;
+        let mut baseline = position.y + self.font.ascent(self.size);
+        for line in self._layout(text) {
+            let widths: Vec<f32> = line
+                .words
+                .iter()
+                .map(|word| self.font.width(word, self.size))
+                .collect();
+            let space = self.font.width(" ", self.size);
+            let natural = widths.iter().sum::<f32>() + space * (widths.len().max(1) - 1) as f32;
+
+            let (mut x, gap) = match self.align {
+                TextAlign::Left => (0.0, space),
+                TextAlign::Center => ((self.max_width - natural) / 2.0, space),
+                TextAlign::Right => (self.max_width - natural, space),
+                TextAlign::Justify if line.justify && widths.len() > 1 => {
+                    let gaps = (widths.len() - 1) as f32;
+                    (0.0, (self.max_width - widths.iter().sum::<f32>()) / gaps)
+                }
+                TextAlign::Justify => (0.0, space),
+            };
+            for (word, width) in line.words.iter().zip(widths.iter()) {
+                let origin = Point::new(position.x + x, baseline);
+                draw::text(src, word, self.font, self.size, origin, color)?;
+                x += width + gap;
+            }
+            baseline += line_height;
+        }
+        Ok(())
+    }
+
+    // Break text into lines of words, then cut them to the maximum number of lines.
+    fn _layout(&self, text: &str) -> Vec<Line> {
+        let fits = |text: &str| self.font.width(text, self.size) <= self.max_width;
+
+        let mut lines: Vec<Line> = Vec::new();
+        for paragraph in text.split('\n') {
+            let mut words: Vec<String> = Vec::new();
+            for word in paragraph.split_whitespace() {
+                let mut candidate = words.clone();
+                candidate.push(word.to_string());
+                if fits(&candidate.join(" ")) {
+                    words = candidate;
+                    continue;
+                }
+                if !words.is_empty() {
+                    lines.push(Line { words, justify: true });
+                }
+
+                // Break words that are too long on their own.
+                let mut rest = word.to_string();
+                while !fits(&rest) {
+                    let mut head = String::new();
+                    for c in rest.chars() {
+                        head.push(c);
+                        if !fits(&head) {
+                            head.pop();
+                            break;
+                        }
+                    }
+                    if head.is_empty() {
+                        head = rest.chars().next().map(|c| c.to_string()).unwrap_or_default();
+                    }
+                    rest = rest[head.len()..].to_string();
+                    lines.push(Line {
+                        words: vec![head],
+                        justify: false,
+                    });
+                }
+                words = if rest.is_empty() { Vec::new() } else { vec![rest] };
+            }
+            lines.push(Line {
+                words,
+                justify: false,
+            });
+        }
+
+        if let Some(max_lines) = self.max_lines {
+            if lines.len() > max_lines {
+                lines.truncate(max_lines);
+                if let Some(last) = lines.last_mut() {
+                    let mut text = last.words.join(" ");
+                    while !text.is_empty() && !fits(&format!("{}…", text)) {
+                        text.pop();
+                    }
+                    last.words = text.trim_end().split(' ').map(|w| w.to_string()).collect();
+                    if let Some(word) = last.words.last_mut() {
+                        word.push('…');
+                    }
+                    last.justify = false;
+                }
+            }
+        }
+        lines
+    }
+}
+
+// A line of a text box.
+struct Line {
+    words: Vec<String>,
+    // Whether the line is stretched to the width when justified.
+    justify: bool,
+}
@@ -0,0 +1,217 @@
+//!  A module for bringing high dynamic range images back to displayable 8-bit images.
+
+// from rust
+
+// from external crate
+
+// from local crate
+use error::{RasterError, RasterResult};
+use Image;
+
+/// A struct for images with floating point channels in linear light.
+///
+/// The channels are not limited to 0.0 - 1.0, so that bright areas of composites, eg. from
+/// several exposures, keep their details until they are tone mapped back to an `Image`.
+#[derive(Debug, Clone)]
+pub struct HdrImage {
+    /// Width of image in pixels.
+    pub width: i32,
+
+    /// Height of image in pixels.
+    pub height: i32,
+
+    /// Vector containing the RGBA values of the pixels. Colors are in linear light where 1.0 is
+    /// the white of an 8-bit image, alpha is in 0.0 - 1.0.
+    pub pixels: Vec<f32>,
+}
+
+impl HdrImage {
+    /// Create a blank HDR image. Defaults to a black background.
+    ///
+    /// # Examples
+    /// ```
+    /// use raster::tonemap::HdrImage;
+    ///
+    /// let hdr = HdrImage::blank(3, 2);
+    /// assert_eq!(3 * 2 * 4, hdr.pixels.len());
+    /// ```
+    pub fn blank(w: i32, h: i32) -> HdrImage {
+        let mut pixels = Vec::with_capacity((w * h) as usize * 4);
+        for _ in 0..(w * h) {
+            pixels.extend_from_slice(&[0.0, 0.0, 0.0, 1.0]);
+        }
+        HdrImage {
+            width: w,
+            height: h,
+            pixels,
+        }
+    }
+
+    /// Create an HDR image from an 8-bit image, converting the channels from sRGB to linear
+    /// light.
+    ///
+    /// # Examples
+    /// ```
+    /// use raster::tonemap::HdrImage;
+    /// use raster::Image;
+    ///
+    /// let hdr = HdrImage::from_image(&Image::blank(3, 2));
+    /// assert_eq!(0.0, hdr.pixels[0]);
+    /// assert_eq!(1.0, hdr.pixels[3]);
+    /// ```
+    pub fn from_image(src: &Image) -> HdrImage {
+        let pixels = src
+            .bytes
+            .chunks(4)
+            .flat_map(|p| {
+                [
+                    srgb_to_linear(p[0] as f32 / 255.0),
+                    srgb_to_linear(p[1] as f32 / 255.0),
+                    srgb_to_linear(p[2] as f32 / 255.0),
+                    p[3] as f32 / 255.0,
+                ]
+            })
+            .collect();
+        HdrImage {
+            width: src.width,
+            height: src.height,
+            pixels,
+        }
+    }
+}
+
+/// Tone map an HDR image with the extended Reinhard operator.
+///
+/// The colors are first multiplied by 2^exposure, like `filter::exposure`. Then the luminance L
+/// of each pixel becomes L * (1 + L / white²) / (1 + L), which compresses highlights smoothly
+/// and maps the luminance white to 1.0. The colors are scaled along, so hues are kept, and
+/// converted to sRGB.
+///
+/// # Errors
+///
+/// If the white point is zero or negative, this fails with `RasterError::InvalidWhitePoint`.
+///
+/// # Examples
+/// ```
+/// use raster::tonemap::{self, HdrImage};
+///
+/// let image = raster::open("tests/in/sample.jpg").unwrap();
+/// let hdr = HdrImage::from_image(&image);
+/// let image = tonemap::reinhard(&hdr, 2.0, 4.0).unwrap();
+/// raster::save(&image, "tests/out/test_tonemap_reinhard.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_tonemap_reinhard.jpg)
+///
+pub fn reinhard(src: &HdrImage, exposure: f32, white: f32) -> RasterResult<Image> {
+    if white.is_nan() || white <= 0.0 {
+        return Err(RasterError::InvalidWhitePoint(white));
+    }
+
+    let factor = 2.0f32.powf(exposure);
+    Ok(_tonemap(src, |rgb| {
+        let rgb = rgb.map(|c| c * factor);
+        let luma = rgb[0] * 0.2126 + rgb[1] * 0.7152 + rgb[2] * 0.0722;
+        if luma <= 0.0 {
+            return [0.0; 3];
+        }
+        let mapped = luma * (1.0 + luma / (white * white)) / (1.0 + luma);
+        rgb.map(|c| c * mapped / luma)
+    }))
+}
+
+/// Tone map an HDR image with the filmic operator of John Hable, as used in Uncharted 2.
+///
+/// The colors are first multiplied by 2^exposure, like `filter::exposure`. Then each channel
+/// goes through a curve with a toe that deepens the shadows and a shoulder that rolls off the
+/// highlights like film, scaled so that the linear value white maps to 1.0, and is converted to
+/// sRGB. Hable suggests an exposure of 1.0 and a white point of 11.2.
+///
+/// # Errors
+///
+/// If the white point is zero or negative, this fails with `RasterError::InvalidWhitePoint`.
+///
+/// # Examples
+/// ```
+/// use raster::tonemap::{self, HdrImage};
+///
+/// let image = raster::open("tests/in/sample.jpg").unwrap();
+/// let hdr = HdrImage::from_image(&image);
+/// let image = tonemap::hable(&hdr, 2.0, 11.2).unwrap();
+/// raster::save(&image, "tests/out/test_tonemap_hable.jpg").unwrap();
+/// ```
+///
+/// ### Before
+/// ![](https://kosinix.github.io/raster/in/sample.jpg)
+///
+/// ### After
+/// ![](https://kosinix.github.io/raster/out/test_tonemap_hable.jpg)
+///
+pub fn hable(src: &HdrImage, exposure: f32, white: f32) -> RasterResult<Image> {
+    if white.is_nan() || white <= 0.0 {
+        return Err(RasterError::InvalidWhitePoint(white));
+    }
+
+    let factor = 2.0f32.powf(exposure);
+    let scale = 1.0 / _hable_curve(white);
+    Ok(_tonemap(src, |rgb| {
+        rgb.map(|c| _hable_curve(c * factor) * scale)
+    }))
+}
+
+/// Convert a 0.0 - 1.0 sRGB channel to linear light.
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a 0.0 - 1.0 linear light channel to sRGB.
+pub(crate) fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Private functions
+
+// Map the linear colors of each pixel and convert them to an 8-bit sRGB image.
+fn _tonemap<F>(src: &HdrImage, map: F) -> Image
+where
+    F: Fn([f32; 3]) -> [f32; 3],
+{
+    let to_byte = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let bytes = src
+        .pixels
+        .chunks(4)
+        .flat_map(|p| {
+            // Negative values have no light, NaN is treated as black too.
+            let rgb = map([p[0], p[1], p[2]].map(|c| c.max(0.0)));
+            [
+                to_byte(linear_to_srgb(rgb[0].clamp(0.0, 1.0))),
+                to_byte(linear_to_srgb(rgb[1].clamp(0.0, 1.0))),
+                to_byte(linear_to_srgb(rgb[2].clamp(0.0, 1.0))),
+                to_byte(p[3]),
+            ]
+        })
+        .collect();
+    Image {
+        width: src.width,
+        height: src.height,
+        bytes,
+    }
+}
+
+// The filmic curve of Hable with his shoulder, linear and toe parameters.
+fn _hable_curve(x: f32) -> f32 {
+    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
@@ -2,19 +2,20 @@
 
 // from rust
 use std::cmp;
+use std::f64::consts::PI;
 
 // from external crate
 
 // from local crate
-use error::RasterResult;
+use error::{RasterError, RasterResult};
 use Image;
 use Color;
-use interpolate::{resample, InterpolationMode};
+use interpolate::{self, resample, InterpolationMode};
 use position::PositionMode;
-use editor::crop;
+use editor::{crop, Direction};
 
 /// An enum for the various modes that can be used for transforming.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TransformMode {
     /// Transform on x axis.
     Horizontal,
@@ -55,42 +56,27 @@ pub enum TransformMode {
 /// ![](https://kosinix.github.io/raster/out/test_transform_flip_y.png)
 ///
 pub fn flip(src: &mut Image, mode: TransformMode) -> RasterResult<()> {
-    let w: i32 = src.width;
-    let h: i32 = src.height;
+    let w = src.width as usize;
+    let h = src.height as usize;
+    let row_len = w * 4;
 
     match mode {
         TransformMode::Horizontal => {
-            for x in 0..w {
-                let src_x = x;
-                let dest_x = w - x - 1;
-                if dest_x <= src_x {
-                    break;
-                }
-                for y in 0..h {
-                    let pixel_left = src.get_pixel(src_x, y)?;
-                    let pixel_right = src.get_pixel(dest_x, y)?;
-
-                    src.set_pixel(dest_x, y, &pixel_left)?;
-                    src.set_pixel(src_x, y, &pixel_right)?;
+            // Reverse the order of pixels within each row
+            for row in src.bytes.chunks_mut(row_len) {
+                for x in 0..w / 2 {
+                    let (left, right) = row.split_at_mut((w - x - 1) * 4);
+                    left[x * 4..x * 4 + 4].swap_with_slice(&mut right[0..4]);
                 }
             }
 
             Ok(())
         }
         TransformMode::Vertical => {
-            for y in 0..h {
-                let src_y = y;
-                let dest_y = h - y - 1;
-                if dest_y <= src_y {
-                    break;
-                }
-                for x in 0..w {
-                    let pixel_top = src.get_pixel(x, src_y)?;
-                    let pixel_bottom = src.get_pixel(x, dest_y)?;
-
-                    src.set_pixel(x, dest_y, &pixel_top)?;
-                    src.set_pixel(x, src_y, &pixel_bottom)?;
-                }
+            // Swap the top rows with the bottom rows
+            for y in 0..h / 2 {
+                let (top, bottom) = src.bytes.split_at_mut((h - y - 1) * row_len);
+                top[y * row_len..(y + 1) * row_len].swap_with_slice(&mut bottom[0..row_len]);
             }
 
             Ok(())
@@ -101,9 +87,10 @@ pub fn flip(src: &mut Image, mode: TransformMode) -> RasterResult<()> {
 /// Rotate an image clockwise. Negate the degrees to do a counter-clockwise rotation. Background
 /// color can be any color.
 ///
-/// Note: If you look closely, the quality for arbitrary angles is not very good due to the simple
-/// sampling algorithm. The 90, 180, and 270 angles looks fine because no pixels are lost. This
-/// will be fixed in the future with a better sampling algorithm.
+/// The canvas is expanded to fit the rotated image and the exposed corners are filled with the
+/// background color. Pixels are sampled using bilinear interpolation so edges stay smooth at any
+/// angle. For multiples of 90 degrees, `rotate_90`, `rotate_180` and `rotate_270` are faster and
+/// lossless.
 ///
 /// # Examples
 ///
@@ -115,7 +102,7 @@ pub fn flip(src: &mut Image, mode: TransformMode) -> RasterResult<()> {
 /// //...
 ///
 /// let mut image = raster::open("tests/in/sample.png").unwrap();
-/// transform::rotate(&mut image, 45, Color::rgb(0,0,0)).unwrap();
+/// transform::rotate(&mut image, 45.0, Color::rgb(0,0,0)).unwrap();
 /// raster::save(&image, "tests/out/test_transform_rotate_45.png").unwrap();
 /// ```
 ///
@@ -130,78 +117,361 @@ pub fn flip(src: &mut Image, mode: TransformMode) -> RasterResult<()> {
 /// //...
 ///
 /// let mut image = raster::open("tests/in/sample.png").unwrap();
-/// transform::rotate(&mut image, -45, Color::rgb(252,145,145)).unwrap();
+/// transform::rotate(&mut image, -45.0, Color::rgb(252,145,145)).unwrap();
 /// raster::save(&image, "tests/out/test_transform_rotate_45cc.png").unwrap();
 /// ```
 ///
 /// ![](https://kosinix.github.io/raster/out/test_transform_rotate_45cc.png)
 ///
-pub fn rotate(src: &mut Image, degree: i32, bg: Color) -> RasterResult<()> {
-    let w1 = src.width;
-    let h1 = src.height;
-
-    let degree = degree as f32; // convert to float
-
-    // Using screen coords system, top left is always at (0,0)
-    let mut min_x = 0;
-    let mut max_x = 0;
-    let mut min_y = 0;
-    let mut max_y = 0;
-
-    let top_right_1: (i32, i32) = (w1, 0);
-    let top_right_2: (i32, i32) = _rotate(top_right_1, degree);
-    min_x = cmp::min(min_x, top_right_2.0);
-    max_x = cmp::max(max_x, top_right_2.0);
-    min_y = cmp::min(min_y, top_right_2.1);
-    max_y = cmp::max(max_y, top_right_2.1);
-
-    let bottom_right_1: (i32, i32) = (w1, h1);
-    let bottom_right_2: (i32, i32) = _rotate(bottom_right_1, degree);
-    min_x = cmp::min(min_x, bottom_right_2.0);
-    max_x = cmp::max(max_x, bottom_right_2.0);
-    min_y = cmp::min(min_y, bottom_right_2.1);
-    max_y = cmp::max(max_y, bottom_right_2.1);
-
-    let bottom_left_1: (i32, i32) = (0, h1);
-    let bottom_left_2: (i32, i32) = _rotate(bottom_left_1, degree);
-    min_x = cmp::min(min_x, bottom_left_2.0);
-    max_x = cmp::max(max_x, bottom_left_2.0);
-    min_y = cmp::min(min_y, bottom_left_2.1);
-    max_y = cmp::max(max_y, bottom_left_2.1);
-
-    let w2 = ((min_x as f32).abs() + (max_x as f32).abs()) as i32 + 1;
-    let h2 = ((min_y as f32).abs() + (max_y as f32).abs()) as i32 + 1;
-    let mut dest = Image::blank(w2, h2);
+pub fn rotate(src: &mut Image, degree: f32, bg: Color) -> RasterResult<()> {
+    let transform = Transform::identity().rotate(degree as f64);
+    affine(src, &transform, InterpolationMode::Bilinear, bg)
+}
 
-    for (dest_y, y) in (0..).zip(min_y..max_y + 1) {
-        for (dest_x, x) in (0..).zip(min_x..max_x + 1) {
-            let point: (i32, i32) = _rotate((x, y), -degree);
+/// A 2D affine transformation matrix.
+///
+/// Transformations are composed by chaining, each one applied after the previous ones. The
+/// coordinates are in pixels with the y axis pointing down, so positive angles rotate clockwise.
+///
+/// # Examples
+/// ```
+/// use raster::Transform;
+///
+/// // Scale by 2 then move 10 pixels to the right
+/// let transform = Transform::identity().scale(2.0, 2.0).translate(10.0, 0.0);
+/// assert_eq!((12.0, 2.0), transform.apply(1.0, 1.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    // Row major 2x3 matrix:
+    // x' = a * x + b * y + c
+    // y' = d * x + e * y + f
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+    f: f64,
+}
 
-            if point.0 >= 0 && point.0 < w1 && point.1 >= 0 && point.1 < h1 {
-                let pixel = src.get_pixel(point.0, point.1)?;
-                dest.set_pixel(dest_x, dest_y, &pixel)?;
-            } else {
-                dest.set_pixel(dest_x, dest_y, &Color::rgba(bg.r, bg.g, bg.b, bg.a))?;
-            }
+impl Transform {
+    /// The transformation that leaves points unchanged.
+    pub fn identity() -> Transform {
+        Transform {
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 0.0,
+            e: 1.0,
+            f: 0.0,
         }
     }
 
-    src.width = dest.width;
-    src.height = dest.height;
-    src.bytes = dest.bytes;
+    /// Move by tx and ty pixels.
+    pub fn translate(self, tx: f64, ty: f64) -> Transform {
+        self.then(&Transform {
+            c: tx,
+            f: ty,
+            ..Transform::identity()
+        })
+    }
+
+    /// Scale by sx horizontally and sy vertically, relative to the origin.
+    pub fn scale(self, sx: f64, sy: f64) -> Transform {
+        self.then(&Transform {
+            a: sx,
+            e: sy,
+            ..Transform::identity()
+        })
+    }
+
+    /// Rotate clockwise by the given degrees around the origin.
+    pub fn rotate(self, degree: f64) -> Transform {
+        let radians = degree.to_radians();
+        let (sin, cos) = (radians.sin(), radians.cos());
+        self.then(&Transform {
+            a: cos,
+            b: -sin,
+            d: sin,
+            e: cos,
+            ..Transform::identity()
+        })
+    }
+
+    /// Shear by kx horizontally and ky vertically, eg. a kx of 0.5 moves points 0.5 pixels to the
+    /// right for every pixel down.
+    pub fn shear(self, kx: f64, ky: f64) -> Transform {
+        self.then(&Transform {
+            b: kx,
+            d: ky,
+            ..Transform::identity()
+        })
+    }
+
+    /// Compose with another transformation, applied after this one.
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform {
+            a: next.a * self.a + next.b * self.d,
+            b: next.a * self.b + next.b * self.e,
+            c: next.a * self.c + next.b * self.f + next.c,
+            d: next.d * self.a + next.e * self.d,
+            e: next.d * self.b + next.e * self.e,
+            f: next.d * self.c + next.e * self.f + next.f,
+        }
+    }
+
+    /// The transformation that undoes this one. Returns None if it cannot be undone, eg. a scale
+    /// of 0.
+    pub fn invert(&self) -> Option<Transform> {
+        let det = self.a * self.e - self.b * self.d;
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        Some(Transform {
+            a: self.e / det,
+            b: -self.b / det,
+            c: (self.b * self.f - self.e * self.c) / det,
+            d: -self.d / det,
+            e: self.a / det,
+            f: (self.d * self.c - self.a * self.f) / det,
+        })
+    }
+
+    /// Transform a point.
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.b * y + self.c,
+            self.d * x + self.e * y + self.f,
+        )
+    }
+}
+
+/// Apply an affine transformation to an image in a single resampling pass.
+///
+/// Combine translations, scales, rotations and shears into one `Transform` instead of chaining
+/// transform functions to avoid losing quality at each step. The canvas is resized to fit the
+/// transformed image and the exposed areas are filled with the background color. Translations
+/// are therefore only useful between other transformations, eg. to rotate around a given point.
+///
+/// # Errors
+///
+/// If the transformation cannot be inverted, this fails with `RasterError::InvalidTransform`.
+///
+/// # Examples
+///
+/// ```
+/// use raster::{transform, Color, InterpolationMode, Transform};
+///
+/// let mut image = raster::open("tests/in/sample.png").unwrap();
+///
+/// let t = Transform::identity().shear(0.3, 0.0).rotate(-10.0).scale(0.8, 0.8);
+/// transform::affine(&mut image, &t, InterpolationMode::Bicubic, Color::rgba(0, 0, 0, 0)).unwrap();
+///
+/// raster::save(&image, "tests/out/test_transform_affine.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_affine.png)
+///
+pub fn affine(
+    src: &mut Image,
+    transform: &Transform,
+    interpolation: InterpolationMode,
+    bg: Color,
+) -> RasterResult<()> {
+    let inverse = transform.invert().ok_or(RasterError::InvalidTransform)?;
+
+    let w1 = src.width as f64;
+    let h1 = src.height as f64;
+
+    // Using screen coords system, top left is always at (0,0). Find the bounding box of the
+    // transformed corners.
+    let corners = [(0.0, 0.0), (w1, 0.0), (w1, h1), (0.0, h1)];
+    let mut min_x = f64::MAX;
+    let mut max_x = f64::MIN;
+    let mut min_y = f64::MAX;
+    let mut max_y = f64::MIN;
+    for &(x, y) in &corners {
+        let (x, y) = transform.apply(x, y);
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+    }
+
+    // Round to cancel floating point errors, eg. 100.00000001 should not add a pixel
+    let w2 = ((max_x - min_x) * 1000.0).round() / 1000.0;
+    let h2 = ((max_y - min_y) * 1000.0).round() / 1000.0;
+    let dest_w = cmp::max(1, w2.ceil() as i32);
+    let dest_h = cmp::max(1, h2.ceil() as i32);
+
+    // Center the transformed image in the canvas
+    let origin_x = min_x - (dest_w as f64 - w2) / 2.0;
+    let origin_y = min_y - (dest_h as f64 - h2) / 2.0;
+
+    _warp(src, dest_w, dest_h, interpolation, &bg, |x, y| {
+        inverse.apply(origin_x + x, origin_y + y)
+    })
+}
+
+/// Rotate an image 90 degrees clockwise.
+///
+/// Pixels are only moved around so there is no loss in quality.
+///
+/// # Examples
+///
+/// ```
+/// use raster::transform;
+///
+/// let mut image = raster::open("tests/in/sample.png").unwrap();
+/// transform::rotate_90(&mut image).unwrap();
+/// raster::save(&image, "tests/out/test_transform_rotate_90.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_rotate_90.png)
+///
+pub fn rotate_90(src: &mut Image) -> RasterResult<()> {
+    let (w, h) = (src.width, src.height);
+    _remap(src, h, w, |x, y| (y, h - 1 - x))
+}
+
+/// Rotate an image 180 degrees.
+///
+/// Pixels are only moved around so there is no loss in quality.
+///
+/// # Examples
+///
+/// ```
+/// use raster::transform;
+///
+/// let mut image = raster::open("tests/in/sample.png").unwrap();
+/// transform::rotate_180(&mut image).unwrap();
+/// raster::save(&image, "tests/out/test_transform_rotate_180.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_rotate_180.png)
+///
+pub fn rotate_180(src: &mut Image) -> RasterResult<()> {
+    let (w, h) = (src.width, src.height);
+    _remap(src, w, h, |x, y| (w - 1 - x, h - 1 - y))
+}
+
+/// Rotate an image 270 degrees clockwise, or 90 degrees counter-clockwise.
+///
+/// Pixels are only moved around so there is no loss in quality.
+///
+/// # Examples
+///
+/// ```
+/// use raster::transform;
+///
+/// let mut image = raster::open("tests/in/sample.png").unwrap();
+/// transform::rotate_270(&mut image).unwrap();
+/// raster::save(&image, "tests/out/test_transform_rotate_270.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_rotate_270.png)
+///
+pub fn rotate_270(src: &mut Image) -> RasterResult<()> {
+    let (w, h) = (src.width, src.height);
+    _remap(src, h, w, |x, y| (w - 1 - y, x))
+}
+
+/// Warp an image so that the quadrilateral `src_quad` of the image is mapped onto the
+/// quadrilateral `dst_quad`.
+///
+/// Corners are given as (x, y) pixel coordinates in the order top left, top right, bottom right,
+/// bottom left. The resulting image is large enough to contain `dst_quad` from the origin (0, 0),
+/// so it can be blended at the top left of another image of the same coordinate space. Areas
+/// outside the warped quadrilateral are transparent. Pixels are sampled using bilinear
+/// interpolation.
+///
+/// # Errors
+///
+/// If either quadrilateral is degenerate, eg. 3 corners on a line, or has a corner that is not
+/// finite, this fails with `RasterError::InvalidTransform`.
+///
+/// # Examples
+///
+/// ```
+/// use raster::{editor, transform, BlendMode, PositionMode};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let (w, h) = (image.width as f64, image.height as f64);
+///
+/// // Place the image on a tilted plane
+/// let src_quad = [(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)];
+/// let dst_quad = [(40.0, 20.0), (w - 60.0, 50.0), (w - 20.0, h - 10.0), (10.0, h - 60.0)];
+/// transform::perspective(&mut image, src_quad, dst_quad).unwrap();
+///
+/// raster::save(&image, "tests/out/test_transform_perspective.png").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_perspective.png)
+///
+pub fn perspective(
+    src: &mut Image,
+    src_quad: [(f64, f64); 4],
+    dst_quad: [(f64, f64); 4],
+) -> RasterResult<()> {
+    let mut corners = src_quad.iter().chain(dst_quad.iter());
+    if corners.any(|&(x, y)| !x.is_finite() || !y.is_finite()) {
+        return Err(RasterError::InvalidTransform);
+    }
+
+    // Map destination points back to the source
+    let h = _homography(dst_quad, src_quad).ok_or(RasterError::InvalidTransform)?;
+
+    let w2 = dst_quad.iter().fold(0.0, |w: f64, p| w.max(p.0)).ceil() as i32;
+    let h2 = dst_quad.iter().fold(0.0, |h: f64, p| h.max(p.1)).ceil() as i32;
 
+    let bg = Color::rgba(0, 0, 0, 0);
+    _warp(src, w2, h2, InterpolationMode::Bilinear, &bg, |x, y| {
+        let z = h[6] * x + h[7] * y + 1.0;
+        (
+            (h[0] * x + h[1] * y + h[2]) / z,
+            (h[3] * x + h[4] * y + h[5]) / z,
+        )
+    })?;
+
+    // The mapping goes on past the edges of the quadrilaterals, clear what lands outside.
+    for (i, pixel) in src.bytes.chunks_mut(4).enumerate() {
+        let x = (i as i32 % w2) as f64 + 0.5;
+        let y = (i as i32 / w2) as f64 + 0.5;
+        if !_in_quad(&dst_quad, x, y) {
+            pixel.copy_from_slice(&[0, 0, 0, 0]);
+        }
+    }
     Ok(())
 }
 
 /// Resize image to exact dimensions ignoring aspect ratio.
 /// Useful if you want to force exact width and height.
 pub fn resize_exact(src: &mut Image, w: i32, h: i32) -> RasterResult<()> {
-    resample(src, w, h, InterpolationMode::Bicubic)
+    resize_exact_with(src, w, h, InterpolationMode::Bicubic)
+}
+
+/// Same as `resize_exact` but with a given interpolation method.
+pub fn resize_exact_with(
+    src: &mut Image,
+    w: i32,
+    h: i32,
+    interpolation: InterpolationMode,
+) -> RasterResult<()> {
+    resample(src, w, h, interpolation)
 }
 
 /// Resize image to exact height. Width is auto calculated.
 /// Useful for creating row of images with the same height.
 pub fn resize_exact_height(src: &mut Image, h: i32) -> RasterResult<()> {
+    resize_exact_height_with(src, h, InterpolationMode::Bicubic)
+}
+
+/// Same as `resize_exact_height` but with a given interpolation method.
+pub fn resize_exact_height_with(
+    src: &mut Image,
+    h: i32,
+    interpolation: InterpolationMode,
+) -> RasterResult<()> {
     let width = src.width;
     let height = src.height;
     let ratio = width as f32 / height as f32;
@@ -209,12 +479,21 @@ pub fn resize_exact_height(src: &mut Image, h: i32) -> RasterResult<()> {
     let resize_height = h;
     let resize_width = (h as f32 * ratio) as i32;
 
-    resample(src, resize_width, resize_height, InterpolationMode::Bicubic)
+    resample(src, resize_width, resize_height, interpolation)
 }
 
 /// Resize image to exact width. Height is auto calculated.
 /// Useful for creating column of images with the same width.
 pub fn resize_exact_width(src: &mut Image, w: i32) -> RasterResult<()> {
+    resize_exact_width_with(src, w, InterpolationMode::Bicubic)
+}
+
+/// Same as `resize_exact_width` but with a given interpolation method.
+pub fn resize_exact_width_with(
+    src: &mut Image,
+    w: i32,
+    interpolation: InterpolationMode,
+) -> RasterResult<()> {
     let width = src.width;
     let height = src.height;
     let ratio = width as f32 / height as f32;
@@ -222,11 +501,21 @@ pub fn resize_exact_width(src: &mut Image, w: i32) -> RasterResult<()> {
     let resize_width = w;
     let resize_height = (w as f32 / ratio).round() as i32;
 
-    resample(src, resize_width, resize_height, InterpolationMode::Bicubic)
+    resample(src, resize_width, resize_height, interpolation)
 }
 
 /// Resize image to fill all the space in the given dimension. Excess parts are removed.
 pub fn resize_fill(src: &mut Image, w: i32, h: i32) -> RasterResult<()> {
+    resize_fill_with(src, w, h, InterpolationMode::Bicubic)
+}
+
+/// Same as `resize_fill` but with a given interpolation method.
+pub fn resize_fill_with(
+    src: &mut Image,
+    w: i32,
+    h: i32,
+    interpolation: InterpolationMode,
+) -> RasterResult<()> {
     let width = src.width;
     let height = src.height;
     let ratio = width as f32 / height as f32;
@@ -242,18 +531,24 @@ pub fn resize_fill(src: &mut Image, w: i32, h: i32) -> RasterResult<()> {
         optimum_height = h;
     }
 
-    resample(
-        src,
-        optimum_width,
-        optimum_height,
-        InterpolationMode::Bicubic,
-    ).and_then(|_| crop(src, w, h, PositionMode::Center, 0, 0)) // Trim excess parts
+    resample(src, optimum_width, optimum_height, interpolation)
+        .and_then(|_| crop(src, w, h, PositionMode::Center, 0, 0)) // Trim excess parts
 }
 
 /// Resize an image to fit within the given width and height.
 /// The re-sized image will not exceed the given dimension.
 /// Preserves the aspect ratio.
 pub fn resize_fit(src: &mut Image, w: i32, h: i32) -> RasterResult<()> {
+    resize_fit_with(src, w, h, InterpolationMode::Bicubic)
+}
+
+/// Same as `resize_fit` but with a given interpolation method.
+pub fn resize_fit_with(
+    src: &mut Image,
+    w: i32,
+    h: i32,
+    interpolation: InterpolationMode,
+) -> RasterResult<()> {
     let ratio: f64 = src.width as f64 / src.height as f64;
 
     // Try basing it on width first
@@ -267,19 +562,481 @@ pub fn resize_fit(src: &mut Image, w: i32, h: i32) -> RasterResult<()> {
         resize_width = (h as f64 * ratio).round() as i32;
     }
 
-    resample(src, resize_width, resize_height, InterpolationMode::Bicubic)
+    resample(src, resize_width, resize_height, interpolation)
+}
+
+/// Bulge or pinch the image inside a circle, as if it was seen through a glass ball.
+///
+/// Positive amounts magnify the center of the circle, negative ones shrink it. The amount is a
+/// value from -1.0 - 1.0, 0.0 leaves the image unchanged. Pixels outside of the circle do not
+/// move.
+///
+/// # Errors
+///
+/// If the radius is zero or negative, this fails with `RasterError::InvalidShapeRadius`.
+///
+/// If the amount is outside -1.0 - 1.0, this fails with `RasterError::InvalidPercentage`.
+///
+/// # Examples
+/// ```
+/// use raster::transform;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let center = (image.width as f64 / 2.0, image.height as f64 / 2.0);
+/// transform::spherize(&mut image, center, 150.0, 0.6).unwrap();
+/// raster::save(&image, "tests/out/test_transform_spherize.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_spherize.jpg)
+///
+pub fn spherize(
+    src: &mut Image,
+    center: (f64, f64),
+    radius: f64,
+    amount: f64,
+) -> RasterResult<()> {
+    if amount.is_nan() || !(-1.0..=1.0).contains(&amount) {
+        return Err(RasterError::InvalidPercentage(amount as f32));
+    }
+    if radius.is_nan() || radius <= 0.0 {
+        return Err(RasterError::InvalidShapeRadius(radius as f32));
+    }
+    if amount == 0.0 {
+        return Ok(());
+    }
+
+    // The distance to the center, as a share of the radius, is raised to this power.
+    let exponent = if amount > 0.0 {
+        1.0 + amount
+    } else {
+        1.0 / (1.0 - amount)
+    };
+    let (cx, cy) = center;
+    _distort(src, |x, y| {
+        let (dx, dy) = (x - cx, y - cy);
+        let t = dx.hypot(dy) / radius;
+        if t >= 1.0 || t == 0.0 {
+            return (x, y);
+        }
+        let scale = t.powf(exponent) / t;
+        (cx + dx * scale, cy + dy * scale)
+    })
+}
+
+/// Twist the image around a center, eg. for playful profile pictures.
+///
+/// Pixels at the center are rotated by angle degrees clockwise, and the rotation fades out
+/// smoothly to nothing at radius pixels from the center. Negative angles twist counter-clockwise.
+///
+/// # Errors
+///
+/// If the radius is zero or negative, this fails with `RasterError::InvalidShapeRadius`.
+///
+/// # Examples
+/// ```
+/// use raster::transform;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let center = (image.width as f64 / 2.0, image.height as f64 / 2.0);
+/// transform::swirl(&mut image, center, 150.0, 180.0).unwrap();
+/// raster::save(&image, "tests/out/test_transform_swirl.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_swirl.jpg)
+///
+pub fn swirl(src: &mut Image, center: (f64, f64), radius: f64, angle: f64) -> RasterResult<()> {
+    if radius.is_nan() || radius <= 0.0 {
+        return Err(RasterError::InvalidShapeRadius(radius as f32));
+    }
+
+    let (cx, cy) = center;
+    _distort(src, |x, y| {
+        let (dx, dy) = (x - cx, y - cy);
+        let distance = dx.hypot(dy);
+        if distance >= radius {
+            return (x, y);
+        }
+        // Rotate back by the angle at this distance, which is the same for the source pixel.
+        let falloff = 1.0 - distance / radius;
+        let (sin, cos) = (-angle.to_radians() * falloff * falloff).sin_cos();
+        (cx + dx * cos - dy * sin, cy + dx * sin + dy * cos)
+    })
+}
+
+/// Bend the image into waves.
+///
+/// With `Direction::Horizontal` the pixels move left and right by up to amplitude pixels, following
+/// a sine wave of wavelength pixels down the rows, so vertical lines become wavy. With
+/// `Direction::Vertical` they move up and down following the columns.
+///
+/// # Errors
+///
+/// If the wavelength is zero or negative, this fails with `RasterError::InvalidWavelength`.
+///
+/// # Examples
+/// ```
+/// use raster::{transform, Direction};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// transform::wave(&mut image, 8.0, 60.0, Direction::Horizontal).unwrap();
+/// raster::save(&image, "tests/out/test_transform_wave.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_wave.jpg)
+///
+pub fn wave(
+    src: &mut Image,
+    amplitude: f32,
+    wavelength: f32,
+    direction: Direction,
+) -> RasterResult<()> {
+    if wavelength.is_nan() || wavelength <= 0.0 {
+        return Err(RasterError::InvalidWavelength(wavelength));
+    }
+
+    let amplitude = amplitude as f64;
+    let k = 2.0 * PI / wavelength as f64;
+    _distort(src, |x, y| match direction {
+        Direction::Horizontal => (x + amplitude * (y * k).sin(), y),
+        Direction::Vertical => (x, y + amplitude * (x * k).sin()),
+    })
+}
+
+/// Move the pixels of the image by the colors of a displacement map, eg. for water, glass or flag
+/// effects.
+///
+/// The red channel of the map moves pixels horizontally and the green channel vertically. A value
+/// of 128 does not move, 255 reads the pixel scale_x or scale_y pixels to the right or below, and
+/// 0 as far to the left or above. Smooth maps, eg. blurred noise, give smooth warps.
+///
+/// # Errors
+///
+/// If the map does not have the dimensions of the image, this fails with
+/// `RasterError::DimensionsMismatch`.
+///
+/// # Examples
+/// ```
+/// use raster::{filter, transform, Image, NoiseType};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let mut map = Image::blank(image.width, image.height);
+/// filter::add_noise(&mut map, NoiseType::Uniform, 1.0, false, 1).unwrap();
+/// filter::blur_box(&mut map, 6).unwrap();
+/// transform::displace(&mut image, &map, 40.0, 40.0).unwrap();
+/// raster::save(&image, "tests/out/test_transform_displace.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_displace.jpg)
+///
+pub fn displace(src: &mut Image, map: &Image, scale_x: f64, scale_y: f64) -> RasterResult<()> {
+    if map.width != src.width || map.height != src.height {
+        return Err(RasterError::DimensionsMismatch);
+    }
+
+    let w = map.width as usize;
+    let offset = |value: u8| (value as f64 - 128.0) / 127.0;
+    _distort(src, |x, y| {
+        let i = (y as usize * w + x as usize) * 4;
+        let (dx, dy) = (offset(map.bytes[i]), offset(map.bytes[i + 1]));
+        (x + dx * scale_x, y + dy * scale_y)
+    })
+}
+
+/// Apply radial lens distortion, eg. to simulate a wide-angle or fisheye lens.
+///
+/// Uses the common polynomial lens model where a point at distance r from the center moves to
+/// r * (1 + k1 * r^2 + k2 * r^4), with r measured in halves of the image diagonal. Negative
+/// coefficients give barrel distortion, where straight lines bow outward, and positive ones
+/// pincushion distortion. `transform::lens_undistort` with the same coefficients reverses it.
+///
+/// # Examples
+/// ```
+/// use raster::transform;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// transform::lens_distort(&mut image, -0.3, 0.05).unwrap();
+/// raster::save(&image, "tests/out/test_transform_lens_distort.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_lens_distort.jpg)
+///
+pub fn lens_distort(src: &mut Image, k1: f64, k2: f64) -> RasterResult<()> {
+    let (cx, cy, norm) = _lens_frame(src);
+    let factor = |r2: f64| 1.0 + k1 * r2 + k2 * r2 * r2;
+    _distort(src, |x, y| {
+        // Find the undistorted point that lands here, by fixed point iteration.
+        let (dx, dy) = ((x - cx) / norm, (y - cy) / norm);
+        let (mut ux, mut uy) = (dx, dy);
+        for _ in 0..20 {
+            let f = factor(ux * ux + uy * uy);
+            if f <= 0.0 {
+                break;
+            }
+            ux = dx / f;
+            uy = dy / f;
+        }
+        (cx + ux * norm, cy + uy * norm)
+    })
+}
+
+/// Remove radial lens distortion, eg. to straighten the lines of a wide-angle snapshot.
+///
+/// Takes the coefficients of the lens, see `transform::lens_distort` for the model. A lens with
+/// barrel distortion has a negative k1, often between -0.4 and -0.05.
+///
+/// # Examples
+/// ```
+/// use raster::transform;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// transform::lens_undistort(&mut image, -0.2, 0.0).unwrap();
+/// raster::save(&image, "tests/out/test_transform_lens_undistort.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_lens_undistort.jpg)
+///
+pub fn lens_undistort(src: &mut Image, k1: f64, k2: f64) -> RasterResult<()> {
+    let (cx, cy, norm) = _lens_frame(src);
+    _distort(src, |x, y| {
+        let (ux, uy) = ((x - cx) / norm, (y - cy) / norm);
+        let r2 = ux * ux + uy * uy;
+        let f = 1.0 + k1 * r2 + k2 * r2 * r2;
+        (cx + ux * f * norm, cy + uy * f * norm)
+    })
+}
+
+/// Wrap the image around its center, turning rows into circles. Also known as rectangular to polar
+/// coordinates.
+///
+/// The columns of the image are spread clockwise around the center, starting straight up, and the
+/// rows from the center out to the edges of the largest circle that fits in the image. Corners
+/// repeat the bottom row. Flip a 360 degree panorama vertically first to get a "little planet".
+/// `transform::from_polar` reverses it.
+///
+/// # Examples
+/// ```
+/// use raster::{transform, TransformMode};
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// transform::flip(&mut image, TransformMode::Vertical).unwrap();
+/// transform::to_polar(&mut image).unwrap();
+/// raster::save(&image, "tests/out/test_transform_to_polar.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_to_polar.jpg)
+///
+pub fn to_polar(src: &mut Image) -> RasterResult<()> {
+    let (w, h) = (src.width as f64, src.height as f64);
+    let (cx, cy) = (w / 2.0, h / 2.0);
+    let radius = cx.min(cy);
+    _distort(src, |x, y| {
+        let (dx, dy) = (x - cx, y - cy);
+        let angle = dx.atan2(-dy).rem_euclid(2.0 * PI);
+        (angle / (2.0 * PI) * w, dx.hypot(dy) / radius * h)
+    })
+}
+
+/// Unwrap the circles around the center of the image into rows. Also known as polar to
+/// rectangular coordinates.
+///
+/// This is the inverse of `transform::to_polar`, eg. to unroll a clock face into a strip.
+///
+/// # Examples
+/// ```
+/// use raster::transform;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// transform::from_polar(&mut image).unwrap();
+/// raster::save(&image, "tests/out/test_transform_from_polar.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_from_polar.jpg)
+///
+pub fn from_polar(src: &mut Image) -> RasterResult<()> {
+    let (w, h) = (src.width as f64, src.height as f64);
+    let (cx, cy) = (w / 2.0, h / 2.0);
+    let radius = cx.min(cy);
+    _distort(src, |x, y| {
+        let (sin, cos) = (x / w * 2.0 * PI).sin_cos();
+        let distance = y / h * radius;
+        (cx + distance * sin, cy - distance * cos)
+    })
+}
+
+/// Add concentric ripples around a center, like a drop falling into water.
+///
+/// The pixels move toward and away from the center by up to amplitude pixels, following a sine
+/// wave of wavelength pixels along the distance from the center.
+///
+/// # Errors
+///
+/// If the wavelength is zero or negative, this fails with `RasterError::InvalidWavelength`.
+///
+/// # Examples
+/// ```
+/// use raster::transform;
+///
+/// let mut image = raster::open("tests/in/sample.jpg").unwrap();
+/// let center = (image.width as f64 / 2.0, image.height as f64 / 2.0);
+/// transform::ripple(&mut image, center, 4.0, 24.0).unwrap();
+/// raster::save(&image, "tests/out/test_transform_ripple.jpg").unwrap();
+/// ```
+///
+/// ![](https://kosinix.github.io/raster/out/test_transform_ripple.jpg)
+///
+pub fn ripple(
+    src: &mut Image,
+    center: (f64, f64),
+    amplitude: f32,
+    wavelength: f32,
+) -> RasterResult<()> {
+    if wavelength.is_nan() || wavelength <= 0.0 {
+        return Err(RasterError::InvalidWavelength(wavelength));
+    }
+
+    let (cx, cy) = center;
+    let amplitude = amplitude as f64;
+    let k = 2.0 * PI / wavelength as f64;
+    _distort(src, |x, y| {
+        let (dx, dy) = (x - cx, y - cy);
+        let distance = dx.hypot(dy);
+        if distance == 0.0 {
+            return (x, y);
+        }
+        let scale = (distance + amplitude * (distance * k).sin()) / distance;
+        (cx + dx * scale, cy + dy * scale)
+    })
 }
 
 // Private functions
 
-// Rotate a point clockwise to a given degree.
-fn _rotate(p: (i32, i32), deg: f32) -> (i32, i32) {
-    let radians: f32 = deg.to_radians();
-    let px: f32 = p.0 as f32;
-    let py: f32 = p.1 as f32;
-    let cos = radians.cos();
-    let sin = radians.sin();
-    let x = ((px * cos) - (py * sin)).round();
-    let y = ((px * sin) + (py * cos)).round();
-    (x as i32, y as i32)
+// Build a new w2 x h2 image where each destination pixel is copied from the source pixel given
+// by the mapping function.
+fn _remap<F>(src: &mut Image, w2: i32, h2: i32, map: F) -> RasterResult<()>
+where
+    F: Fn(i32, i32) -> (i32, i32),
+{
+    let w1 = src.width;
+    let mut bytes = Vec::with_capacity(src.bytes.len());
+    for y in 0..h2 {
+        for x in 0..w2 {
+            let (src_x, src_y) = map(x, y);
+            let start = ((src_y * w1 + src_x) * 4) as usize;
+            bytes.extend_from_slice(&src.bytes[start..start + 4]);
+        }
+    }
+    src.width = w2;
+    src.height = h2;
+    src.bytes = bytes;
+
+    Ok(())
+}
+
+// Build a new w2 x h2 image where each destination pixel is sampled from the source position
+// given by the mapping function. The mapping works on continuous coordinates where pixel centers
+// are at half coordinates.
+fn _warp<F>(
+    src: &mut Image,
+    w2: i32,
+    h2: i32,
+    interpolation: InterpolationMode,
+    bg: &Color,
+    map: F,
+) -> RasterResult<()>
+where
+    F: Fn(f64, f64) -> (f64, f64),
+{
+    if w2 <= 0 || h2 <= 0 {
+        return Err(RasterError::InvalidDimensions(w2, h2));
+    }
+
+    let mut dest = Image::blank(w2, h2);
+    for y in 0..h2 {
+        for x in 0..w2 {
+            let (src_x, src_y) = map(x as f64 + 0.5, y as f64 + 0.5);
+            let pixel = interpolate::sample(src, src_x, src_y, interpolation, bg)?;
+            dest.set_pixel(x, y, &pixel)?;
+        }
+    }
+    src.width = dest.width;
+    src.height = dest.height;
+    src.bytes = dest.bytes;
+
+    Ok(())
+}
+
+// Whether a point is inside a quadrilateral, with the even-odd rule so that it also works for
+// concave ones.
+fn _in_quad(quad: &[(f64, f64); 4], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    for (i, &(x1, y1)) in quad.iter().enumerate() {
+        let (x2, y2) = quad[(i + 1) % 4];
+        if (y1 <= y) != (y2 <= y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+// Move the pixels of the image without changing its size. The mapping gives the source position
+// of each destination position, sampled with bilinear interpolation. Positions outside the image
+// are moved back onto its edge so that no holes appear.
+fn _distort<F>(src: &mut Image, map: F) -> RasterResult<()>
+where
+    F: Fn(f64, f64) -> (f64, f64),
+{
+    let (w, h) = (src.width, src.height);
+    if w <= 0 || h <= 0 {
+        return Ok(());
+    }
+    let (max_x, max_y) = (w as f64 - 0.5, h as f64 - 0.5);
+    let bg = Color::rgba(0, 0, 0, 0);
+    _warp(src, w, h, InterpolationMode::Bilinear, &bg, |x, y| {
+        let (x, y) = map(x, y);
+        (x.clamp(0.5, max_x), y.clamp(0.5, max_y))
+    })
+}
+
+// Center of the image and half of its diagonal, the unit of the lens model.
+fn _lens_frame(src: &Image) -> (f64, f64, f64) {
+    let (cx, cy) = (src.width as f64 / 2.0, src.height as f64 / 2.0);
+    (cx, cy, cx.hypot(cy).max(1.0))
+}
+
+// Compute the homography that maps the 4 `from` points onto the 4 `to` points. Returns the first
+// 8 coefficients of the 3x3 matrix, the last one being 1. Returns None if the points are
+// degenerate.
+fn _homography(from: [(f64, f64); 4], to: [(f64, f64); 4]) -> Option<[f64; 8]> {
+    // Each pair of points gives 2 equations:
+    // u = (h0 x + h1 y + h2) / (h6 x + h7 y + 1)
+    // v = (h3 x + h4 y + h5) / (h6 x + h7 y + 1)
+    let mut m = [[0.0; 9]; 8];
+    for (i, (&(x, y), &(u, v))) in from.iter().zip(to.iter()).enumerate() {
+        m[i * 2] = [x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y, u];
+        m[i * 2 + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y, v];
+    }
+
+    // Gaussian elimination with partial pivoting
+    for col in 0..8 {
+        let pivot = (col..8).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))?;
+        if m[pivot][col].abs() < 1e-10 {
+            return None;
+        }
+        m.swap(col, pivot);
+        let pivot_row = m[col];
+        for (row, values) in m.iter_mut().enumerate() {
+            if row != col {
+                let factor = values[col] / pivot_row[col];
+                for (value, pivot_value) in values.iter_mut().zip(pivot_row.iter()).skip(col) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+    }
+
+    let mut h = [0.0; 8];
+    for (i, coefficient) in h.iter_mut().enumerate() {
+        *coefficient = m[i][8] / m[i][i];
+    }
+    Some(h)
 }
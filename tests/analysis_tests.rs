@@ -0,0 +1,145 @@
+extern crate raster;
+
+use raster::analysis;
+use raster::{editor, Color, Image, MatchMethod, PositionMode, Rect};
+
+#[test]
+fn dominant_colors_test() {
+    let image = raster::open("tests/in/sample.jpg").unwrap();
+    let colors = analysis::dominant_colors(&image, 5).unwrap();
+    assert_eq!(5, colors.len());
+    let total: f32 = colors.iter().map(|c| c.1).sum();
+    assert!((total - 1.0).abs() < 0.001);
+    assert!(colors.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+}
+
+#[test]
+fn dominant_colors_shares() {
+    // 6 green pixels, 2 red and 1 transparent blue
+    let mut image = Image::blank(3, 3);
+    for i in 0..9 {
+        let color = match i {
+            0..=5 => Color::rgba(0, 200, 0, 255),
+            6 | 7 => Color::rgba(200, 0, 0, 255),
+            _ => Color::rgba(0, 0, 200, 0),
+        };
+        image.set_pixel(i % 3, i / 3, &color).unwrap();
+    }
+
+    let colors = analysis::dominant_colors(&image, 3).unwrap();
+    assert_eq!(2, colors.len());
+    assert_eq!((0, 200, 0), (colors[0].0.r, colors[0].0.g, colors[0].0.b));
+    assert_eq!(0.75, colors[0].1);
+    assert_eq!((200, 0, 0), (colors[1].0.r, colors[1].0.g, colors[1].0.b));
+    assert_eq!(0.25, colors[1].1);
+
+    assert!(analysis::dominant_colors(&image, 0).is_err());
+}
+
+#[test]
+fn pyramid_test() {
+    // Left half red, right half transparent blue
+    let mut image = Image::blank(5, 4);
+    for y in 0..4 {
+        for x in 0..5 {
+            let color = if x < 2 {
+                Color::red()
+            } else {
+                Color::rgba(0, 0, 255, 0)
+            };
+            image.set_pixel(x, y, &color).unwrap();
+        }
+    }
+
+    let pyramid = analysis::pyramid(&image, 5);
+    let sizes: Vec<(i32, i32)> = pyramid.iter().map(|i| (i.width, i.height)).collect();
+    assert_eq!(vec![(5, 4), (2, 2), (1, 1)], sizes);
+    let p = pyramid[1].get_pixel(0, 0).unwrap();
+    assert_eq!((255, 0, 255), (p.r, p.b, p.a));
+    // The transparent blue does not tint the red
+    let p = pyramid[2].get_pixel(0, 0).unwrap();
+    assert_eq!((255, 0), (p.r, p.b));
+    assert_eq!(128, p.a);
+
+    assert_eq!(1, analysis::pyramid(&image, 0).len());
+    assert_eq!(1, analysis::pyramid(&Image::blank(0, 8), 3).len());
+    assert_eq!(1, analysis::pyramid(&Image::blank(8, 0), 3).len());
+}
+
+#[test]
+fn connected_components_test() {
+    let mut image = Image::blank(8, 6);
+    // An L shape, a diagonal pair touching by a corner and a single pixel
+    for &(x, y) in [(1, 1), (1, 2), (1, 3), (2, 3), (5, 0), (6, 1), (4, 5)].iter() {
+        image.set_pixel(x, y, &Color::white()).unwrap();
+    }
+
+    let (labels, components) = analysis::connected_components(&image);
+    assert_eq!(48, labels.len());
+    assert_eq!(3, components.len());
+
+    // Labeled in the order of their first pixel
+    let l = &components[1];
+    assert_eq!((2, 2), (labels[8 + 1], labels[3 * 8 + 2]));
+    assert_eq!(2, l.label);
+    assert_eq!(4, l.area);
+    assert_eq!(Rect::new(1, 1, 2, 3), l.bounds);
+    assert_eq!((1.25, 2.25), l.centroid);
+
+    let pair = &components[0];
+    assert_eq!(1, pair.label);
+    assert_eq!(2, pair.area);
+    assert_eq!(Rect::new(5, 0, 2, 2), pair.bounds);
+    assert_eq!((5.5, 0.5), pair.centroid);
+    assert_eq!(1, labels[8 + 6]);
+
+    assert_eq!(1, components[2].area);
+    assert_eq!(3, labels[5 * 8 + 4]);
+    assert_eq!(0, labels[0]);
+
+    let (labels, components) = analysis::connected_components(&Image::blank(3, 3));
+    assert!(labels.iter().all(|&l| l == 0));
+    assert!(components.is_empty());
+}
+
+#[test]
+fn match_template_test() {
+    // Gradient haystack so that every window is different
+    let mut haystack = Image::blank(20, 12);
+    for y in 0..12 {
+        for x in 0..20 {
+            let color = Color::rgb((x * 12) as u8, (y * 20) as u8, ((x * y) % 256) as u8);
+            haystack.set_pixel(x, y, &color).unwrap();
+        }
+    }
+    let mut needle = haystack.clone();
+    editor::crop(&mut needle, 5, 4, PositionMode::TopLeft, 9, 6).unwrap();
+
+    let method = MatchMethod::SquaredDifference;
+    let found = analysis::match_template(&haystack, &needle, method).unwrap();
+    assert_eq!((16, 9), (found.width, found.height));
+    assert_eq!(16 * 9, found.scores.len());
+    assert_eq!((9, 6), found.best);
+    assert_eq!(0.0, found.best_score);
+    assert!(found.scores.iter().all(|&s| s >= 0.0));
+
+    // Still found when the needle is darker and has less contrast
+    for pixel in needle.bytes.chunks_mut(4) {
+        for value in &mut pixel[0..3] {
+            *value = (*value as f32 * 0.5 + 10.0) as u8;
+        }
+    }
+    let method = MatchMethod::NormalizedCrossCorrelation;
+    let found = analysis::match_template(&haystack, &needle, method).unwrap();
+    assert_eq!((9, 6), found.best);
+    assert!(found.best_score > 0.99 && found.best_score <= 1.0);
+    assert!(found.scores.iter().all(|&s| (-1.0..=1.0).contains(&s)));
+
+    // A flat needle has no correlation.
+    let flat = Image::blank(3, 3);
+    let found = analysis::match_template(&haystack, &flat, method).unwrap();
+    assert!(found.scores.iter().all(|&s| s == 0.0));
+
+    assert!(analysis::match_template(&needle, &haystack, method).is_err());
+    assert!(analysis::match_template(&haystack, &Image::blank(0, 3), method).is_err());
+}
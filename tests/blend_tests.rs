@@ -0,0 +1,355 @@
+extern crate raster;
+
+use raster::{editor, BlendMode, Color, Image, Layer, PositionMode};
+
+// Blend a single top pixel over a single base pixel and return the resulting RGB.
+fn blend_pixel(base: Color, top: Color, mode: BlendMode, opacity: f32) -> (u8, u8, u8) {
+    let mut image1 = Image::blank(1, 1);
+    image1.set_pixel(0, 0, &base).unwrap();
+    let mut image2 = Image::blank(1, 1);
+    image2.set_pixel(0, 0, &top).unwrap();
+
+    let blended =
+        editor::blend(&image1, &image2, mode, opacity, PositionMode::TopLeft, 0, 0).unwrap();
+    let pixel = blended.get_pixel(0, 0).unwrap();
+    (pixel.r, pixel.g, pixel.b)
+}
+
+#[test]
+fn darken_test() {
+    let base = Color::rgb(100, 150, 200);
+    let top = Color::rgb(150, 100, 250);
+    assert_eq!(
+        (100, 100, 200),
+        blend_pixel(base.clone(), top.clone(), BlendMode::Darken, 1.0)
+    );
+    assert_eq!(
+        (100, 125, 200),
+        blend_pixel(base, top, BlendMode::Darken, 0.5)
+    );
+}
+
+#[test]
+fn lighten_test() {
+    let base = Color::rgb(100, 150, 200);
+    let top = Color::rgb(150, 100, 250);
+    assert_eq!(
+        (150, 150, 250),
+        blend_pixel(base.clone(), top.clone(), BlendMode::Lighten, 1.0)
+    );
+    assert_eq!(
+        (125, 150, 225),
+        blend_pixel(base, top, BlendMode::Lighten, 0.5)
+    );
+}
+
+#[test]
+fn soft_light_test() {
+    // A mid gray top leaves the base unchanged, black darkens and white lightens
+    let base = Color::rgb(100, 100, 100);
+    let (r, _, _) = blend_pixel(
+        base.clone(),
+        Color::rgb(128, 128, 128),
+        BlendMode::SoftLight,
+        1.0,
+    );
+    assert!((r as i32 - 100).abs() <= 1);
+    let (r, _, _) = blend_pixel(base.clone(), Color::rgb(0, 0, 0), BlendMode::SoftLight, 1.0);
+    assert!(r < 100);
+    let (r, _, _) = blend_pixel(base, Color::rgb(255, 255, 255), BlendMode::SoftLight, 1.0);
+    assert!(r > 100);
+}
+
+#[test]
+fn hard_light_test() {
+    let base = Color::rgb(100, 100, 100);
+    assert_eq!(
+        (0, 0, 0),
+        blend_pixel(base.clone(), Color::rgb(0, 0, 0), BlendMode::HardLight, 1.0)
+    );
+    assert_eq!(
+        (255, 255, 255),
+        blend_pixel(
+            base.clone(),
+            Color::rgb(255, 255, 255),
+            BlendMode::HardLight,
+            1.0
+        )
+    );
+    assert_eq!(
+        (188, 188, 188),
+        blend_pixel(base, Color::rgb(200, 200, 200), BlendMode::HardLight, 1.0)
+    );
+}
+
+#[test]
+fn color_dodge_test() {
+    let base = Color::rgb(0, 100, 200);
+    assert_eq!(
+        (0, 201, 255),
+        blend_pixel(
+            base.clone(),
+            Color::rgb(128, 128, 128),
+            BlendMode::ColorDodge,
+            1.0
+        )
+    );
+    // White top does not divide by zero
+    assert_eq!(
+        (0, 255, 255),
+        blend_pixel(base, Color::rgb(255, 255, 255), BlendMode::ColorDodge, 1.0)
+    );
+}
+
+#[test]
+fn color_burn_test() {
+    let base = Color::rgb(255, 100, 200);
+    assert_eq!(
+        (255, 0, 145),
+        blend_pixel(
+            base.clone(),
+            Color::rgb(128, 128, 128),
+            BlendMode::ColorBurn,
+            1.0
+        )
+    );
+    // Black top does not divide by zero
+    assert_eq!(
+        (255, 0, 0),
+        blend_pixel(base.clone(), Color::rgb(0, 0, 0), BlendMode::ColorBurn, 1.0)
+    );
+    assert_eq!(
+        (255, 50, 100),
+        blend_pixel(base, Color::rgb(0, 0, 0), BlendMode::ColorBurn, 0.5)
+    );
+}
+
+#[test]
+fn exclusion_test() {
+    let base = Color::rgb(0, 255, 100);
+    assert_eq!(
+        (200, 55, 143),
+        blend_pixel(base, Color::rgb(200, 200, 200), BlendMode::Exclusion, 1.0)
+    );
+}
+
+#[test]
+fn subtract_test() {
+    let base = Color::rgb(50, 150, 250);
+    assert_eq!(
+        (0, 50, 150),
+        blend_pixel(base, Color::rgb(100, 100, 100), BlendMode::Subtract, 1.0)
+    );
+}
+
+#[test]
+fn add_test() {
+    let base = Color::rgb(50, 150, 250);
+    assert_eq!(
+        (150, 250, 255),
+        blend_pixel(base, Color::rgb(100, 100, 100), BlendMode::Add, 1.0)
+    );
+}
+
+#[test]
+fn divide_test() {
+    let base = Color::rgb(0, 50, 200);
+    assert_eq!(
+        (0, 128, 255),
+        blend_pixel(
+            base.clone(),
+            Color::rgb(100, 100, 100),
+            BlendMode::Divide,
+            1.0
+        )
+    );
+    // Black top does not divide by zero
+    assert_eq!(
+        (0, 255, 255),
+        blend_pixel(base, Color::rgb(0, 0, 0), BlendMode::Divide, 1.0)
+    );
+}
+
+#[test]
+fn hue_test() {
+    // Hue of the top, saturation and luminosity of the base
+    let base = Color::rgb(200, 100, 100);
+    assert_eq!(
+        (119, 119, 219),
+        blend_pixel(base, Color::rgb(100, 100, 200), BlendMode::Hue, 1.0)
+    );
+}
+
+#[test]
+fn saturation_test() {
+    // A gray top desaturates the base while keeping its luminosity
+    let base = Color::rgb(200, 100, 100);
+    assert_eq!(
+        (130, 130, 130),
+        blend_pixel(base, Color::rgb(50, 50, 50), BlendMode::Saturation, 1.0)
+    );
+}
+
+#[test]
+fn color_test() {
+    // Hue and saturation of the top, luminosity of the base
+    let base = Color::rgb(150, 150, 150);
+    assert_eq!(
+        (220, 120, 120),
+        blend_pixel(base, Color::rgb(200, 100, 100), BlendMode::Color, 1.0)
+    );
+}
+
+#[test]
+fn luminosity_test() {
+    // Luminosity of the top, hue and saturation of the base
+    let base = Color::rgb(100, 100, 200);
+    assert_eq!(
+        (119, 119, 219),
+        blend_pixel(base, Color::rgb(130, 130, 130), BlendMode::Luminosity, 1.0)
+    );
+}
+
+#[test]
+fn blend_with_mask_test() {
+    let image1 = Image::blank(2, 1);
+    let mut image2 = Image::blank(2, 1);
+    editor::fill(&mut image2, Color::white()).unwrap();
+
+    // Left pixel fully masked out, right pixel half visible
+    let mut mask = Image::blank(2, 1);
+    mask.set_pixel(1, 0, &Color::rgb(128, 128, 128)).unwrap();
+
+    let blended = editor::blend_with_mask(
+        &image1,
+        &image2,
+        &mask,
+        BlendMode::Normal,
+        1.0,
+        PositionMode::TopLeft,
+        0,
+        0,
+    )
+    .unwrap();
+    assert_eq!(0, blended.get_pixel(0, 0).unwrap().r);
+    assert_eq!(128, blended.get_pixel(1, 0).unwrap().r);
+}
+
+#[test]
+fn blend_with_mask_dimensions_mismatch() {
+    let image = Image::blank(2, 2);
+    let mask = Image::blank(1, 2);
+    let result = editor::blend_with_mask(
+        &image,
+        &image,
+        &mask,
+        BlendMode::Normal,
+        1.0,
+        PositionMode::TopLeft,
+        0,
+        0,
+    );
+    assert!(matches!(
+        result,
+        Err(raster::error::RasterError::DimensionsMismatch)
+    ));
+}
+
+#[test]
+fn composite_test() {
+    let base = raster::open("tests/in/sample.jpg").unwrap();
+    let watermark = raster::open("tests/in/watermark.png").unwrap();
+
+    let layers = [
+        Layer {
+            image: &watermark,
+            mode: BlendMode::Normal,
+            opacity: 1.0,
+            position: PositionMode::Center,
+            offset_x: 0,
+            offset_y: 0,
+        },
+        Layer {
+            image: &watermark,
+            mode: BlendMode::Multiply,
+            opacity: 0.5,
+            position: PositionMode::TopLeft,
+            offset_x: -20,
+            offset_y: 10,
+        },
+    ];
+    let composite = editor::composite(&base, &layers).unwrap();
+
+    // Same as chaining blends
+    let chained = editor::blend(
+        &base,
+        &watermark,
+        BlendMode::Normal,
+        1.0,
+        PositionMode::Center,
+        0,
+        0,
+    )
+    .unwrap();
+    let chained = editor::blend(
+        &chained,
+        &watermark,
+        BlendMode::Multiply,
+        0.5,
+        PositionMode::TopLeft,
+        -20,
+        10,
+    )
+    .unwrap();
+
+    assert_eq!(chained.bytes, composite.bytes);
+}
+
+#[test]
+fn normal_over_transparent_test() {
+    // Blending on a fully transparent base keeps the top pixel as is
+    let mut image1 = Image::blank(1, 1);
+    image1.set_pixel(0, 0, &Color::rgba(0, 0, 0, 0)).unwrap();
+    let mut image2 = Image::blank(1, 1);
+    image2
+        .set_pixel(0, 0, &Color::rgba(255, 0, 0, 128))
+        .unwrap();
+
+    let blended = editor::blend(
+        &image1,
+        &image2,
+        BlendMode::Multiply,
+        1.0,
+        PositionMode::TopLeft,
+        0,
+        0,
+    )
+    .unwrap();
+    let pixel = blended.get_pixel(0, 0).unwrap();
+    assert_eq!((255, 0, 0, 128), (pixel.r, pixel.g, pixel.b, pixel.a));
+}
+
+#[test]
+fn normal_over_semi_transparent_test() {
+    let mut image1 = Image::blank(1, 1);
+    image1
+        .set_pixel(0, 0, &Color::rgba(0, 0, 255, 128))
+        .unwrap();
+    let mut image2 = Image::blank(1, 1);
+    image2
+        .set_pixel(0, 0, &Color::rgba(255, 0, 0, 128))
+        .unwrap();
+
+    let blended = editor::blend(
+        &image1,
+        &image2,
+        BlendMode::Normal,
+        1.0,
+        PositionMode::TopLeft,
+        0,
+        0,
+    )
+    .unwrap();
+    let pixel = blended.get_pixel(0, 0).unwrap();
+    assert_eq!((170, 0, 85, 192), (pixel.r, pixel.g, pixel.b, pixel.a));
+}
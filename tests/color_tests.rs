@@ -41,3 +41,33 @@ fn hex_test() {
     let color = Color::hex("#FFF");
     assert!(color.is_err());
 }
+
+#[test]
+fn hsl_test() {
+    let hsl = Color::to_hsl(50, 50, 100);
+
+    assert_eq!(240, hsl.0);
+    assert_eq!(33, (hsl.1).round() as i32);
+    assert_eq!(29, (hsl.2).round() as i32);
+
+    // Pure red, white and gray
+    let hsl = Color::to_hsl(255, 0, 0);
+    assert_eq!((0, 100, 50), (hsl.0, hsl.1.round() as i32, hsl.2.round() as i32));
+    let hsl = Color::to_hsl(255, 255, 255);
+    assert_eq!((0, 100), (hsl.1.round() as i32, hsl.2.round() as i32));
+    assert_eq!((0, 0, 0), Color::from_hsl(120, 100.0, 0.0));
+    assert_eq!((128, 128, 128), Color::from_hsl(200, 0.0, 50.2));
+    assert_eq!((0, 255, 0), Color::from_hsl(120, 100.0, 50.0));
+}
+
+#[test]
+fn hsl_conversion_accuracy_test() {
+    for &rgb1 in [(127, 70, 60), (10, 200, 90), (255, 255, 0), (3, 3, 3)].iter() {
+        let hsl = Color::to_hsl(rgb1.0, rgb1.1, rgb1.2);
+        let rgb2 = Color::from_hsl(hsl.0, hsl.1, hsl.2);
+
+        assert!((rgb1.0 as i32 - rgb2.0 as i32).abs() <= 1);
+        assert!((rgb1.1 as i32 - rgb2.1 as i32).abs() <= 1);
+        assert!((rgb1.2 as i32 - rgb2.2 as i32).abs() <= 1);
+    }
+}
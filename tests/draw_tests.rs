@@ -0,0 +1,376 @@
+extern crate raster;
+
+use raster::error::RasterError;
+use raster::{draw, Color, DrawMode, Image, Point, Rect};
+
+#[test]
+fn line_hard() {
+    let mut image = Image::blank(10, 10);
+    let (start, end) = (Point::new(1.0, 1.0), Point::new(8.0, 8.0));
+    draw::line(&mut image, start, end, &Color::red(), 1.0, DrawMode::Hard).unwrap();
+    for i in 1..9 {
+        assert_eq!(255, image.get_pixel(i, i).unwrap().r);
+    }
+    assert_eq!(0, image.get_pixel(2, 1).unwrap().r);
+    assert_eq!(0, image.get_pixel(9, 9).unwrap().r);
+
+    // Clipped outside of the image
+    let (start, end) = (Point::new(-5.0, 0.0), Point::new(20.0, 0.0));
+    draw::line(&mut image, start, end, &Color::blue(), 1.0, DrawMode::Hard).unwrap();
+    assert_eq!(255, image.get_pixel(9, 0).unwrap().b);
+}
+
+#[test]
+fn line_antialiased() {
+    let mut image = Image::blank(10, 5);
+    let (start, end) = (Point::new(0.0, 2.5), Point::new(9.0, 2.5));
+    draw::line(&mut image, start, end, &Color::white(), 1.0, DrawMode::Antialiased).unwrap();
+    // Half way between two rows, both get half of the color
+    let upper = image.get_pixel(5, 2).unwrap();
+    let lower = image.get_pixel(5, 3).unwrap();
+    assert_eq!(128, upper.r);
+    assert_eq!(128, lower.r);
+    assert_eq!(0, image.get_pixel(5, 1).unwrap().r);
+}
+
+#[test]
+fn line_thick() {
+    let mut image = Image::blank(20, 20);
+    let (start, end) = (Point::new(5.0, 10.0), Point::new(15.0, 10.0));
+    draw::line(&mut image, start, end, &Color::green(), 5.0, DrawMode::Hard).unwrap();
+    assert_eq!(255, image.get_pixel(10, 8).unwrap().g);
+    assert_eq!(255, image.get_pixel(10, 12).unwrap().g);
+    assert_eq!(0, image.get_pixel(10, 13).unwrap().g);
+    // Round ends
+    assert_eq!(255, image.get_pixel(3, 10).unwrap().g);
+    assert_eq!(0, image.get_pixel(3, 8).unwrap().g);
+
+    let mut smooth = Image::blank(20, 20);
+    draw::line(&mut smooth, start, end, &Color::green(), 4.0, DrawMode::Antialiased).unwrap();
+    let edge = smooth.get_pixel(10, 12).unwrap().g;
+    assert!(edge > 0 && edge < 255);
+
+    assert!(draw::line(&mut image, start, end, &Color::green(), 0.0, DrawMode::Hard).is_err());
+}
+
+#[test]
+fn line_blends_alpha() {
+    let mut image = Image::blank(3, 1);
+    let (start, end) = (Point::new(0.0, 0.0), Point::new(2.0, 0.0));
+    let color = Color::rgba(255, 255, 255, 128);
+    draw::line(&mut image, start, end, &color, 1.0, DrawMode::Hard).unwrap();
+    let p = image.get_pixel(1, 0).unwrap();
+    assert_eq!((128, 255), (p.r, p.a));
+}
+
+#[test]
+fn line_clipped() {
+    let modes = [
+        (DrawMode::Hard, 1.0),
+        (DrawMode::Antialiased, 1.0),
+        (DrawMode::Antialiased, 3.0),
+    ];
+    for &(mode, thickness) in modes.iter() {
+        // Far off-canvas ends, only the visible part is drawn.
+        let mut image = Image::blank(10, 10);
+        let (start, end) = (Point::new(-1e30, 4.0), Point::new(1e30, 4.0));
+        draw::line(&mut image, start, end, &Color::red(), thickness, mode).unwrap();
+        for x in 0..10 {
+            assert_eq!(255, image.get_pixel(x, 4).unwrap().r);
+        }
+        assert_eq!(0, image.get_pixel(5, 8).unwrap().r);
+
+        // A diagonal a billion pixels long
+        let mut image = Image::blank(10, 10);
+        let (start, end) = (Point::new(-1e9, -1e9), Point::new(1e9, 1e9));
+        draw::line(&mut image, start, end, &Color::red(), thickness, mode).unwrap();
+        for i in 0..10 {
+            assert_eq!(255, image.get_pixel(i, i).unwrap().r);
+        }
+
+        // Nothing to draw
+        let mut image = Image::blank(10, 10);
+        let (start, end) = (Point::new(-50.0, 20.0), Point::new(50.0, 20.0));
+        draw::line(&mut image, start, end, &Color::red(), thickness, mode).unwrap();
+        let (start, end) = (Point::new(f32::NAN, 2.0), Point::new(5.0, 2.0));
+        draw::line(&mut image, start, end, &Color::red(), thickness, mode).unwrap();
+        assert!(image.bytes.chunks(4).all(|p| p[0] == 0));
+    }
+}
+
+#[test]
+fn rect_stroke_and_fill() {
+    let mut image = Image::blank(20, 20);
+    let rect = Rect::new(2, 3, 10, 8);
+    draw::rect(&mut image, rect, Some((Color::red(), 2)), Some(Color::blue())).unwrap();
+
+    let stroke = image.get_pixel(3, 4).unwrap();
+    let inside = image.get_pixel(4, 5).unwrap();
+    let outside = image.get_pixel(12, 5).unwrap();
+    assert_eq!((255, 0), (stroke.r, stroke.b));
+    assert_eq!((0, 255), (inside.r, inside.b));
+    assert_eq!((0, 0), (outside.r, outside.b));
+    assert_eq!(255, image.get_pixel(11, 10).unwrap().r);
+    assert_eq!(0, image.get_pixel(11, 11).unwrap().r);
+}
+
+#[test]
+fn rect_clipped() {
+    let mut image = Image::blank(10, 10);
+    draw::rect(&mut image, Rect::new(-5, -5, 10, 10), None, Some(Color::white())).unwrap();
+    assert_eq!(255, image.get_pixel(4, 4).unwrap().r);
+    assert_eq!(0, image.get_pixel(5, 5).unwrap().r);
+
+    // Stroke only, wider than the rectangle
+    draw::rect(&mut image, Rect::new(6, 6, 3, 3), Some((Color::red(), 2)), None).unwrap();
+    assert_eq!(255, image.get_pixel(7, 7).unwrap().r);
+
+    assert!(draw::rect(&mut image, Rect::new(0, 0, -1, 2), None, None).is_err());
+    assert!(draw::rect(&mut image, Rect::new(0, 0, 2, 2), Some((Color::red(), 0)), None).is_err());
+}
+
+#[test]
+fn circle_fill() {
+    let mut image = Image::blank(21, 21);
+    let center = Point::new(10.0, 10.0);
+    draw::circle(&mut image, center, 5.0, None, Some(Color::red()), DrawMode::Hard).unwrap();
+    assert_eq!(255, image.get_pixel(10, 10).unwrap().r);
+    assert_eq!(255, image.get_pixel(15, 10).unwrap().r);
+    assert_eq!(0, image.get_pixel(16, 10).unwrap().r);
+    assert_eq!(0, image.get_pixel(14, 14).unwrap().r);
+
+    let mut smooth = Image::blank(21, 21);
+    draw::circle(&mut smooth, center, 5.0, None, Some(Color::red()), DrawMode::Antialiased)
+        .unwrap();
+    let edge = smooth.get_pixel(15, 10).unwrap().r;
+    assert!(edge > 100 && edge < 160);
+
+    match draw::circle(&mut image, center, 0.0, None, None, DrawMode::Hard) {
+        Err(RasterError::InvalidShapeRadius(0.0)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn ellipse_stroke() {
+    let mut image = Image::blank(41, 21);
+    let center = Point::new(20.0, 10.0);
+    let stroke = Some((Color::green(), 2.0));
+    let fill = Some(Color::blue());
+    draw::ellipse(&mut image, center, 15.0, 6.0, stroke, fill, DrawMode::Hard).unwrap();
+    let right = image.get_pixel(35, 10).unwrap();
+    let top = image.get_pixel(20, 4).unwrap();
+    let inside = image.get_pixel(20, 10).unwrap();
+    assert_eq!(255, right.g);
+    assert_eq!(255, top.g);
+    assert_eq!((0, 255), (inside.g, inside.b));
+    assert_eq!(0, image.get_pixel(20, 1).unwrap().g);
+
+    let zero = Some((Color::green(), 0.0));
+    assert!(draw::ellipse(&mut image, center, 5.0, 5.0, zero, None, DrawMode::Hard).is_err());
+    match draw::ellipse(&mut image, center, 5.0, -2.0, None, None, DrawMode::Hard) {
+        Err(RasterError::InvalidShapeRadius(-2.0)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn polygon_even_odd() {
+    // Outer square and inner square in the same path, the inner one stays empty
+    let mut image = Image::blank(20, 20);
+    let points = [
+        Point::new(2.0, 2.0),
+        Point::new(17.0, 2.0),
+        Point::new(17.0, 17.0),
+        Point::new(2.0, 17.0),
+        Point::new(2.0, 2.0),
+        Point::new(7.0, 7.0),
+        Point::new(12.0, 7.0),
+        Point::new(12.0, 12.0),
+        Point::new(7.0, 12.0),
+        Point::new(7.0, 7.0),
+    ];
+    draw::polygon(&mut image, &points, None, Some(Color::red())).unwrap();
+    assert_eq!(255, image.get_pixel(4, 4).unwrap().r);
+    assert_eq!(255, image.get_pixel(4, 15).unwrap().r);
+    assert_eq!(0, image.get_pixel(9, 9).unwrap().r);
+    assert_eq!(0, image.get_pixel(18, 9).unwrap().r);
+
+    // Pixels on the edges are half covered
+    let mut smooth = Image::blank(20, 20);
+    let red = Some(Color::red());
+    draw::polygon_with_mode(&mut smooth, &points, None, red, DrawMode::Antialiased).unwrap();
+    assert_eq!(255, smooth.get_pixel(4, 4).unwrap().r);
+    assert_eq!(0, smooth.get_pixel(9, 9).unwrap().r);
+    assert_eq!(0, smooth.get_pixel(18, 9).unwrap().r);
+    for &(x, y) in [(2, 9), (17, 9), (9, 2), (7, 9), (9, 12)].iter() {
+        let r = smooth.get_pixel(x, y).unwrap().r;
+        assert!(r > 100 && r < 160, "{} at ({}, {})", r, x, y);
+    }
+}
+
+#[test]
+fn polygon_triangle_stroke() {
+    let mut image = Image::blank(30, 30);
+    let points = [Point::new(5.0, 25.0), Point::new(15.0, 5.0), Point::new(25.0, 25.0)];
+    let stroke = Some((Color::rgba(0, 255, 0, 128), 3.0));
+    draw::polygon(&mut image, &points, stroke, Some(Color::blue())).unwrap();
+    assert_eq!(255, image.get_pixel(15, 20).unwrap().b);
+    // Translucent stroke painted once, also at the corners
+    let edge = image.get_pixel(15, 25).unwrap();
+    let corner = image.get_pixel(5, 25).unwrap();
+    assert_eq!(128, edge.g);
+    assert_eq!(128, corner.g);
+    assert_eq!(0, image.get_pixel(2, 2).unwrap().g);
+
+    let zero = Some((Color::green(), -1.0));
+    assert!(draw::polygon(&mut image, &points, zero, None).is_err());
+    assert!(draw::polygon(&mut image, &[], None, Some(Color::red())).is_ok());
+}
+
+#[test]
+fn bezier_test() {
+    // A straight quadratic curve covers the same pixels as a line
+    let mut image = Image::blank(20, 10);
+    let points = [Point::new(2.0, 5.0), Point::new(10.0, 5.0), Point::new(18.0, 5.0)];
+    draw::bezier(&mut image, &points, &Color::white(), 4.0).unwrap();
+    assert_eq!(255, image.get_pixel(10, 5).unwrap().r);
+    assert_eq!(255, image.get_pixel(10, 6).unwrap().r);
+    assert_eq!(0, image.get_pixel(10, 8).unwrap().r);
+    let edge = image.get_pixel(10, 7).unwrap().r;
+    assert!(edge > 0 && edge < 255);
+
+    // Cubic curve through its end points, away from the middle control points
+    let mut image = Image::blank(40, 40);
+    let points = [
+        Point::new(5.0, 35.0),
+        Point::new(5.0, 5.0),
+        Point::new(35.0, 5.0),
+        Point::new(35.0, 35.0),
+    ];
+    draw::bezier(&mut image, &points, &Color::red(), 2.0).unwrap();
+    assert!(image.get_pixel(5, 35).unwrap().r > 100);
+    assert!(image.get_pixel(35, 35).unwrap().r > 100);
+    // Middle of the curve at y = 12.5
+    assert!(image.get_pixel(20, 12).unwrap().r > 200);
+    assert!(image.get_pixel(20, 13).unwrap().r > 200);
+    assert_eq!(0, image.get_pixel(5, 5).unwrap().r);
+
+    assert!(draw::bezier(&mut image, &points[0..2], &Color::red(), 2.0).is_err());
+    assert!(draw::bezier(&mut image, &points, &Color::red(), 0.0).is_err());
+}
+
+#[test]
+fn arc_test() {
+    // Lower half of a circle, clockwise from the right to the left
+    let mut image = Image::blank(41, 41);
+    let center = Point::new(20.0, 20.0);
+    draw::arc(&mut image, center, 15.0, 0.0, 180.0, &Color::red(), 3.0).unwrap();
+    assert_eq!(255, image.get_pixel(20, 35).unwrap().r);
+    assert_eq!(0, image.get_pixel(20, 5).unwrap().r);
+    assert!(image.get_pixel(35, 20).unwrap().r > 100);
+
+    // Wraps around when the end is before the start
+    let mut image = Image::blank(41, 41);
+    draw::arc(&mut image, center, 15.0, 180.0, 0.0, &Color::red(), 3.0).unwrap();
+    assert_eq!(255, image.get_pixel(20, 5).unwrap().r);
+    assert_eq!(0, image.get_pixel(20, 35).unwrap().r);
+
+    match draw::arc(&mut image, center, 0.0, 0.0, 90.0, &Color::red(), 1.0) {
+        Err(RasterError::InvalidShapeRadius(0.0)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(draw::arc(&mut image, center, 5.0, 0.0, 90.0, &Color::red(), 0.0).is_err());
+}
+
+#[test]
+fn arc_huge_radius() {
+    // Almost straight down through the image, from a center far to the right
+    let mut image = Image::blank(10, 10);
+    let center = Point::new(5e6 + 5.0, 5.0);
+    draw::arc(&mut image, center, 5e6, 179.0, 181.0, &Color::red(), 3.0).unwrap();
+    for y in 0..10 {
+        assert_eq!(255, image.get_pixel(5, y).unwrap().r);
+    }
+    assert_eq!(0, image.get_pixel(0, 5).unwrap().r);
+
+    let mut image = Image::blank(10, 10);
+    let center = Point::new(5.0, 1e30);
+    draw::arc(&mut image, center, 1e30, 0.0, 360.0, &Color::red(), 1.0).unwrap();
+    let fill = Some(Color::blue());
+    draw::pie(&mut image, center, 5e6, 260.0, 280.0, None, fill, DrawMode::Antialiased).unwrap();
+}
+
+#[test]
+fn pie_test() {
+    let mut image = Image::blank(41, 41);
+    let center = Point::new(20.0, 20.0);
+    draw::pie(&mut image, center, 15.0, 0.0, 90.0, None, Some(Color::blue()), DrawMode::Hard)
+        .unwrap();
+    // Only the lower right quarter
+    assert_eq!(255, image.get_pixel(26, 26).unwrap().b);
+    assert_eq!(0, image.get_pixel(14, 26).unwrap().b);
+    assert_eq!(0, image.get_pixel(26, 14).unwrap().b);
+
+    let mut image = Image::blank(41, 41);
+    let fill = Some(Color::blue());
+    draw::pie(&mut image, center, 15.0, 0.0, 360.0, None, fill, DrawMode::Hard).unwrap();
+    assert_eq!(255, image.get_pixel(14, 14).unwrap().b);
+    assert_eq!(255, image.get_pixel(26, 26).unwrap().b);
+    assert_eq!(0, image.get_pixel(2, 2).unwrap().b);
+
+    // Antialiased, the rim and the straight edges are half covered
+    let mut image = Image::blank(41, 41);
+    let fill = Some(Color::blue());
+    draw::pie(&mut image, center, 15.0, 0.0, 90.0, None, fill, DrawMode::Antialiased).unwrap();
+    assert_eq!(255, image.get_pixel(26, 26).unwrap().b);
+    assert_eq!(0, image.get_pixel(14, 26).unwrap().b);
+    for &(x, y) in [(29, 32), (32, 29), (27, 20), (20, 27)].iter() {
+        let b = image.get_pixel(x, y).unwrap().b;
+        assert!(b > 100 && b < 160, "{} at ({}, {})", b, x, y);
+    }
+
+    match draw::pie(&mut image, center, -1.0, 0.0, 90.0, None, None, DrawMode::Hard) {
+        Err(RasterError::InvalidShapeRadius(-1.0)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn flood_fill_test() {
+    // A ring splits the image into an inside and an outside
+    let mut image = Image::blank(30, 30);
+    let center = Point::new(15.0, 15.0);
+    let stroke = Some((Color::white(), 2.0));
+    draw::circle(&mut image, center, 10.0, stroke, None, DrawMode::Hard).unwrap();
+
+    draw::flood_fill(&mut image, 15, 15, &Color::red(), 0).unwrap();
+    assert_eq!(255, image.get_pixel(15, 15).unwrap().r);
+    assert_eq!(255, image.get_pixel(10, 12).unwrap().r);
+    assert_eq!(0, image.get_pixel(1, 1).unwrap().r);
+    let ring = image.get_pixel(25, 15).unwrap();
+    assert_eq!((255, 255), (ring.r, ring.g));
+
+    draw::flood_fill(&mut image, 0, 0, &Color::blue(), 0).unwrap();
+    assert_eq!(255, image.get_pixel(29, 29).unwrap().b);
+    assert_eq!(255, image.get_pixel(1, 15).unwrap().b);
+    assert_eq!(0, image.get_pixel(15, 15).unwrap().b);
+
+    assert!(draw::flood_fill(&mut image, 30, 0, &Color::blue(), 0).is_err());
+}
+
+#[test]
+fn flood_fill_tolerance() {
+    let mut image = Image::blank(4, 1);
+    for x in 0..4 {
+        let v = x as u8 * 10;
+        image.set_pixel(x, 0, &Color::rgb(v, v, v)).unwrap();
+    }
+    let mut strict = image.clone();
+    draw::flood_fill(&mut strict, 0, 0, &Color::red(), 15).unwrap();
+    assert_eq!(0, strict.get_pixel(1, 0).unwrap().g);
+    assert_eq!(20, strict.get_pixel(2, 0).unwrap().g);
+
+    draw::flood_fill(&mut image, 0, 0, &Color::red(), 30).unwrap();
+    assert_eq!(0, image.get_pixel(3, 0).unwrap().g);
+}
@@ -0,0 +1,572 @@
+extern crate raster;
+
+use raster::error::RasterError;
+use raster::{editor, Alignment, BlendMode, Color, Direction, Image, PasteMode, PositionMode};
+use raster::{PackOptions, Rect, ResizeMode, StackMode};
+
+#[test]
+fn add_border_sides_test() {
+    let mut image = Image::blank(2, 3);
+    editor::fill(&mut image, Color::red()).unwrap();
+
+    editor::add_border_sides(&mut image, 1, 2, 3, 4, Color::blue()).unwrap();
+    assert_eq!((8, 7), (image.width, image.height));
+    for (x, y) in [(4, 1), (5, 3)] {
+        assert_eq!(255, image.get_pixel(x, y).unwrap().r);
+    }
+    for (x, y) in [(0, 0), (3, 1), (6, 1), (4, 0), (4, 4), (7, 6)] {
+        let p = image.get_pixel(x, y).unwrap();
+        assert_eq!((0, 255), (p.r, p.b));
+    }
+
+    match editor::add_border(&mut image, -1, Color::white()) {
+        Err(RasterError::InvalidBorder(-1)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    match editor::add_border_sides(&mut image, 1, 2, -3, -4, Color::white()) {
+        Err(RasterError::InvalidBorder(-3)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn append_test() {
+    let mut red = Image::blank(2, 4);
+    editor::fill(&mut red, Color::red()).unwrap();
+    let mut blue = Image::blank(3, 2);
+    editor::fill(&mut blue, Color::blue()).unwrap();
+
+    let strip =
+        editor::append(&[&red, &blue], Direction::Horizontal, Alignment::End, 1, Color::white())
+            .unwrap();
+    assert_eq!((6, 4), (strip.width, strip.height));
+    assert_eq!(255, strip.get_pixel(1, 0).unwrap().r);
+    assert_eq!(255, strip.get_pixel(2, 3).unwrap().g);
+    assert_eq!(255, strip.get_pixel(3, 1).unwrap().g);
+    let p = strip.get_pixel(5, 2).unwrap();
+    assert_eq!((0, 255), (p.r, p.b));
+
+    let column =
+        editor::append(&[&red, &blue], Direction::Vertical, Alignment::Center, 0, Color::white())
+            .unwrap();
+    assert_eq!((3, 6), (column.width, column.height));
+    assert_eq!(255, column.get_pixel(2, 0).unwrap().g);
+    assert_eq!(255, column.get_pixel(0, 5).unwrap().b);
+
+    assert!(editor::append(&[], Direction::Vertical, Alignment::Start, 0, Color::white()).is_err());
+    match editor::append(&[&red], Direction::Vertical, Alignment::Start, -2, Color::white()) {
+        Err(RasterError::InvalidSpacing(-2)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn crop_aspect_test() {
+    let mut image = Image::blank(40, 20);
+    image.set_pixel(39, 0, &Color::white()).unwrap();
+
+    let mut square = image.clone();
+    editor::crop_aspect(&mut square, 1, 1, PositionMode::TopRight).unwrap();
+    assert_eq!((20, 20), (square.width, square.height));
+    assert_eq!(255, square.get_pixel(19, 0).unwrap().r);
+
+    let mut wide = image.clone();
+    editor::crop_aspect(&mut wide, 16, 3, PositionMode::Center).unwrap();
+    assert_eq!((40, 8), (wide.width, wide.height));
+
+    match editor::crop_aspect(&mut image, 16, 0, PositionMode::Center) {
+        Err(RasterError::InvalidDimensions(16, 0)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn crop_circle_test() {
+    let mut image = Image::blank(20, 20);
+    editor::fill(&mut image, Color::red()).unwrap();
+
+    // Circle partly outside the image
+    editor::crop_circle(&mut image, (16, 10), 6).unwrap();
+    assert_eq!((12, 12), (image.width, image.height));
+    assert_eq!(0, image.get_pixel(0, 0).unwrap().a);
+    assert_eq!(0, image.get_pixel(11, 11).unwrap().a);
+    assert_eq!(0, image.get_pixel(10, 6).unwrap().a);
+    let p = image.get_pixel(6, 6).unwrap();
+    assert_eq!((255, 255), (p.r, p.a));
+    let a = image.get_pixel(0, 6).unwrap().a;
+    assert!(a > 0 && a < 255);
+
+    match editor::crop_circle(&mut image, (0, 0), 0) {
+        Err(RasterError::InvalidShapeRadius(0.0)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn crop_ellipse_test() {
+    let mut image = Image::blank(20, 20);
+    editor::crop_ellipse(&mut image, (10, 10), 8, 4).unwrap();
+    assert_eq!((16, 8), (image.width, image.height));
+    assert_eq!(255, image.get_pixel(8, 4).unwrap().a);
+    assert_eq!(255, image.get_pixel(1, 4).unwrap().a);
+    assert_eq!(0, image.get_pixel(0, 0).unwrap().a);
+
+    match editor::crop_ellipse(&mut image, (10, 10), 8, -2) {
+        Err(RasterError::InvalidShapeRadius(-2.0)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn crop_invalid_dimensions() {
+    let mut image = Image::blank(10, 10);
+    match editor::crop(&mut image, 0, 5, PositionMode::TopLeft, 0, 0) {
+        Err(RasterError::InvalidDimensions(0, 5)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn resize_invalid_dimensions() {
+    let mut image = Image::blank(10, 10);
+    match editor::resize(&mut image, -1, 5, ResizeMode::Exact) {
+        Err(RasterError::InvalidDimensions(-1, 5)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn crop_smart_test() {
+    // A detailed square on a flat background
+    let mut image = Image::blank(40, 20);
+    for y in 4..10 {
+        for x in 28..34 {
+            let value = if (x + y) % 2 == 0 { 255 } else { 0 };
+            image.set_pixel(x, y, &Color::rgb(value, value, value)).unwrap();
+        }
+    }
+
+    let mut cropped = image.clone();
+    editor::crop_smart(&mut cropped, 10, 10).unwrap();
+    assert_eq!((10, 10), (cropped.width, cropped.height));
+    let white = cropped.bytes.chunks(4).filter(|p| p[0] == 255).count();
+    assert_eq!(18, white);
+
+    // Ties go to the window closest to the center
+    let mut flat = Image::blank(40, 20);
+    flat.set_pixel(20, 10, &Color::white()).unwrap();
+    editor::crop_smart(&mut flat, 60, 10).unwrap();
+    assert_eq!((40, 10), (flat.width, flat.height));
+    assert_eq!(255, flat.get_pixel(20, 5).unwrap().r);
+
+    match editor::crop_smart(&mut image, 0, 10) {
+        Err(RasterError::InvalidDimensions(0, 10)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn drop_shadow_test() {
+    let mut image = Image::blank(8, 8);
+    editor::fill(&mut image, Color::red()).unwrap();
+
+    editor::drop_shadow(&mut image, 3, -1, 2, &Color::blue()).unwrap();
+    // Margin of 2 around the shadow
+    assert_eq!((8 + 5, 8 + 3 + 1), (image.width, image.height));
+    let p = image.get_pixel(0, 3).unwrap();
+    assert_eq!((255, 255), (p.r, p.a));
+    assert_eq!(0, image.get_pixel(0, 0).unwrap().a);
+    // Shadow next to the image
+    let p = image.get_pixel(8, 5).unwrap();
+    assert_eq!((0, 255, 255), (p.r, p.b, p.a));
+    let a = image.get_pixel(11, 5).unwrap().a;
+    assert!(a > 0 && a < 255);
+
+    match editor::drop_shadow(&mut image, 0, 0, -1, &Color::black()) {
+        Err(RasterError::InvalidRadius(-1)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn montage_test() {
+    let mut red = Image::blank(20, 10);
+    editor::fill(&mut red, Color::red()).unwrap();
+    let mut blue = Image::blank(5, 10);
+    editor::fill(&mut blue, Color::blue()).unwrap();
+
+    let sheet = editor::montage(&[&red, &blue, &red], 2, (10, 10), 2, Color::white()).unwrap();
+    assert_eq!((2 + 12 * 2, 2 + 12 * 2), (sheet.width, sheet.height));
+    // Red fits as 10x5 in the middle of its cell
+    assert_eq!(255, sheet.get_pixel(3, 3).unwrap().b);
+    let p = sheet.get_pixel(6, 7).unwrap();
+    assert_eq!((255, 0), (p.r, p.b));
+    // Blue fits as 5x10
+    let p = sheet.get_pixel(19, 7).unwrap();
+    assert_eq!((0, 255), (p.r, p.b));
+    assert_eq!(255, sheet.get_pixel(15, 7).unwrap().r);
+    // Third image on the second row
+    assert_eq!(0, sheet.get_pixel(6, 19).unwrap().b);
+
+    match editor::montage(&[&red], 0, (10, 10), 2, Color::white()) {
+        Err(RasterError::InvalidColumns(0)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    match editor::montage(&[&red], 1, (10, 10), -2, Color::white()) {
+        Err(RasterError::InvalidSpacing(-2)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(editor::montage(&[&red], 1, (10, 0), 2, Color::white()).is_err());
+}
+
+#[test]
+fn pack_test() {
+    let mut tall = Image::blank(4, 10);
+    editor::fill(&mut tall, Color::red()).unwrap();
+    let small = Image::blank(5, 4);
+    let wide = Image::blank(9, 3);
+
+    let options = PackOptions::new(20).padding(1);
+    let (sheet, rects) = editor::pack(&[&small, &wide, &tall], &options).unwrap();
+    assert_eq!(
+        vec![Rect::new(6, 1, 5, 4), Rect::new(1, 12, 9, 3), Rect::new(1, 1, 4, 10)],
+        rects
+    );
+    assert_eq!((12, 16), (sheet.width, sheet.height));
+    let p = sheet.get_pixel(1, 1).unwrap();
+    assert_eq!((255, 255), (p.r, p.a));
+    assert_eq!(0, sheet.get_pixel(0, 0).unwrap().a);
+    assert_eq!(255, sheet.get_pixel(6, 1).unwrap().a);
+
+    let options = options.power_of_two(true);
+    let (sheet, _) = editor::pack(&[&small, &wide, &tall], &options).unwrap();
+    assert_eq!((16, 16), (sheet.width, sheet.height));
+
+    match editor::pack(&[&wide], &PackOptions::new(10).padding(1)) {
+        Err(RasterError::InvalidSheetWidth(10)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    match editor::pack(&[&wide], &PackOptions::new(100).padding(-1)) {
+        Err(RasterError::InvalidSpacing(-1)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(editor::pack(&[], &PackOptions::new(10)).is_err());
+}
+
+#[test]
+fn pad_test() {
+    let mut image = Image::blank(2, 2);
+    editor::fill(&mut image, Color::red()).unwrap();
+
+    editor::pad(&mut image, 6, 4, PositionMode::BottomRight, Color::blue()).unwrap();
+    assert_eq!((6, 4), (image.width, image.height));
+    assert_eq!(255, image.get_pixel(4, 2).unwrap().r);
+    assert_eq!(255, image.get_pixel(5, 3).unwrap().r);
+    assert_eq!(255, image.get_pixel(3, 3).unwrap().b);
+    assert_eq!(255, image.get_pixel(0, 0).unwrap().b);
+
+    let mut image = Image::blank(2, 2);
+    editor::pad(&mut image, 5, 3, PositionMode::Center, Color::blue()).unwrap();
+    assert_eq!(0, image.get_pixel(1, 0).unwrap().b);
+    assert_eq!(255, image.get_pixel(1, 2).unwrap().b);
+
+    match editor::pad(&mut image, 4, 3, PositionMode::Center, Color::blue()) {
+        Err(RasterError::InvalidDimensions(4, 3)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn paste_replace() {
+    let mut base = Image::blank(4, 4);
+    let mut top = Image::blank(2, 2);
+    editor::fill(&mut top, Color::rgba(255, 0, 0, 100)).unwrap();
+
+    editor::paste(&mut base, &top, 3, -1, PasteMode::Replace).unwrap();
+    let p = base.get_pixel(3, 0).unwrap();
+    assert_eq!((255, 100), (p.r, p.a));
+    assert_eq!(0, base.get_pixel(3, 1).unwrap().r);
+    assert_eq!(0, base.get_pixel(2, 0).unwrap().r);
+
+    // Completely outside
+    let before = base.clone();
+    editor::paste(&mut base, &top, 10, 10, PasteMode::Replace).unwrap();
+    assert_eq!(before.bytes, base.bytes);
+}
+
+#[test]
+fn paste_over() {
+    let mut base = Image::blank(3, 1);
+    editor::fill(&mut base, Color::blue()).unwrap();
+    let mut top = Image::blank(3, 1);
+    top.set_pixel(0, 0, &Color::rgba(255, 0, 0, 255)).unwrap();
+    top.set_pixel(1, 0, &Color::rgba(255, 0, 0, 0)).unwrap();
+    top.set_pixel(2, 0, &Color::rgba(255, 0, 0, 128)).unwrap();
+
+    let blended =
+        editor::blend(&base, &top, BlendMode::Normal, 1.0, PositionMode::TopLeft, 0, 0).unwrap();
+    editor::paste(&mut base, &top, 0, 0, PasteMode::Over).unwrap();
+    assert_eq!(blended.bytes, base.bytes);
+    assert_eq!(255, base.get_pixel(1, 0).unwrap().b);
+}
+
+#[test]
+fn resize_liquid_test() {
+    // A checkered block on the right of a flat background
+    let mut image = Image::blank(30, 12);
+    for y in 0..12 {
+        for x in 20..26 {
+            let value = if (x + y) % 2 == 0 { 255 } else { 0 };
+            image.set_pixel(x, y, &Color::rgb(value, value, value)).unwrap();
+        }
+    }
+    let count = |image: &Image| image.bytes.chunks(4).filter(|p| p[0] == 255).count();
+
+    let mut smaller = image.clone();
+    editor::resize_liquid(&mut smaller, 12, 12).unwrap();
+    assert_eq!((12, 12), (smaller.width, smaller.height));
+    assert_eq!(36, count(&smaller));
+    editor::resize_liquid(&mut smaller, 12, 8).unwrap();
+    assert_eq!((12, 8), (smaller.width, smaller.height));
+
+    let mut larger = image.clone();
+    editor::resize_liquid(&mut larger, 70, 12).unwrap();
+    assert_eq!((70, 12), (larger.width, larger.height));
+    assert_eq!(36, count(&larger));
+
+    match editor::resize_liquid(&mut image, 0, 8) {
+        Err(RasterError::InvalidDimensions(0, 8)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn resize_nine_patch_test() {
+    // Red frame of 2 pixels around a blue center, with a green top left corner
+    let mut image = Image::blank(6, 6);
+    editor::fill(&mut image, Color::red()).unwrap();
+    for y in 2..4 {
+        for x in 2..4 {
+            image.set_pixel(x, y, &Color::blue()).unwrap();
+        }
+    }
+    image.set_pixel(0, 0, &Color::green()).unwrap();
+
+    editor::resize_nine_patch(&mut image, (2, 2, 2, 2), 20, 10).unwrap();
+    assert_eq!((20, 10), (image.width, image.height));
+    assert_eq!(255, image.get_pixel(0, 0).unwrap().g);
+    assert_eq!(255, image.get_pixel(1, 0).unwrap().r);
+    assert_eq!(255, image.get_pixel(10, 1).unwrap().r);
+    assert_eq!(255, image.get_pixel(19, 9).unwrap().r);
+    assert_eq!(255, image.get_pixel(1, 5).unwrap().r);
+    let p = image.get_pixel(10, 5).unwrap();
+    assert_eq!((0, 255), (p.r, p.b));
+    let p = image.get_pixel(2, 2).unwrap();
+    assert_eq!((0, 255), (p.r, p.b));
+
+    match editor::resize_nine_patch(&mut image, (2, 2, 2, 2), 3, 10) {
+        Err(RasterError::InvalidDimensions(3, 10)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn round_corners_test() {
+    let mut image = Image::blank(20, 10);
+    editor::fill(&mut image, Color::red()).unwrap();
+
+    editor::round_corners(&mut image, 4).unwrap();
+    for (x, y) in [(0, 0), (19, 0), (0, 9), (19, 9)] {
+        assert_eq!(0, image.get_pixel(x, y).unwrap().a);
+    }
+    for (x, y) in [(4, 0), (0, 4), (10, 5), (19, 5), (15, 9)] {
+        assert_eq!(255, image.get_pixel(x, y).unwrap().a);
+    }
+    // Partly covered edge pixel
+    let a = image.get_pixel(1, 1).unwrap().a;
+    assert!(a > 0 && a < 255);
+
+    match editor::round_corners(&mut image, -1) {
+        Err(RasterError::InvalidRadius(-1)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn thumbnails_test() {
+    let mut image = Image::blank(80, 40);
+    editor::fill(&mut image, Color::red()).unwrap();
+
+    let sizes = [
+        (10, 10, ResizeMode::Fill),
+        (40, 40, ResizeMode::Fit),
+        (20, 0, ResizeMode::ExactWidth),
+        (30, 5, ResizeMode::Exact),
+    ];
+    let thumbnails = editor::thumbnails(&image, &sizes).unwrap();
+    let dimensions: Vec<(i32, i32)> = thumbnails.iter().map(|t| (t.width, t.height)).collect();
+    assert_eq!(vec![(10, 10), (40, 20), (20, 10), (30, 5)], dimensions);
+    for thumbnail in &thumbnails {
+        let p = thumbnail.get_pixel(thumbnail.width / 2, thumbnail.height / 2).unwrap();
+        assert_eq!((255, 0), (p.r, p.b));
+    }
+
+    assert!(editor::thumbnails(&image, &[(0, 10, ResizeMode::Exact)]).is_err());
+
+    let sizes = [(10, 10, ResizeMode::Fit)];
+    match editor::thumbnails(&Image::blank(0, 8), &sizes) {
+        Err(RasterError::InvalidDimensions(0, 8)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn tile_test() {
+    // 3x1 image with a red pixel on the left
+    let mut image = Image::blank(3, 1);
+    image.set_pixel(0, 0, &Color::red()).unwrap();
+    let red = |image: &Image| -> Vec<i32> {
+        (0..image.width)
+            .filter(|&x| image.get_pixel(x, 0).unwrap().r == 255)
+            .collect()
+    };
+
+    let pattern = editor::tile(&image, 8, 2, (1, 0), false).unwrap();
+    assert_eq!((8, 2), (pattern.width, pattern.height));
+    assert_eq!(vec![1, 4, 7], red(&pattern));
+    assert_eq!(255, pattern.get_pixel(4, 1).unwrap().r);
+
+    let mirrored = editor::tile(&image, 8, 1, (0, 0), true).unwrap();
+    assert_eq!(vec![0, 5, 6], red(&mirrored));
+
+    assert!(editor::tile(&image, 0, 2, (0, 0), false).is_err());
+}
+
+#[test]
+fn watermark_tile_test() {
+    let image = Image::blank(20, 10);
+    let mut mark = Image::blank(2, 2);
+    editor::fill(&mut mark, Color::white()).unwrap();
+
+    // Tiles every 4 pixels from 1 pixel in, odd rows shifted by 2 pixels
+    let tiled = editor::watermark_tile(&image, &mark, 2, 0.0, 1.0).unwrap();
+    assert_eq!(255, tiled.get_pixel(1, 1).unwrap().r);
+    assert_eq!(255, tiled.get_pixel(5, 2).unwrap().r);
+    assert_eq!(0, tiled.get_pixel(3, 1).unwrap().r);
+    assert_eq!(0, tiled.get_pixel(1, 3).unwrap().r);
+    assert_eq!(255, tiled.get_pixel(0, 5).unwrap().r);
+    assert_eq!(255, tiled.get_pixel(3, 5).unwrap().r);
+
+    let faint = editor::watermark_tile(&image, &mark, 2, 0.0, 0.5).unwrap();
+    assert_eq!(128, faint.get_pixel(1, 1).unwrap().r);
+
+    let rotated = editor::watermark_tile(&image, &mark, 2, 45.0, 1.0).unwrap();
+    assert_eq!((20, 10), (rotated.width, rotated.height));
+
+    match editor::watermark_tile(&image, &mark, -2, 0.0, 1.0) {
+        Err(RasterError::InvalidSpacing(-2)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn stack_test() {
+    let frames: Vec<Image> = [10u8, 40, 20, 250]
+        .iter()
+        .map(|&v| {
+            let mut frame = Image::blank(3, 2);
+            editor::fill(&mut frame, Color::rgba(v, 255 - v, 0, 255)).unwrap();
+            frame
+        })
+        .collect();
+    let frames: Vec<&Image> = frames.iter().collect();
+
+    let expected = [
+        (StackMode::Mean, 80),
+        (StackMode::Median, 30),
+        (StackMode::Max, 250),
+        (StackMode::Min, 10),
+    ];
+    for &(mode, value) in expected.iter() {
+        let stacked = editor::stack(&frames, mode).unwrap();
+        assert_eq!((3, 2), (stacked.width, stacked.height));
+        let pixel = stacked.get_pixel(2, 1).unwrap();
+        assert_eq!(value, pixel.r);
+        assert_eq!(255, pixel.a);
+    }
+    // Odd number of frames
+    let stacked = editor::stack(&frames[0..3], StackMode::Median).unwrap();
+    assert_eq!(20, stacked.get_pixel(0, 0).unwrap().r);
+
+    let other = Image::blank(2, 2);
+    assert!(editor::stack(&[frames[0], &other], StackMode::Mean).is_err());
+    assert!(editor::stack(&[], StackMode::Mean).is_err());
+}
+
+#[test]
+fn fuse_exposures_test() {
+    // A dark shot with details on the left and a bright one with details on the right
+    let mut dark = Image::blank(16, 8);
+    let mut bright = Image::blank(16, 8);
+    for y in 0..8 {
+        for x in 0..16 {
+            let stripe = if (x + y) % 2 == 0 { 60 } else { 0 };
+            let (d, b) = if x < 8 { (60 + stripe, 255) } else { (0, 140 + stripe) };
+            dark.set_pixel(x, y, &Color::rgb(d, d / 2, 0)).unwrap();
+            bright.set_pixel(x, y, &Color::rgb(b, b / 2, 0)).unwrap();
+        }
+    }
+
+    let fused = editor::fuse_exposures(&[&dark, &bright]).unwrap();
+    assert_eq!((16, 8), (fused.width, fused.height));
+    // Each side mostly comes from the shot with details there.
+    let left = fused.get_pixel(2, 4).unwrap().r as i32;
+    assert!((left - dark.get_pixel(2, 4).unwrap().r as i32).abs() < 40);
+    let right = fused.get_pixel(13, 4).unwrap().r as i32;
+    assert!((right - bright.get_pixel(13, 4).unwrap().r as i32).abs() < 40);
+    assert_eq!(255, fused.get_pixel(8, 4).unwrap().a);
+
+    // A single shot is kept as it is.
+    let same = editor::fuse_exposures(&[&dark]).unwrap();
+    assert_eq!(dark.bytes, same.bytes);
+
+    assert!(editor::fuse_exposures(&[&dark, &Image::blank(4, 4)]).is_err());
+    assert!(editor::fuse_exposures(&[]).is_err());
+}
+
+#[test]
+fn stitch_test() {
+    let mut left = Image::blank(10, 4);
+    editor::fill(&mut left, Color::rgb(0, 0, 0)).unwrap();
+    let mut right = Image::blank(12, 4);
+    editor::fill(&mut right, Color::rgb(200, 0, 0)).unwrap();
+
+    let stitched = editor::stitch(&[&left, &right], 4, 2).unwrap();
+    assert_eq!((18, 4), (stitched.width, stitched.height));
+    let reds: Vec<u8> = (5..10).map(|x| stitched.get_pixel(x, 2).unwrap().r).collect();
+    assert_eq!(vec![0, 0, 50, 150, 200], reds);
+    assert_eq!(200, stitched.get_pixel(17, 3).unwrap().r);
+
+    // Hard seam in the middle of the overlap
+    let stitched = editor::stitch(&[&left, &right, &left], 4, 0).unwrap();
+    assert_eq!(24, stitched.width);
+    assert_eq!(0, stitched.get_pixel(7, 0).unwrap().r);
+    assert_eq!(200, stitched.get_pixel(8, 0).unwrap().r);
+    assert_eq!(200, stitched.get_pixel(15, 0).unwrap().r);
+    assert_eq!(0, stitched.get_pixel(16, 0).unwrap().r);
+
+    assert!(editor::stitch(&[&left, &Image::blank(10, 5)], 4, 2).is_err());
+    match editor::stitch(&[&left, &right], 11, 2) {
+        Err(RasterError::InvalidOverlap(11)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    match editor::stitch(&[&left, &right], -1, 2) {
+        Err(RasterError::InvalidOverlap(-1)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    match editor::stitch(&[&left, &right], 4, -3) {
+        Err(RasterError::InvalidOverlap(-3)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(editor::stitch(&[], 4, 2).is_err());
+}
@@ -1,6 +1,10 @@
 extern crate raster;
 
-use raster::{filter, Orientation};
+use raster::error::RasterError;
+use raster::{compare, editor, filter, transform};
+use raster::{Channel, Color, Direction, DitherPalette, EdgeMode, GradientStop, GrayscaleMode};
+use raster::{HalftoneShape, Image, Kernel, NoiseType, Orientation, ThresholdMode};
+use raster::{BlurMode, TransformMode, WhiteBalanceMode};
 
 #[test]
 fn brightness_test() {
@@ -43,3 +47,1027 @@ fn sobel_d2_test() {
     filter::sobel(&mut image, Orientation::DiagonalDown).unwrap();
     raster::save(&image, "tests/out/test_filter_sobel_d2.jpg").unwrap();
 }
+
+#[test]
+fn blur_box_matches_convolve() {
+    let image = raster::open("tests/in/sample.jpg").unwrap();
+
+    let mut expected = image.clone();
+    filter::convolve(&mut expected, &Kernel::new(&[[1.0; 3]; 3]).unwrap().divisor(9.0)).unwrap();
+
+    let mut blurred = image.clone();
+    filter::blur_box(&mut blurred, 1).unwrap();
+    assert!(compare::equal(&expected, &blurred).unwrap());
+
+    let mut blurred = image.clone();
+    filter::blur(&mut blurred, BlurMode::Box).unwrap();
+    assert!(compare::equal(&expected, &blurred).unwrap());
+}
+
+#[test]
+fn blur_box_large_radius() {
+    let mut image = Image::blank(3, 1);
+    image.set_pixel(0, 0, &Color::rgba(0, 0, 0, 255)).unwrap();
+    image.set_pixel(1, 0, &Color::rgba(90, 0, 0, 255)).unwrap();
+    image.set_pixel(2, 0, &Color::rgba(180, 0, 0, 255)).unwrap();
+
+    // Radius far larger than the image, the edges are repeated:
+    // (0 x 6 + 90 + 180 x 6) / 13 and (0 x 7 + 90 + 180 x 5) / 13
+    filter::blur_box(&mut image, 6).unwrap();
+    assert_eq!(90, image.get_pixel(1, 0).unwrap().r);
+    assert_eq!(76, image.get_pixel(0, 0).unwrap().r);
+
+    assert!(filter::blur_box(&mut image, -1).is_err());
+}
+
+#[test]
+fn sharpen_matches_convolve() {
+    let image = raster::open("tests/in/sample.jpg").unwrap();
+
+    let mut expected = image.clone();
+    let kernel = Kernel::new(&[[0.0, -1.0, 0.0], [-1.0, 5.0, -1.0], [0.0, -1.0, 0.0]]).unwrap();
+    filter::convolve(&mut expected, &kernel).unwrap();
+
+    let mut sharpened = image.clone();
+    filter::sharpen(&mut sharpened, 1.0).unwrap();
+    assert!(compare::equal(&expected, &sharpened).unwrap());
+
+    let mut unchanged = image.clone();
+    filter::sharpen(&mut unchanged, 0.0).unwrap();
+    assert!(compare::equal(&image, &unchanged).unwrap());
+}
+
+#[test]
+fn unsharp_mask_test() {
+    let mut image = raster::open("tests/in/sample.jpg").unwrap();
+    filter::unsharp_mask(&mut image, 2, 0.8, 3).unwrap();
+    raster::save(&image, "tests/out/test_filter_unsharp_mask.jpg").unwrap();
+}
+
+#[test]
+fn unsharp_mask_edge() {
+    let mut image = Image::blank(4, 1);
+    for x in 0..4 {
+        let v = if x < 2 { 100 } else { 150 };
+        image.set_pixel(x, 0, &Color::rgba(v, v, v, 255)).unwrap();
+    }
+
+    // Above the threshold the edge gets steeper on both sides.
+    let mut sharpened = image.clone();
+    filter::unsharp_mask(&mut sharpened, 2, 1.0, 0).unwrap();
+    assert!(sharpened.get_pixel(1, 0).unwrap().r < 100);
+    assert!(sharpened.get_pixel(2, 0).unwrap().r > 150);
+    assert_eq!(255, sharpened.get_pixel(1, 0).unwrap().a);
+
+    // Below the threshold nothing changes.
+    let mut unchanged = image.clone();
+    filter::unsharp_mask(&mut unchanged, 2, 1.0, 60).unwrap();
+    assert!(compare::equal(&image, &unchanged).unwrap());
+
+    assert!(filter::unsharp_mask(&mut image, -1, 1.0, 0).is_err());
+}
+
+#[test]
+fn median_test() {
+    let mut image = raster::open("tests/in/sample.jpg").unwrap();
+    filter::median(&mut image, 2).unwrap();
+    raster::save(&image, "tests/out/test_filter_median.jpg").unwrap();
+}
+
+#[test]
+fn median_removes_speckle() {
+    let mut image = Image::blank(5, 5);
+    for y in 0..5 {
+        for x in 0..5 {
+            image.set_pixel(x, y, &Color::rgba(40, 80, 120, 255)).unwrap();
+        }
+    }
+    image.set_pixel(2, 2, &Color::rgba(255, 255, 255, 255)).unwrap();
+    image.set_pixel(0, 4, &Color::rgba(0, 0, 0, 255)).unwrap();
+
+    filter::median(&mut image, 1).unwrap();
+    for y in 0..5 {
+        for x in 0..5 {
+            let p = image.get_pixel(x, y).unwrap();
+            assert_eq!((40, 80, 120, 255), (p.r, p.g, p.b, p.a));
+        }
+    }
+
+    assert!(filter::median(&mut image, -1).is_err());
+}
+
+#[test]
+fn bilateral_test() {
+    let mut image = raster::open("tests/in/sample.jpg").unwrap();
+    filter::bilateral(&mut image, 3.0, 30.0).unwrap();
+    raster::save(&image, "tests/out/test_filter_bilateral.jpg").unwrap();
+}
+
+#[test]
+fn bilateral_keeps_edges() {
+    let mut image = Image::blank(6, 2);
+    for y in 0..2 {
+        for x in 0..6 {
+            let v = if x < 3 { 20 } else { 220 };
+            image.set_pixel(x, y, &Color::rgba(v, v, v, 255)).unwrap();
+        }
+    }
+    image.set_pixel(0, 0, &Color::rgba(30, 30, 30, 255)).unwrap();
+
+    filter::bilateral(&mut image, 2.0, 20.0).unwrap();
+
+    // The step survives while the small bump is smoothed.
+    assert_eq!(220, image.get_pixel(3, 1).unwrap().r);
+    assert!(image.get_pixel(2, 1).unwrap().r < 30);
+    assert!(image.get_pixel(0, 0).unwrap().r < 30);
+
+    assert!(filter::bilateral(&mut image, 0.0, 20.0).is_err());
+    assert!(filter::bilateral(&mut image, 2.0, -1.0).is_err());
+}
+
+#[test]
+fn kernel_invalid() {
+    assert!(Kernel::new(&[[1.0, 1.0], [1.0, 1.0]]).is_err());
+    assert!(Kernel::new(&[vec![1.0, 1.0, 1.0], vec![1.0], vec![1.0, 1.0, 1.0]]).is_err());
+
+    let mut image = Image::blank(2, 2);
+    let kernel = Kernel::new(&[[1.0]]).unwrap().divisor(0.0);
+    assert!(filter::convolve(&mut image, &kernel).is_err());
+}
+
+#[test]
+fn convolve_edge_modes() {
+    let mut image = Image::blank(3, 1);
+    image.set_pixel(0, 0, &Color::rgba(10, 0, 0, 255)).unwrap();
+    image.set_pixel(1, 0, &Color::rgba(20, 0, 0, 255)).unwrap();
+    image.set_pixel(2, 0, &Color::rgba(90, 0, 0, 255)).unwrap();
+
+    // Read the pixel on the left of each pixel
+    let left = Kernel::new(&[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 0.0]]).unwrap();
+
+    let mut clamped = image.clone();
+    filter::convolve(&mut clamped, &left).unwrap();
+    assert_eq!(10, clamped.get_pixel(0, 0).unwrap().r);
+
+    let mut wrapped = image.clone();
+    filter::convolve(&mut wrapped, &left.clone().edge(EdgeMode::Wrap)).unwrap();
+    assert_eq!(90, wrapped.get_pixel(0, 0).unwrap().r);
+
+    let mut mirrored = image.clone();
+    filter::convolve(&mut mirrored, &left.edge(EdgeMode::Mirror)).unwrap();
+    assert_eq!(20, mirrored.get_pixel(0, 0).unwrap().r);
+    assert_eq!(10, mirrored.get_pixel(1, 0).unwrap().r);
+}
+
+#[test]
+fn convolve_offset() {
+    let mut image = Image::blank(2, 2);
+    let kernel = Kernel::new(&[[0.0, 0.0, 0.0], [0.0, 0.5, 0.0], [0.0, 0.0, 0.0]]).unwrap();
+    filter::convolve(&mut image, &kernel.offset(128.0)).unwrap();
+    assert_eq!(128, image.get_pixel(1, 1).unwrap().g);
+}
+
+#[test]
+fn sobel_gradient_test() {
+    let image = raster::open("tests/in/sample.jpg").unwrap();
+    let (magnitude, direction) = filter::sobel_gradient(&image).unwrap();
+    raster::save(&magnitude, "tests/out/test_filter_sobel_magnitude.jpg").unwrap();
+    raster::save(&direction, "tests/out/test_filter_sobel_direction.jpg").unwrap();
+}
+
+#[test]
+fn sobel_gradient_step() {
+    // Dark on the left, bright on the right
+    let mut image = Image::blank(4, 3);
+    for y in 0..3 {
+        for x in 2..4 {
+            image.set_pixel(x, y, &Color::rgba(100, 100, 100, 255)).unwrap();
+        }
+    }
+
+    let (magnitude, direction) = filter::sobel_gradient(&image).unwrap();
+    assert_eq!(0, magnitude.get_pixel(0, 1).unwrap().r);
+    assert_eq!(255, magnitude.get_pixel(1, 1).unwrap().r);
+    assert_eq!(255, magnitude.get_pixel(1, 1).unwrap().a);
+    // Pointing right, the middle of the range
+    assert_eq!(128, direction.get_pixel(1, 1).unwrap().r);
+
+    // The falling side of the step is found too
+    let mut both = image.clone();
+    transform::flip(&mut both, TransformMode::Horizontal).unwrap();
+    filter::sobel(&mut both, Orientation::Both).unwrap();
+    assert_eq!(255, both.get_pixel(1, 1).unwrap().r);
+    assert_eq!(255, both.get_pixel(1, 1).unwrap().a);
+}
+
+#[test]
+fn sobel_empty() {
+    for &(w, h) in [(0, 5), (5, 0)].iter() {
+        let mut image = Image::blank(w, h);
+        filter::sobel(&mut image, Orientation::Both).unwrap();
+        filter::sobel(&mut image, Orientation::DiagonalUp).unwrap();
+        let (magnitude, direction) = filter::sobel_gradient(&image).unwrap();
+        assert_eq!((w, h), (magnitude.width, magnitude.height));
+        assert_eq!((w, h), (direction.width, direction.height));
+    }
+}
+
+#[test]
+fn canny_test() {
+    let mut image = raster::open("tests/in/sample.jpg").unwrap();
+    filter::canny(&mut image, 50.0, 150.0).unwrap();
+    raster::save(&image, "tests/out/test_filter_canny.jpg").unwrap();
+}
+
+#[test]
+fn canny_thin_edge() {
+    // A bright square in the middle of a dark image
+    let mut image = Image::blank(20, 20);
+    for y in 5..15 {
+        for x in 5..15 {
+            image.set_pixel(x, y, &Color::rgba(200, 200, 200, 255)).unwrap();
+        }
+    }
+
+    filter::canny(&mut image, 50.0, 150.0).unwrap();
+
+    // The left side of the square is a single column of edge pixels, and the flat areas are empty.
+    let row: Vec<u8> = (0..10).map(|x| image.get_pixel(x, 10).unwrap().r).collect();
+    assert_eq!(1, row.iter().filter(|&&v| v == 255).count());
+    assert!(row.iter().all(|&v| v == 0 || v == 255));
+    assert_eq!(0, image.get_pixel(10, 10).unwrap().r);
+    assert_eq!(0, image.get_pixel(1, 1).unwrap().r);
+
+    assert!(filter::canny(&mut image, 150.0, 50.0).is_err());
+    assert!(filter::canny(&mut image, -1.0, 50.0).is_err());
+
+    filter::canny(&mut Image::blank(0, 5), 50.0, 150.0).unwrap();
+    filter::canny(&mut Image::blank(5, 0), 50.0, 150.0).unwrap();
+}
+
+#[test]
+fn emboss_test() {
+    let mut image = raster::open("tests/in/sample.jpg").unwrap();
+    filter::emboss(&mut image).unwrap();
+    raster::save(&image, "tests/out/test_filter_emboss.jpg").unwrap();
+}
+
+#[test]
+fn edge_enhance_test() {
+    let mut image = raster::open("tests/in/sample.jpg").unwrap();
+    filter::edge_enhance(&mut image).unwrap();
+    raster::save(&image, "tests/out/test_filter_edge_enhance.jpg").unwrap();
+}
+
+#[test]
+fn emboss_and_edge_enhance_flat() {
+    let mut image = Image::blank(3, 3);
+    for y in 0..3 {
+        for x in 0..3 {
+            image.set_pixel(x, y, &Color::rgba(60, 120, 180, 200)).unwrap();
+        }
+    }
+
+    let mut enhanced = image.clone();
+    filter::edge_enhance(&mut enhanced).unwrap();
+    assert!(compare::equal(&image, &enhanced).unwrap());
+
+    let mut embossed = image.clone();
+    filter::emboss(&mut embossed).unwrap();
+    let p = embossed.get_pixel(1, 1).unwrap();
+    assert_eq!((127, 127, 127, 200), (p.r, p.g, p.b, p.a));
+}
+
+#[test]
+fn contrast_test() {
+    let mut image = raster::open("tests/in/sample.jpg").unwrap();
+    filter::contrast(&mut image, 40.0).unwrap();
+    raster::save(&image, "tests/out/test_filter_contrast.jpg").unwrap();
+}
+
+#[test]
+fn contrast_range() {
+    let mut image = Image::blank(2, 1);
+    image.set_pixel(0, 0, &Color::rgba(100, 128, 160, 255)).unwrap();
+    image.set_pixel(1, 0, &Color::rgba(10, 240, 128, 100)).unwrap();
+
+    let mut unchanged = image.clone();
+    filter::contrast(&mut unchanged, 0.0).unwrap();
+    assert!(compare::equal(&image, &unchanged).unwrap());
+
+    let mut flat = image.clone();
+    filter::contrast(&mut flat, -100.0).unwrap();
+    let p = flat.get_pixel(1, 0).unwrap();
+    assert_eq!((128, 128, 128, 100), (p.r, p.g, p.b, p.a));
+
+    let mut full = image.clone();
+    filter::contrast(&mut full, 100.0).unwrap();
+    let p = full.get_pixel(0, 0).unwrap();
+    assert_eq!((0, 128, 255, 255), (p.r, p.g, p.b, p.a));
+
+    assert!(filter::contrast(&mut image, 100.5).is_err());
+}
+
+#[test]
+fn gamma_test() {
+    let mut image = Image::blank(1, 1);
+    image.set_pixel(0, 0, &Color::rgba(0, 64, 255, 128)).unwrap();
+
+    let mut lighter = image.clone();
+    filter::gamma(&mut lighter, 2.0).unwrap();
+    let p = lighter.get_pixel(0, 0).unwrap();
+    assert_eq!((0, 128, 255, 128), (p.r, p.g, p.b, p.a));
+
+    let mut darker = image.clone();
+    filter::gamma(&mut darker, 0.5).unwrap();
+    assert_eq!(16, darker.get_pixel(0, 0).unwrap().g);
+
+    assert!(filter::gamma(&mut image, 10.0).is_err());
+}
+
+#[test]
+fn gradient_map_test() {
+    let mut image = Image::blank(3, 1);
+    image.set_pixel(1, 0, &Color::rgba(128, 128, 128, 100)).unwrap();
+    image.set_pixel(2, 0, &Color::white()).unwrap();
+
+    let stops = [
+        GradientStop::new(0.0, Color::red()),
+        GradientStop::new(128.0 / 255.0, Color::rgba(0, 255, 0, 128)),
+        GradientStop::new(1.0, Color::blue()),
+    ];
+    filter::gradient_map(&mut image, &stops).unwrap();
+    let black = image.get_pixel(0, 0).unwrap();
+    let gray = image.get_pixel(1, 0).unwrap();
+    let white = image.get_pixel(2, 0).unwrap();
+    assert_eq!((255, 0, 0, 255), (black.r, black.g, black.b, black.a));
+    assert_eq!((0, 255, 0, 50), (gray.r, gray.g, gray.b, gray.a));
+    assert_eq!((0, 0, 255, 255), (white.r, white.g, white.b, white.a));
+
+    assert!(filter::gradient_map(&mut image, &[]).is_err());
+}
+
+#[test]
+fn exposure_test() {
+    let mut image = Image::blank(1, 1);
+    image.set_pixel(0, 0, &Color::rgba(0, 188, 255, 128)).unwrap();
+
+    let mut unchanged = image.clone();
+    filter::exposure(&mut unchanged, 0.0).unwrap();
+    assert!(compare::equal(&image, &unchanged).unwrap());
+
+    // 188 is about half of the light of 255 in sRGB
+    let mut darker = image.clone();
+    filter::exposure(&mut darker, -1.0).unwrap();
+    let p = darker.get_pixel(0, 0).unwrap();
+    assert_eq!((0, 137, 188, 128), (p.r, p.g, p.b, p.a));
+
+    let mut lighter = image.clone();
+    filter::exposure(&mut lighter, 1.0).unwrap();
+    assert_eq!(255, lighter.get_pixel(0, 0).unwrap().g);
+}
+
+#[test]
+fn vibrance_test() {
+    let mut image = Image::blank(4, 1);
+    image.set_pixel(0, 0, &Color::rgba(100, 110, 140, 255)).unwrap(); // muted blue
+    image.set_pixel(1, 0, &Color::rgba(20, 40, 250, 255)).unwrap(); // saturated blue
+    image.set_pixel(2, 0, &Color::rgba(140, 110, 100, 255)).unwrap(); // skin
+    image.set_pixel(3, 0, &Color::rgba(90, 90, 90, 128)).unwrap(); // gray
+
+    let mut vibrant = image.clone();
+    filter::vibrance(&mut vibrant, 1.0).unwrap();
+
+    let spread = |image: &Image, x: i32| {
+        let p = image.get_pixel(x, 0).unwrap();
+        p.r.max(p.g).max(p.b) as i32 - p.r.min(p.g).min(p.b) as i32
+    };
+    let muted = spread(&vibrant, 0) - spread(&image, 0);
+    let saturated = spread(&vibrant, 1) - spread(&image, 1);
+    let skin = spread(&vibrant, 2) - spread(&image, 2);
+    assert!(muted > saturated);
+    assert!(muted > skin && skin > 0);
+
+    let p = vibrant.get_pixel(3, 0).unwrap();
+    assert_eq!((90, 90, 90, 128), (p.r, p.g, p.b, p.a));
+}
+
+#[test]
+fn temperature_test() {
+    let mut image = Image::blank(1, 1);
+    image.set_pixel(0, 0, &Color::rgba(128, 128, 128, 200)).unwrap();
+
+    let mut unchanged = image.clone();
+    filter::temperature(&mut unchanged, 0.0).unwrap();
+    assert!(compare::equal(&image, &unchanged).unwrap());
+
+    let mut warmer = image.clone();
+    filter::temperature(&mut warmer, 2000.0).unwrap();
+    let p = warmer.get_pixel(0, 0).unwrap();
+    assert!(p.r > 128 && p.b < 128);
+    assert_eq!(200, p.a);
+
+    let mut cooler = image.clone();
+    filter::temperature(&mut cooler, -2000.0).unwrap();
+    let p = cooler.get_pixel(0, 0).unwrap();
+    assert!(p.r < 128 && p.b > 128);
+}
+
+#[test]
+fn tint_test() {
+    let mut image = Image::blank(1, 1);
+    image.set_pixel(0, 0, &Color::rgba(128, 128, 128, 200)).unwrap();
+
+    let mut unchanged = image.clone();
+    filter::tint(&mut unchanged, 0.0).unwrap();
+    assert!(compare::equal(&image, &unchanged).unwrap());
+
+    let mut magenta = image.clone();
+    filter::tint(&mut magenta, 50.0).unwrap();
+    let p = magenta.get_pixel(0, 0).unwrap();
+    assert!(p.g < 128 && p.r > 128 && p.r == p.b);
+    assert_eq!(200, p.a);
+
+    let mut green = image.clone();
+    filter::tint(&mut green, -50.0).unwrap();
+    let p = green.get_pixel(0, 0).unwrap();
+    assert!(p.g > 128 && p.r < 128);
+}
+
+#[test]
+fn levels_test() {
+    let mut image = Image::blank(3, 1);
+    image.set_pixel(0, 0, &Color::rgba(10, 20, 100, 200)).unwrap();
+    image.set_pixel(1, 0, &Color::rgba(60, 110, 160, 200)).unwrap();
+    image.set_pixel(2, 0, &Color::rgba(210, 230, 250, 200)).unwrap();
+
+    let mut unchanged = image.clone();
+    filter::levels(&mut unchanged, 0, 255, 1.0, 0, 255).unwrap();
+    assert!(compare::equal(&image, &unchanged).unwrap());
+
+    // Stretch 20 - 210 to the output range 5 - 195, which keeps whole steps
+    let mut stretched = image.clone();
+    filter::levels(&mut stretched, 20, 210, 1.0, 5, 195).unwrap();
+    let p = stretched.get_pixel(1, 0).unwrap();
+    assert_eq!((45, 95, 145, 200), (p.r, p.g, p.b, p.a));
+    let p = stretched.get_pixel(2, 0).unwrap();
+    assert_eq!((195, 195, 195), (p.r, p.g, p.b));
+    assert_eq!(5, stretched.get_pixel(0, 0).unwrap().r);
+
+    let mut blue = image.clone();
+    filter::levels_channel(&mut blue, Channel::Blue, 100, 200, 1.0, 0, 255).unwrap();
+    let p = blue.get_pixel(1, 0).unwrap();
+    assert_eq!((60, 110, 153, 200), (p.r, p.g, p.b, p.a));
+
+    assert!(filter::levels(&mut image, 200, 100, 1.0, 0, 255).is_err());
+    assert!(filter::levels(&mut image, 0, 255, 0.0, 0, 255).is_err());
+}
+
+#[test]
+fn curves_test() {
+    let mut image = Image::blank(4, 1);
+    image.set_pixel(0, 0, &Color::rgba(0, 10, 64, 200)).unwrap();
+    image.set_pixel(1, 0, &Color::rgba(100, 128, 150, 200)).unwrap();
+    image.set_pixel(2, 0, &Color::rgba(192, 230, 255, 200)).unwrap();
+    image.set_pixel(3, 0, &Color::rgba(50, 60, 70, 40)).unwrap();
+
+    let mut unchanged = image.clone();
+    filter::curves(&mut unchanged, &[(0, 0), (255, 255)]).unwrap();
+    assert!(compare::equal(&image, &unchanged).unwrap());
+
+    // The curve goes through its points and stays monotone between them
+    let mut curved = image.clone();
+    filter::curves(&mut curved, &[(0, 0), (64, 48), (192, 208), (255, 255)]).unwrap();
+    let p = curved.get_pixel(0, 0).unwrap();
+    assert_eq!((0, 48, 200), (p.r, p.b, p.a));
+    assert_eq!(208, curved.get_pixel(2, 0).unwrap().r);
+    let mut last = 0;
+    for x in 0..3 {
+        let p = curved.get_pixel(x, 0).unwrap();
+        for v in [p.r, p.g, p.b].iter() {
+            assert!(*v >= last);
+            last = *v;
+        }
+    }
+
+    // Flat outside of the points
+    let mut clipped = image.clone();
+    filter::curves_channel(&mut clipped, Channel::Alpha, &[(60, 10), (100, 250)]).unwrap();
+    let p = clipped.get_pixel(3, 0).unwrap();
+    assert_eq!((50, 60, 70, 10), (p.r, p.g, p.b, p.a));
+    assert_eq!(250, clipped.get_pixel(0, 0).unwrap().a);
+
+    assert!(filter::curves(&mut image, &[(0, 0)]).is_err());
+    assert!(filter::curves(&mut image, &[(0, 0), (128, 100), (100, 255)]).is_err());
+}
+
+#[test]
+fn grayscale_modes() {
+    let mut image = Image::blank(1, 1);
+    image.set_pixel(0, 0, &Color::rgba(200, 100, 50, 128)).unwrap();
+
+    let modes = [
+        (GrayscaleMode::Rec601, 124),
+        (GrayscaleMode::Rec709, 118),
+        (GrayscaleMode::Average, 117),
+        (GrayscaleMode::Desaturate, 125),
+    ];
+    for &(mode, gray) in modes.iter() {
+        let mut result = image.clone();
+        filter::grayscale(&mut result, mode).unwrap();
+        let p = result.get_pixel(0, 0).unwrap();
+        assert_eq!((gray, gray, gray, 128), (p.r, p.g, p.b, p.a));
+    }
+}
+
+#[test]
+fn invert_test() {
+    let mut image = Image::blank(1, 1);
+    image.set_pixel(0, 0, &Color::rgba(0, 100, 255, 60)).unwrap();
+
+    let mut negative = image.clone();
+    filter::invert(&mut negative).unwrap();
+    let p = negative.get_pixel(0, 0).unwrap();
+    assert_eq!((255, 155, 0, 60), (p.r, p.g, p.b, p.a));
+
+    // Inverting twice gives back the image
+    filter::invert(&mut negative).unwrap();
+    assert!(compare::equal(&image, &negative).unwrap());
+
+    filter::invert_channels(&mut image, &[Channel::Green, Channel::Alpha]).unwrap();
+    let p = image.get_pixel(0, 0).unwrap();
+    assert_eq!((0, 155, 255, 195), (p.r, p.g, p.b, p.a));
+}
+
+#[test]
+fn threshold_test() {
+    let mut image = Image::blank(3, 1);
+    image.set_pixel(0, 0, &Color::rgba(127, 127, 127, 255)).unwrap();
+    image.set_pixel(1, 0, &Color::rgba(128, 128, 128, 90)).unwrap();
+    image.set_pixel(2, 0, &Color::rgba(255, 0, 0, 255)).unwrap(); // luminance 76.5
+
+    filter::threshold(&mut image, 128).unwrap();
+    let p = image.get_pixel(0, 0).unwrap();
+    assert_eq!((0, 0, 0, 255), (p.r, p.g, p.b, p.a));
+    let p = image.get_pixel(1, 0).unwrap();
+    assert_eq!((255, 255, 255, 90), (p.r, p.g, p.b, p.a));
+    assert_eq!(0, image.get_pixel(2, 0).unwrap().r);
+}
+
+#[test]
+fn threshold_otsu_test() {
+    // Two groups of grays around 40 and 200
+    let mut image = Image::blank(6, 1);
+    for (x, &v) in [30u8, 40, 50, 190, 200, 210].iter().enumerate() {
+        image.set_pixel(x as i32, 0, &Color::rgba(v, v, v, 255)).unwrap();
+    }
+
+    let threshold = filter::threshold_otsu(&mut image).unwrap();
+    assert!(threshold > 50 && threshold <= 190);
+    assert_eq!(0, image.get_pixel(2, 0).unwrap().r);
+    assert_eq!(255, image.get_pixel(3, 0).unwrap().r);
+
+    // A flat image has nothing to separate
+    let mut flat = Image::blank(2, 2);
+    assert_eq!(0, filter::threshold_otsu(&mut flat).unwrap());
+}
+
+#[test]
+fn threshold_adaptive_test() {
+    let mut image = raster::open("tests/in/sample.jpg").unwrap();
+    filter::threshold_adaptive(&mut image, 15, 5.0, ThresholdMode::Gaussian).unwrap();
+    raster::save(&image, "tests/out/test_filter_threshold_adaptive.jpg").unwrap();
+}
+
+#[test]
+fn threshold_adaptive_uneven_light() {
+    // Dark text strokes on paper lit from the left, darker on the right than the left text
+    let mut image = Image::blank(12, 5);
+    for y in 0..5 {
+        for x in 0..12 {
+            let paper = 240 - x as u8 * 12;
+            let v = if x == 2 || x == 9 { paper - 60 } else { paper };
+            image.set_pixel(x, y, &Color::rgba(v, v, v, 255)).unwrap();
+        }
+    }
+
+    for &mode in [ThresholdMode::Mean, ThresholdMode::Gaussian].iter() {
+        let mut binary = image.clone();
+        filter::threshold_adaptive(&mut binary, 5, 5.0, mode).unwrap();
+        for x in 0..12 {
+            let expected = if x == 2 || x == 9 { 0 } else { 255 };
+            assert_eq!(expected, binary.get_pixel(x, 2).unwrap().r);
+        }
+    }
+
+    assert!(filter::threshold_adaptive(&mut image, 4, 5.0, ThresholdMode::Mean).is_err());
+    assert!(filter::threshold_adaptive(&mut image, 1, 5.0, ThresholdMode::Mean).is_err());
+
+    for &mode in [ThresholdMode::Mean, ThresholdMode::Gaussian].iter() {
+        let mut empty = Image::blank(0, 5);
+        filter::threshold_adaptive(&mut empty, 5, 5.0, mode).unwrap();
+        let mut empty = Image::blank(5, 0);
+        filter::threshold_adaptive(&mut empty, 5, 5.0, mode).unwrap();
+    }
+}
+
+#[test]
+fn equalize_test() {
+    // Low contrast grays between 100 and 130
+    let mut image = Image::blank(4, 1);
+    for x in 0..4 {
+        let v = 100 + x as u8 * 10;
+        image.set_pixel(x, 0, &Color::rgba(v, v, v, 200)).unwrap();
+    }
+
+    filter::equalize(&mut image).unwrap();
+    let grays: Vec<u8> = (0..4).map(|x| image.get_pixel(x, 0).unwrap().r).collect();
+    assert_eq!(vec![0, 85, 170, 255], grays);
+    let p = image.get_pixel(1, 0).unwrap();
+    assert_eq!((85, 85, 200), (p.g, p.b, p.a));
+
+    let mut flat = Image::blank(2, 2);
+    filter::equalize(&mut flat).unwrap();
+    assert_eq!(0, flat.get_pixel(0, 0).unwrap().r);
+}
+
+#[test]
+fn match_histogram_test() {
+    let mut image = Image::blank(4, 1);
+    let mut reference = Image::blank(2, 2);
+    for x in 0..4 {
+        let v = 10 + x as u8 * 10;
+        image.set_pixel(x, 0, &Color::rgba(v, v, 0, 100)).unwrap();
+        let v = 100 + x as u8 * 40;
+        reference.set_pixel(x % 2, x / 2, &Color::rgba(v, 0, 0, 255)).unwrap();
+    }
+
+    filter::match_histogram(&mut image, &reference).unwrap();
+    let reds: Vec<u8> = (0..4).map(|x| image.get_pixel(x, 0).unwrap().r).collect();
+    assert_eq!(vec![100, 140, 180, 220], reds);
+    let p = image.get_pixel(3, 0).unwrap();
+    assert_eq!((0, 0, 100), (p.g, p.b, p.a));
+}
+
+#[test]
+fn auto_levels_test() {
+    // Red between 50 and 150 with an outlier at 250, flat green
+    let mut image = Image::blank(10, 1);
+    for x in 0..10 {
+        let r = if x == 9 { 250 } else { 50 + x as u8 * 12 };
+        image.set_pixel(x, 0, &Color::rgba(r, 80, 0, 100)).unwrap();
+    }
+
+    let mut stretched = image.clone();
+    filter::auto_levels(&mut stretched, 0.0).unwrap();
+    assert_eq!(0, stretched.get_pixel(0, 0).unwrap().r);
+    assert_eq!(255, stretched.get_pixel(9, 0).unwrap().r);
+    let p = stretched.get_pixel(4, 0).unwrap();
+    assert_eq!((80, 0, 100), (p.g, p.b, p.a));
+
+    // Clipping the outlier stretches the rest further
+    let mut clipped = image.clone();
+    filter::auto_levels(&mut clipped, 10.0).unwrap();
+    assert_eq!(255, clipped.get_pixel(8, 0).unwrap().r);
+    assert!(clipped.get_pixel(4, 0).unwrap().r > stretched.get_pixel(4, 0).unwrap().r);
+
+    assert!(filter::auto_levels(&mut image, 60.0).is_err());
+}
+
+#[test]
+fn auto_white_balance_test() {
+    // Grays with a warm cast: red 20% too high and blue 20% too low
+    let mut image = Image::blank(4, 1);
+    for x in 0..4 {
+        let v = 50.0 + x as f32 * 50.0;
+        let cast = Color::rgba((v * 1.2) as u8, v as u8, (v * 0.8) as u8, 100);
+        image.set_pixel(x, 0, &cast).unwrap();
+    }
+
+    let mut gray_world = image.clone();
+    filter::auto_white_balance(&mut gray_world, WhiteBalanceMode::GrayWorld).unwrap();
+    let p = gray_world.get_pixel(2, 0).unwrap();
+    assert_eq!((150, 150, 150, 100), (p.r, p.g, p.b, p.a));
+
+    let mut white_patch = image.clone();
+    filter::auto_white_balance(&mut white_patch, WhiteBalanceMode::WhitePatch).unwrap();
+    let p = white_patch.get_pixel(3, 0).unwrap();
+    assert_eq!((255, 255, 255, 100), (p.r, p.g, p.b, p.a));
+    let p = white_patch.get_pixel(1, 0).unwrap();
+    assert!(p.r.max(p.g).max(p.b) - p.r.min(p.g).min(p.b) <= 1);
+}
+
+#[test]
+fn dither_floyd_steinberg_test() {
+    let mut image = raster::open("tests/in/sample.jpg").unwrap();
+    filter::dither_floyd_steinberg(&mut image, &DitherPalette::Levels(2)).unwrap();
+    raster::save(&image, "tests/out/test_filter_dither_floyd_steinberg.png").unwrap();
+}
+
+#[test]
+fn dither_floyd_steinberg_average() {
+    // A flat 25% gray dithered to black and white keeps about 25% of white pixels
+    let mut image = Image::blank(16, 16);
+    for y in 0..16 {
+        for x in 0..16 {
+            image.set_pixel(x, y, &Color::rgba(64, 64, 64, 200)).unwrap();
+        }
+    }
+
+    let mut levels = image.clone();
+    filter::dither_floyd_steinberg(&mut levels, &DitherPalette::Levels(2)).unwrap();
+    let mut white = 0;
+    for y in 0..16 {
+        for x in 0..16 {
+            let p = levels.get_pixel(x, y).unwrap();
+            assert!(p.r == 0 || p.r == 255);
+            assert_eq!(200, p.a);
+            if p.r == 255 {
+                white += 1;
+            }
+        }
+    }
+    assert!((60..=68).contains(&white));
+
+    let palette = DitherPalette::Colors(vec![Color::rgb(0, 0, 0), Color::rgb(255, 0, 0)]);
+    let mut colors = image.clone();
+    filter::dither_floyd_steinberg(&mut colors, &palette).unwrap();
+    let p = colors.get_pixel(0, 0).unwrap();
+    assert_eq!((0, 0), (p.g, p.b));
+
+    assert!(filter::dither_floyd_steinberg(&mut image, &DitherPalette::Levels(1)).is_err());
+    assert!(filter::dither_floyd_steinberg(&mut image, &DitherPalette::Colors(vec![])).is_err());
+}
+
+#[test]
+fn vignette_test() {
+    let mut image = Image::blank(21, 11);
+    editor::fill(&mut image, Color::rgba(200, 200, 200, 128)).unwrap();
+
+    filter::vignette(&mut image, 1.0, 0.5, (0.5, 0.5), &Color::black()).unwrap();
+    assert_eq!(200, image.get_pixel(10, 5).unwrap().r);
+    assert_eq!(200, image.get_pixel(12, 5).unwrap().r);
+    let corner = image.get_pixel(0, 0).unwrap();
+    assert!(corner.r < 10);
+    assert_eq!(128, corner.a);
+    let edge = image.get_pixel(0, 5).unwrap().r;
+    assert!(edge > corner.r && edge < 200);
+
+    // Off center toward the left, lightening
+    let mut image = Image::blank(20, 10);
+    filter::vignette(&mut image, 0.5, 0.0, (0.0, 0.5), &Color::white()).unwrap();
+    assert!(image.get_pixel(1, 5).unwrap().r < image.get_pixel(18, 5).unwrap().r);
+
+    assert!(filter::vignette(&mut image, 1.5, 0.5, (0.5, 0.5), &Color::black()).is_err());
+    assert!(filter::vignette(&mut image, 0.5, -0.1, (0.5, 0.5), &Color::black()).is_err());
+}
+
+#[test]
+fn oil_paint_test() {
+    // Mostly dark gray with a few light pixels
+    let mut image = Image::blank(5, 5);
+    editor::fill(&mut image, Color::rgb(40, 40, 40)).unwrap();
+    image.set_pixel(2, 2, &Color::rgb(250, 250, 250)).unwrap();
+    image.set_pixel(0, 0, &Color::rgb(44, 40, 40)).unwrap();
+
+    let mut painted = image.clone();
+    filter::oil_paint(&mut painted, 1, 4).unwrap();
+    // The light pixel is outnumbered, the close one is averaged with its bucket
+    assert_eq!(40, painted.get_pixel(2, 2).unwrap().r);
+    assert_eq!(41, painted.get_pixel(0, 0).unwrap().r);
+
+    let mut unchanged = image.clone();
+    filter::oil_paint(&mut unchanged, 0, 4).unwrap();
+    assert_eq!(image.bytes, unchanged.bytes);
+
+    assert!(filter::oil_paint(&mut image, -1, 4).is_err());
+    assert!(filter::oil_paint(&mut image, 1, 0).is_err());
+}
+
+#[test]
+fn kuwahara_test() {
+    // Black left half, white right half, with a noisy pixel
+    let mut image = Image::blank(12, 8);
+    for y in 0..8 {
+        for x in 6..12 {
+            image.set_pixel(x, y, &Color::white()).unwrap();
+        }
+    }
+    image.set_pixel(1, 1, &Color::rgb(90, 90, 90)).unwrap();
+
+    let mut smoothed = image.clone();
+    filter::kuwahara(&mut smoothed, 2).unwrap();
+    // The edge stays sharp
+    for y in 0..8 {
+        assert_eq!(0, smoothed.get_pixel(5, y).unwrap().r);
+        assert_eq!(255, smoothed.get_pixel(6, y).unwrap().r);
+    }
+    // The noise is spread over the flattest quadrant
+    assert_eq!(10, smoothed.get_pixel(1, 1).unwrap().r);
+
+    assert!(filter::kuwahara(&mut image, -1).is_err());
+}
+
+#[test]
+fn halftone_test() {
+    // White top half, black bottom half and mid gray in between
+    let mut image = Image::blank(32, 48);
+    for y in 0..16 {
+        for x in 0..32 {
+            image.set_pixel(x, y, &Color::white()).unwrap();
+            image.set_pixel(x, y + 16, &Color::rgb(128, 128, 128)).unwrap();
+        }
+    }
+    let ink = |image: &Image, y0: i32| -> f32 {
+        let mut sum = 0.0;
+        for y in y0..y0 + 16 {
+            for x in 0..32 {
+                sum += 1.0 - image.get_pixel(x, y).unwrap().r as f32 / 255.0;
+            }
+        }
+        sum / (16.0 * 32.0)
+    };
+
+    for shape in [HalftoneShape::Dot, HalftoneShape::Line, HalftoneShape::Square] {
+        let mut printed = image.clone();
+        filter::halftone(&mut printed, 8, 0.0, shape).unwrap();
+        assert_eq!(0.0, ink(&printed, 0));
+        assert!((ink(&printed, 16) - 0.5).abs() < 0.1);
+        assert!(ink(&printed, 32) > 0.95);
+    }
+
+    let mut rotated = image.clone();
+    filter::halftone(&mut rotated, 8, 45.0, HalftoneShape::Dot).unwrap();
+    assert!((ink(&rotated, 16) - 0.5).abs() < 0.15);
+
+    match filter::halftone(&mut image, 1, 0.0, HalftoneShape::Dot) {
+        Err(RasterError::InvalidCellSize(1)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn add_noise_test() {
+    let mut image = Image::blank(64, 64);
+    editor::fill(&mut image, Color::rgba(128, 128, 128, 200)).unwrap();
+    let stats = |image: &Image| -> (f32, f32) {
+        let values: Vec<f32> = image.bytes.chunks(4).map(|p| p[0] as f32).collect();
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        let variance =
+            values.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / values.len() as f32;
+        (mean, variance.sqrt())
+    };
+
+    let mut gaussian = image.clone();
+    filter::add_noise(&mut gaussian, NoiseType::Gaussian, 0.1, false, 7).unwrap();
+    let (mean, deviation) = stats(&gaussian);
+    assert!((mean - 128.0).abs() < 2.0);
+    assert!((deviation - 25.5).abs() < 2.0);
+    assert!(gaussian.bytes.chunks(4).all(|p| p[3] == 200));
+    assert!(gaussian.bytes.chunks(4).any(|p| p[0] != p[1]));
+
+    // Same seed, same noise
+    let mut again = image.clone();
+    filter::add_noise(&mut again, NoiseType::Gaussian, 0.1, false, 7).unwrap();
+    assert_eq!(gaussian.bytes, again.bytes);
+
+    let mut uniform = image.clone();
+    filter::add_noise(&mut uniform, NoiseType::Uniform, 0.1, true, 7).unwrap();
+    assert!(uniform.bytes.chunks(4).all(|p| p[0] == p[1] && p[1] == p[2]));
+    assert!(uniform.bytes.chunks(4).all(|p| (102..=154).contains(&p[0])));
+
+    let mut salt = image.clone();
+    filter::add_noise(&mut salt, NoiseType::SaltPepper, 0.2, true, 7).unwrap();
+    let changed = salt.bytes.chunks(4).filter(|p| p[0] != 128).count() as f32;
+    assert!((changed / 4096.0 - 0.2).abs() < 0.03);
+    assert!(salt.bytes.chunks(4).all(|p| p[0] == 0 || p[0] == 128 || p[0] == 255));
+
+    assert!(filter::add_noise(&mut image, NoiseType::Uniform, 1.5, true, 7).is_err());
+}
+
+#[test]
+fn denoise_test() {
+    // Dark and light halves with noise
+    let mut image = Image::blank(32, 16);
+    for y in 0..16 {
+        for x in 0..32 {
+            let value = if x < 16 { 50 } else { 200 };
+            image.set_pixel(x, y, &Color::rgb(value, value, value)).unwrap();
+        }
+    }
+    let clean = image.clone();
+    filter::add_noise(&mut image, NoiseType::Gaussian, 0.04, true, 3).unwrap();
+    let error = |image: &Image| -> f32 {
+        let diff = image.bytes.iter().zip(clean.bytes.iter());
+        diff.map(|(&a, &b)| (a as f32 - b as f32).abs()).sum::<f32>() / image.bytes.len() as f32
+    };
+
+    let mut denoised = image.clone();
+    filter::denoise(&mut denoised, 15.0).unwrap();
+    assert!(error(&denoised) < error(&image) / 2.0);
+    // The edge stays sharp
+    for y in 0..16 {
+        assert!(denoised.get_pixel(15, y).unwrap().r < 70);
+        assert!(denoised.get_pixel(16, y).unwrap().r > 180);
+    }
+
+    assert!(filter::denoise(&mut image, 0.0).is_err());
+}
+
+#[test]
+fn pixel_sort_test() {
+    let values = [200, 100, 150, 0, 180, 120, 255, 90];
+    let mut image = Image::blank(8, 2);
+    for (x, &value) in values.iter().enumerate() {
+        image.set_pixel(x as i32, 1, &Color::rgb(value, value, value)).unwrap();
+    }
+    let row = |image: &Image, y: i32| -> Vec<u8> {
+        (0..image.width).map(|x| image.get_pixel(x, y).unwrap().r).collect()
+    };
+
+    let mut sorted = image.clone();
+    filter::pixel_sort(&mut sorted, Direction::Horizontal, (50, 210)).unwrap();
+    assert_eq!(vec![100, 150, 200, 0, 120, 180, 255, 90], row(&sorted, 1));
+    assert_eq!(vec![0; 8], row(&sorted, 0));
+
+    // Columns are sorted from the top down
+    let mut sorted = image.clone();
+    filter::pixel_sort(&mut sorted, Direction::Vertical, (0, 255)).unwrap();
+    assert_eq!(vec![0; 8], row(&sorted, 0));
+    assert_eq!(values.to_vec(), row(&sorted, 1));
+
+    assert!(filter::pixel_sort(&mut image, Direction::Vertical, (100, 50)).is_err());
+}
+
+#[test]
+fn chromatic_aberration_test() {
+    // White square in the middle of a black image
+    let mut image = Image::blank(41, 41);
+    for y in 10..31 {
+        for x in 10..31 {
+            image.set_pixel(x, y, &Color::white()).unwrap();
+        }
+    }
+
+    let mut shifted = image.clone();
+    filter::chromatic_aberration(&mut shifted, 8.0).unwrap();
+    // Red spreads outward past the right edge, blue pulls in from it, green stays
+    let p = shifted.get_pixel(32, 20).unwrap();
+    assert_eq!((255, 0, 0), (p.r, p.g, p.b));
+    let p = shifted.get_pixel(29, 20).unwrap();
+    assert_eq!((255, 255, 0), (p.r, p.g, p.b));
+    let p = shifted.get_pixel(20, 20).unwrap();
+    assert_eq!((255, 255, 255), (p.r, p.g, p.b));
+}
+
+#[test]
+fn tilt_shift_test() {
+    // Vertical black and white stripes
+    let mut image = Image::blank(20, 40);
+    for y in 0..40 {
+        for x in (0..20).step_by(2) {
+            image.set_pixel(x, y, &Color::white()).unwrap();
+        }
+    }
+
+    let mut miniature = image.clone();
+    filter::tilt_shift(&mut miniature, (0.4, 0.6), 3, 0.0).unwrap();
+    // Sharp in the band, blurred to gray far from it
+    assert_eq!(255, miniature.get_pixel(10, 20).unwrap().r);
+    assert_eq!(0, miniature.get_pixel(11, 20).unwrap().r);
+    let far = miniature.get_pixel(10, 0).unwrap().r;
+    assert!(far > 100 && far < 160);
+    let near = miniature.get_pixel(10, 14).unwrap().r;
+    assert!(near > far && near < 255);
+
+    assert!(filter::tilt_shift(&mut image, (0.6, 0.4), 3, 0.0).is_err());
+    assert!(filter::tilt_shift(&mut image, (0.4, 0.6), -1, 0.0).is_err());
+}
+
+#[test]
+fn depth_blur_test() {
+    // Vertical black and white stripes, in focus on the left half only
+    let mut image = Image::blank(20, 10);
+    let mut depth = Image::blank(20, 10);
+    for y in 0..10 {
+        for x in 0..20 {
+            if x % 2 == 0 {
+                image.set_pixel(x, y, &Color::white()).unwrap();
+            }
+            if x >= 10 {
+                depth.set_pixel(x, y, &Color::white()).unwrap();
+            }
+        }
+    }
+
+    let mut blurred = image.clone();
+    filter::depth_blur(&mut blurred, &depth, 0, 1).unwrap();
+    assert_eq!(255, blurred.get_pixel(4, 5).unwrap().r);
+    assert_eq!(0, blurred.get_pixel(5, 5).unwrap().r);
+    // 3x3 averages of the stripes
+    assert_eq!(170, blurred.get_pixel(15, 5).unwrap().r);
+    assert_eq!(85, blurred.get_pixel(16, 5).unwrap().r);
+
+    assert!(filter::depth_blur(&mut image, &Image::blank(5, 5), 0, 1).is_err());
+    assert!(filter::depth_blur(&mut image, &depth, 0, -1).is_err());
+}
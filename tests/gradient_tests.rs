@@ -0,0 +1,100 @@
+extern crate raster;
+
+use raster::{gradient, Color, GradientStop};
+
+#[test]
+fn linear_horizontal() {
+    let image = gradient::linear(256, 1, &Color::black(), &Color::white(), 0.0).unwrap();
+    let first = image.get_pixel(0, 0).unwrap();
+    let middle = image.get_pixel(128, 0).unwrap();
+    let last = image.get_pixel(255, 0).unwrap();
+    assert_eq!(0, first.r);
+    assert_eq!(128, middle.r);
+    assert_eq!(255, last.r);
+    assert_eq!(255, middle.a);
+}
+
+#[test]
+fn linear_angle() {
+    // 90 degrees goes from top to bottom, 180 degrees from right to left
+    let image = gradient::linear(4, 4, &Color::red(), &Color::blue(), 90.0).unwrap();
+    assert!(image.get_pixel(0, 0).unwrap().r > 200);
+    assert_eq!(image.bytes[0..4], image.bytes[12..16]);
+    assert!(image.get_pixel(0, 3).unwrap().b > 200);
+
+    let image = gradient::linear(4, 4, &Color::red(), &Color::blue(), 180.0).unwrap();
+    assert!(image.get_pixel(3, 0).unwrap().r > 200);
+    assert!(image.get_pixel(0, 0).unwrap().b > 200);
+}
+
+#[test]
+fn linear_stops_test() {
+    let stops = [
+        GradientStop::new(0.0, Color::red()),
+        GradientStop::new(0.5, Color::red()),
+        GradientStop::new(0.5, Color::rgba(0, 0, 255, 255)),
+        GradientStop::new(1.0, Color::rgba(0, 0, 255, 0)),
+    ];
+    let image = gradient::linear_stops(100, 1, &stops, 0.0).unwrap();
+    assert_eq!(255, image.get_pixel(49, 0).unwrap().r);
+    let blue = image.get_pixel(50, 0).unwrap();
+    assert_eq!((0, 0, 255), (blue.r, blue.g, blue.b));
+    // Fading to transparent keeps the color
+    let faded = image.get_pixel(90, 0).unwrap();
+    assert_eq!((0, 0, 255), (faded.r, faded.g, faded.b));
+    assert!(faded.a < 50);
+}
+
+#[test]
+fn linear_invalid() {
+    let black = Color::black();
+    assert!(gradient::linear(0, 10, &black, &black, 0.0).is_err());
+    assert!(gradient::linear_stops(10, 10, &[], 0.0).is_err());
+
+    let unordered = [
+        GradientStop::new(0.8, Color::red()),
+        GradientStop::new(0.2, Color::blue()),
+    ];
+    assert!(gradient::linear_stops(10, 10, &unordered, 0.0).is_err());
+    let outside = [GradientStop::new(1.5, Color::red())];
+    assert!(gradient::linear_stops(10, 10, &outside, 0.0).is_err());
+}
+
+#[test]
+fn radial_test() {
+    let stops = [
+        GradientStop::new(0.0, Color::white()),
+        GradientStop::new(1.0, Color::black()),
+    ];
+    let image = gradient::radial(101, 101, (50.5, 50.5), &stops).unwrap();
+    let center = image.get_pixel(50, 50).unwrap();
+    let edge = image.get_pixel(100, 50).unwrap();
+    let corner = image.get_pixel(0, 0).unwrap();
+    assert_eq!(255, center.r);
+    assert!(edge.r > corner.r);
+    assert!(corner.r < 5);
+    // Symmetric around the center
+    assert_eq!(image.get_pixel(10, 30).unwrap().r, image.get_pixel(90, 70).unwrap().r);
+
+    assert!(gradient::radial(10, -1, (0.0, 0.0), &stops).is_err());
+}
+
+#[test]
+fn conic_test() {
+    let stops = [
+        GradientStop::new(0.0, Color::black()),
+        GradientStop::new(1.0, Color::white()),
+    ];
+    let image = gradient::conic(100, 100, (50.0, 50.0), &stops).unwrap();
+    // Right, below, left and above the center, a quarter turn apart
+    let right = image.get_pixel(90, 50).unwrap().r;
+    let below = image.get_pixel(50, 90).unwrap().r;
+    let left = image.get_pixel(10, 50).unwrap().r;
+    let above = image.get_pixel(50, 10).unwrap().r;
+    assert!(right < 5);
+    assert!((below as i32 - 64).abs() <= 2);
+    assert!((left as i32 - 128).abs() <= 2);
+    assert!((above as i32 - 191).abs() <= 2);
+
+    assert!(gradient::conic(10, 10, (5.0, 5.0), &[]).is_err());
+}
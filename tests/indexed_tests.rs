@@ -0,0 +1,66 @@
+extern crate raster;
+
+use raster::error::RasterError;
+use raster::quantize::QuantizeMode;
+use raster::{compare, Color, Image, IndexedImage};
+
+#[test]
+fn from_image_roundtrip() {
+    let mut image = Image::blank(3, 2);
+    image.set_pixel(1, 0, &Color::rgba(255, 0, 0, 128)).unwrap();
+    image.set_pixel(2, 1, &Color::rgba(0, 0, 255, 0)).unwrap();
+
+    let indexed = IndexedImage::from_image(&image).unwrap();
+    assert_eq!(3, indexed.palette.len());
+    assert_eq!(vec![0, 1, 0, 0, 0, 2], indexed.indices);
+    assert_eq!(image.bytes, indexed.to_image().bytes);
+}
+
+#[test]
+fn from_image_too_many_colors() {
+    let mut image = Image::blank(17, 17);
+    for i in 0..289 {
+        let color = Color::rgb(i as u8, (i / 256) as u8, 0);
+        image.set_pixel(i % 17, i / 17, &color).unwrap();
+    }
+    match IndexedImage::from_image(&image) {
+        Err(RasterError::TooManyColors(count)) => assert_eq!(289, count),
+        _ => panic!("expected too many colors"),
+    }
+}
+
+#[test]
+fn quantize_reserves_transparent() {
+    let mut image = Image::blank(4, 1);
+    image.set_pixel(0, 0, &Color::rgba(250, 0, 0, 255)).unwrap();
+    image.set_pixel(1, 0, &Color::rgba(0, 0, 250, 255)).unwrap();
+    image.set_pixel(2, 0, &Color::rgba(0, 250, 0, 255)).unwrap();
+    image.set_pixel(3, 0, &Color::rgba(10, 10, 10, 0)).unwrap();
+
+    let indexed = IndexedImage::quantize(&image, 3, QuantizeMode::MedianCut).unwrap();
+    assert_eq!(3, indexed.palette.len());
+    assert_eq!(0, indexed.palette[indexed.indices[3] as usize].a);
+
+    let solid = Image::blank(2, 2);
+    assert!(IndexedImage::quantize(&solid, 1, QuantizeMode::Octree).is_ok());
+    assert!(IndexedImage::quantize(&image, 1, QuantizeMode::Octree).is_err());
+    assert!(IndexedImage::quantize(&image, 257, QuantizeMode::Octree).is_err());
+}
+
+#[test]
+fn save_indexed_roundtrip() {
+    let image = raster::open("tests/in/sample.jpg").unwrap();
+    let mut indexed = IndexedImage::quantize(&image, 32, QuantizeMode::Octree).unwrap();
+    indexed.palette[0].a = 0;
+    let expected = indexed.to_image();
+
+    raster::save_indexed(&indexed, "tests/out/test_indexed_roundtrip.png").unwrap();
+    let png = raster::open("tests/out/test_indexed_roundtrip.png").unwrap();
+    assert_eq!(expected.bytes, png.bytes);
+
+    raster::save_indexed(&indexed, "tests/out/test_indexed_roundtrip.gif").unwrap();
+    let gif = raster::open("tests/out/test_indexed_roundtrip.gif").unwrap();
+    assert!(compare::equal(&expected, &gif).unwrap());
+
+    assert!(raster::save_indexed(&indexed, "tests/out/test_indexed.jpg").is_err());
+}
@@ -0,0 +1,152 @@
+extern crate raster;
+
+use raster::{interpolate, Color, Image, InterpolationMode};
+
+// A 2x1 image with a black and a white pixel.
+fn black_white() -> Image {
+    let mut image = Image::blank(2, 1);
+    image.set_pixel(1, 0, &Color::white()).unwrap();
+    image
+}
+
+#[test]
+fn bilinear_upscale_is_smooth() {
+    let mut bilinear = black_white();
+    interpolate::resample(&mut bilinear, 8, 1, InterpolationMode::Bilinear).unwrap();
+    let mut nearest = black_white();
+    interpolate::resample(&mut nearest, 8, 1, InterpolationMode::Nearest).unwrap();
+
+    let reds = |image: &Image| -> Vec<u8> {
+        (0..image.width)
+            .map(|x| image.get_pixel(x, 0).unwrap().r)
+            .collect()
+    };
+    let bilinear = reds(&bilinear);
+    let nearest = reds(&nearest);
+
+    // Nearest only ever produces the source colors
+    assert!(nearest.iter().all(|&r| r == 0 || r == 255));
+
+    // Bilinear produces a monotonic gradient with intermediate values, clamped at the edges
+    assert_eq!(0, bilinear[0]);
+    assert_eq!(255, bilinear[7]);
+    assert!(bilinear.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(bilinear.iter().any(|&r| r > 0 && r < 255));
+}
+
+#[test]
+fn bilinear_preserves_solid_color() {
+    let mut image = Image::blank(3, 3);
+    raster::editor::fill(&mut image, Color::rgba(10, 20, 30, 40)).unwrap();
+    interpolate::resample(&mut image, 7, 5, InterpolationMode::Bilinear).unwrap();
+
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let pixel = image.get_pixel(x, y).unwrap();
+            assert_eq!((10, 20, 30, 40), (pixel.r, pixel.g, pixel.b, pixel.a));
+        }
+    }
+}
+
+#[test]
+fn bilinear_keeps_color_next_to_transparent() {
+    // Opaque red next to transparent black
+    let mut image = Image::blank(2, 1);
+    image.set_pixel(0, 0, &Color::red()).unwrap();
+    image.set_pixel(1, 0, &Color::rgba(0, 0, 0, 0)).unwrap();
+    interpolate::resample(&mut image, 8, 1, InterpolationMode::Bilinear).unwrap();
+
+    for x in 0..8 {
+        let pixel = image.get_pixel(x, 0).unwrap();
+        if pixel.a > 0 {
+            assert_eq!(255, pixel.r, "column {}", x);
+        }
+    }
+    assert!((0..8).any(|x| {
+        let a = image.get_pixel(x, 0).unwrap().a;
+        a > 0 && a < 255
+    }));
+}
+
+#[test]
+fn bicubic_upscale_is_smooth() {
+    let mut image = black_white();
+    interpolate::resample(&mut image, 8, 1, InterpolationMode::Bicubic).unwrap();
+
+    let reds: Vec<u8> = (0..8).map(|x| image.get_pixel(x, 0).unwrap().r).collect();
+    assert_eq!(0, reds[0]);
+    assert_eq!(255, reds[7]);
+    assert!(reds.iter().filter(|&&r| r > 0 && r < 255).count() >= 4);
+}
+
+#[test]
+fn bicubic_keeps_color_next_to_transparent() {
+    // Opaque red next to transparent black
+    let mut image = Image::blank(2, 1);
+    image.set_pixel(0, 0, &Color::red()).unwrap();
+    image.set_pixel(1, 0, &Color::rgba(0, 0, 0, 0)).unwrap();
+    interpolate::resample(&mut image, 8, 1, InterpolationMode::Bicubic).unwrap();
+
+    for x in 0..8 {
+        let pixel = image.get_pixel(x, 0).unwrap();
+        if pixel.a > 0 {
+            assert_eq!(255, pixel.r, "column {}", x);
+        }
+    }
+    assert!((0..8).any(|x| {
+        let a = image.get_pixel(x, 0).unwrap().a;
+        a > 0 && a < 255
+    }));
+}
+
+#[test]
+fn bicubic_preserves_solid_color() {
+    let mut image = Image::blank(5, 4);
+    raster::editor::fill(&mut image, Color::rgba(200, 100, 50, 255)).unwrap();
+    interpolate::resample(&mut image, 2, 9, InterpolationMode::Bicubic).unwrap();
+
+    assert_eq!(2, image.width);
+    assert_eq!(9, image.height);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let pixel = image.get_pixel(x, y).unwrap();
+            assert_eq!((200, 100, 50, 255), (pixel.r, pixel.g, pixel.b, pixel.a));
+        }
+    }
+}
+
+#[test]
+fn lanczos3_preserves_solid_color() {
+    let mut image = Image::blank(9, 9);
+    raster::editor::fill(&mut image, Color::rgba(90, 180, 30, 255)).unwrap();
+    interpolate::resample(&mut image, 4, 13, InterpolationMode::Lanczos3).unwrap();
+
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let pixel = image.get_pixel(x, y).unwrap();
+            assert_eq!((90, 180, 30, 255), (pixel.r, pixel.g, pixel.b, pixel.a));
+        }
+    }
+}
+
+#[test]
+fn lanczos3_downscale_averages_stripes() {
+    // Alternating black and white columns average out to gray instead of aliasing
+    let mut image = Image::blank(32, 4);
+    for y in 0..4 {
+        for x in (0..32).filter(|x| x % 2 == 1) {
+            image.set_pixel(x, y, &Color::white()).unwrap();
+        }
+    }
+    interpolate::resample(&mut image, 8, 4, InterpolationMode::Lanczos3).unwrap();
+
+    for x in 1..7 {
+        let pixel = image.get_pixel(x, 2).unwrap();
+        assert!(
+            pixel.r > 100 && pixel.r < 155,
+            "column {} is {}",
+            x,
+            pixel.r
+        );
+    }
+}
@@ -1,3 +1,6 @@
+// The older tests spell out their matches instead of using is_ok, is_err or matches!.
+#![allow(clippy::match_like_matches_macro, clippy::redundant_pattern_matching)]
+
 extern crate raster;
 
 #[test]
@@ -123,3 +126,12 @@ fn read_png_format_fail() {
         }
     });
 }
+
+#[test]
+fn error_display() {
+    let err = raster::open("tests/in/unsupported.txt").unwrap_err();
+    assert_eq!("Unsupported image format \"txt\"", err.to_string());
+
+    let err: Box<dyn std::error::Error> = Box::new(err);
+    assert!(err.source().is_none());
+}
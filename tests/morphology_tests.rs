@@ -0,0 +1,169 @@
+extern crate raster;
+
+use raster::morphology::{self, DistanceMetric, StructuringElement};
+use raster::{editor, Color, Image};
+
+// Black 9x9 image with a white 3x3 square in the middle and a white speck in a corner.
+fn mask() -> Image {
+    let mut image = Image::blank(9, 9);
+    for y in 3..6 {
+        for x in 3..6 {
+            image.set_pixel(x, y, &Color::white()).unwrap();
+        }
+    }
+    image.set_pixel(0, 0, &Color::white()).unwrap();
+    image
+}
+
+fn white(image: &Image) -> usize {
+    image.bytes.chunks(4).filter(|p| p[0] == 255).count()
+}
+
+#[test]
+fn structuring_element_test() {
+    assert_eq!(3, StructuringElement::square(1).unwrap().size());
+    assert_eq!(1, StructuringElement::disk(0).unwrap().size());
+    assert_eq!(
+        StructuringElement::cross(1).unwrap(),
+        StructuringElement::new(&[
+            [false, true, false],
+            [true, true, true],
+            [false, true, false],
+        ])
+        .unwrap()
+    );
+    assert_eq!(
+        StructuringElement::disk(1).unwrap(),
+        StructuringElement::cross(1).unwrap()
+    );
+
+    assert!(StructuringElement::new(&[[true; 2]; 2]).is_err());
+    assert!(StructuringElement::new(&[vec![true; 3], vec![true; 2], vec![true; 3]]).is_err());
+    assert!(StructuringElement::square(-1).is_err());
+}
+
+#[test]
+fn erode_test() {
+    let mut image = mask();
+    morphology::erode(&mut image, &StructuringElement::square(1).unwrap()).unwrap();
+    // Only the center of the square is left.
+    assert_eq!(1, white(&image));
+    assert_eq!(255, image.get_pixel(4, 4).unwrap().r);
+
+    // Grayscale: smallest value of the neighborhood
+    let mut image = Image::blank(3, 1);
+    image.bytes = vec![10, 20, 30, 255, 50, 5, 60, 255, 90, 80, 0, 128];
+    morphology::erode(&mut image, &StructuringElement::cross(1).unwrap()).unwrap();
+    assert_eq!(vec![10, 5, 30, 255, 10, 5, 0, 255, 50, 5, 0, 128], image.bytes);
+}
+
+#[test]
+fn dilate_test() {
+    let mut image = mask();
+    morphology::dilate(&mut image, &StructuringElement::square(1).unwrap()).unwrap();
+    // 5x5 square and 2x2 corner
+    assert_eq!(25 + 4, white(&image));
+    assert_eq!(255, image.get_pixel(2, 2).unwrap().r);
+    assert_eq!(0, image.get_pixel(1, 2).unwrap().r);
+
+    // The element is mirrored: a line going right grows the pixel to the left.
+    let right = StructuringElement::new(&[
+        [false, false, false],
+        [false, true, true],
+        [false, false, false],
+    ])
+    .unwrap();
+    let mut image = Image::blank(3, 1);
+    editor::fill(&mut image, Color::black()).unwrap();
+    image.set_pixel(1, 0, &Color::white()).unwrap();
+    morphology::dilate(&mut image, &right).unwrap();
+    assert_eq!(255, image.get_pixel(1, 0).unwrap().r);
+    assert_eq!(255, image.get_pixel(2, 0).unwrap().r);
+    assert_eq!(0, image.get_pixel(0, 0).unwrap().r);
+}
+
+#[test]
+fn open_close_test() {
+    let square = StructuringElement::square(1).unwrap();
+
+    // Opening removes the speck and keeps the square.
+    let mut image = mask();
+    morphology::open(&mut image, &square).unwrap();
+    assert_eq!(9, white(&image));
+    assert_eq!(0, image.get_pixel(0, 0).unwrap().r);
+
+    // Closing fills a hole of the square.
+    let mut image = mask();
+    image.set_pixel(4, 4, &Color::black()).unwrap();
+    morphology::close(&mut image, &square).unwrap();
+    assert_eq!(255, image.get_pixel(4, 4).unwrap().r);
+    assert_eq!(0, image.get_pixel(2, 4).unwrap().r);
+}
+
+#[test]
+fn gradient_test() {
+    let mut image = mask();
+    morphology::gradient(&mut image, &StructuringElement::square(1).unwrap()).unwrap();
+    // 5x5 ring without the center, and the 2x2 corner
+    assert_eq!(24 + 4, white(&image));
+    assert_eq!(0, image.get_pixel(4, 4).unwrap().r);
+    assert_eq!(255, image.get_pixel(2, 4).unwrap().r);
+    assert_eq!(255, image.get_pixel(4, 4).unwrap().a);
+}
+
+#[test]
+fn tophat_blackhat_test() {
+    let square = StructuringElement::square(1).unwrap();
+
+    // Top hat keeps only the speck.
+    let mut image = mask();
+    morphology::tophat(&mut image, &square).unwrap();
+    assert_eq!(1, white(&image));
+    assert_eq!(255, image.get_pixel(0, 0).unwrap().r);
+
+    // Black hat finds the hole of the square.
+    let mut image = mask();
+    image.set_pixel(4, 4, &Color::black()).unwrap();
+    morphology::blackhat(&mut image, &square).unwrap();
+    assert_eq!(1, white(&image));
+    assert_eq!(255, image.get_pixel(4, 4).unwrap().r);
+}
+
+#[test]
+fn distance_transform_test() {
+    let mut image = Image::blank(12, 10);
+    image.set_pixel(2, 1, &Color::white()).unwrap();
+    image.set_pixel(11, 9, &Color::white()).unwrap();
+    let at = |distances: &[f32], x: usize, y: usize| distances[y * 12 + x];
+
+    let euclidean = morphology::distance_transform(&image, DistanceMetric::Euclidean);
+    let manhattan = morphology::distance_transform(&image, DistanceMetric::Manhattan);
+    let chebyshev = morphology::distance_transform(&image, DistanceMetric::Chebyshev);
+    for distances in [&euclidean, &manhattan, &chebyshev].iter() {
+        assert_eq!(120, distances.len());
+        assert_eq!(0.0, at(distances, 2, 1));
+        assert_eq!(0.0, at(distances, 11, 9));
+    }
+    // 3 right and 4 down of the first pixel
+    assert!((at(&euclidean, 5, 5) - 5.0).abs() < 1e-6);
+    assert_eq!(7.0, at(&manhattan, 5, 5));
+    assert_eq!(4.0, at(&chebyshev, 5, 5));
+    // Nearer to the second pixel
+    assert!((at(&euclidean, 9, 8) - 5f32.sqrt()).abs() < 1e-6);
+    assert_eq!(3.0, at(&manhattan, 9, 8));
+    assert_eq!(2.0, at(&chebyshev, 9, 8));
+
+    // Brute force check of the Euclidean distances
+    for y in 0..10 {
+        for x in 0..12 {
+            let nearest = [(2.0f32, 1.0f32), (11.0, 9.0)]
+                .iter()
+                .map(|&(fx, fy)| (x as f32 - fx).hypot(y as f32 - fy))
+                .fold(f32::INFINITY, f32::min);
+            assert!((at(&euclidean, x, y) - nearest).abs() < 1e-5);
+        }
+    }
+
+    let empty = morphology::distance_transform(&Image::blank(3, 3), DistanceMetric::Euclidean);
+    assert!(empty.iter().all(|d| d.is_infinite()));
+}
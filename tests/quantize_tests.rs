@@ -0,0 +1,52 @@
+extern crate raster;
+
+use raster::quantize::{self, QuantizeMode};
+use raster::{Color, Image};
+
+#[test]
+fn reduce_test() {
+    let image = raster::open("tests/in/sample.jpg").unwrap();
+    let modes = [
+        (QuantizeMode::MedianCut, "median_cut"),
+        (QuantizeMode::Octree, "octree"),
+    ];
+    for &(mode, name) in modes.iter() {
+        let (quantized, palette) = quantize::reduce(&image, 16, mode).unwrap();
+        assert!(!palette.is_empty() && palette.len() <= 16);
+        raster::save(&quantized, &format!("tests/out/test_quantize_reduce_{}.png", name)).unwrap();
+    }
+}
+
+#[test]
+fn reduce_two_groups() {
+    // Reds and blues with slight variations reduce to one red and one blue
+    let mut image = Image::blank(4, 2);
+    for x in 0..4 {
+        let v = x as u8 * 4;
+        image.set_pixel(x, 0, &Color::rgba(200 + v, 10, 10, 255)).unwrap();
+        image.set_pixel(x, 1, &Color::rgba(10, 10, 200 + v, 100)).unwrap();
+    }
+
+    for &mode in [QuantizeMode::MedianCut, QuantizeMode::Octree].iter() {
+        let (quantized, palette) = quantize::reduce(&image, 2, mode).unwrap();
+        assert_eq!(2, palette.len());
+
+        let red = quantized.get_pixel(0, 0).unwrap();
+        let blue = quantized.get_pixel(3, 1).unwrap();
+        assert_eq!((206, 10, 10, 255), (red.r, red.g, red.b, red.a));
+        assert_eq!((10, 10, 206, 100), (blue.r, blue.g, blue.b, blue.a));
+        let other_red = quantized.get_pixel(3, 0).unwrap();
+        assert_eq!(206, other_red.r);
+    }
+}
+
+#[test]
+fn reduce_fewer_colors_than_asked() {
+    let image = Image::blank(3, 3);
+    let (quantized, palette) = quantize::reduce(&image, 8, QuantizeMode::MedianCut).unwrap();
+    assert_eq!(1, palette.len());
+    assert_eq!(0, quantized.get_pixel(1, 1).unwrap().r);
+
+    assert!(quantize::reduce(&image, 0, QuantizeMode::Octree).is_err());
+    assert!(quantize::reduce(&image, 257, QuantizeMode::Octree).is_err());
+}
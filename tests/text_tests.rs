@@ -0,0 +1,140 @@
+#![cfg(feature = "text")]
+
+extern crate raster;
+
+use raster::error::RasterError;
+use raster::{draw, Color, Font, Image, Point, TextAlign, TextBox};
+
+// Rows and columns of the painted pixels as (top, bottom, left, right).
+fn painted_bounds(image: &Image) -> (i32, i32, i32, i32) {
+    let mut bounds = (image.height, -1, image.width, -1);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            if image.get_pixel(x, y).unwrap().r > 0 {
+                bounds.0 = bounds.0.min(y);
+                bounds.1 = bounds.1.max(y);
+                bounds.2 = bounds.2.min(x);
+                bounds.3 = bounds.3.max(x);
+            }
+        }
+    }
+    bounds
+}
+
+#[test]
+fn text_baseline() {
+    let font = Font::open("tests/in/DejaVuSans.ttf").unwrap();
+    let mut image = Image::blank(100, 60);
+    draw::text(&mut image, "H", &font, 40.0, Point::new(10.0, 40.0), &Color::white()).unwrap();
+
+    // Capital letters sit on the baseline and are about 0.73 em high in DejaVu Sans
+    let (top, bottom, left, _) = painted_bounds(&image);
+    assert_eq!(40, bottom);
+    assert!((top - 11).abs() <= 1);
+    assert!((10..=16).contains(&left));
+
+    // Descenders go below the baseline
+    let mut image = Image::blank(100, 60);
+    draw::text(&mut image, "g", &font, 40.0, Point::new(10.0, 40.0), &Color::white()).unwrap();
+    assert!(painted_bounds(&image).1 > 45);
+}
+
+#[test]
+fn text_lines() {
+    let font = Font::open("tests/in/DejaVuSans.ttf").unwrap();
+    let mut one = Image::blank(200, 200);
+    draw::text(&mut one, "Ab", &font, 20.0, Point::new(10.0, 30.0), &Color::white()).unwrap();
+    let mut two = Image::blank(200, 200);
+    draw::text(&mut two, "Ab\nAb", &font, 20.0, Point::new(10.0, 30.0), &Color::white()).unwrap();
+
+    let first = painted_bounds(&one);
+    let both = painted_bounds(&two);
+    assert_eq!((first.0, first.2, first.3), (both.0, both.2, both.3));
+    // DejaVu Sans lines are about 1.16 em apart
+    assert!((both.1 - first.1 - 23).abs() <= 1);
+}
+
+#[test]
+fn text_invalid() {
+    assert!(Font::open("tests/in/sample.png").is_err());
+    assert!(Font::from_bytes(vec![0; 16]).is_err());
+
+    let font = Font::open("tests/in/DejaVuSans.ttf").unwrap();
+    let mut image = Image::blank(10, 10);
+    let origin = Point::new(0.0, 5.0);
+    assert!(draw::text(&mut image, "x", &font, 0.0, origin, &Color::white()).is_err());
+}
+
+#[test]
+fn text_box_wrap() {
+    let font = Font::open("tests/in/DejaVuSans.ttf").unwrap();
+    let width = font.width("aaa bbb", 20.0) + 1.0;
+    let text_box = TextBox::new(&font, 20.0, width).unwrap();
+
+    let lines = text_box.lines("aaa bbb ccc\n\nddd");
+    assert_eq!(vec!["aaa bbb", "ccc", "", "ddd"], lines);
+
+    // Words longer than the width are broken
+    let lines = text_box.lines("aaaaaaaaaaaaaaaaaaaa");
+    assert!(lines.len() > 2);
+    assert!(lines.iter().all(|line| font.width(line, 20.0) <= width));
+    assert_eq!("aaaaaaaaaaaaaaaaaaaa", lines.concat());
+
+    let line_height = text_box.height("aaa");
+    let spaced = text_box.clone().line_spacing(2.0);
+    assert_eq!(line_height * 3.0, spaced.height("aaa bbb ccc"));
+}
+
+#[test]
+fn text_box_ellipsis() {
+    let font = Font::open("tests/in/DejaVuSans.ttf").unwrap();
+    let width = font.width("aaa bbb", 20.0) + 1.0;
+    let text_box = TextBox::new(&font, 20.0, width).unwrap().max_lines(1);
+
+    let lines = text_box.lines("aaa bbb ccc");
+    assert_eq!(1, lines.len());
+    assert!(lines[0].ends_with('…'));
+    assert!(font.width(&lines[0], 20.0) <= width);
+    assert_eq!(vec!["aaa bbb"], text_box.lines("aaa bbb"));
+}
+
+#[test]
+fn text_box_align() {
+    let font = Font::open("tests/in/DejaVuSans.ttf").unwrap();
+    let draw = |align: TextAlign, text: &str| {
+        let text_box = TextBox::new(&font, 20.0, 180.0).unwrap().align(align);
+        let mut image = Image::blank(200, 100);
+        text_box.draw(&mut image, text, Point::new(10.0, 10.0), &Color::white()).unwrap();
+        // Only the first line
+        raster::editor::crop(&mut image, 200, 30, raster::PositionMode::TopLeft, 0, 0).unwrap();
+        painted_bounds(&image)
+    };
+
+    let left = draw(TextAlign::Left, "ab cd");
+    let right = draw(TextAlign::Right, "ab cd");
+    let center = draw(TextAlign::Center, "ab cd");
+    assert!(left.2 <= 12 && left.3 < 100);
+    assert!(right.2 > 100 && right.3 >= 185);
+    assert!(center.2 > left.2 && center.3 < right.3);
+    assert!(((center.2 - 10) - (190 - center.3)).abs() <= 2);
+
+    // Justified lines span the width, except the last one
+    let text = "ab cd ef gh ij kl mn op qr st uv wx";
+    let left = draw(TextAlign::Left, text);
+    let justify = draw(TextAlign::Justify, text);
+    assert_eq!(left.2, justify.2);
+    assert!(left.3 < 185);
+    assert!(justify.3 >= 185);
+    assert_eq!(draw(TextAlign::Left, "ab cd"), draw(TextAlign::Justify, "ab cd"));
+
+    assert!(TextBox::new(&font, 0.0, 100.0).is_err());
+    assert!(TextBox::new(&font, 10.0, 0.0).is_err());
+    match TextBox::new(&font, 10.0, -5.5) {
+        Err(RasterError::InvalidWidth(-5.5)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    match TextBox::new(&font, 10.0, f32::NAN) {
+        Err(RasterError::InvalidWidth(width)) if width.is_nan() => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
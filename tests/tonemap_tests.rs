@@ -0,0 +1,63 @@
+extern crate raster;
+
+use raster::tonemap::{self, HdrImage};
+use raster::Image;
+
+#[test]
+fn from_image_test() {
+    let mut image = Image::blank(2, 1);
+    image.bytes[4..8].copy_from_slice(&[255, 128, 0, 128]);
+    let hdr = HdrImage::from_image(&image);
+    assert_eq!(&[0.0, 0.0, 0.0, 1.0], &hdr.pixels[0..4]);
+    assert_eq!(1.0, hdr.pixels[4]);
+    assert!((hdr.pixels[5] - 0.2158).abs() < 0.001);
+    assert!((hdr.pixels[7] - 0.502).abs() < 0.001);
+}
+
+#[test]
+fn reinhard_test() {
+    // Gray ramp far above white
+    let mut hdr = HdrImage::blank(4, 1);
+    for (i, pixel) in hdr.pixels.chunks_mut(4).enumerate() {
+        let value = [0.0, 0.5, 4.0, 100.0][i];
+        pixel[0..3].copy_from_slice(&[value, value, value]);
+    }
+
+    let image = tonemap::reinhard(&hdr, 0.0, 4.0).unwrap();
+    let values: Vec<u8> = image.bytes.chunks(4).map(|p| p[0]).collect();
+    assert_eq!(0, values[0]);
+    assert!(values[1] < values[2]);
+    // The white point maps to white and brighter values are clipped.
+    assert_eq!(255, values[2]);
+    assert_eq!(255, values[3]);
+    assert_eq!(255, image.bytes[3]);
+
+    // Hue is kept
+    let mut hdr = HdrImage::blank(1, 1);
+    hdr.pixels[0..3].copy_from_slice(&[2.0, 1.0, 0.0]);
+    let image = tonemap::reinhard(&hdr, 0.0, 100.0).unwrap();
+    assert!(image.bytes[0] > image.bytes[1]);
+    assert_eq!(0, image.bytes[2]);
+
+    assert!(tonemap::reinhard(&hdr, 0.0, 0.0).is_err());
+}
+
+#[test]
+fn hable_test() {
+    let mut hdr = HdrImage::blank(3, 1);
+    for (i, pixel) in hdr.pixels.chunks_mut(4).enumerate() {
+        let value = [0.0, 1.0, 11.2][i];
+        pixel[0..3].copy_from_slice(&[value, value, value]);
+    }
+
+    let image = tonemap::hable(&hdr, 0.0, 11.2).unwrap();
+    assert_eq!(0, image.bytes[0]);
+    assert!(image.bytes[4] > 0 && image.bytes[4] < 255);
+    assert_eq!(255, image.bytes[8]);
+
+    // More exposure is brighter
+    let brighter = tonemap::hable(&hdr, 1.0, 11.2).unwrap();
+    assert!(brighter.bytes[4] > image.bytes[4]);
+
+    assert!(tonemap::hable(&hdr, 0.0, -1.0).is_err());
+}
@@ -0,0 +1,431 @@
+extern crate raster;
+
+use raster::error::RasterError;
+use raster::{
+    compare, editor, transform, Color, Direction, Image, InterpolationMode, Transform,
+    TransformMode,
+};
+
+#[test]
+fn rotate_zero_is_identity() {
+    let image = raster::open("tests/in/sample.png").unwrap();
+    let mut rotated = image.clone();
+    transform::rotate(&mut rotated, 0.0, Color::black()).unwrap();
+
+    assert!(compare::equal(&image, &rotated).unwrap());
+}
+
+#[test]
+fn rotate_expands_canvas() {
+    let mut image = Image::blank(30, 20);
+    transform::rotate(&mut image, 90.0, Color::black()).unwrap();
+    assert_eq!((20, 30), (image.width, image.height));
+
+    let mut image = Image::blank(10, 10);
+    transform::rotate(&mut image, 45.0, Color::black()).unwrap();
+    assert_eq!((15, 15), (image.width, image.height));
+}
+
+#[test]
+fn rotate_smooth_edges() {
+    let mut image = Image::blank(20, 20);
+    editor::fill(&mut image, Color::white()).unwrap();
+    transform::rotate(&mut image, 30.0, Color::black()).unwrap();
+
+    // Corners are background, center is untouched and edges are anti-aliased
+    assert_eq!(0, image.get_pixel(0, 0).unwrap().r);
+    let center = image.get_pixel(image.width / 2, image.height / 2).unwrap();
+    assert_eq!(255, center.r);
+    let reds: Vec<u8> = (0..image.width)
+        .map(|x| image.get_pixel(x, 2).unwrap().r)
+        .collect();
+    assert!(reds.iter().any(|&r| r > 0 && r < 255));
+}
+
+// A 3x2 image where each pixel has a distinct red value:
+// 0 1 2
+// 3 4 5
+fn numbered() -> Image {
+    let mut image = Image::blank(3, 2);
+    for y in 0..2 {
+        for x in 0..3 {
+            let n = (y * 3 + x) as u8;
+            image.set_pixel(x, y, &Color::rgb(n, 0, 0)).unwrap();
+        }
+    }
+    image
+}
+
+fn reds(image: &Image) -> Vec<u8> {
+    image.bytes.chunks(4).map(|p| p[0]).collect()
+}
+
+#[test]
+fn rotate_90_test() {
+    let mut image = numbered();
+    transform::rotate_90(&mut image).unwrap();
+    assert_eq!((2, 3), (image.width, image.height));
+    assert_eq!(vec![3, 0, 4, 1, 5, 2], reds(&image));
+}
+
+#[test]
+fn rotate_180_test() {
+    let mut image = numbered();
+    transform::rotate_180(&mut image).unwrap();
+    assert_eq!((3, 2), (image.width, image.height));
+    assert_eq!(vec![5, 4, 3, 2, 1, 0], reds(&image));
+}
+
+#[test]
+fn rotate_270_test() {
+    let mut image = numbered();
+    transform::rotate_270(&mut image).unwrap();
+    assert_eq!((2, 3), (image.width, image.height));
+    assert_eq!(vec![2, 5, 1, 4, 0, 3], reds(&image));
+}
+
+#[test]
+fn rotate_90_matches_rotate() {
+    let mut image = numbered();
+    let mut rotated = image.clone();
+    transform::rotate_90(&mut image).unwrap();
+    transform::rotate(&mut rotated, 90.0, Color::black()).unwrap();
+    assert!(compare::equal(&image, &rotated).unwrap());
+}
+
+#[test]
+fn flip_horizontal_test() {
+    for file in &["tests/in/flip_x_even.png", "tests/in/flip_x_odd.png"] {
+        let image = raster::open(file).unwrap();
+        let mut flipped = image.clone();
+        transform::flip(&mut flipped, TransformMode::Horizontal).unwrap();
+
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let expected = image.get_pixel(image.width - x - 1, y).unwrap();
+                let pixel = flipped.get_pixel(x, y).unwrap();
+                assert_eq!(
+                    (expected.r, expected.g, expected.b, expected.a),
+                    (pixel.r, pixel.g, pixel.b, pixel.a)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn flip_vertical_test() {
+    let mut image = numbered();
+    transform::flip(&mut image, TransformMode::Vertical).unwrap();
+    assert_eq!(vec![3, 4, 5, 0, 1, 2], reds(&image));
+
+    // Flipping twice gives back the original
+    transform::flip(&mut image, TransformMode::Vertical).unwrap();
+    assert_eq!(vec![0, 1, 2, 3, 4, 5], reds(&image));
+}
+
+#[test]
+fn perspective_identity() {
+    let image = raster::open("tests/in/sample.png").unwrap();
+    let (w, h) = (image.width as f64, image.height as f64);
+    let quad = [(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)];
+
+    let mut warped = image.clone();
+    transform::perspective(&mut warped, quad, quad).unwrap();
+    assert!(compare::equal(&image, &warped).unwrap());
+}
+
+#[test]
+fn perspective_scale_and_translate() {
+    let mut image = Image::blank(10, 10);
+    editor::fill(&mut image, Color::white()).unwrap();
+
+    let src_quad = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+    let dst_quad = [(10.0, 10.0), (30.0, 10.0), (30.0, 30.0), (10.0, 30.0)];
+    transform::perspective(&mut image, src_quad, dst_quad).unwrap();
+
+    assert_eq!((30, 30), (image.width, image.height));
+    assert_eq!(0, image.get_pixel(5, 5).unwrap().a);
+    let inside = image.get_pixel(20, 20).unwrap();
+    assert_eq!((255, 255), (inside.r, inside.a));
+}
+
+#[test]
+fn perspective_clears_outside() {
+    // Only the middle of the image is kept, the rest of it is not copied around the quad.
+    let mut image = Image::blank(10, 10);
+    editor::fill(&mut image, Color::white()).unwrap();
+    let quad = [(2.0, 2.0), (8.0, 2.0), (8.0, 8.0), (2.0, 8.0)];
+    transform::perspective(&mut image, quad, quad).unwrap();
+
+    assert_eq!((8, 8), (image.width, image.height));
+    assert_eq!(0, image.get_pixel(0, 0).unwrap().a);
+    assert_eq!(0, image.get_pixel(1, 5).unwrap().a);
+    let inside = image.get_pixel(5, 5).unwrap();
+    assert_eq!((255, 255), (inside.r, inside.a));
+}
+
+#[test]
+fn perspective_degenerate() {
+    let mut image = Image::blank(10, 10);
+    let src_quad = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+    let dst_quad = [(0.0, 0.0), (5.0, 0.0), (10.0, 0.0), (0.0, 10.0)];
+    assert!(transform::perspective(&mut image, src_quad, dst_quad).is_err());
+}
+
+#[test]
+fn perspective_not_finite() {
+    let mut image = Image::blank(10, 10);
+    let quad = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+    let nan = [(0.0, 0.0), (10.0, f64::NAN), (10.0, 10.0), (0.0, 10.0)];
+    assert!(transform::perspective(&mut image, quad, nan).is_err());
+    assert!(transform::perspective(&mut image, nan, quad).is_err());
+    let infinite = [(0.0, 0.0), (f64::INFINITY, 0.0), (10.0, 10.0), (0.0, 10.0)];
+    assert!(transform::perspective(&mut image, quad, infinite).is_err());
+}
+
+#[test]
+fn transform_compose_and_invert() {
+    let t = Transform::identity()
+        .scale(2.0, 3.0)
+        .rotate(90.0)
+        .translate(5.0, 0.0);
+    let (x, y) = t.apply(1.0, 1.0);
+    assert!((x - 2.0).abs() < 1e-9 && (y - 2.0).abs() < 1e-9);
+
+    let (x, y) = t.invert().unwrap().apply(x, y);
+    assert!((x - 1.0).abs() < 1e-9 && (y - 1.0).abs() < 1e-9);
+
+    assert!(Transform::identity().scale(0.0, 1.0).invert().is_none());
+}
+
+#[test]
+fn affine_identity() {
+    let image = raster::open("tests/in/sample.png").unwrap();
+    let mut transformed = image.clone();
+    transform::affine(
+        &mut transformed,
+        &Transform::identity(),
+        InterpolationMode::Nearest,
+        Color::white(),
+    )
+    .unwrap();
+    assert!(compare::equal(&image, &transformed).unwrap());
+}
+
+#[test]
+fn affine_scale_and_shear() {
+    let mut image = numbered();
+    transform::affine(
+        &mut image,
+        &Transform::identity().scale(2.0, 2.0),
+        InterpolationMode::Nearest,
+        Color::white(),
+    )
+    .unwrap();
+    assert_eq!((6, 4), (image.width, image.height));
+    assert_eq!(4, image.get_pixel(3, 2).unwrap().r);
+
+    let mut image = Image::blank(10, 10);
+    transform::affine(
+        &mut image,
+        &Transform::identity().shear(0.5, 0.0),
+        InterpolationMode::Bilinear,
+        Color::white(),
+    )
+    .unwrap();
+    assert_eq!((15, 10), (image.width, image.height));
+}
+
+#[test]
+fn affine_not_invertible() {
+    let mut image = Image::blank(10, 10);
+    let t = Transform::identity().scale(1.0, 0.0);
+    assert!(
+        transform::affine(&mut image, &t, InterpolationMode::Bilinear, Color::white()).is_err()
+    );
+}
+
+#[test]
+fn swirl_test() {
+    // Left half red, right half blue
+    let mut image = Image::blank(41, 41);
+    for y in 0..41 {
+        for x in 0..41 {
+            let color = if x < 20 { Color::red() } else { Color::blue() };
+            image.set_pixel(x, y, &color).unwrap();
+        }
+    }
+
+    let mut swirled = image.clone();
+    transform::swirl(&mut swirled, (20.5, 20.5), 15.0, 180.0).unwrap();
+    // Outside of the radius nothing moves
+    assert_eq!(255, swirled.get_pixel(4, 20).unwrap().r);
+    assert_eq!(255, swirled.get_pixel(36, 20).unwrap().b);
+    // Near the center the halves are swapped
+    assert_eq!(255, swirled.get_pixel(18, 20).unwrap().b);
+    assert_eq!(255, swirled.get_pixel(23, 20).unwrap().r);
+
+    let mut unchanged = image.clone();
+    transform::swirl(&mut unchanged, (20.5, 20.5), 15.0, 0.0).unwrap();
+    assert!(compare::equal(&image, &unchanged).unwrap());
+
+    match transform::swirl(&mut unchanged, (20.5, 20.5), 0.0, 180.0) {
+        Err(RasterError::InvalidShapeRadius(0.0)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+// A 40x40 image with a vertical black line at x = 20.
+fn vertical_line() -> Image {
+    let mut image = Image::blank(40, 40);
+    editor::fill(&mut image, Color::white()).unwrap();
+    for y in 0..40 {
+        image.set_pixel(20, y, &Color::black()).unwrap();
+    }
+    image
+}
+
+// Columns of the darkest pixel of each row.
+fn darkest_columns(image: &Image) -> Vec<i32> {
+    (0..image.height)
+        .map(|y| {
+            (0..image.width)
+                .min_by_key(|&x| image.get_pixel(x, y).unwrap().r)
+                .unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn wave_test() {
+    let mut image = vertical_line();
+    transform::wave(&mut image, 4.0, 40.0, Direction::Horizontal).unwrap();
+    let columns = darkest_columns(&image);
+    // The line moves left where the sine is positive, it is sampled from the right
+    assert_eq!(16, columns[10]);
+    assert_eq!(24, columns[30]);
+    assert_eq!(20, columns[0]);
+
+    // Moving up and down leaves a vertical line in place
+    let mut image = vertical_line();
+    transform::wave(&mut image, 4.0, 40.0, Direction::Vertical).unwrap();
+    assert!(darkest_columns(&image).iter().all(|&x| x == 20));
+
+    assert!(transform::wave(&mut image, 4.0, 0.0, Direction::Vertical).is_err());
+}
+
+#[test]
+fn ripple_test() {
+    let mut image = vertical_line();
+    transform::ripple(&mut image, (0.5, 20.5), 3.0, 80.0).unwrap();
+    // At 20 pixels from the center the sine peaks, the line comes 3 pixels closer
+    assert_eq!(17, darkest_columns(&image)[20]);
+
+    match transform::ripple(&mut image, (0.0, 0.0), 3.0, -1.5) {
+        Err(RasterError::InvalidWavelength(-1.5)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn lens_distort_test() {
+    // A dot near the right edge
+    let mut image = Image::blank(41, 41);
+    editor::fill(&mut image, Color::white()).unwrap();
+    image.set_pixel(35, 20, &Color::black()).unwrap();
+    let darkest = |image: &Image| darkest_columns(image)[20];
+
+    // Barrel distortion pulls the edges in
+    let mut barrel = image.clone();
+    transform::lens_distort(&mut barrel, -0.2, 0.0).unwrap();
+    assert!(darkest(&barrel) < 35);
+
+    let mut pincushion = image.clone();
+    transform::lens_distort(&mut pincushion, 0.2, 0.0).unwrap();
+    assert!(darkest(&pincushion) > 35);
+
+    // Undistorting puts the dot back
+    let mut corrected = barrel.clone();
+    transform::lens_undistort(&mut corrected, -0.2, 0.0).unwrap();
+    assert_eq!(35, darkest(&corrected));
+}
+
+#[test]
+fn spherize_test() {
+    let image = vertical_line();
+    let width = |image: &Image| {
+        (0..image.width)
+            .filter(|&x| image.get_pixel(x, 20).unwrap().r < 128)
+            .count()
+    };
+
+    // The line at the center gets wider when bulging
+    let mut bulge = image.clone();
+    transform::spherize(&mut bulge, (20.5, 20.5), 15.0, 1.0).unwrap();
+    assert!(width(&bulge) > 1);
+    assert_eq!(20, darkest_columns(&bulge)[0]);
+
+    // A line off center is drawn toward it when pinching
+    let mut pinch = image.clone();
+    transform::spherize(&mut pinch, (15.5, 20.5), 15.0, -1.0).unwrap();
+    assert!(darkest_columns(&pinch)[20] < 20);
+
+    assert!(transform::spherize(&mut pinch, (0.0, 0.0), 15.0, 1.5).is_err());
+    match transform::spherize(&mut pinch, (0.0, 0.0), -4.0, 0.5) {
+        Err(RasterError::InvalidShapeRadius(-4.0)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn polar_test() {
+    // Red top half, blue bottom half, with a green right quarter
+    let mut image = Image::blank(40, 40);
+    for y in 0..40 {
+        for x in 0..40 {
+            let color = match (x, y) {
+                (30.., _) => Color::green(),
+                (_, 0..=19) => Color::red(),
+                _ => Color::blue(),
+            };
+            image.set_pixel(x, y, &color).unwrap();
+        }
+    }
+
+    // The top rows go to the center, the right columns to the top left
+    let mut polar = image.clone();
+    transform::to_polar(&mut polar).unwrap();
+    assert_eq!(255, polar.get_pixel(20, 16).unwrap().r);
+    assert_eq!(255, polar.get_pixel(20, 36).unwrap().b);
+    assert_eq!(255, polar.get_pixel(12, 4).unwrap().g);
+    assert_eq!(255, polar.get_pixel(4, 24).unwrap().b);
+
+    let mut back = polar.clone();
+    transform::from_polar(&mut back).unwrap();
+    for &(x, y) in &[(10, 10), (20, 30), (35, 10), (35, 30)] {
+        let (p1, p2) = (image.get_pixel(x, y).unwrap(), back.get_pixel(x, y).unwrap());
+        assert_eq!((p1.r, p1.g, p1.b), (p2.r, p2.g, p2.b));
+    }
+}
+
+#[test]
+fn displace_test() {
+    let image = vertical_line();
+
+    // Left half reads 10 pixels to the right, right half stays
+    let mut map = Image::blank(40, 40);
+    for y in 0..40 {
+        for x in 0..40 {
+            let red = if x < 20 { 255 } else { 128 };
+            map.set_pixel(x, y, &Color::rgb(red, 128, 0)).unwrap();
+        }
+    }
+    let mut displaced = image.clone();
+    transform::displace(&mut displaced, &map, 10.0, 10.0).unwrap();
+    assert_eq!(0, displaced.get_pixel(10, 5).unwrap().r);
+    assert_eq!(0, displaced.get_pixel(20, 5).unwrap().r);
+    assert_eq!(255, displaced.get_pixel(19, 5).unwrap().r);
+
+    assert!(transform::displace(&mut displaced, &Image::blank(4, 4), 1.0, 1.0).is_err());
+}